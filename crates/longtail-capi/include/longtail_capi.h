@@ -0,0 +1,207 @@
+#ifndef LONGTAIL_CAPI_H
+#define LONGTAIL_CAPI_H
+
+/* Generated by cbindgen from crates/longtail-capi/src/lib.rs. Do not edit; run
+ * `LONGTAIL_CAPI_BLESS=1 cargo test -p longtail-capi --test header` instead. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// The outcome of an `lt_*` call. Every value but `LT_STATUS_OK` is a class of
+// failure — the same classes, with the same meaning, as the Rust API's
+// `ErrorClass` — and `lt_last_error_message` has the details.
+typedef enum {
+  // Success.
+  LT_STATUS_OK = 0,
+  // The cancellation callback asked to stop. Not a failure; the target is
+  // left resumable.
+  LT_STATUS_CANCELLED = 1,
+  // The named version, block or object is not in the store.
+  LT_STATUS_NOT_FOUND = 2,
+  // Credentials were rejected.
+  LT_STATUS_UNAUTHORIZED = 3,
+  // A transport or contention failure expected to clear on its own; safe to
+  // retry the whole operation.
+  LT_STATUS_TRANSIENT = 4,
+  // The request itself is wrong: a missing or unknown option, a bad URI, a
+  // null argument. Retrying will not help.
+  LT_STATUS_INVALID_INPUT = 5,
+  // Data read from the store did not decode, verify, or agree with its index.
+  LT_STATUS_CORRUPT = 6,
+  // A local filesystem failure.
+  LT_STATUS_IO = 7,
+  // A bug, or a state that should be unreachable — a caught panic included.
+  LT_STATUS_INTERNAL = 8,
+} LtStatus;
+
+// The options for any `lt_*` operation. Opaque to C: made by
+// `lt_options_new`, filled by `lt_options_set` and the callback setters,
+// released by `lt_options_free`. Each operation reads the keys it needs and
+// ignores the rest, so one builder can serve several calls.
+typedef struct LtOptions LtOptions;
+
+// One progress report. `phase` names the current phase and is valid only for
+// the duration of the callback. Either dimension is unknown while its total
+// is 0.
+typedef struct {
+  const char *phase;
+  uint64_t done_items;
+  uint64_t total_items;
+  uint64_t done_bytes;
+  uint64_t total_bytes;
+} LtProgress;
+
+// Receives progress reports. Called from library threads, never from two at
+// once; it must return promptly.
+typedef void (*LtProgressCallback)(void *user_data, const LtProgress *progress);
+
+// Polled while an operation runs; returning `true` cancels it. Called from a
+// library thread.
+typedef bool (*LtCancelCallback)(void *user_data);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// The library version, e.g. `"0.1.0"`. Static; do not free.
+const char *lt_version(void);
+
+// A new, empty options builder. Free it with `lt_options_free`.
+LtOptions *lt_options_new(void);
+
+// Release an options builder. Null is ignored.
+//
+// # Safety
+// `options` is null or came from `lt_options_new` and has not been freed.
+void lt_options_free(LtOptions *options);
+
+// Set option `name` to `value`; both are UTF-8. Names are the CLI's long flag
+// names:
+//
+// - `storage-uri`, `target-path`, `cache-path`, `version-index-path`,
+//   `store-index-path`, `include-filter-regex`, `exclude-filter-regex`,
+//   `s3-endpoint-resolver-uri`, `compression-algorithm`, `hash-algorithm`;
+// - `source-path` and `version-local-store-index-path`, which add a value on
+//   each call — `lt_downsync` and `lt_get` merge several sources;
+// - the numbers `worker-count`, `remote-worker-count`, `target-chunk-size`,
+//   `target-block-size`, `max-chunks-per-block`, `min-block-usage-percent`;
+// - the booleans (`true`/`false`) `retain-permissions`, `delete-removed`,
+//   `validate`, `cache-target-index`, `verify-chunks`.
+//
+// An unknown name or an unparsable value is `LT_STATUS_INVALID_INPUT`.
+//
+// # Safety
+// `options` came from `lt_options_new`; `name` and `value` are
+// NUL-terminated strings.
+LtStatus lt_options_set(LtOptions *options, const char *name, const char *value);
+
+// Report progress to `callback` with `user_data`; a null callback clears it.
+// Honoured by `lt_downsync`, `lt_get`, `lt_upsync` and `lt_put`.
+//
+// # Safety
+// `options` came from `lt_options_new`. `callback` and `user_data` must be
+// usable from any thread for as long as an operation using these options runs.
+LtStatus lt_options_set_progress_callback(LtOptions *options,
+                                          LtProgressCallback callback,
+                                          void *user_data);
+
+// Poll `callback` with `user_data` every 50 ms while an operation runs, and
+// cancel the operation once it returns `true`; a null callback clears it.
+// Honoured by `lt_downsync`, `lt_get`, `lt_upsync` and `lt_put`, which then
+// return `LT_STATUS_CANCELLED` and leave their target resumable.
+//
+// # Safety
+// As for `lt_options_set_progress_callback`.
+LtStatus lt_options_set_cancel_callback(LtOptions *options,
+                                        LtCancelCallback callback,
+                                        void *user_data);
+
+// Download the version(s) named by `source-path` from `storage-uri` into
+// `target-path`. On success, if `report_json` is not null, it receives the
+// run's report as JSON, to be freed with `lt_string_free`.
+//
+// # Safety
+// `options` came from `lt_options_new`; `report_json` is null or writable.
+LtStatus lt_downsync(const LtOptions *options, char **report_json);
+
+// Download the version named by the get-config JSON at `source-path` (or
+// several, merged). Reports as `lt_downsync` does.
+//
+// # Safety
+// As for `lt_downsync`.
+LtStatus lt_get(const LtOptions *options, char **report_json);
+
+// Publish the folder at `source-path` into `storage-uri`, writing its version
+// index to `target-path`. Reports as `lt_downsync` does.
+//
+// # Safety
+// As for `lt_downsync`.
+LtStatus lt_upsync(const LtOptions *options, char **report_json);
+
+// Publish the folder at `source-path` and write a get-config JSON to
+// `target-path`, deriving the store and index paths from it unless
+// `storage-uri` is set. Reports as `lt_downsync` does.
+//
+// # Safety
+// As for `lt_downsync`.
+LtStatus lt_put(const LtOptions *options, char **report_json);
+
+// Copy the asset `source-path` of the version at `version-index-path` out of
+// `storage-uri` into the file `target-path`. A copy cannot be cancelled: with
+// a cancel callback set it is `LT_STATUS_INVALID_INPUT`.
+//
+// # Safety
+// `options` came from `lt_options_new`.
+LtStatus lt_cp(const LtOptions *options);
+
+// Summarize the version index at `version-index-path` into `out_json`: its
+// hash identifier, target chunk size, and asset and chunk counts and sizes.
+// Free the string with `lt_string_free`.
+//
+// # Safety
+// `options` came from `lt_options_new`; `out_json` is writable.
+LtStatus lt_version_info(const LtOptions *options, char **out_json);
+
+// List the assets of the version index at `version-index-path` into
+// `out_json`: an array of `{"path", "size", "permissions", "is_dir"}`, paths
+// `/`-separated with a trailing `/` on directories. Free the string with
+// `lt_string_free`.
+//
+// # Safety
+// As for `lt_version_info`.
+LtStatus lt_version_assets(const LtOptions *options, char **out_json);
+
+// Summarize the store index at `store-index-path` into `out_json`: its hash
+// identifier, block and chunk counts, and stored chunk sizes with and without
+// duplicates. Free the string with `lt_string_free`.
+//
+// # Safety
+// As for `lt_version_info`.
+LtStatus lt_store_info(const LtOptions *options, char **out_json);
+
+// Check that `storage-uri` holds every chunk the version index at
+// `version-index-path` needs, without downloading anything.
+//
+// # Safety
+// `options` came from `lt_options_new`.
+LtStatus lt_validate_version(const LtOptions *options);
+
+// What went wrong in the most recent `lt_*` call on this thread, cause chain
+// included, or null if it succeeded. Valid until the next `lt_*` call on this
+// thread; do not free.
+const char *lt_last_error_message(void);
+
+// Release a string returned through an `lt_*` out-parameter. Null is ignored.
+//
+// # Safety
+// `s` is null or a string from an `lt_*` out-parameter, not yet freed.
+void lt_string_free(char *s);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* LONGTAIL_CAPI_H */
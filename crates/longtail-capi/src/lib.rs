@@ -0,0 +1,945 @@
+//! A C ABI over the `longtail` facade, for hosts that cannot link Rust — Unreal
+//! editor tools, a C# tool — and until now linked the legacy C library instead.
+//!
+//! The shape is the usual one for a C library:
+//! - an opaque `LtOptions` builder, filled with string key/value pairs whose
+//!   keys are the CLI's long flag names (`lt_options_set`), plus a progress and
+//!   a cancellation callback;
+//! - one blocking call per operation (`lt_downsync`, `lt_get`, `lt_upsync`,
+//!   `lt_put`, `lt_cp`) and per inspection (`lt_version_info`,
+//!   `lt_version_assets`, `lt_store_info`, `lt_validate_version`), each
+//!   returning an `LtStatus` that mirrors [`longtail::ErrorClass`];
+//! - reports and inspection results as JSON strings the caller frees with
+//!   `lt_string_free`, and the failure's full text from
+//!   `lt_last_error_message`.
+//!
+//! Operations run on one process-wide tokio runtime, so concurrent calls from
+//! several host threads share its workers. A panic never crosses the boundary:
+//! it is caught and reported as `LT_STATUS_INTERNAL`.
+//!
+//! `include/longtail_capi.h` is generated from this file by cbindgen; the
+//! `header` test fails when the two disagree and rewrites the header when run
+//! with `LONGTAIL_CAPI_BLESS=1`.
+
+#![deny(unsafe_op_in_unsafe_fn)]
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char, c_void};
+use std::future::Future;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::path::PathBuf;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+
+use longtail::{
+    CancellationToken, CpOptions, DownsyncOptions, ErrorClass, GetOptions, LongtailError, Progress,
+    ProgressSink, PutOptions, S3OptionsArg, UpsyncOptions, ValidateVersionOptions,
+};
+use longtail_core::VersionIndex;
+use serde_json::json;
+
+/// How often a cancellation callback is polled while an operation runs.
+const CANCEL_POLL: Duration = Duration::from_millis(50);
+
+/// The outcome of an `lt_*` call. Every value but `LT_STATUS_OK` is a class of
+/// failure — the same classes, with the same meaning, as the Rust API's
+/// `ErrorClass` — and `lt_last_error_message` has the details.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LtStatus {
+    /// Success.
+    Ok = 0,
+    /// The cancellation callback asked to stop. Not a failure; the target is
+    /// left resumable.
+    Cancelled = 1,
+    /// The named version, block or object is not in the store.
+    NotFound = 2,
+    /// Credentials were rejected.
+    Unauthorized = 3,
+    /// A transport or contention failure expected to clear on its own; safe to
+    /// retry the whole operation.
+    Transient = 4,
+    /// The request itself is wrong: a missing or unknown option, a bad URI, a
+    /// null argument. Retrying will not help.
+    InvalidInput = 5,
+    /// Data read from the store did not decode, verify, or agree with its index.
+    Corrupt = 6,
+    /// A local filesystem failure.
+    Io = 7,
+    /// A bug, or a state that should be unreachable — a caught panic included.
+    Internal = 8,
+}
+
+impl LtStatus {
+    fn of(class: ErrorClass) -> LtStatus {
+        match class {
+            ErrorClass::Cancelled => LtStatus::Cancelled,
+            ErrorClass::NotFound => LtStatus::NotFound,
+            ErrorClass::Unauthorized => LtStatus::Unauthorized,
+            ErrorClass::Transient => LtStatus::Transient,
+            ErrorClass::InvalidInput => LtStatus::InvalidInput,
+            ErrorClass::Corrupt => LtStatus::Corrupt,
+            ErrorClass::Io => LtStatus::Io,
+            ErrorClass::Internal => LtStatus::Internal,
+            // `ErrorClass` is non-exhaustive; a class this table has not met
+            // is reported as a bug rather than guessed at.
+            _ => LtStatus::Internal,
+        }
+    }
+}
+
+/// One progress report. `phase` names the current phase and is valid only for
+/// the duration of the callback. Either dimension is unknown while its total
+/// is 0.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct LtProgress {
+    pub phase: *const c_char,
+    pub done_items: u64,
+    pub total_items: u64,
+    pub done_bytes: u64,
+    pub total_bytes: u64,
+}
+
+/// Receives progress reports. Called from library threads, never from two at
+/// once; it must return promptly.
+pub type LtProgressCallback =
+    Option<unsafe extern "C" fn(user_data: *mut c_void, progress: *const LtProgress)>;
+
+/// Polled while an operation runs; returning `true` cancels it. Called from a
+/// library thread.
+pub type LtCancelCallback = Option<unsafe extern "C" fn(user_data: *mut c_void) -> bool>;
+
+/// A C callback and the pointer it is called with.
+#[derive(Clone, Copy)]
+struct Callback<F> {
+    f: F,
+    user_data: *mut c_void,
+}
+
+// SAFETY: the header requires callbacks, and what their `user_data` points to,
+// to be usable from any thread.
+unsafe impl<F> Send for Callback<F> {}
+// SAFETY: as above.
+unsafe impl<F> Sync for Callback<F> {}
+
+type ProgressFn = unsafe extern "C" fn(*mut c_void, *const LtProgress);
+type CancelFn = unsafe extern "C" fn(*mut c_void) -> bool;
+
+impl Callback<CancelFn> {
+    fn should_cancel(&self) -> bool {
+        // SAFETY: the caller registered this function for this `user_data`,
+        // usable from any thread.
+        unsafe { (self.f)(self.user_data) }
+    }
+}
+
+/// The options for any `lt_*` operation. Opaque to C: made by
+/// `lt_options_new`, filled by `lt_options_set` and the callback setters,
+/// released by `lt_options_free`. Each operation reads the keys it needs and
+/// ignores the rest, so one builder can serve several calls.
+#[derive(Default)]
+pub struct LtOptions {
+    storage_uri: Option<String>,
+    source_paths: Vec<String>,
+    target_path: Option<String>,
+    cache_path: Option<String>,
+    version_index_path: Option<String>,
+    store_index_path: Option<String>,
+    version_local_store_index_paths: Vec<String>,
+    include_filter_regex: Option<String>,
+    exclude_filter_regex: Option<String>,
+    s3_endpoint_resolver_uri: Option<String>,
+    compression_algorithm: Option<String>,
+    hash_algorithm: Option<String>,
+    target_chunk_size: Option<u32>,
+    target_block_size: Option<u32>,
+    max_chunks_per_block: Option<u32>,
+    min_block_usage_percent: Option<u32>,
+    worker_count: usize,
+    remote_worker_count: usize,
+    retain_permissions: Option<bool>,
+    delete_removed: Option<bool>,
+    validate: Option<bool>,
+    cache_target_index: Option<bool>,
+    verify_chunks: Option<bool>,
+    progress: Option<Callback<ProgressFn>>,
+    cancel: Option<Callback<CancelFn>>,
+}
+
+fn parse<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, LongtailError> {
+    value
+        .parse()
+        .map_err(|_| LongtailError::InvalidArgument(format!("`{name}`: cannot parse `{value}`")))
+}
+
+impl LtOptions {
+    fn set(&mut self, name: &str, value: &str) -> Result<(), LongtailError> {
+        let text = || Some(value.to_string());
+        match name {
+            "storage-uri" => self.storage_uri = text(),
+            "source-path" => self.source_paths.push(value.to_string()),
+            "target-path" => self.target_path = text(),
+            "cache-path" => self.cache_path = text(),
+            "version-index-path" => self.version_index_path = text(),
+            "store-index-path" => self.store_index_path = text(),
+            "version-local-store-index-path" => {
+                self.version_local_store_index_paths.push(value.to_string())
+            }
+            "include-filter-regex" => self.include_filter_regex = text(),
+            "exclude-filter-regex" => self.exclude_filter_regex = text(),
+            "s3-endpoint-resolver-uri" => self.s3_endpoint_resolver_uri = text(),
+            "compression-algorithm" => self.compression_algorithm = text(),
+            "hash-algorithm" => self.hash_algorithm = text(),
+            "target-chunk-size" => self.target_chunk_size = Some(parse(name, value)?),
+            "target-block-size" => self.target_block_size = Some(parse(name, value)?),
+            "max-chunks-per-block" => self.max_chunks_per_block = Some(parse(name, value)?),
+            "min-block-usage-percent" => self.min_block_usage_percent = Some(parse(name, value)?),
+            "worker-count" => self.worker_count = parse(name, value)?,
+            "remote-worker-count" => self.remote_worker_count = parse(name, value)?,
+            "retain-permissions" => self.retain_permissions = Some(parse(name, value)?),
+            "delete-removed" => self.delete_removed = Some(parse(name, value)?),
+            "validate" => self.validate = Some(parse(name, value)?),
+            "cache-target-index" => self.cache_target_index = Some(parse(name, value)?),
+            "verify-chunks" => self.verify_chunks = Some(parse(name, value)?),
+            _ => {
+                return Err(LongtailError::InvalidArgument(format!(
+                    "unknown option `{name}`"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn required<'a>(
+        &self,
+        op: &str,
+        name: &str,
+        value: &'a Option<String>,
+    ) -> Result<&'a str, LongtailError> {
+        value
+            .as_deref()
+            .ok_or_else(|| LongtailError::InvalidArgument(format!("{op} needs `{name}`")))
+    }
+
+    /// The one `source-path` an operation that takes a single source needs.
+    fn single_source(&self, op: &str) -> Result<&str, LongtailError> {
+        match self.source_paths.as_slice() {
+            [one] => Ok(one),
+            _ => Err(LongtailError::InvalidArgument(format!(
+                "{op} needs `source-path` set exactly once"
+            ))),
+        }
+    }
+
+    fn s3(&self) -> S3OptionsArg {
+        #[cfg(feature = "s3")]
+        {
+            let mut o = longtail::S3Options::default();
+            if let Some(u) = &self.s3_endpoint_resolver_uri {
+                o.endpoint_url = Some(u.clone());
+            }
+            o
+        }
+    }
+
+    fn progress_sink(&self) -> Option<Arc<dyn ProgressSink>> {
+        self.progress.map(|callback| {
+            Arc::new(CProgress {
+                callback,
+                phase: Mutex::new(CString::default()),
+            }) as Arc<dyn ProgressSink>
+        })
+    }
+
+    fn downsync_options(
+        &self,
+        cancel: &CancellationToken,
+    ) -> Result<DownsyncOptions, LongtailError> {
+        let storage_uri = self.required("lt_downsync", "storage-uri", &self.storage_uri)?;
+        if self.source_paths.is_empty() {
+            return Err(LongtailError::InvalidArgument(
+                "lt_downsync needs `source-path`".into(),
+            ));
+        }
+        let mut o = DownsyncOptions::new(self.source_paths.clone(), storage_uri, "");
+        o.target_path = self.target_path.clone();
+        o.cache_path = self.cache_path.as_ref().map(PathBuf::from);
+        o.version_local_store_index_paths = self.version_local_store_index_paths.clone();
+        o.include_filter_regex = self.include_filter_regex.clone();
+        o.exclude_filter_regex = self.exclude_filter_regex.clone();
+        o.worker_count = self.worker_count;
+        o.remote_worker_count = self.remote_worker_count;
+        o.retain_permissions = self.retain_permissions.unwrap_or(o.retain_permissions);
+        o.delete_removed = self.delete_removed.unwrap_or(o.delete_removed);
+        o.validate = self.validate.unwrap_or(o.validate);
+        o.cache_target_index = self.cache_target_index.unwrap_or(o.cache_target_index);
+        o.verify_chunks = self.verify_chunks.unwrap_or(o.verify_chunks);
+        o.progress = self.progress_sink();
+        o.cancel = Some(cancel.clone());
+        #[cfg(feature = "s3")]
+        {
+            o.s3_options = self.s3();
+        }
+        Ok(o)
+    }
+
+    fn get_options(&self, cancel: &CancellationToken) -> Result<GetOptions, LongtailError> {
+        if self.source_paths.is_empty() {
+            return Err(LongtailError::InvalidArgument(
+                "lt_get needs `source-path`".into(),
+            ));
+        }
+        let mut o = GetOptions::new(self.source_paths.clone(), "");
+        o.target_path = self.target_path.clone();
+        o.cache_path = self.cache_path.as_ref().map(PathBuf::from);
+        o.include_filter_regex = self.include_filter_regex.clone();
+        o.exclude_filter_regex = self.exclude_filter_regex.clone();
+        o.worker_count = self.worker_count;
+        o.remote_worker_count = self.remote_worker_count;
+        o.retain_permissions = self.retain_permissions.unwrap_or(o.retain_permissions);
+        o.delete_removed = self.delete_removed.unwrap_or(o.delete_removed);
+        o.validate = self.validate.unwrap_or(o.validate);
+        o.cache_target_index = self.cache_target_index.unwrap_or(o.cache_target_index);
+        o.verify_chunks = self.verify_chunks.unwrap_or(o.verify_chunks);
+        o.progress = self.progress_sink();
+        o.cancel = Some(cancel.clone());
+        #[cfg(feature = "s3")]
+        {
+            o.s3_options = self.s3();
+        }
+        Ok(o)
+    }
+
+    fn upsync_options(&self, cancel: &CancellationToken) -> Result<UpsyncOptions, LongtailError> {
+        let op = "lt_upsync";
+        let mut o = UpsyncOptions::new(
+            self.single_source(op)?,
+            self.required(op, "storage-uri", &self.storage_uri)?,
+            self.required(op, "target-path", &self.target_path)?,
+        );
+        o.version_local_store_index_path = match self.version_local_store_index_paths.as_slice() {
+            [] => None,
+            [one] => Some(one.clone()),
+            _ => {
+                return Err(LongtailError::InvalidArgument(format!(
+                    "{op} takes at most one `version-local-store-index-path`"
+                )));
+            }
+        };
+        o.include_filter_regex = self.include_filter_regex.clone();
+        o.exclude_filter_regex = self.exclude_filter_regex.clone();
+        o.worker_count = self.worker_count;
+        o.remote_worker_count = self.remote_worker_count;
+        o.target_chunk_size = self.target_chunk_size.unwrap_or(o.target_chunk_size);
+        o.target_block_size = self.target_block_size.unwrap_or(o.target_block_size);
+        o.max_chunks_per_block = self.max_chunks_per_block.unwrap_or(o.max_chunks_per_block);
+        o.min_block_usage_percent = self
+            .min_block_usage_percent
+            .unwrap_or(o.min_block_usage_percent);
+        if let Some(c) = &self.compression_algorithm {
+            o.compression_algorithm = c.clone();
+        }
+        if let Some(h) = &self.hash_algorithm {
+            o.hash_algorithm = h.clone();
+        }
+        o.progress = self.progress_sink();
+        o.cancel = Some(cancel.clone());
+        #[cfg(feature = "s3")]
+        {
+            o.s3_options = self.s3();
+        }
+        Ok(o)
+    }
+
+    fn put_options(&self, cancel: &CancellationToken) -> Result<PutOptions, LongtailError> {
+        let op = "lt_put";
+        let mut o = PutOptions::new(
+            self.required(op, "target-path", &self.target_path)?,
+            self.single_source(op)?,
+        );
+        o.storage_uri = self.storage_uri.clone();
+        o.s3_endpoint_resolver_uri = self.s3_endpoint_resolver_uri.clone();
+        o.include_filter_regex = self.include_filter_regex.clone();
+        o.exclude_filter_regex = self.exclude_filter_regex.clone();
+        o.worker_count = self.worker_count;
+        o.remote_worker_count = self.remote_worker_count;
+        o.target_chunk_size = self.target_chunk_size.unwrap_or(o.target_chunk_size);
+        o.target_block_size = self.target_block_size.unwrap_or(o.target_block_size);
+        o.max_chunks_per_block = self.max_chunks_per_block.unwrap_or(o.max_chunks_per_block);
+        o.min_block_usage_percent = self
+            .min_block_usage_percent
+            .unwrap_or(o.min_block_usage_percent);
+        if let Some(c) = &self.compression_algorithm {
+            o.compression_algorithm = c.clone();
+        }
+        if let Some(h) = &self.hash_algorithm {
+            o.hash_algorithm = h.clone();
+        }
+        o.progress = self.progress_sink();
+        o.cancel = Some(cancel.clone());
+        #[cfg(feature = "s3")]
+        {
+            o.s3_options = self.s3();
+        }
+        Ok(o)
+    }
+
+    fn cp_options(&self) -> Result<CpOptions, LongtailError> {
+        let op = "lt_cp";
+        // Refused rather than ignored: a host that set one expects it to work.
+        if self.cancel.is_some() {
+            return Err(LongtailError::InvalidArgument(
+                "lt_cp cannot be cancelled; clear the cancel callback".into(),
+            ));
+        }
+        let mut o = CpOptions::new(
+            self.required(op, "storage-uri", &self.storage_uri)?,
+            self.required(op, "version-index-path", &self.version_index_path)?,
+            self.single_source(op)?,
+            self.required(op, "target-path", &self.target_path)?,
+        );
+        o.cache_path = self.cache_path.as_ref().map(PathBuf::from);
+        o.remote_worker_count = self.remote_worker_count;
+        #[cfg(feature = "s3")]
+        {
+            o.s3_options = self.s3();
+        }
+        Ok(o)
+    }
+}
+
+/// The [`ProgressSink`] behind a progress callback. The lock serializes the
+/// callback as well as guarding the phase name.
+struct CProgress {
+    callback: Callback<ProgressFn>,
+    phase: Mutex<CString>,
+}
+
+impl CProgress {
+    fn emit(&self, phase: &CStr, p: Progress) {
+        let report = LtProgress {
+            phase: phase.as_ptr(),
+            done_items: p.done_items,
+            total_items: p.total_items,
+            done_bytes: p.done_bytes,
+            total_bytes: p.total_bytes,
+        };
+        // SAFETY: the caller registered this function for this `user_data`,
+        // and `report` (with the phase it points to) outlives the call.
+        unsafe { (self.callback.f)(self.callback.user_data, &report) }
+    }
+}
+
+impl ProgressSink for CProgress {
+    fn on_progress(&self, p: Progress) {
+        let phase = self.phase.lock().unwrap_or_else(|e| e.into_inner());
+        self.emit(&phase, p);
+    }
+
+    fn on_phase(&self, phase: &str) {
+        let mut current = self.phase.lock().unwrap_or_else(|e| e.into_inner());
+        *current = CString::new(phase).unwrap_or_default();
+        self.emit(&current, Progress::default());
+    }
+}
+
+static RUNTIME: LazyLock<Result<tokio::runtime::Runtime, String>> = LazyLock::new(|| {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .thread_name("longtail-capi")
+        .build()
+        .map_err(|e| format!("failed to build tokio runtime: {e}"))
+});
+
+/// Run `op` to completion on the shared runtime, polling the options'
+/// cancellation callback (if any) into `cancel` meanwhile. The poller has
+/// stopped by the time this returns, so the callback is never called after the
+/// `lt_*` call that registered it is over.
+fn block_on<T>(
+    opts: &LtOptions,
+    cancel: CancellationToken,
+    op: impl Future<Output = Result<T, LongtailError>>,
+) -> Result<T, LongtailError> {
+    let runtime = RUNTIME
+        .as_ref()
+        .map_err(|e| LongtailError::Internal(e.clone()))?;
+    let poller = opts.cancel.map(|callback| {
+        let cancel = cancel.clone();
+        runtime.spawn(async move {
+            loop {
+                if callback.should_cancel() {
+                    cancel.cancel();
+                    return;
+                }
+                tokio::time::sleep(CANCEL_POLL).await;
+            }
+        })
+    });
+    let result = runtime.block_on(op);
+    if let Some(poller) = poller {
+        poller.abort();
+        let _ = runtime.block_on(poller);
+    }
+    result
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(message));
+}
+
+/// Run one `lt_*` call: record its failure for `lt_last_error_message` and
+/// keep a panic from unwinding into C.
+fn guard(f: impl FnOnce() -> Result<(), LongtailError>) -> LtStatus {
+    LAST_ERROR.with(|e| *e.borrow_mut() = None);
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => LtStatus::Ok,
+        Ok(Err(e)) => {
+            set_last_error(e.full_chain());
+            LtStatus::of(e.class())
+        }
+        Err(panic) => {
+            let what = panic
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".into());
+            set_last_error(format!("internal error: panic: {what}"));
+            LtStatus::Internal
+        }
+    }
+}
+
+fn null_argument(what: &str) -> LongtailError {
+    LongtailError::InvalidArgument(format!("`{what}` is null"))
+}
+
+/// # Safety
+/// `p` is null or a NUL-terminated string valid for `'a`.
+unsafe fn str_arg<'a>(p: *const c_char, what: &str) -> Result<&'a str, LongtailError> {
+    if p.is_null() {
+        return Err(null_argument(what));
+    }
+    // SAFETY: non-null, and NUL-terminated and live per the contract.
+    unsafe { CStr::from_ptr(p) }
+        .to_str()
+        .map_err(|_| LongtailError::InvalidArgument(format!("`{what}` is not UTF-8")))
+}
+
+/// # Safety
+/// `p` is null or a live pointer from `lt_options_new`.
+unsafe fn options_arg<'a>(p: *const LtOptions) -> Result<&'a LtOptions, LongtailError> {
+    // SAFETY: null or live per the contract.
+    unsafe { p.as_ref() }.ok_or_else(|| null_argument("options"))
+}
+
+/// # Safety
+/// `p` is null or a live pointer from `lt_options_new`, not used elsewhere
+/// for the duration of the call.
+unsafe fn options_mut<'a>(p: *mut LtOptions) -> Result<&'a mut LtOptions, LongtailError> {
+    // SAFETY: null or live and unaliased per the contract.
+    unsafe { p.as_mut() }.ok_or_else(|| null_argument("options"))
+}
+
+/// Hand `value` to the caller as JSON through `out`, if `out` is not null.
+///
+/// # Safety
+/// `out` is null or valid for a pointer write.
+unsafe fn write_json(
+    out: *mut *mut c_char,
+    value: &impl serde::Serialize,
+) -> Result<(), LongtailError> {
+    if out.is_null() {
+        return Ok(());
+    }
+    let text = serde_json::to_string(value)
+        .map_err(|e| LongtailError::Internal(format!("serializing a report: {e}")))?;
+    let text = CString::new(text)
+        .map_err(|_| LongtailError::Internal("a report contains a NUL byte".into()))?;
+    // SAFETY: non-null and writable per the contract.
+    unsafe { *out = text.into_raw() };
+    Ok(())
+}
+
+/// Hand `value` to the caller through `out`, which must not be null.
+///
+/// # Safety
+/// As for [`write_json`].
+unsafe fn write_required_json(
+    out: *mut *mut c_char,
+    value: &impl serde::Serialize,
+) -> Result<(), LongtailError> {
+    if out.is_null() {
+        return Err(null_argument("out_json"));
+    }
+    // SAFETY: forwarded contract.
+    unsafe { write_json(out, value) }
+}
+
+/// The library version, e.g. `"0.1.0"`. Static; do not free.
+#[unsafe(no_mangle)]
+pub extern "C" fn lt_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast()
+}
+
+/// A new, empty options builder. Free it with `lt_options_free`.
+#[unsafe(no_mangle)]
+pub extern "C" fn lt_options_new() -> *mut LtOptions {
+    Box::into_raw(Box::default())
+}
+
+/// Release an options builder. Null is ignored.
+///
+/// # Safety
+/// `options` is null or came from `lt_options_new` and has not been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn lt_options_free(options: *mut LtOptions) {
+    if !options.is_null() {
+        // SAFETY: from `lt_options_new`, freed once, per the contract.
+        drop(unsafe { Box::from_raw(options) });
+    }
+}
+
+/// Set option `name` to `value`; both are UTF-8. Names are the CLI's long flag
+/// names:
+///
+/// - `storage-uri`, `target-path`, `cache-path`, `version-index-path`,
+///   `store-index-path`, `include-filter-regex`, `exclude-filter-regex`,
+///   `s3-endpoint-resolver-uri`, `compression-algorithm`, `hash-algorithm`;
+/// - `source-path` and `version-local-store-index-path`, which add a value on
+///   each call — `lt_downsync` and `lt_get` merge several sources;
+/// - the numbers `worker-count`, `remote-worker-count`, `target-chunk-size`,
+///   `target-block-size`, `max-chunks-per-block`, `min-block-usage-percent`;
+/// - the booleans (`true`/`false`) `retain-permissions`, `delete-removed`,
+///   `validate`, `cache-target-index`, `verify-chunks`.
+///
+/// An unknown name or an unparsable value is `LT_STATUS_INVALID_INPUT`.
+///
+/// # Safety
+/// `options` came from `lt_options_new`; `name` and `value` are
+/// NUL-terminated strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn lt_options_set(
+    options: *mut LtOptions,
+    name: *const c_char,
+    value: *const c_char,
+) -> LtStatus {
+    guard(|| {
+        // SAFETY: forwarded contract.
+        let (options, name, value) = unsafe {
+            (
+                options_mut(options)?,
+                str_arg(name, "name")?,
+                str_arg(value, "value")?,
+            )
+        };
+        options.set(name, value)
+    })
+}
+
+/// Report progress to `callback` with `user_data`; a null callback clears it.
+/// Honoured by `lt_downsync`, `lt_get`, `lt_upsync` and `lt_put`.
+///
+/// # Safety
+/// `options` came from `lt_options_new`. `callback` and `user_data` must be
+/// usable from any thread for as long as an operation using these options runs.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn lt_options_set_progress_callback(
+    options: *mut LtOptions,
+    callback: LtProgressCallback,
+    user_data: *mut c_void,
+) -> LtStatus {
+    guard(|| {
+        // SAFETY: forwarded contract.
+        let options = unsafe { options_mut(options)? };
+        options.progress = callback.map(|f| Callback { f, user_data });
+        Ok(())
+    })
+}
+
+/// Poll `callback` with `user_data` every 50 ms while an operation runs, and
+/// cancel the operation once it returns `true`; a null callback clears it.
+/// Honoured by `lt_downsync`, `lt_get`, `lt_upsync` and `lt_put`, which then
+/// return `LT_STATUS_CANCELLED` and leave their target resumable.
+///
+/// # Safety
+/// As for `lt_options_set_progress_callback`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn lt_options_set_cancel_callback(
+    options: *mut LtOptions,
+    callback: LtCancelCallback,
+    user_data: *mut c_void,
+) -> LtStatus {
+    guard(|| {
+        // SAFETY: forwarded contract.
+        let options = unsafe { options_mut(options)? };
+        options.cancel = callback.map(|f| Callback { f, user_data });
+        Ok(())
+    })
+}
+
+/// Download the version(s) named by `source-path` from `storage-uri` into
+/// `target-path`. On success, if `report_json` is not null, it receives the
+/// run's report as JSON, to be freed with `lt_string_free`.
+///
+/// # Safety
+/// `options` came from `lt_options_new`; `report_json` is null or writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn lt_downsync(
+    options: *const LtOptions,
+    report_json: *mut *mut c_char,
+) -> LtStatus {
+    guard(|| {
+        // SAFETY: forwarded contract.
+        let options = unsafe { options_arg(options)? };
+        let cancel = CancellationToken::new();
+        let opts = options.downsync_options(&cancel)?;
+        let report = block_on(options, cancel, longtail::downsync(opts))?;
+        // SAFETY: forwarded contract.
+        unsafe { write_json(report_json, &report) }
+    })
+}
+
+/// Download the version named by the get-config JSON at `source-path` (or
+/// several, merged). Reports as `lt_downsync` does.
+///
+/// # Safety
+/// As for `lt_downsync`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn lt_get(
+    options: *const LtOptions,
+    report_json: *mut *mut c_char,
+) -> LtStatus {
+    guard(|| {
+        // SAFETY: forwarded contract.
+        let options = unsafe { options_arg(options)? };
+        let cancel = CancellationToken::new();
+        let opts = options.get_options(&cancel)?;
+        let report = block_on(options, cancel, longtail::get(opts))?;
+        // SAFETY: forwarded contract.
+        unsafe { write_json(report_json, &report) }
+    })
+}
+
+/// Publish the folder at `source-path` into `storage-uri`, writing its version
+/// index to `target-path`. Reports as `lt_downsync` does.
+///
+/// # Safety
+/// As for `lt_downsync`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn lt_upsync(
+    options: *const LtOptions,
+    report_json: *mut *mut c_char,
+) -> LtStatus {
+    guard(|| {
+        // SAFETY: forwarded contract.
+        let options = unsafe { options_arg(options)? };
+        let cancel = CancellationToken::new();
+        let opts = options.upsync_options(&cancel)?;
+        let report = block_on(options, cancel, longtail::upsync(opts))?;
+        // SAFETY: forwarded contract.
+        unsafe { write_json(report_json, &report) }
+    })
+}
+
+/// Publish the folder at `source-path` and write a get-config JSON to
+/// `target-path`, deriving the store and index paths from it unless
+/// `storage-uri` is set. Reports as `lt_downsync` does.
+///
+/// # Safety
+/// As for `lt_downsync`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn lt_put(
+    options: *const LtOptions,
+    report_json: *mut *mut c_char,
+) -> LtStatus {
+    guard(|| {
+        // SAFETY: forwarded contract.
+        let options = unsafe { options_arg(options)? };
+        let cancel = CancellationToken::new();
+        let opts = options.put_options(&cancel)?;
+        let report = block_on(options, cancel, longtail::put(opts))?;
+        // SAFETY: forwarded contract.
+        unsafe { write_json(report_json, &report) }
+    })
+}
+
+/// Copy the asset `source-path` of the version at `version-index-path` out of
+/// `storage-uri` into the file `target-path`. A copy cannot be cancelled: with
+/// a cancel callback set it is `LT_STATUS_INVALID_INPUT`.
+///
+/// # Safety
+/// `options` came from `lt_options_new`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn lt_cp(options: *const LtOptions) -> LtStatus {
+    guard(|| {
+        // SAFETY: forwarded contract.
+        let options = unsafe { options_arg(options)? };
+        let opts = options.cp_options()?;
+        block_on(options, CancellationToken::new(), longtail::cp(opts))
+    })
+}
+
+async fn read_version(options: &LtOptions, op: &str) -> Result<VersionIndex, LongtailError> {
+    let path = options.required(op, "version-index-path", &options.version_index_path)?;
+    longtail::read_version_index_from_uri(path, &options.s3()).await
+}
+
+/// Summarize the version index at `version-index-path` into `out_json`: its
+/// hash identifier, target chunk size, and asset and chunk counts and sizes.
+/// Free the string with `lt_string_free`.
+///
+/// # Safety
+/// `options` came from `lt_options_new`; `out_json` is writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn lt_version_info(
+    options: *const LtOptions,
+    out_json: *mut *mut c_char,
+) -> LtStatus {
+    guard(|| {
+        // SAFETY: forwarded contract.
+        let options = unsafe { options_arg(options)? };
+        let vi = block_on(
+            options,
+            CancellationToken::new(),
+            read_version(options, "lt_version_info"),
+        )?;
+        let info = json!({
+            "version": longtail_core::VERSION_INDEX_VERSION,
+            "hash_identifier": vi.hash_identifier,
+            "target_chunk_size": vi.target_chunk_size,
+            "asset_count": vi.asset_count(),
+            "asset_size": vi.asset_sizes.iter().sum::<u64>(),
+            "chunk_count": vi.chunk_count(),
+            "chunk_size": vi.chunk_sizes.iter().map(|&s| s as u64).sum::<u64>(),
+        });
+        // SAFETY: forwarded contract.
+        unsafe { write_required_json(out_json, &info) }
+    })
+}
+
+/// List the assets of the version index at `version-index-path` into
+/// `out_json`: an array of `{"path", "size", "permissions", "is_dir"}`, paths
+/// `/`-separated with a trailing `/` on directories. Free the string with
+/// `lt_string_free`.
+///
+/// # Safety
+/// As for `lt_version_info`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn lt_version_assets(
+    options: *const LtOptions,
+    out_json: *mut *mut c_char,
+) -> LtStatus {
+    guard(|| {
+        // SAFETY: forwarded contract.
+        let options = unsafe { options_arg(options)? };
+        let vi = block_on(
+            options,
+            CancellationToken::new(),
+            read_version(options, "lt_version_assets"),
+        )?;
+        let assets = (0..vi.asset_count() as usize)
+            .map(|i| {
+                Ok(json!({
+                    "path": vi.path(i)?,
+                    "size": vi.asset_sizes[i],
+                    "permissions": vi.permissions[i].0,
+                    "is_dir": vi.is_dir(i)?,
+                }))
+            })
+            .collect::<Result<Vec<_>, longtail_core::FormatError>>()?;
+        // SAFETY: forwarded contract.
+        unsafe { write_required_json(out_json, &assets) }
+    })
+}
+
+/// Summarize the store index at `store-index-path` into `out_json`: its hash
+/// identifier, block and chunk counts, and stored chunk sizes with and without
+/// duplicates. Free the string with `lt_string_free`.
+///
+/// # Safety
+/// As for `lt_version_info`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn lt_store_info(
+    options: *const LtOptions,
+    out_json: *mut *mut c_char,
+) -> LtStatus {
+    guard(|| {
+        // SAFETY: forwarded contract.
+        let options = unsafe { options_arg(options)? };
+        let path = options.required(
+            "lt_store_info",
+            "store-index-path",
+            &options.store_index_path,
+        )?;
+        let si = block_on(
+            options,
+            CancellationToken::new(),
+            longtail::read_store_index_from_uri(path, &options.s3()),
+        )?;
+        let stats = longtail::store_index_stats(&si);
+        let info = json!({
+            "version": stats.version,
+            "hash_identifier": stats.hash_identifier,
+            "block_count": stats.block_count,
+            "chunk_count": stats.chunk_count,
+            "stored_chunks_size": stats.stored_chunks_size,
+            "unique_stored_chunks_size": stats.unique_stored_chunks_size,
+        });
+        // SAFETY: forwarded contract.
+        unsafe { write_required_json(out_json, &info) }
+    })
+}
+
+/// Check that `storage-uri` holds every chunk the version index at
+/// `version-index-path` needs, without downloading anything.
+///
+/// # Safety
+/// `options` came from `lt_options_new`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn lt_validate_version(options: *const LtOptions) -> LtStatus {
+    guard(|| {
+        // SAFETY: forwarded contract.
+        let options = unsafe { options_arg(options)? };
+        let op = "lt_validate_version";
+        let mut opts = ValidateVersionOptions::new(
+            options.required(op, "storage-uri", &options.storage_uri)?,
+            options.required(op, "version-index-path", &options.version_index_path)?,
+        );
+        opts.remote_worker_count = options.remote_worker_count;
+        #[cfg(feature = "s3")]
+        {
+            opts.s3_options = options.s3();
+        }
+        block_on(
+            options,
+            CancellationToken::new(),
+            longtail::validate_version(opts),
+        )
+    })
+}
+
+/// What went wrong in the most recent `lt_*` call on this thread, cause chain
+/// included, or null if it succeeded. Valid until the next `lt_*` call on this
+/// thread; do not free.
+#[unsafe(no_mangle)]
+pub extern "C" fn lt_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(std::ptr::null(), |m| m.as_ptr()))
+}
+
+/// Release a string returned through an `lt_*` out-parameter. Null is ignored.
+///
+/// # Safety
+/// `s` is null or a string from an `lt_*` out-parameter, not yet freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn lt_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        // SAFETY: from `CString::into_raw`, freed once, per the contract.
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
@@ -0,0 +1,209 @@
+/* Drives longtail-capi through its C header, the way a C host would.
+ *
+ * usage: harness <storage-uri> <v1.lvi> <v2.lvi> <work-dir>
+ *
+ * Downsyncs v1 into <work-dir>/target, copies one asset to
+ * <work-dir>/copied.txt, republishes the tree with upsync and put, and gets
+ * the put back into <work-dir>/got, for the Rust side to compare. Checks the
+ * error, inspection and cancellation paths along the way. Prints one line per
+ * failed check and exits non-zero if there was any. */
+
+#define _POSIX_C_SOURCE 200809L
+
+#include <stdatomic.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <time.h>
+
+#include "longtail_capi.h"
+
+static int failures = 0;
+
+#define CHECK(cond, ...)                                                       \
+    do {                                                                       \
+        if (!(cond)) {                                                         \
+            fprintf(stderr, "%s:%d: check failed: %s: ", __FILE__, __LINE__,   \
+                    #cond);                                                    \
+            fprintf(stderr, __VA_ARGS__);                                      \
+            fprintf(stderr, " (last error: %s)\n",                             \
+                    lt_last_error_message() ? lt_last_error_message() : "none"); \
+            failures++;                                                        \
+        }                                                                      \
+    } while (0)
+
+struct progress_seen {
+    atomic_int calls;
+    atomic_int phases;
+};
+
+static void on_progress(void *user_data, const LtProgress *progress) {
+    struct progress_seen *seen = user_data;
+    atomic_fetch_add(&seen->calls, 1);
+    if (progress->phase != NULL && progress->phase[0] != '\0' &&
+        progress->done_items == 0 && progress->done_bytes == 0) {
+        atomic_fetch_add(&seen->phases, 1);
+    }
+}
+
+/* Holds the first phase report until the cancel callback has been polled,
+ * so the cancellation lands before the operation can finish. The wait sleeps
+ * and gives up after a few seconds: it blocks a runtime worker, and on a
+ * single-CPU machine the poller may need that worker. Giving up fails the
+ * cancellation check instead of hanging the harness. */
+static atomic_bool cancel_polled = false;
+
+static void wait_for_cancel_poll(void *user_data, const LtProgress *progress) {
+    (void)user_data;
+    (void)progress;
+    const struct timespec pause = {0, 1000000};
+    for (int waited_ms = 0; waited_ms < 5000 && !atomic_load(&cancel_polled); waited_ms++) {
+        nanosleep(&pause, NULL);
+    }
+}
+
+static bool cancel_now(void *user_data) {
+    (void)user_data;
+    atomic_store(&cancel_polled, true);
+    return true;
+}
+
+static char *join(char *buf, size_t len, const char *dir, const char *name) {
+    snprintf(buf, len, "%s/%s", dir, name);
+    return buf;
+}
+
+int main(int argc, char **argv) {
+    if (argc != 5) {
+        fprintf(stderr, "usage: %s <storage-uri> <v1.lvi> <v2.lvi> <work-dir>\n", argv[0]);
+        return 2;
+    }
+    const char *store = argv[1], *v1 = argv[2], *v2 = argv[3], *work = argv[4];
+    char target[4096], copied[4096], cancelled[4096];
+    char upstore[4096], uplvi[4096], config[4096], got[4096];
+    join(target, sizeof target, work, "target");
+    join(copied, sizeof copied, work, "copied.txt");
+    join(cancelled, sizeof cancelled, work, "cancelled");
+    join(upstore, sizeof upstore, work, "upsynced/store");
+    join(uplvi, sizeof uplvi, work, "upsynced/v1.lvi");
+    join(config, sizeof config, work, "put/v1.json");
+    join(got, sizeof got, work, "got");
+
+    /* Downsynced permissions are applied through the umask; pin it so the
+     * tree matches the fixture manifest. */
+    umask(022);
+
+    CHECK(lt_version() != NULL && lt_version()[0] != '\0', "no version string");
+
+    /* An unknown option is the caller's mistake, and says which option. */
+    LtOptions *opts = lt_options_new();
+    CHECK(lt_options_set(opts, "no-such-option", "1") == LT_STATUS_INVALID_INPUT,
+          "unknown option accepted");
+    CHECK(lt_last_error_message() != NULL &&
+              strstr(lt_last_error_message(), "no-such-option") != NULL,
+          "error message does not name the option");
+    CHECK(lt_options_set(opts, "worker-count", "many") == LT_STATUS_INVALID_INPUT,
+          "unparsable number accepted");
+    /* So is an operation missing a required option. */
+    CHECK(lt_downsync(opts, NULL) == LT_STATUS_INVALID_INPUT, "downsync without options ran");
+
+    /* Downsync, reporting progress and the run's report. */
+    struct progress_seen seen = {0, 0};
+    CHECK(lt_options_set(opts, "storage-uri", store) == LT_STATUS_OK, "storage-uri");
+    CHECK(lt_options_set(opts, "source-path", v1) == LT_STATUS_OK, "source-path");
+    CHECK(lt_options_set(opts, "target-path", target) == LT_STATUS_OK, "target-path");
+    /* Keep the index cache out of the tree the test compares. */
+    CHECK(lt_options_set(opts, "cache-target-index", "false") == LT_STATUS_OK,
+          "cache-target-index");
+    CHECK(lt_options_set_progress_callback(opts, on_progress, &seen) == LT_STATUS_OK,
+          "progress callback");
+    char *report = NULL;
+    LtStatus status = lt_downsync(opts, &report);
+    CHECK(status == LT_STATUS_OK, "downsync returned %d", (int)status);
+    CHECK(lt_last_error_message() == NULL, "error message left after success");
+    CHECK(report != NULL && report[0] == '{', "no JSON report");
+    CHECK(atomic_load(&seen.calls) > 0, "progress callback never called");
+    CHECK(atomic_load(&seen.phases) > 0, "no phase was announced");
+    lt_string_free(report);
+    lt_options_free(opts);
+
+    /* Inspect the version that was just downsynced. */
+    opts = lt_options_new();
+    lt_options_set(opts, "storage-uri", store);
+    lt_options_set(opts, "version-index-path", v1);
+    char *json = NULL;
+    CHECK(lt_version_info(opts, &json) == LT_STATUS_OK, "version info");
+    CHECK(json != NULL && strstr(json, "\"asset_count\"") != NULL, "version info: %s",
+          json ? json : "(null)");
+    lt_string_free(json);
+    json = NULL;
+    CHECK(lt_version_assets(opts, &json) == LT_STATUS_OK, "version assets");
+    CHECK(json != NULL && strstr(json, "\"abitoftext.txt\"") != NULL, "version assets: %s",
+          json ? json : "(null)");
+    lt_string_free(json);
+    CHECK(lt_version_info(opts, NULL) == LT_STATUS_INVALID_INPUT, "null out-parameter accepted");
+    CHECK(lt_validate_version(opts) == LT_STATUS_OK, "validate version");
+
+    /* Copy one asset out of the store. */
+    lt_options_set(opts, "source-path", "abitoftext.txt");
+    lt_options_set(opts, "target-path", copied);
+    CHECK(lt_cp(opts) == LT_STATUS_OK, "cp");
+    /* A copy cannot be cancelled, so a cancel callback is refused. */
+    lt_options_set_cancel_callback(opts, cancel_now, NULL);
+    CHECK(lt_cp(opts) == LT_STATUS_INVALID_INPUT, "cp accepted a cancel callback");
+    lt_options_free(opts);
+
+    /* Publish the downsynced tree into a fresh store. */
+    opts = lt_options_new();
+    lt_options_set(opts, "storage-uri", upstore);
+    lt_options_set(opts, "source-path", target);
+    lt_options_set(opts, "target-path", uplvi);
+    report = NULL;
+    CHECK(lt_upsync(opts, &report) == LT_STATUS_OK, "upsync");
+    CHECK(report != NULL && report[0] == '{', "no upsync report");
+    lt_string_free(report);
+    lt_options_free(opts);
+    opts = lt_options_new();
+    lt_options_set(opts, "storage-uri", upstore);
+    lt_options_set(opts, "version-index-path", uplvi);
+    CHECK(lt_validate_version(opts) == LT_STATUS_OK, "validate the upsynced version");
+    lt_options_free(opts);
+
+    /* Publish it again as a get-config, and get it back from there. */
+    opts = lt_options_new();
+    lt_options_set(opts, "source-path", target);
+    lt_options_set(opts, "target-path", config);
+    report = NULL;
+    CHECK(lt_put(opts, &report) == LT_STATUS_OK, "put");
+    CHECK(report != NULL && report[0] == '{', "no put report");
+    lt_string_free(report);
+    lt_options_free(opts);
+    opts = lt_options_new();
+    lt_options_set(opts, "source-path", config);
+    lt_options_set(opts, "target-path", got);
+    lt_options_set(opts, "cache-target-index", "false");
+    report = NULL;
+    CHECK(lt_get(opts, &report) == LT_STATUS_OK, "get");
+    CHECK(report != NULL && report[0] == '{', "no get report");
+    lt_string_free(report);
+    lt_options_free(opts);
+
+    /* A cancel callback that says stop makes the operation report
+     * cancellation rather than failure. */
+    opts = lt_options_new();
+    lt_options_set(opts, "storage-uri", store);
+    lt_options_set(opts, "source-path", v2);
+    lt_options_set(opts, "target-path", cancelled);
+    lt_options_set_progress_callback(opts, wait_for_cancel_poll, NULL);
+    lt_options_set_cancel_callback(opts, cancel_now, NULL);
+    status = lt_downsync(opts, NULL);
+    CHECK(status == LT_STATUS_CANCELLED, "cancelled downsync returned %d", (int)status);
+    lt_options_free(opts);
+
+    /* Null handles are refused, not dereferenced. */
+    CHECK(lt_downsync(NULL, NULL) == LT_STATUS_INVALID_INPUT, "null options accepted");
+    lt_options_free(NULL);
+    lt_string_free(NULL);
+
+    return failures == 0 ? 0 : 1;
+}
@@ -0,0 +1,80 @@
+//! Builds `tests/c/harness.c` against the checked-in header and the cdylib,
+//! runs it, and checks what it left on disk. This is the only test that goes
+//! through the C ABI the way a host does: a separate process, a C compiler,
+//! the dynamic linker.
+#![cfg(unix)]
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use longtail_testkit::paths::fixtures_dir;
+use longtail_testkit::tree_manifest::TreeManifest;
+
+/// `target/<profile>`, where cargo put the cdylib next to this test's `deps/`.
+fn library_dir() -> PathBuf {
+    let exe = std::env::current_exe().unwrap();
+    exe.parent().and_then(Path::parent).unwrap().to_path_buf()
+}
+
+#[test]
+fn a_c_host_runs_every_operation_and_cancels() {
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let lib_dir = library_dir();
+    assert!(
+        lib_dir.join("liblongtail_capi.so").exists()
+            || lib_dir.join("liblongtail_capi.dylib").exists(),
+        "no cdylib in {}",
+        lib_dir.display()
+    );
+    let work = tempfile::tempdir().unwrap();
+    let harness = work.path().join("harness");
+
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".into());
+    let compiled = Command::new(&cc)
+        .arg("-std=c11")
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-I")
+        .arg(crate_dir.join("include"))
+        .arg(crate_dir.join("tests/c/harness.c"))
+        .arg("-o")
+        .arg(&harness)
+        .arg("-L")
+        .arg(&lib_dir)
+        .arg("-llongtail_capi")
+        .status()
+        .unwrap_or_else(|e| panic!("running `{cc}`: {e}"));
+    assert!(compiled.success(), "compiling the C harness failed");
+
+    let stores = fixtures_dir().join("stores/default");
+    let output = Command::new(&harness)
+        .arg(stores.join("store"))
+        .arg(stores.join("chain-v1.lvi"))
+        .arg(stores.join("chain-v2.lvi"))
+        .arg(work.path())
+        .env("LD_LIBRARY_PATH", &lib_dir)
+        .env("DYLD_LIBRARY_PATH", &lib_dir)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "the C harness reported failures:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let manifest = fixtures_dir().join("manifests/chain-v1.json");
+    let manifest = TreeManifest::from_json(&std::fs::read_to_string(manifest).unwrap()).unwrap();
+    TreeManifest::capture(&work.path().join("target"))
+        .unwrap()
+        .compare(&manifest, false)
+        .expect("the tree downsynced through the C ABI matches the manifest");
+    TreeManifest::capture(&work.path().join("got"))
+        .unwrap()
+        .compare(&manifest, false)
+        .expect("the tree put and got back through the C ABI matches the manifest");
+    assert_eq!(
+        std::fs::read(work.path().join("copied.txt")).unwrap(),
+        std::fs::read(work.path().join("target/abitoftext.txt")).unwrap(),
+        "lt_cp copied the asset's bytes"
+    );
+}
@@ -0,0 +1,33 @@
+//! The checked-in C header must be what cbindgen generates from the crate
+//! today, so a signature change cannot ship with a stale header.
+//!
+//! Run with `LONGTAIL_CAPI_BLESS=1` to rewrite the header instead of comparing.
+
+use std::path::Path;
+
+#[test]
+fn checked_in_header_matches_the_code() {
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml")).unwrap();
+    let mut generated = Vec::new();
+    cbindgen::Builder::new()
+        .with_crate(crate_dir)
+        .with_config(config)
+        .generate()
+        .expect("cbindgen generates the header")
+        .write(&mut generated);
+    let generated = String::from_utf8(generated).unwrap();
+
+    let header = crate_dir.join("include/longtail_capi.h");
+    if std::env::var_os("LONGTAIL_CAPI_BLESS").is_some() {
+        std::fs::write(&header, &generated).unwrap();
+        return;
+    }
+    let checked_in = std::fs::read_to_string(&header).unwrap_or_default();
+    assert!(
+        checked_in == generated,
+        "{} is stale; regenerate it with \
+         `LONGTAIL_CAPI_BLESS=1 cargo test -p longtail-capi --test header`",
+        header.display()
+    );
+}
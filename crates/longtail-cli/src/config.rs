@@ -0,0 +1,440 @@
+//! Config-file profiles: flag defaults read from TOML, so a pipeline stops
+//! repeating `--storage-uri`, endpoints, cache paths and worker counts on every
+//! invocation.
+//!
+//! ```toml
+//! [profiles.default]
+//! storage-uri = "s3://builds/store"
+//! s3-region = "eu-north-1"
+//! s3-force-path-style = true
+//! worker-count = 8
+//!
+//! [profiles.default.downsync]
+//! cache-path = "/var/cache/longtail"
+//! source-paths = ["s3://builds/v1.lvi", "s3://builds/v2.lvi"]
+//! ```
+//!
+//! Keys are long flag names. A profile's top-level keys apply to every
+//! subcommand that has the flag; a table named after a subcommand applies to
+//! that subcommand only and wins over the top level. A flag typed on the command
+//! line, or its `no-` counterpart, wins over both; a switch typed as
+//! `--flag=false` turns off one a profile turns on.
+//!
+//! The profile is merged by adding its flags to argv before clap sees it, so
+//! every value is parsed, validated and defaulted exactly as if it had been
+//! typed, and a flag added to a subcommand is configurable with no change here.
+
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+use clap::{Arg, Command};
+use serde::Serialize;
+
+/// Names the config file when `--config` does not. Set it empty to read none.
+pub const CONFIG_ENV: &str = "LONGTAIL_CONFIG";
+/// Names the profile when `--profile` does not.
+pub const PROFILE_ENV: &str = "LONGTAIL_PROFILE";
+/// The profile used when none is named, if the file has one.
+const DEFAULT_PROFILE: &str = "default";
+
+/// Flags that choose the profile, or that mean nothing unless typed.
+const NOT_CONFIGURABLE: &[&str] = &["config", "profile", "help", "version"];
+
+/// What `main` learned while merging the profile into argv.
+#[derive(Debug, Default, Clone)]
+pub struct Applied {
+    /// The config file read, if any.
+    pub path: Option<PathBuf>,
+    /// The profile taken from it.
+    pub profile: Option<String>,
+    /// Flags typed on the command line, with their values (none for a switch).
+    explicit: BTreeMap<String, Vec<String>>,
+    settings: Profile,
+}
+
+/// One profile's keys, each value rendered as the text a flag would be given.
+#[derive(Debug, Default, Clone)]
+struct Profile {
+    /// Keys for every subcommand that has the flag.
+    common: BTreeMap<String, Vec<String>>,
+    /// Keys for one subcommand, by its canonical name.
+    commands: BTreeMap<String, BTreeMap<String, Vec<String>>>,
+}
+
+/// A flag's effective value, as `config show` reports it.
+#[derive(Debug, Clone, Serialize)]
+pub struct Effective {
+    pub flag: String,
+    /// One entry per occurrence; a switch is `true` or `false`.
+    pub values: Vec<String>,
+    /// `command line`, `profile` or `default`.
+    pub source: &'static str,
+}
+
+/// Merge the selected profile into `args` (`argv`, program name first).
+///
+/// Returns the arguments to hand clap and what was merged. With no config file
+/// and no profile named, `args` come back unchanged.
+pub fn apply(
+    cmd: &Command,
+    mut args: Vec<OsString>,
+) -> Result<(Vec<OsString>, Applied), ApplyError> {
+    let mut scan = scan(cmd, &args);
+    // clap takes a switch bare or not at all: `--flag=true` becomes `--flag`
+    // and `--flag=false` goes, having already counted as typed.
+    for (index, flag, on) in std::mem::take(&mut scan.switches).into_iter().rev() {
+        if on {
+            args[index] = OsString::from(format!("--{flag}"));
+        } else {
+            args.remove(index);
+            if let Some((sub, _)) = scan.subcommand.as_mut().filter(|(sub, _)| *sub > index) {
+                *sub -= 1;
+            }
+        }
+    }
+    let output = scan.explicit.get("output").and_then(|v| v.last()).cloned();
+    let subcommand = scan.subcommand.as_ref().map(|(_, name)| name.clone());
+    merge(cmd, args, scan).map_err(|message| ApplyError {
+        message,
+        output,
+        subcommand,
+    })
+}
+
+/// A config that could not be merged, with what the command line asked for so
+/// the error can be reported in the requested format.
+#[derive(Debug)]
+pub struct ApplyError {
+    pub message: String,
+    /// The `--output` typed, if any.
+    pub output: Option<String>,
+    /// The subcommand typed, by its canonical name.
+    pub subcommand: Option<String>,
+}
+
+fn merge(
+    cmd: &Command,
+    mut args: Vec<OsString>,
+    scan: Scan,
+) -> Result<(Vec<OsString>, Applied), String> {
+    let mut applied = Applied {
+        explicit: scan.explicit,
+        ..Applied::default()
+    };
+
+    let typed = |flag: &str| applied.explicit.get(flag).and_then(|v| v.last()).cloned();
+    let named = typed("profile").or_else(|| env_value(PROFILE_ENV));
+    let Some((path, required)) = locate(typed("config")) else {
+        return match named {
+            Some(name) => Err(format!("--profile {name}: no config file to read it from")),
+            None => Ok((args, applied)),
+        };
+    };
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if !required && named.is_none() && e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok((args, applied));
+        }
+        Err(e) => return Err(format!("config {}: {e}", path.display())),
+    };
+    let fail = |msg: String| format!("config {}: {msg}", path.display());
+    let table: toml::Table = text.parse().map_err(|e| fail(format!("{e}")))?;
+    let profiles = match table.get("profiles") {
+        None => None,
+        Some(toml::Value::Table(t)) => Some(t),
+        Some(_) => return Err(fail("`profiles` must be a table".into())),
+    };
+    let name = named.clone().unwrap_or_else(|| DEFAULT_PROFILE.to_string());
+    let profile = match profiles.and_then(|p| p.get(&name)) {
+        Some(toml::Value::Table(t)) => t,
+        Some(_) => return Err(fail(format!("[profiles.{name}] must be a table"))),
+        None if named.is_some() => return Err(fail(format!("no profile named `{name}`"))),
+        None => return Ok((args, applied)),
+    };
+    applied.settings =
+        Profile::parse(cmd, profile).map_err(|e| fail(format!("[profiles.{name}] {e}")))?;
+    applied.path = Some(path);
+    applied.profile = Some(name);
+
+    let mut injected = Vec::new();
+    let sub = scan.subcommand.as_ref().map(|(_, name)| name.as_str());
+    for (flag, values) in applied.profile_values(cmd, sub) {
+        let Some(arg) = find_arg(cmd, sub, &flag) else {
+            continue;
+        };
+        if takes_values(arg) {
+            injected.extend(
+                values
+                    .iter()
+                    .map(|v| OsString::from(format!("--{flag}={v}"))),
+            );
+        } else if values.iter().any(|v| v == "true") {
+            injected.push(OsString::from(format!("--{flag}")));
+        }
+    }
+    // Right after the subcommand: global flags are accepted there too, and it
+    // is ahead of any `--` or positional argument.
+    let at = scan
+        .subcommand
+        .map_or(1, |(index, _)| index + 1)
+        .min(args.len());
+    args.splice(at..at, injected);
+    Ok((args, applied))
+}
+
+impl Applied {
+    /// The profile's values for the flags of `sub` (or the global flags alone),
+    /// subcommand table over common keys, less anything typed.
+    fn profile_values(&self, cmd: &Command, sub: Option<&str>) -> BTreeMap<String, Vec<String>> {
+        let mut out: BTreeMap<String, Vec<String>> = self
+            .settings
+            .common
+            .iter()
+            .filter(|(flag, _)| find_arg(cmd, sub, flag).is_some())
+            .map(|(flag, values)| (flag.clone(), values.clone()))
+            .collect();
+        if let Some(table) = sub.and_then(|s| self.settings.commands.get(s)) {
+            out.extend(table.iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+        out.retain(|flag, _| !self.typed(flag));
+        out
+    }
+
+    /// Whether `flag`, or its `no-` counterpart, was on the command line.
+    fn typed(&self, flag: &str) -> bool {
+        let counterpart = match flag.strip_prefix("no-") {
+            Some(positive) => positive.to_string(),
+            None => format!("no-{flag}"),
+        };
+        self.explicit.contains_key(flag) || self.explicit.contains_key(&counterpart)
+    }
+
+    /// Every global flag's effective value, then `sub`'s own flags when given.
+    /// Flags with no value from anywhere are left out.
+    pub fn effective(&self, cmd: &Command, sub: Option<&str>) -> Result<Vec<Effective>, String> {
+        let sub_cmd = match sub {
+            None => None,
+            Some(name) => Some(
+                cmd.find_subcommand(name)
+                    .ok_or_else(|| format!("no subcommand named `{name}`"))?,
+            ),
+        };
+        let sub = sub_cmd.map(Command::get_name);
+        let profile = self.profile_values(cmd, sub);
+        let args = cmd
+            .get_arguments()
+            .chain(sub_cmd.into_iter().flat_map(Command::get_arguments));
+        let mut out = Vec::new();
+        for arg in args {
+            let Some(flag) = arg.get_long() else {
+                continue;
+            };
+            if NOT_CONFIGURABLE.contains(&flag) {
+                continue;
+            }
+            let (values, source) = if let Some(values) = self.explicit.get(flag) {
+                (values.clone(), "command line")
+            } else if let Some(values) = profile.get(flag) {
+                (values.clone(), "profile")
+            } else {
+                let defaults: Vec<String> = arg
+                    .get_default_values()
+                    .iter()
+                    .map(|v| v.to_string_lossy().into_owned())
+                    .collect();
+                (defaults, "default")
+            };
+            let values = if takes_values(arg) {
+                values
+            } else {
+                // A typed switch carries no value; one from a profile or a
+                // default is `true` or `false`.
+                let on = values == ["true"] || (source == "command line" && values.is_empty());
+                vec![on.to_string()]
+            };
+            if values.is_empty() {
+                continue;
+            }
+            out.push(Effective {
+                flag: flag.to_string(),
+                values,
+                source,
+            });
+        }
+        Ok(out)
+    }
+}
+
+impl Profile {
+    fn parse(cmd: &Command, profile: &toml::Table) -> Result<Profile, String> {
+        let mut out = Profile::default();
+        for (key, value) in profile {
+            if let toml::Value::Table(table) = value {
+                let sub = cmd
+                    .find_subcommand(key)
+                    .ok_or_else(|| format!("[{key}]: no subcommand named `{key}`"))?;
+                let mut keys = BTreeMap::new();
+                for (flag, value) in table {
+                    let arg = find_arg(cmd, Some(sub.get_name()), flag)
+                        .ok_or_else(|| format!("[{key}] {flag}: `{key}` has no --{flag}"))?;
+                    keys.insert(flag.clone(), render(arg, flag, value)?);
+                }
+                out.commands.insert(sub.get_name().to_string(), keys);
+            } else {
+                let arg = find_arg(cmd, None, key)
+                    .or_else(|| {
+                        cmd.get_subcommands()
+                            .find_map(|s| find_arg(cmd, Some(s.get_name()), key))
+                    })
+                    .ok_or_else(|| format!("{key}: no subcommand has --{key}"))?;
+                out.common.insert(key.clone(), render(arg, key, value)?);
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// `value` as the text `--flag` would be given, one entry per occurrence.
+fn render(arg: &Arg, flag: &str, value: &toml::Value) -> Result<Vec<String>, String> {
+    if NOT_CONFIGURABLE.contains(&flag) {
+        return Err(format!("{flag}: --{flag} cannot be set from a profile"));
+    }
+    if !takes_values(arg) {
+        return match value {
+            toml::Value::Boolean(b) => Ok(vec![b.to_string()]),
+            _ => Err(format!("{flag}: --{flag} is a switch; use true or false")),
+        };
+    }
+    let scalar = |v: &toml::Value| match v {
+        toml::Value::String(s) => Ok(s.clone()),
+        toml::Value::Integer(i) => Ok(i.to_string()),
+        toml::Value::Float(f) => Ok(f.to_string()),
+        toml::Value::Boolean(b) => Ok(b.to_string()),
+        toml::Value::Datetime(d) => Ok(d.to_string()),
+        _ => Err(format!("{flag}: expected a string, number or boolean")),
+    };
+    match value {
+        toml::Value::Array(items) => items.iter().map(scalar).collect(),
+        v => Ok(vec![scalar(v)?]),
+    }
+}
+
+/// The `--flag` accepted globally or by `sub`.
+fn find_arg<'a>(cmd: &'a Command, sub: Option<&str>, flag: &str) -> Option<&'a Arg> {
+    let long = |a: &&Arg| a.get_long() == Some(flag);
+    cmd.get_arguments().find(long).or_else(|| {
+        sub.and_then(|s| cmd.find_subcommand(s))
+            .and_then(|s| s.get_arguments().find(long))
+    })
+}
+
+fn find_short<'a>(cmd: &'a Command, sub: Option<&str>, flag: char) -> Option<&'a Arg> {
+    let short = |a: &&Arg| a.get_short() == Some(flag);
+    cmd.get_arguments().find(short).or_else(|| {
+        sub.and_then(|s| cmd.find_subcommand(s))
+            .and_then(|s| s.get_arguments().find(short))
+    })
+}
+
+fn takes_values(arg: &Arg) -> bool {
+    arg.get_action().takes_values()
+}
+
+fn env_value(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+/// The config file to read, and whether it must exist: a path someone named
+/// must, the XDG default need not.
+fn locate(typed: Option<String>) -> Option<(PathBuf, bool)> {
+    if let Some(path) = typed.or_else(|| std::env::var(CONFIG_ENV).ok()) {
+        return (!path.is_empty()).then(|| (PathBuf::from(path), true));
+    }
+    let base = env_value("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env_value("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some((base.join("longtail-rs").join("config.toml"), false))
+}
+
+struct Scan {
+    /// The subcommand's position in argv and canonical name.
+    subcommand: Option<(usize, String)>,
+    explicit: BTreeMap<String, Vec<String>>,
+    /// Switches typed as `--flag=true` or `--flag=false`, by position in argv,
+    /// which clap does not accept.
+    switches: Vec<(usize, String, bool)>,
+}
+
+/// Find the subcommand and the flags typed, without parsing: clap has not run
+/// yet, and must not until the profile is merged. A short flag (`-R`, or one
+/// of a cluster like `-Rl`) is recorded under its long name, so a profile
+/// does not add the same flag again.
+fn scan(cmd: &Command, args: &[OsString]) -> Scan {
+    let mut out = Scan {
+        subcommand: None,
+        explicit: BTreeMap::new(),
+        switches: Vec::new(),
+    };
+    let mut i = 1;
+    while i < args.len() {
+        let token = args[i].to_string_lossy();
+        if token == "--" {
+            break;
+        }
+        if let Some(long) = token.strip_prefix("--") {
+            let (flag, inline) = match long.split_once('=') {
+                Some((flag, value)) => (flag, Some(value.to_string())),
+                None => (long, None),
+            };
+            let sub = out.subcommand.as_ref().map(|(_, name)| name.as_str());
+            let value = match find_arg(cmd, sub, flag) {
+                Some(arg) if takes_values(arg) => inline.or_else(|| {
+                    i += 1;
+                    args.get(i).map(|v| v.to_string_lossy().into_owned())
+                }),
+                Some(_) => {
+                    if let Some(on) = inline.as_deref().and_then(|v| v.parse().ok()) {
+                        out.switches.push((i, flag.to_string(), on));
+                    }
+                    inline
+                }
+                None => inline,
+            };
+            out.explicit
+                .entry(flag.to_string())
+                .or_default()
+                .extend(value);
+        } else if let Some(cluster) = token.strip_prefix('-').filter(|c| !c.is_empty()) {
+            let sub = out.subcommand.as_ref().map(|(_, name)| name.as_str());
+            for (at, flag) in cluster.char_indices() {
+                let Some(arg) = find_short(cmd, sub, flag) else {
+                    continue;
+                };
+                let Some(long) = arg.get_long() else {
+                    continue;
+                };
+                let entry = out.explicit.entry(long.to_string()).or_default();
+                if takes_values(arg) {
+                    // The rest of the cluster is the value, or else the next token.
+                    let rest = &cluster[at + flag.len_utf8()..];
+                    let rest = rest.strip_prefix('=').unwrap_or(rest);
+                    if rest.is_empty() {
+                        i += 1;
+                        entry.extend(args.get(i).map(|v| v.to_string_lossy().into_owned()));
+                    } else {
+                        entry.push(rest.to_string());
+                    }
+                    break;
+                }
+            }
+        } else if out.subcommand.is_none()
+            && !token.starts_with('-')
+            && let Some(sub) = cmd.find_subcommand(token.as_ref())
+        {
+            out.subcommand = Some((i, sub.get_name().to_string()));
+        }
+        i += 1;
+    }
+    out
+}
@@ -9,19 +9,26 @@
 
 #![forbid(unsafe_code)]
 
+mod config;
+mod output;
 mod progress;
+mod serve;
 
 use std::process::ExitCode;
 use std::sync::Arc;
 
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, CommandFactory, Parser, Subcommand};
 use longtail::{
-    DownsyncOptions, GetOptions, ValidateVersionOptions, downsync, get,
-    read_version_index_from_uri, validate_version,
+    DownsyncOptions, GetOptions, PathGlob, ValidateVersionOptions, downsync, get, plan_downsync,
+    plan_get, read_version_index_from_uri, validate_version,
 };
 use longtail_core::VersionIndex;
 
-use crate::progress::CliProgress;
+use crate::output::{
+    AssetEntry, CloneDoc, ConfigDoc, CpDoc, InitDoc, OutputFormat, PublishedVersionDoc, StoreDoc,
+    ValidateDoc, VersionDoc, VersionSummary, WrittenDoc,
+};
+use crate::progress::{CliProgress, ProgressFormat};
 
 #[derive(Parser)]
 #[command(
@@ -78,6 +85,42 @@ struct Cli {
     /// See `--mem-trace`.
     #[arg(long, global = true)]
     mem_trace_csv: Option<String>,
+    /// How to print the command's result: `text` (the golongtail-compatible
+    /// output) or `json` (one versioned document on stdout, errors included).
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+    /// How to report progress: `auto` (a bar on a terminal, plain lines
+    /// otherwise), `plain`, or `jsonl` (one JSON object per line, for a
+    /// wrapping process).
+    #[arg(long, global = true, value_enum, default_value_t = ProgressFormat::Auto)]
+    progress_format: ProgressFormat,
+    /// Write `--progress-format jsonl` lines to this already-open file
+    /// descriptor instead of stderr, keeping them apart from logs. Unix only.
+    #[arg(long, global = true)]
+    progress_fd: Option<u32>,
+    /// S3 region, for every store and index the command opens (else the AWS
+    /// default region chain).
+    #[arg(long, global = true)]
+    s3_region: Option<String>,
+    /// Use path-style S3 addressing (`endpoint/bucket/key`), which minio and
+    /// most S3-compatible stores need.
+    #[arg(long, global = true, default_value_t = false)]
+    s3_force_path_style: bool,
+    /// Named profile in the shared AWS config and credentials files to take S3
+    /// credentials from (else `AWS_PROFILE`, else `default`).
+    #[arg(long, global = true)]
+    s3_credentials_profile: Option<String>,
+    /// Read flag defaults from this config file instead of `$LONGTAIL_CONFIG`
+    /// or `$XDG_CONFIG_HOME/longtail-rs/config.toml`.
+    #[arg(long, global = true)]
+    config: Option<String>,
+    /// The config-file profile to take flag defaults from (else
+    /// `$LONGTAIL_PROFILE`, else `default` when the file has one).
+    #[arg(long, global = true)]
+    profile: Option<String>,
+    /// Where each flag default came from; filled in by `main`, not by clap.
+    #[arg(skip)]
+    applied: config::Applied,
     #[command(subcommand)]
     command: Command,
 }
@@ -89,6 +132,28 @@ impl Cli {
     fn wants_stats(&self) -> bool {
         self.show_stats || self.show_store_stats
     }
+
+    fn json(&self) -> bool {
+        self.output == OutputFormat::Json
+    }
+
+    /// Overlay the global S3 flags, and the command's own
+    /// `--s3-endpoint-resolver-uri`, onto `o`.
+    #[cfg(feature = "s3")]
+    fn apply_s3(&self, endpoint: Option<&String>, o: &mut longtail::S3Options) {
+        if let Some(u) = endpoint {
+            o.endpoint_url = Some(u.clone());
+        }
+        if let Some(r) = &self.s3_region {
+            o.region = Some(r.clone());
+        }
+        if let Some(p) = &self.s3_credentials_profile {
+            o.profile_name = Some(p.clone());
+        }
+        if self.s3_force_path_style {
+            o.force_path_style = true;
+        }
+    }
 }
 
 #[derive(Subcommand)]
@@ -134,10 +199,77 @@ enum Command {
     /// List all asset paths inside a version index.
     #[command(visible_alias = "dump")]
     DumpVersionAssets(DumpVersionAssetsArgs),
-    /// Copy one asset out of a version into a local file.
+    /// Copy one asset, a directory subtree or a glob of assets out of a version.
     Cp(CpArgs),
+    /// Write one asset of a version to stdout, streaming its blocks.
+    Cat(CatArgs),
+    /// Compare two version indexes: changed assets, new chunks, and with a
+    /// store the download an update between them makes.
+    DiffVersions(DiffVersionsArgs),
+    /// Attribute the blocks an update fetches to the assets that need them,
+    /// and show the bytes fetched only because they share a block.
+    ExplainUpdate(ExplainUpdateArgs),
+    /// List the version indexes published under a folder or bucket prefix.
+    ListVersions(ListVersionsArgs),
+    /// Show each version's blocks: the bytes only it references and the bytes
+    /// it shares with the others.
+    StoreDu(StoreDuArgs),
+    /// Find the versions and assets that depend on a block, a chunk or an
+    /// asset path.
+    WhoUses(WhoUsesArgs),
+    /// Serve a version's files over HTTP, fetching blocks as they are read.
+    ServeVersion(ServeVersionArgs),
     /// Show version number.
     Version,
+    /// Inspect the config-file profiles.
+    #[command(subcommand)]
+    Config(ConfigCommand),
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Print the effective flag values and where each came from.
+    Show(ConfigShowArgs),
+}
+
+#[derive(Args)]
+struct ConfigShowArgs {
+    /// Also show the values a profile supplies to this subcommand's flags.
+    subcommand: Option<String>,
+}
+
+impl Command {
+    /// The subcommand's canonical name, as `--output json` reports it.
+    fn name(&self) -> &'static str {
+        match self {
+            Command::Downsync(_) => "downsync",
+            Command::Get(_) => "get",
+            Command::Ls(_) => "ls",
+            Command::ValidateVersion(_) => "validate-version",
+            Command::PrintVersion(_) => "print-version",
+            Command::Upsync(_) => "upsync",
+            Command::Put(_) => "put",
+            Command::InitRemoteStore(_) => "init-remote-store",
+            Command::CreateVersionStoreIndex(_) => "create-version-store-index",
+            Command::PruneStore(_) => "prune-store",
+            Command::PruneStoreIndex(_) => "prune-store-index",
+            Command::PruneStoreBlocks(_) => "prune-store-blocks",
+            Command::CloneStore(_) => "clone-store",
+            Command::PrintStore(_) => "print-store",
+            Command::PrintVersionUsage(_) => "print-version-usage",
+            Command::DumpVersionAssets(_) => "dump-version-assets",
+            Command::Cp(_) => "cp",
+            Command::Cat(_) => "cat",
+            Command::DiffVersions(_) => "diff-versions",
+            Command::ExplainUpdate(_) => "explain-update",
+            Command::ListVersions(_) => "list-versions",
+            Command::StoreDu(_) => "store-du",
+            Command::WhoUses(_) => "who-uses",
+            Command::ServeVersion(_) => "serve-version",
+            Command::Version => "version",
+            Command::Config(ConfigCommand::Show(_)) => "config show",
+        }
+    }
 }
 
 #[derive(Args)]
@@ -194,10 +326,22 @@ struct DownsyncArgs {
     include_filter_regex: Option<String>,
     #[arg(long)]
     exclude_filter_regex: Option<String>,
+    /// Write the assets matching this filter first, and report each one on
+    /// stderr as soon as it is ready.
+    #[arg(long)]
+    priority_filter_regex: Option<String>,
     #[arg(long, default_value_t = false)]
     scan_target: bool,
     #[arg(long, default_value_t = false)]
     no_scan_target: bool,
+    /// Keep an apply journal, and use one an interrupted run left, so a
+    /// resumed run hashes only what the interrupted one left incomplete.
+    #[arg(long, default_value_t = false)]
+    resume_journal: bool,
+    /// Record each file's size, times and inode after a successful run, and on
+    /// the next scan re-hash only the files where they changed.
+    #[arg(long, default_value_t = false)]
+    fingerprint_cache: bool,
     #[arg(long, default_value_t = false)]
     cache_target_index: bool,
     #[arg(long, default_value_t = false)]
@@ -206,6 +350,10 @@ struct DownsyncArgs {
     enable_file_mapping: bool,
     #[arg(long, default_value_t = false)]
     use_legacy_write: bool,
+    /// Print what the run would fetch, write and delete, then stop without
+    /// touching the target.
+    #[arg(long, default_value_t = false)]
+    dry_run: bool,
 }
 
 #[derive(Args)]
@@ -259,10 +407,22 @@ struct GetArgs {
     include_filter_regex: Option<String>,
     #[arg(long)]
     exclude_filter_regex: Option<String>,
+    /// Write the assets matching this filter first, and report each one on
+    /// stderr as soon as it is ready.
+    #[arg(long)]
+    priority_filter_regex: Option<String>,
     #[arg(long, default_value_t = false)]
     scan_target: bool,
     #[arg(long, default_value_t = false)]
     no_scan_target: bool,
+    /// Keep an apply journal, and use one an interrupted run left, so a
+    /// resumed run hashes only what the interrupted one left incomplete.
+    #[arg(long, default_value_t = false)]
+    resume_journal: bool,
+    /// Record each file's size, times and inode after a successful run, and on
+    /// the next scan re-hash only the files where they changed.
+    #[arg(long, default_value_t = false)]
+    fingerprint_cache: bool,
     #[arg(long, default_value_t = false)]
     cache_target_index: bool,
     #[arg(long, default_value_t = false)]
@@ -271,6 +431,10 @@ struct GetArgs {
     enable_file_mapping: bool,
     #[arg(long, default_value_t = false)]
     use_legacy_write: bool,
+    /// Print what the run would fetch, write and delete, then stop without
+    /// touching the target.
+    #[arg(long, default_value_t = false)]
+    dry_run: bool,
 }
 
 #[derive(Args)]
@@ -281,6 +445,19 @@ struct LsArgs {
     s3_endpoint_resolver_uri: Option<String>,
     /// Directory inside the version to list (`.` or empty = root).
     path: Option<String>,
+    /// List everything below the directory, not just its own entries.
+    #[arg(short = 'R', long, default_value_t = false)]
+    recursive: bool,
+    /// Also print each asset's chunk count and content hash.
+    #[arg(short = 'l', long, default_value_t = false)]
+    long: bool,
+    /// Only list assets whose full in-version path matches this glob (`*`,
+    /// `?`, `[...]`, `**` across directories). Repeatable; implies `-R`.
+    #[arg(long = "glob")]
+    globs: Vec<String>,
+    /// Order the listing; the version index's own order by default.
+    #[arg(long, value_enum)]
+    sort: Option<LsSort>,
 }
 
 #[derive(Args)]
@@ -565,12 +742,183 @@ struct CpArgs {
     cache_path: Option<String>,
     #[arg(long, default_value_t = false)]
     enable_file_mapping: bool,
-    /// Asset path inside the version index.
+    /// Copy the directory `SOURCE_PATH` names, and everything below it, into
+    /// the local folder `TARGET_PATH`. A glob source does this without `-r`.
+    #[arg(short = 'r', long, default_value_t = false)]
+    recursive: bool,
+    /// Asset path inside the version index, or with `-r` a directory; a glob
+    /// (`config/**/*.ini`) selects several assets.
     source_path: String,
-    /// Destination file path/URI.
+    /// Destination file path/URI; a local folder for `-r` or a glob.
     target_path: String,
 }
 
+#[derive(Args)]
+struct CatArgs {
+    #[arg(long)]
+    storage_uri: String,
+    #[arg(long)]
+    s3_endpoint_resolver_uri: Option<String>,
+    #[arg(long)]
+    version_index_path: String,
+    #[arg(long)]
+    cache_path: Option<String>,
+    /// Blocks fetched ahead of what has been written out.
+    #[arg(long, default_value_t = longtail::DEFAULT_READ_AHEAD)]
+    read_ahead: usize,
+    /// Asset path inside the version index.
+    source_path: String,
+}
+
+#[derive(Args)]
+struct ServeVersionArgs {
+    #[arg(long)]
+    storage_uri: String,
+    #[arg(long)]
+    s3_endpoint_resolver_uri: Option<String>,
+    #[arg(long)]
+    version_index_path: String,
+    #[arg(long)]
+    cache_path: Option<String>,
+    /// Address to listen on; port 0 picks a free one.
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    listen: String,
+    /// Decompressed blocks kept in memory between requests (`256MiB`).
+    #[arg(long, value_parser = parse_size, default_value_t = longtail::DEFAULT_BLOCK_CACHE_BYTES)]
+    block_cache_bytes: u64,
+}
+
+#[derive(Args)]
+struct DiffVersionsArgs {
+    /// The older version index.
+    #[arg(long)]
+    from_path: String,
+    /// The newer version index.
+    #[arg(long)]
+    to_path: String,
+    /// The store holding the newer version's blocks; adds the download an
+    /// update from one to the other makes.
+    #[arg(long)]
+    storage_uri: Option<String>,
+    #[arg(long)]
+    s3_endpoint_resolver_uri: Option<String>,
+}
+
+#[derive(Args)]
+struct ExplainUpdateArgs {
+    /// The installed version's index.
+    #[arg(long)]
+    from_path: String,
+    /// The version being updated to.
+    #[arg(long)]
+    to_path: String,
+    /// The store holding the newer version's blocks.
+    #[arg(long)]
+    storage_uri: String,
+    #[arg(long)]
+    s3_endpoint_resolver_uri: Option<String>,
+    /// Rows shown per breakdown; 0 shows all. JSON output always has all.
+    #[arg(long, default_value_t = 20)]
+    top: usize,
+}
+
+#[derive(Args)]
+struct ListVersionsArgs {
+    /// The folder, store or bucket prefix to search.
+    #[arg(long)]
+    source_path: String,
+    /// Only objects whose name under --source-path starts with this.
+    #[arg(long, default_value = "")]
+    prefix: String,
+    /// A store holding the versions' blocks, for the compression of versions
+    /// `put` did not write a version-local store index for.
+    #[arg(long)]
+    storage_uri: Option<String>,
+    #[arg(long)]
+    s3_endpoint_resolver_uri: Option<String>,
+    #[arg(long, value_enum, default_value_t = VersionSort::Path)]
+    sort: VersionSort,
+}
+
+#[derive(Args)]
+struct StoreDuArgs {
+    #[arg(long)]
+    storage_uri: String,
+    #[arg(long)]
+    s3_endpoint_resolver_uri: Option<String>,
+    /// Path to a text file listing version-index URIs (one per line), as
+    /// `prune-store --source-paths` takes.
+    #[arg(long)]
+    source_paths: String,
+}
+
+#[derive(Args)]
+#[command(group(
+    clap::ArgGroup::new("target")
+        .required(true)
+        .args(["block_hash", "chunk_hash", "asset_path"])
+))]
+struct WhoUsesArgs {
+    #[arg(long)]
+    storage_uri: String,
+    #[arg(long)]
+    s3_endpoint_resolver_uri: Option<String>,
+    /// `0x`-prefixed hex, or the block's file name or path in the store.
+    #[arg(long, value_parser = parse_hash)]
+    block_hash: Option<u64>,
+    /// `0x`-prefixed hex, or decimal.
+    #[arg(long, value_parser = parse_hash)]
+    chunk_hash: Option<u64>,
+    /// An asset path inside the versions; a directory's ends in `/`.
+    #[arg(long)]
+    asset_path: Option<String>,
+    /// Path to a text file listing version-index URIs (one per line).
+    #[arg(long)]
+    source_paths: Option<String>,
+    /// Also search every version `list-versions` finds here.
+    #[arg(long)]
+    versions_uri: Option<String>,
+    /// With --versions-uri, only object names starting with this.
+    #[arg(long, default_value = "", requires = "versions_uri")]
+    versions_prefix: String,
+}
+
+/// A block or chunk hash: `0x`-prefixed hex — anywhere in the argument, so a
+/// block's `chunks/xxxx/0x….lsb` path works — or decimal.
+fn parse_hash(s: &str) -> Result<u64, String> {
+    let parsed = match s.rfind("0x") {
+        Some(i) => {
+            let hex = &s[i + 2..];
+            let hex = hex.strip_suffix(".lsb").unwrap_or(hex);
+            u64::from_str_radix(hex, 16)
+        }
+        None => s.parse(),
+    };
+    parsed.map_err(|e| format!("`{s}` is not a hash: {e}"))
+}
+
+/// `list-versions --sort`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum VersionSort {
+    /// By path, ascending.
+    Path,
+    /// Newest first.
+    Modified,
+    /// Largest total asset size first.
+    Size,
+    /// Most assets first.
+    Assets,
+}
+
+/// `ls --sort`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum LsSort {
+    /// By path, ascending.
+    Name,
+    /// Largest first.
+    Size,
+}
+
 /// Install the `tracing` subscriber so library logs (cache-eviction summaries,
 /// store-index fallbacks, retries) surface. `RUST_LOG` wins when set; otherwise
 /// the `--log-level` default applies. `try_init` so tests/repeat calls don't
@@ -637,7 +985,27 @@ fn warn_about_ignored_flags(cli: &Cli) {
 }
 
 fn main() -> ExitCode {
-    let cli = Cli::parse();
+    let (args, applied) = match config::apply(&Cli::command(), std::env::args_os().collect()) {
+        Ok(merged) => merged,
+        Err(e) => {
+            // The same exit status clap gives a bad flag: the invocation, not
+            // the operation, is wrong.
+            if e.output.as_deref() == Some("json") {
+                let command = e
+                    .subcommand
+                    .unwrap_or_else(|| Cli::command().get_name().to_string());
+                output::print_error(
+                    &command,
+                    &longtail::LongtailError::InvalidArgument(e.message),
+                );
+            } else {
+                eprintln!("error: {}", e.message);
+            }
+            return ExitCode::from(2);
+        }
+    };
+    let mut cli = Cli::parse_from(args);
+    cli.applied = applied;
     if let Err(code) = init_tracing(&cli) {
         return code;
     }
@@ -652,7 +1020,20 @@ fn main() -> ExitCode {
             return ExitCode::FAILURE;
         }
     };
-    match runtime.block_on(run(&cli)) {
+    let result = runtime.block_on(run(&cli));
+    if cli.json() {
+        // The document replaces the text below; stderr still gets nothing but
+        // logs, so a wrapper reads one stream for the outcome.
+        if let Err(e) = &result {
+            output::print_error(cli.command.name(), e);
+        }
+        return match result {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(longtail::LongtailError::Cancelled) => ExitCode::from(130),
+            Err(_) => ExitCode::FAILURE,
+        };
+    }
+    match result {
         Ok(()) => ExitCode::SUCCESS,
         // A ctrl-c cancel is a clean stop, not an error: the partial target is
         // left resumable (re-run the same command to continue). Exit 130 (SIGINT).
@@ -672,9 +1053,10 @@ fn main() -> ExitCode {
 }
 
 /// Build a cancellation token and spawn a ctrl-c watcher that triggers it on the
-/// first SIGINT (in-flight blocks finish, the store flushes/closes, the target is
-/// left resumable) and force-quits on a second. The returned token goes into
-/// `opts.cancel` for the long-running download/upload ops.
+/// first SIGINT (in-flight reads are dropped, received blocks finish writing,
+/// the store flushes/closes, the target is left resumable) and force-quits on a
+/// second. The returned token goes into `opts.cancel` for the long-running
+/// download/upload ops.
 fn install_cancel_handler() -> longtail::CancellationToken {
     let token = longtail::CancellationToken::new();
     let watch = token.clone();
@@ -685,7 +1067,9 @@ fn install_cancel_handler() -> longtail::CancellationToken {
             if n == 1 {
                 // Interactive feedback in response to the user's signal → eprintln
                 // (must show regardless of RUST_LOG), consistent with the bar.
-                eprintln!("\nCancelling… finishing in-flight blocks (ctrl-c again to force quit)");
+                eprintln!(
+                    "\nCancelling… finishing blocks already received (ctrl-c again to force quit)"
+                );
                 watch.cancel();
             } else {
                 std::process::exit(130);
@@ -700,29 +1084,78 @@ async fn run(cli: &Cli) -> Result<(), longtail::LongtailError> {
         Command::Version => {
             // golongtail publishes this alongside `--version`; a pipeline may use
             // either spelling.
-            println!("{}", env!("CARGO_PKG_VERSION"));
+            if cli.json() {
+                output::print_result(
+                    cli.command.name(),
+                    &VersionDoc {
+                        version: env!("CARGO_PKG_VERSION"),
+                    },
+                );
+            } else {
+                println!("{}", env!("CARGO_PKG_VERSION"));
+            }
             Ok(())
         }
         Command::Downsync(a) => run_downsync(cli, a).await,
         Command::Get(a) => run_get(cli, a).await,
-        Command::Ls(a) => run_ls(a).await,
+        Command::Ls(a) => run_ls(cli, a).await,
         Command::ValidateVersion(a) => run_validate(cli, a).await,
-        Command::PrintVersion(a) => run_print(a).await,
+        Command::PrintVersion(a) => run_print(cli, a).await,
         Command::Upsync(a) => run_upsync(cli, a).await,
         Command::Put(a) => run_put(cli, a).await,
         Command::InitRemoteStore(a) => run_init(cli, a).await,
         Command::CreateVersionStoreIndex(a) => run_create_vsi(cli, a).await,
         Command::PruneStore(a) => run_prune_store(cli, a).await,
-        Command::PruneStoreIndex(a) => run_prune_store_index(a).await,
-        Command::PruneStoreBlocks(a) => run_prune_store_blocks(a).await,
+        Command::PruneStoreIndex(a) => run_prune_store_index(cli, a).await,
+        Command::PruneStoreBlocks(a) => run_prune_store_blocks(cli, a).await,
         Command::CloneStore(a) => run_clone_store(cli, a).await,
-        Command::PrintStore(a) => run_print_store(a).await,
+        Command::PrintStore(a) => run_print_store(cli, a).await,
         Command::PrintVersionUsage(a) => run_print_version_usage(cli, a).await,
-        Command::DumpVersionAssets(a) => run_dump_version_assets(a).await,
+        Command::DumpVersionAssets(a) => run_dump_version_assets(cli, a).await,
         Command::Cp(a) => run_cp(cli, a).await,
+        Command::Cat(a) => run_cat(cli, a).await,
+        Command::DiffVersions(a) => run_diff_versions(cli, a).await,
+        Command::ExplainUpdate(a) => run_explain_update(cli, a).await,
+        Command::ListVersions(a) => run_list_versions(cli, a).await,
+        Command::StoreDu(a) => run_store_du(cli, a).await,
+        Command::WhoUses(a) => run_who_uses(cli, a).await,
+        Command::ServeVersion(a) => run_serve_version(cli, a).await,
+        Command::Config(ConfigCommand::Show(a)) => run_config_show(cli, a),
     }
 }
 
+/// The progress sink `--progress-format`/`--progress-fd` ask for.
+fn cli_progress(cli: &Cli) -> Result<Arc<CliProgress>, longtail::LongtailError> {
+    let out: Option<Box<dyn std::io::Write + Send>> = match cli.progress_fd {
+        None => None,
+        Some(_) if cli.progress_format != ProgressFormat::Jsonl => {
+            return Err(longtail::LongtailError::InvalidArgument(
+                "--progress-fd needs --progress-format jsonl".into(),
+            ));
+        }
+        // `/dev/fd/N` reopens a descriptor the parent handed down without an
+        // `unsafe` `from_raw_fd`.
+        Some(fd) if cfg!(unix) => {
+            let path = format!("/dev/fd/{fd}");
+            let file = std::fs::OpenOptions::new()
+                .append(true)
+                .open(&path)
+                .map_err(|e| {
+                    longtail::LongtailError::InvalidArgument(format!(
+                        "--progress-fd {fd}: cannot open {path}: {e}"
+                    ))
+                })?;
+            Some(Box::new(file))
+        }
+        Some(_) => {
+            return Err(longtail::LongtailError::InvalidArgument(
+                "--progress-fd is supported on Unix only".into(),
+            ));
+        }
+    };
+    Ok(Arc::new(CliProgress::new(cli.progress_format, out)))
+}
+
 /// Read a text file of one URI per line into a `Vec<String>` (golongtail's
 /// `--source-paths`/`--target-paths` list files).
 fn read_lines_file(path: &str) -> Result<Vec<String>, longtail::LongtailError> {
@@ -754,22 +1187,21 @@ fn parse_size(s: &str) -> Result<u64, String> {
     s.parse::<bytesize::ByteSize>().map(|b| b.as_u64())
 }
 
-/// Build the S3 options for a bare index read from `--s3-endpoint-resolver-uri`.
+/// Build the S3 options for a bare index read from `--s3-endpoint-resolver-uri`
+/// and the global S3 flags.
 ///
 /// Commands that open a block store carry these on their options struct; the
 /// read-only inspection commands have no store to open, so they build them here.
 /// Without this the flag parsed and then did nothing on those commands.
 #[cfg(feature = "s3")]
-fn s3_read_options(endpoint: Option<&String>) -> longtail::S3OptionsArg {
+fn s3_read_options(cli: &Cli, endpoint: Option<&String>) -> longtail::S3OptionsArg {
     let mut o = longtail::S3Options::default();
-    if let Some(u) = endpoint {
-        o.endpoint_url = Some(u.clone());
-    }
+    cli.apply_s3(endpoint, &mut o);
     o
 }
 
 #[cfg(not(feature = "s3"))]
-fn s3_read_options(_endpoint: Option<&String>) -> longtail::S3OptionsArg {}
+fn s3_read_options(_cli: &Cli, _endpoint: Option<&String>) -> longtail::S3OptionsArg {}
 
 async fn run_downsync(cli: &Cli, a: &DownsyncArgs) -> Result<(), longtail::LongtailError> {
     let sources = merge_paths(&a.source_path, &a.source_paths);
@@ -788,29 +1220,33 @@ async fn run_downsync(cli: &Cli, a: &DownsyncArgs) -> Result<(), longtail::Longt
     );
     opts.include_filter_regex = a.include_filter_regex.clone();
     opts.exclude_filter_regex = a.exclude_filter_regex.clone();
+    opts.priority_filter_regex = a.priority_filter_regex.clone();
     opts.scan_target = !a.no_scan_target;
+    opts.resume_journal = a.resume_journal;
+    opts.fingerprint_cache = a.fingerprint_cache;
     opts.cache_target_index = !a.no_cache_target_index;
     opts.enable_file_mapping = a.enable_file_mapping;
     opts.use_legacy_write = a.use_legacy_write;
     opts.worker_count = cli.worker_count;
     opts.remote_worker_count = cli.remote_worker_count;
     #[cfg(feature = "s3")]
-    if let Some(u) = &a.s3_endpoint_resolver_uri {
-        opts.s3_options.endpoint_url = Some(u.clone());
-    }
+    cli.apply_s3(a.s3_endpoint_resolver_uri.as_ref(), &mut opts.s3_options);
     #[cfg(feature = "s3")]
     if a.no_stalled_stream_protection {
         opts.s3_options.stalled_stream_protection = false;
     }
     opts.cancel = Some(install_cancel_handler());
-    let progress = Arc::new(CliProgress::new());
+    let progress = cli_progress(cli)?;
     opts.progress = Some(progress.clone());
+    if a.dry_run {
+        let result = plan_downsync(opts).await;
+        progress.finish(result.is_ok());
+        print_plan(cli, &result?);
+        return Ok(());
+    }
     let result = downsync(opts).await;
     progress.finish(result.is_ok());
-    let report = result?;
-    if cli.wants_stats() {
-        print_stats(&report);
-    }
+    print_report(cli, &result?);
     Ok(())
 }
 
@@ -827,44 +1263,79 @@ async fn run_get(cli: &Cli, a: &GetArgs) -> Result<(), longtail::LongtailError>
     opts.validate = a.validate;
     opts.include_filter_regex = a.include_filter_regex.clone();
     opts.exclude_filter_regex = a.exclude_filter_regex.clone();
+    opts.priority_filter_regex = a.priority_filter_regex.clone();
     opts.scan_target = !a.no_scan_target;
+    opts.resume_journal = a.resume_journal;
+    opts.fingerprint_cache = a.fingerprint_cache;
     opts.cache_target_index = !a.no_cache_target_index;
     opts.enable_file_mapping = a.enable_file_mapping;
     opts.use_legacy_write = a.use_legacy_write;
     opts.worker_count = cli.worker_count;
     opts.remote_worker_count = cli.remote_worker_count;
     #[cfg(feature = "s3")]
-    if let Some(u) = &a.s3_endpoint_resolver_uri {
-        opts.s3_options.endpoint_url = Some(u.clone());
-    }
+    cli.apply_s3(a.s3_endpoint_resolver_uri.as_ref(), &mut opts.s3_options);
     #[cfg(feature = "s3")]
     if a.no_stalled_stream_protection {
         opts.s3_options.stalled_stream_protection = false;
     }
     opts.cancel = Some(install_cancel_handler());
-    let progress = Arc::new(CliProgress::new());
+    let progress = cli_progress(cli)?;
     opts.progress = Some(progress.clone());
+    if a.dry_run {
+        let result = plan_get(opts).await;
+        progress.finish(result.is_ok());
+        print_plan(cli, &result?);
+        return Ok(());
+    }
     let result = get(opts).await;
     progress.finish(result.is_ok());
-    let report = result?;
-    if cli.wants_stats() {
-        print_stats(&report);
-    }
+    print_report(cli, &result?);
     Ok(())
 }
 
-async fn run_ls(a: &LsArgs) -> Result<(), longtail::LongtailError> {
+async fn run_ls(cli: &Cli, a: &LsArgs) -> Result<(), longtail::LongtailError> {
     let vi = read_version_index_from_uri(
         &a.version_index_path,
-        &s3_read_options(a.s3_endpoint_resolver_uri.as_ref()),
+        &s3_read_options(cli, a.s3_endpoint_resolver_uri.as_ref()),
     )
     .await?;
     let search = match a.path.as_deref() {
         Some(".") | Some("") | None => String::new(),
         Some(p) => p.trim_end_matches('/').to_string(),
     };
-    for line in ls_entries(&vi, &search)? {
-        println!("{line}");
+    let globs = a
+        .globs
+        .iter()
+        .map(|g| PathGlob::new(g))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut entries = ls_entries(&vi, &search, a.recursive || !globs.is_empty())?;
+    if !globs.is_empty() {
+        entries.retain(|e| globs.iter().any(|g| g.matches(&e.path)));
+    }
+    match a.sort {
+        Some(LsSort::Name) => entries.sort_by(|x, y| x.path.cmp(&y.path)),
+        Some(LsSort::Size) => {
+            entries.sort_by(|x, y| y.size.cmp(&x.size).then_with(|| x.path.cmp(&y.path)))
+        }
+        None => {}
+    }
+    if cli.json() {
+        output::print_result(cli.command.name(), &entries);
+        return Ok(());
+    }
+    for e in &entries {
+        if a.long {
+            let details = details_string("", e.size, e.permissions, e.is_dir, 16);
+            println!(
+                "{details}{:>7} {:016x} {}",
+                e.chunk_count, e.content_hash, e.name
+            );
+        } else {
+            println!(
+                "{}",
+                details_string(&e.name, e.size, e.permissions, e.is_dir, 16)
+            );
+        }
     }
     Ok(())
 }
@@ -873,25 +1344,38 @@ async fn run_validate(cli: &Cli, a: &ValidateArgs) -> Result<(), longtail::Longt
     let mut opts = ValidateVersionOptions::new(a.storage_uri.clone(), a.version_index_path.clone());
     opts.remote_worker_count = cli.remote_worker_count;
     #[cfg(feature = "s3")]
-    if let Some(u) = &a.s3_endpoint_resolver_uri {
-        opts.s3_options.endpoint_url = Some(u.clone());
-    }
+    cli.apply_s3(a.s3_endpoint_resolver_uri.as_ref(), &mut opts.s3_options);
     #[cfg(feature = "s3")]
     if a.no_stalled_stream_protection {
         opts.s3_options.stalled_stream_protection = false;
     }
     validate_version(opts).await?;
-    println!("Version index `{}` is valid", a.version_index_path);
+    if cli.json() {
+        output::print_result(
+            cli.command.name(),
+            &ValidateDoc {
+                version_index_path: &a.version_index_path,
+                valid: true,
+            },
+        );
+    } else {
+        println!("Version index `{}` is valid", a.version_index_path);
+    }
     Ok(())
 }
 
-async fn run_print(a: &PrintArgs) -> Result<(), longtail::LongtailError> {
+async fn run_print(cli: &Cli, a: &PrintArgs) -> Result<(), longtail::LongtailError> {
     let vi = read_version_index_from_uri(
         &a.version_index_path,
-        &s3_read_options(a.s3_endpoint_resolver_uri.as_ref()),
+        &s3_read_options(cli, a.s3_endpoint_resolver_uri.as_ref()),
     )
     .await?;
-    print_version_index(&a.version_index_path, &vi, a.compact);
+    let summary = VersionSummary::of(&a.version_index_path, &vi);
+    if cli.json() {
+        output::print_result(cli.command.name(), &summary);
+    } else {
+        print_version_index(&summary, a.compact);
+    }
     Ok(())
 }
 
@@ -916,20 +1400,20 @@ async fn run_upsync(cli: &Cli, a: &UpsyncArgs) -> Result<(), longtail::LongtailE
     opts.worker_count = cli.worker_count;
     opts.remote_worker_count = cli.remote_worker_count;
     #[cfg(feature = "s3")]
-    if let Some(u) = &a.s3_endpoint_resolver_uri {
-        opts.s3_options.endpoint_url = Some(u.clone());
-    }
+    cli.apply_s3(a.s3_endpoint_resolver_uri.as_ref(), &mut opts.s3_options);
     #[cfg(feature = "s3")]
     if a.no_stalled_stream_protection {
         opts.s3_options.stalled_stream_protection = false;
     }
     opts.cancel = Some(install_cancel_handler());
-    let progress = Arc::new(CliProgress::new());
+    let progress = cli_progress(cli)?;
     opts.progress = Some(progress.clone());
     let result = longtail::upsync(opts).await;
     progress.finish(result.is_ok());
     let report = result?;
-    if cli.wants_stats() {
+    if cli.json() {
+        output::print_result(cli.command.name(), &report);
+    } else if cli.wants_stats() {
         eprintln!(
             "upsync complete: {} blocks written, {} bytes, target {}",
             report.blocks_written, report.bytes_written, report.target_path
@@ -958,15 +1442,15 @@ async fn run_put(cli: &Cli, a: &PutArgs) -> Result<(), longtail::LongtailError>
     opts.worker_count = cli.worker_count;
     opts.remote_worker_count = cli.remote_worker_count;
     #[cfg(feature = "s3")]
-    if let Some(u) = &a.s3_endpoint_resolver_uri {
-        opts.s3_options.endpoint_url = Some(u.clone());
-    }
-    let progress = Arc::new(CliProgress::new());
+    cli.apply_s3(a.s3_endpoint_resolver_uri.as_ref(), &mut opts.s3_options);
+    let progress = cli_progress(cli)?;
     opts.progress = Some(progress.clone());
     let result = longtail::put(opts).await;
     progress.finish(result.is_ok());
     let report = result?;
-    if cli.wants_stats() {
+    if cli.json() {
+        output::print_result(cli.command.name(), &report);
+    } else if cli.wants_stats() {
         eprintln!(
             "put complete: {} blocks written, get-config {}",
             report.blocks_written, a.target_path
@@ -979,11 +1463,12 @@ async fn run_init(cli: &Cli, a: &InitArgs) -> Result<(), longtail::LongtailError
     let mut opts = longtail::InitRemoteStoreOptions::new(a.storage_uri.clone());
     opts.remote_worker_count = cli.remote_worker_count;
     #[cfg(feature = "s3")]
-    if let Some(u) = &a.s3_endpoint_resolver_uri {
-        opts.s3_options.endpoint_url = Some(u.clone());
-    }
+    cli.apply_s3(a.s3_endpoint_resolver_uri.as_ref(), &mut opts.s3_options);
     let _ = &a.hash_algorithm; // accepted for parity; rebuild derives it from blocks
-    longtail::init_remote_store(opts).await?;
+    let block_count = longtail::init_remote_store(opts).await?;
+    if cli.json() {
+        output::print_result(cli.command.name(), &InitDoc { block_count });
+    }
     Ok(())
 }
 
@@ -995,10 +1480,17 @@ async fn run_create_vsi(cli: &Cli, a: &CreateVsiArgs) -> Result<(), longtail::Lo
     );
     opts.remote_worker_count = cli.remote_worker_count;
     #[cfg(feature = "s3")]
-    if let Some(u) = &a.s3_endpoint_resolver_uri {
-        opts.s3_options.endpoint_url = Some(u.clone());
+    cli.apply_s3(a.s3_endpoint_resolver_uri.as_ref(), &mut opts.s3_options);
+    longtail::create_version_store_index(opts).await?;
+    if cli.json() {
+        output::print_result(
+            cli.command.name(),
+            &WrittenDoc {
+                path: &a.version_local_store_index_path,
+            },
+        );
     }
-    longtail::create_version_store_index(opts).await
+    Ok(())
 }
 
 async fn run_prune_store(cli: &Cli, a: &PruneStoreArgs) -> Result<(), longtail::LongtailError> {
@@ -1014,11 +1506,11 @@ async fn run_prune_store(cli: &Cli, a: &PruneStoreArgs) -> Result<(), longtail::
     opts.allow_empty_keep_set = a.allow_empty_keep_set;
     opts.remote_worker_count = cli.remote_worker_count;
     #[cfg(feature = "s3")]
-    if let Some(u) = &a.s3_endpoint_resolver_uri {
-        opts.s3_options.endpoint_url = Some(u.clone());
-    }
+    cli.apply_s3(a.s3_endpoint_resolver_uri.as_ref(), &mut opts.s3_options);
     let r = longtail::prune_store(opts).await?;
-    if r.dry_run {
+    if cli.json() {
+        output::print_result(cli.command.name(), &r);
+    } else if r.dry_run {
         println!("Prune would keep {} blocks", r.keep_blocks);
     } else if cli.wants_stats() {
         eprintln!("Pruned {} blocks", r.pruned_blocks);
@@ -1026,7 +1518,10 @@ async fn run_prune_store(cli: &Cli, a: &PruneStoreArgs) -> Result<(), longtail::
     Ok(())
 }
 
-async fn run_prune_store_index(a: &PruneStoreIndexArgs) -> Result<(), longtail::LongtailError> {
+async fn run_prune_store_index(
+    cli: &Cli,
+    a: &PruneStoreIndexArgs,
+) -> Result<(), longtail::LongtailError> {
     let sources = read_lines_file(&a.source_paths)?;
     let mut opts = longtail::PruneStoreIndexOptions::new(a.store_index_path.clone(), sources);
     if let Some(p) = &a.version_local_store_index_paths {
@@ -1038,10 +1533,12 @@ async fn run_prune_store_index(a: &PruneStoreIndexArgs) -> Result<(), longtail::
     opts.skip_invalid_versions = a.skip_invalid_versions;
     opts.allow_empty_keep_set = a.allow_empty_keep_set;
     #[cfg(feature = "s3")]
-    if let Some(u) = &a.s3_endpoint_resolver_uri {
-        opts.s3_options.endpoint_url = Some(u.clone());
-    }
+    cli.apply_s3(a.s3_endpoint_resolver_uri.as_ref(), &mut opts.s3_options);
     let r = longtail::prune_store_index(opts).await?;
+    if cli.json() {
+        output::print_result(cli.command.name(), &r);
+        return Ok(());
+    }
     println!(
         "Pruned {} blocks out of {}",
         r.old_block_count - r.new_block_count,
@@ -1050,7 +1547,10 @@ async fn run_prune_store_index(a: &PruneStoreIndexArgs) -> Result<(), longtail::
     Ok(())
 }
 
-async fn run_prune_store_blocks(a: &PruneStoreBlocksArgs) -> Result<(), longtail::LongtailError> {
+async fn run_prune_store_blocks(
+    cli: &Cli,
+    a: &PruneStoreBlocksArgs,
+) -> Result<(), longtail::LongtailError> {
     let mut opts = longtail::PruneStoreBlocksOptions::new(
         a.store_index_path.clone(),
         a.blocks_root_path.clone(),
@@ -1058,10 +1558,12 @@ async fn run_prune_store_blocks(a: &PruneStoreBlocksArgs) -> Result<(), longtail
     opts.block_extension = a.block_extension.clone();
     opts.dry_run = a.dry_run;
     #[cfg(feature = "s3")]
-    if let Some(u) = &a.s3_endpoint_resolver_uri {
-        opts.s3_options.endpoint_url = Some(u.clone());
-    }
+    cli.apply_s3(a.s3_endpoint_resolver_uri.as_ref(), &mut opts.s3_options);
     let r = longtail::prune_store_blocks(opts).await?;
+    if cli.json() {
+        output::print_result(cli.command.name(), &r);
+        return Ok(());
+    }
     println!("Found {} blocks", r.found_blocks);
     println!("Found {} blocks to prune", r.blocks_to_prune);
     if !r.dry_run {
@@ -1097,33 +1599,51 @@ async fn run_clone_store(cli: &Cli, a: &CloneStoreArgs) -> Result<(), longtail::
     let _ = (&a.hash_algorithm, &a.compression_algorithm); // ignored (source-derived)
     #[cfg(feature = "s3")]
     {
-        if let Some(u) = &a.source_s3_endpoint_resolver_uri {
-            opts.source_s3_options.endpoint_url = Some(u.clone());
-        }
-        if let Some(u) = &a.target_s3_endpoint_resolver_uri {
-            opts.target_s3_options.endpoint_url = Some(u.clone());
-        }
+        cli.apply_s3(
+            a.source_s3_endpoint_resolver_uri.as_ref(),
+            &mut opts.source_s3_options,
+        );
+        cli.apply_s3(
+            a.target_s3_endpoint_resolver_uri.as_ref(),
+            &mut opts.target_s3_options,
+        );
     }
-    let progress = Arc::new(CliProgress::new());
+    let progress = cli_progress(cli)?;
     opts.progress = Some(progress.clone());
     let result = longtail::clone_store(opts).await;
     progress.finish(result.is_ok());
     let cloned = result?;
-    if cli.wants_stats() {
+    if cli.json() {
+        output::print_result(
+            cli.command.name(),
+            &CloneDoc {
+                versions_cloned: cloned,
+            },
+        );
+    } else if cli.wants_stats() {
         eprintln!("clone-store complete: {cloned} versions cloned");
     }
     Ok(())
 }
 
-async fn run_print_store(a: &PrintStoreArgs) -> Result<(), longtail::LongtailError> {
+async fn run_print_store(cli: &Cli, a: &PrintStoreArgs) -> Result<(), longtail::LongtailError> {
     let si = longtail::read_store_index_from_uri(
         &a.store_index_path,
-        &s3_read_options(a.s3_endpoint_resolver_uri.as_ref()),
+        &s3_read_options(cli, a.s3_endpoint_resolver_uri.as_ref()),
     )
     .await?;
     let s = longtail::store_index_stats(&si);
     let hash_str = hash_identifier_string(s.hash_identifier);
-    if a.compact {
+    if cli.json() {
+        output::print_result(
+            cli.command.name(),
+            &StoreDoc {
+                store_index_path: &a.store_index_path,
+                hash: hash_str,
+                stats: s,
+            },
+        );
+    } else if a.compact {
         let mut line = format!(
             "{}\t{}\t{}\t{}\t{}",
             a.store_index_path, s.version, hash_str, s.block_count, s.chunk_count
@@ -1175,10 +1695,12 @@ async fn run_print_version_usage(
     opts.cache_path = a.cache_path.clone().map(Into::into);
     opts.remote_worker_count = cli.remote_worker_count;
     #[cfg(feature = "s3")]
-    if let Some(u) = &a.s3_endpoint_resolver_uri {
-        opts.s3_options.endpoint_url = Some(u.clone());
-    }
+    cli.apply_s3(a.s3_endpoint_resolver_uri.as_ref(), &mut opts.s3_options);
     let stats = longtail::print_version_usage_stats(opts).await?;
+    if cli.json() {
+        output::print_result(cli.command.name(), &stats);
+        return Ok(());
+    }
     println!("Block Usage:          {}%", stats.block_usage_percent);
     println!(
         "Asset Fragmentation:  {}%",
@@ -1187,13 +1709,23 @@ async fn run_print_version_usage(
     Ok(())
 }
 
-async fn run_dump_version_assets(a: &DumpVersionAssetsArgs) -> Result<(), longtail::LongtailError> {
+async fn run_dump_version_assets(
+    cli: &Cli,
+    a: &DumpVersionAssetsArgs,
+) -> Result<(), longtail::LongtailError> {
     let vi = read_version_index_from_uri(
         &a.version_index_path,
-        &s3_read_options(a.s3_endpoint_resolver_uri.as_ref()),
+        &s3_read_options(cli, a.s3_endpoint_resolver_uri.as_ref()),
     )
     .await?;
     let asset_count = vi.asset_count() as usize;
+    if cli.json() {
+        let assets = (0..asset_count)
+            .map(|i| AssetEntry::of(&vi, i))
+            .collect::<Result<Vec<_>, _>>()?;
+        output::print_result(cli.command.name(), &assets);
+        return Ok(());
+    }
     let biggest = vi.asset_sizes.iter().copied().max().unwrap_or(0);
     let pad = biggest.to_string().len();
     for i in 0..asset_count {
@@ -1218,6 +1750,9 @@ async fn run_dump_version_assets(a: &DumpVersionAssetsArgs) -> Result<(), longta
 }
 
 async fn run_cp(cli: &Cli, a: &CpArgs) -> Result<(), longtail::LongtailError> {
+    if a.recursive || PathGlob::is_glob(&a.source_path) {
+        return run_cp_tree(cli, a).await;
+    }
     let mut opts = longtail::CpOptions::new(
         a.storage_uri.clone(),
         a.version_index_path.clone(),
@@ -1228,10 +1763,502 @@ async fn run_cp(cli: &Cli, a: &CpArgs) -> Result<(), longtail::LongtailError> {
     opts.remote_worker_count = cli.remote_worker_count;
     let _ = a.enable_file_mapping; // accepted for parity; no-op
     #[cfg(feature = "s3")]
-    if let Some(u) = &a.s3_endpoint_resolver_uri {
-        opts.s3_options.endpoint_url = Some(u.clone());
+    cli.apply_s3(a.s3_endpoint_resolver_uri.as_ref(), &mut opts.s3_options);
+    longtail::cp(opts).await?;
+    if cli.json() {
+        output::print_result(
+            cli.command.name(),
+            &CpDoc {
+                source_path: &a.source_path,
+                target_path: &a.target_path,
+            },
+        );
+    }
+    Ok(())
+}
+
+async fn run_cat(cli: &Cli, a: &CatArgs) -> Result<(), longtail::LongtailError> {
+    use tokio::io::AsyncWriteExt;
+
+    let mut opts = longtail::AssetStreamOptions::new(
+        a.storage_uri.clone(),
+        a.version_index_path.clone(),
+        a.source_path.clone(),
+    );
+    opts.read_ahead = a.read_ahead;
+    opts.cache_path = a.cache_path.clone().map(Into::into);
+    opts.remote_worker_count = cli.remote_worker_count;
+    #[cfg(feature = "s3")]
+    cli.apply_s3(a.s3_endpoint_resolver_uri.as_ref(), &mut opts.s3_options);
+    let mut stream = longtail::open_asset_stream(opts).await?;
+    let mut stdout = tokio::io::stdout();
+    // A read error wraps the `LongtailError` that stopped the fetch; hand that
+    // back so it is classified like any other failure.
+    let copied = match tokio::io::copy(&mut stream, &mut stdout).await {
+        Ok(_) => stdout.flush().await,
+        Err(e) => match e.downcast::<longtail::LongtailError>() {
+            Ok(e) => return Err(e),
+            Err(e) => Err(e),
+        },
+    };
+    match copied {
+        // `cat … | head`: the reader has what it wanted.
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+        other => other.map_err(|source| longtail::LongtailError::Io {
+            context: "write stdout".into(),
+            source,
+        }),
+    }
+}
+
+async fn run_serve_version(cli: &Cli, a: &ServeVersionArgs) -> Result<(), longtail::LongtailError> {
+    let mut opts =
+        longtail::VersionFsOptions::new(a.storage_uri.clone(), a.version_index_path.clone());
+    opts.block_cache_bytes = a.block_cache_bytes;
+    opts.cache_path = a.cache_path.clone().map(Into::into);
+    opts.remote_worker_count = cli.remote_worker_count;
+    #[cfg(feature = "s3")]
+    cli.apply_s3(a.s3_endpoint_resolver_uri.as_ref(), &mut opts.s3_options);
+    let listen_error = |source| longtail::LongtailError::Io {
+        context: format!("listen on {}", a.listen),
+        source,
+    };
+    let listener = tokio::net::TcpListener::bind(&a.listen)
+        .await
+        .map_err(listen_error)?;
+    let addr = listener.local_addr().map_err(listen_error)?;
+    let fs = Arc::new(longtail::VersionFs::open(opts).await?);
+    // The address goes to stdout first, so a script binding port 0 can read it.
+    let url = format!("http://{addr}/");
+    if cli.json() {
+        let doc = output::ServeDoc {
+            version_index_path: &a.version_index_path,
+            address: addr.to_string(),
+            url,
+        };
+        output::print_result(cli.command.name(), &doc);
+    } else {
+        println!("Serving {} on {url}", a.version_index_path);
+    }
+    let cancel = longtail::CancellationToken::new();
+    let watch = cancel.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            watch.cancel();
+        }
+    });
+    serve::serve(fs.clone(), listener, cancel).await;
+    // `serve` has joined its connections, so this is the last reference.
+    match Arc::into_inner(fs) {
+        Some(fs) => fs.close().await,
+        None => Ok(()),
+    }
+}
+
+async fn run_cp_tree(cli: &Cli, a: &CpArgs) -> Result<(), longtail::LongtailError> {
+    let mut opts = longtail::CpTreeOptions::new(
+        a.storage_uri.clone(),
+        a.version_index_path.clone(),
+        a.source_path.clone(),
+        a.target_path.clone(),
+    );
+    opts.cache_path = a.cache_path.clone().map(Into::into);
+    opts.remote_worker_count = cli.remote_worker_count;
+    #[cfg(feature = "s3")]
+    cli.apply_s3(a.s3_endpoint_resolver_uri.as_ref(), &mut opts.s3_options);
+    let report = longtail::cp_tree(opts).await?;
+    if cli.json() {
+        output::print_result(cli.command.name(), &report);
+        return Ok(());
+    }
+    println!(
+        "Copied {} files ({}) and {} directories into {}, fetching {} blocks",
+        report.file_count,
+        byte_count_binary(report.bytes),
+        report.directory_count,
+        a.target_path,
+        report.block_count
+    );
+    Ok(())
+}
+
+async fn run_diff_versions(cli: &Cli, a: &DiffVersionsArgs) -> Result<(), longtail::LongtailError> {
+    let mut opts = longtail::DiffVersionsOptions::new(a.from_path.clone(), a.to_path.clone());
+    opts.storage_uri = a.storage_uri.clone();
+    opts.remote_worker_count = cli.remote_worker_count;
+    #[cfg(feature = "s3")]
+    cli.apply_s3(a.s3_endpoint_resolver_uri.as_ref(), &mut opts.s3_options);
+    let report = longtail::diff_versions(opts).await?;
+    if cli.json() {
+        output::print_result(cli.command.name(), &report);
+        return Ok(());
+    }
+    let total =
+        |list: &[longtail::AssetChange]| byte_count_binary(list.iter().map(|c| c.size).sum());
+    let entry = |mark: char, c: &longtail::AssetChange| -> String {
+        let is_dir = c.path.ends_with('/');
+        let details = details_string(&c.path, c.size, c.permissions, is_dir, 16);
+        let was = match (c.previous_size, c.previous_permissions) {
+            (Some(size), _) => format!(" (was {size})"),
+            (_, Some(perms)) => format!(" (was {})", mode_string(perms, is_dir)),
+            _ => String::new(),
+        };
+        format!("  {mark} {details}{was}")
+    };
+    let section = |title: &str, mark: char, list: &[longtail::AssetChange], sized: bool| {
+        if sized {
+            println!("{title:<22}{} ({})", list.len(), total(list));
+        } else {
+            println!("{title:<22}{}", list.len());
+        }
+        for c in list {
+            println!("{}", entry(mark, c));
+        }
+    };
+    section("Added:", '+', &report.added, true);
+    section("Removed:", '-', &report.removed, true);
+    section("Content modified:", '~', &report.content_modified, true);
+    section(
+        "Permissions modified:",
+        '~',
+        &report.permissions_modified,
+        false,
+    );
+    println!(
+        "Required chunks:      {} ({})",
+        report.required_chunk_count,
+        byte_count_binary(report.required_chunk_bytes)
+    );
+    println!(
+        "New chunks:           {} ({})",
+        report.new_chunk_count,
+        byte_count_binary(report.new_chunk_bytes)
+    );
+    if let Some(d) = &report.download {
+        println!(
+            "Download:             {} blocks, {} compressed ({} payload)",
+            d.block_count,
+            byte_count_binary(d.compressed_bytes),
+            byte_count_binary(d.payload_bytes)
+        );
+        if d.unlisted_blocks > 0 {
+            println!(
+                "                      {} blocks not found in the store listing",
+                d.unlisted_blocks
+            );
+        }
+        if d.missing_chunks > 0 {
+            println!(
+                "                      {} chunks in no stored block: the update would fail",
+                d.missing_chunks
+            );
+        }
+    }
+    Ok(())
+}
+
+async fn run_explain_update(
+    cli: &Cli,
+    a: &ExplainUpdateArgs,
+) -> Result<(), longtail::LongtailError> {
+    let mut opts = longtail::ExplainUpdateOptions::new(
+        a.from_path.clone(),
+        a.to_path.clone(),
+        a.storage_uri.clone(),
+    );
+    opts.remote_worker_count = cli.remote_worker_count;
+    #[cfg(feature = "s3")]
+    cli.apply_s3(a.s3_endpoint_resolver_uri.as_ref(), &mut opts.s3_options);
+    let report = longtail::explain_update(opts).await?;
+    if cli.json() {
+        output::print_result(cli.command.name(), &report);
+        return Ok(());
     }
-    longtail::cp(opts).await
+    let total = &report.total;
+    let payload = total.needed_bytes + total.unneeded_bytes;
+    let percent = |n: u64| (100 * n).checked_div(payload).unwrap_or(0);
+    println!(
+        "Download:             {} blocks, {} compressed ({} payload)",
+        report.block_count,
+        byte_count_binary(total.compressed_bytes),
+        byte_count_binary(payload)
+    );
+    println!(
+        "Needed:               {} ({}%)",
+        byte_count_binary(total.needed_bytes),
+        percent(total.needed_bytes)
+    );
+    println!(
+        "Unneeded:             {} ({}%) sharing blocks with needed chunks",
+        byte_count_binary(total.unneeded_bytes),
+        percent(total.unneeded_bytes)
+    );
+    if report.missing_chunks > 0 {
+        println!(
+            "                      {} chunks in no stored block: the update would fail",
+            report.missing_chunks
+        );
+    }
+    let top = if a.top == 0 { usize::MAX } else { a.top };
+    let heading = |title: &str, shown: usize, of: usize| {
+        println!();
+        if shown < of {
+            println!("{title} (top {shown} of {of}):");
+        } else {
+            println!("{title}:");
+        }
+        println!(
+            "  {:>10} {:>10} {:>10}  Path",
+            "Compressed", "Needed", "Unneeded"
+        );
+    };
+    let row = |c: &longtail::UpdateCost, path: &str| {
+        println!(
+            "  {:>10} {:>10} {:>10}  {path}",
+            byte_count_binary(c.compressed_bytes),
+            byte_count_binary(c.needed_bytes),
+            byte_count_binary(c.unneeded_bytes)
+        );
+    };
+    let assets = &report.assets[..report.assets.len().min(top)];
+    heading("Assets", assets.len(), report.assets.len());
+    for x in assets {
+        row(&x.cost, &x.path);
+    }
+    let directories = &report.directories[..report.directories.len().min(top)];
+    heading("Directories", directories.len(), report.directories.len());
+    for x in directories {
+        row(&x.cost, &x.path);
+    }
+    let blocks = &report.blocks[..report.blocks.len().min(top)];
+    println!();
+    if blocks.len() < report.blocks.len() {
+        println!(
+            "Blocks by unneeded bytes (top {} of {}):",
+            blocks.len(),
+            report.blocks.len()
+        );
+    } else {
+        println!("Blocks by unneeded bytes:");
+    }
+    for b in blocks {
+        println!(
+            "  0x{:016x}  {:>3}% used, {} unneeded, {}/{} chunks, {} compressed",
+            b.block_hash,
+            b.usage_percent(),
+            byte_count_binary(b.cost.unneeded_bytes),
+            b.needed_chunk_count,
+            b.chunk_count,
+            byte_count_binary(b.cost.compressed_bytes)
+        );
+        for path in &b.assets {
+            println!("      {path}");
+        }
+    }
+    Ok(())
+}
+
+async fn run_list_versions(cli: &Cli, a: &ListVersionsArgs) -> Result<(), longtail::LongtailError> {
+    let mut opts = longtail::ListVersionsOptions::new(a.source_path.clone());
+    opts.prefix = a.prefix.clone();
+    opts.storage_uri = a.storage_uri.clone();
+    opts.remote_worker_count = cli.remote_worker_count;
+    #[cfg(feature = "s3")]
+    cli.apply_s3(a.s3_endpoint_resolver_uri.as_ref(), &mut opts.s3_options);
+    let mut versions = longtail::list_versions(opts).await?;
+    match a.sort {
+        VersionSort::Path => {}
+        VersionSort::Modified => versions.sort_by_key(|v| std::cmp::Reverse(v.last_modified)),
+        VersionSort::Size => versions.sort_by_key(|v| std::cmp::Reverse(v.total_asset_size)),
+        VersionSort::Assets => versions.sort_by_key(|v| std::cmp::Reverse(v.asset_count)),
+    }
+    let docs: Vec<PublishedVersionDoc> = versions
+        .iter()
+        .map(|v| PublishedVersionDoc {
+            hash: hash_identifier_string(v.hash_identifier),
+            compression: v
+                .compression_types
+                .iter()
+                .map(|&id| compression_string(id))
+                .collect(),
+            version: v,
+        })
+        .collect();
+    if cli.json() {
+        output::print_result(cli.command.name(), &docs);
+        return Ok(());
+    }
+    println!(
+        "{:<19}  {:>10}  {:>7}  {:>10}  {:>7}  {:<6}  {:<11}  Path",
+        "Modified", "Object", "Assets", "Size", "Chunks", "Hash", "Compression"
+    );
+    for d in &docs {
+        let v = d.version;
+        let modified = v.last_modified.map_or("-".to_string(), utc_timestamp);
+        let config = v
+            .get_config
+            .as_ref()
+            .map_or(String::new(), |c| format!(" ({c})"));
+        if let Some(e) = &v.error {
+            println!(
+                "{modified:<19}  {:>10}  unreadable: {e}  {}{config}",
+                byte_count_binary(v.object_size),
+                v.path
+            );
+            continue;
+        }
+        let compression = if d.compression.is_empty() {
+            "-".to_string()
+        } else {
+            d.compression.join(",")
+        };
+        println!(
+            "{modified:<19}  {:>10}  {:>7}  {:>10}  {:>7}  {:<6}  {:<11}  {}{config}",
+            byte_count_binary(v.object_size),
+            v.asset_count,
+            byte_count_binary(v.total_asset_size),
+            v.chunk_count,
+            d.hash,
+            compression,
+            v.path
+        );
+    }
+    Ok(())
+}
+
+async fn run_store_du(cli: &Cli, a: &StoreDuArgs) -> Result<(), longtail::LongtailError> {
+    let sources = read_lines_file(&a.source_paths)?;
+    let mut opts = longtail::StoreDuOptions::new(a.storage_uri.clone(), sources);
+    opts.remote_worker_count = cli.remote_worker_count;
+    #[cfg(feature = "s3")]
+    cli.apply_s3(a.s3_endpoint_resolver_uri.as_ref(), &mut opts.s3_options);
+    let report = longtail::store_du(opts).await?;
+    if cli.json() {
+        output::print_result(cli.command.name(), &report);
+        return Ok(());
+    }
+    println!(
+        "{:>7}  {:>10}  {:>7}  {:>10}  {:>10}  Version",
+        "Blocks", "Bytes", "Unique", "Unique B", "Shared B"
+    );
+    for v in &report.versions {
+        let missing = if v.missing_chunks > 0 {
+            format!(" ({} chunks missing)", v.missing_chunks)
+        } else {
+            String::new()
+        };
+        println!(
+            "{:>7}  {:>10}  {:>7}  {:>10}  {:>10}  {}{missing}",
+            v.block_count,
+            byte_count_binary(v.bytes),
+            v.unique_block_count,
+            byte_count_binary(v.unique_bytes),
+            byte_count_binary(v.shared_bytes),
+            v.path
+        );
+    }
+    println!(
+        "{:>7}  {:>10}  referenced by any of these",
+        report.block_count,
+        byte_count_binary(report.bytes)
+    );
+    if !report.overlaps.is_empty() {
+        println!();
+        println!("Shared between:");
+        for o in &report.overlaps {
+            println!(
+                "{:>7}  {:>10}  {} and {}",
+                o.block_count,
+                byte_count_binary(o.bytes),
+                report.versions[o.first].path,
+                report.versions[o.second].path
+            );
+        }
+    }
+    Ok(())
+}
+
+async fn run_who_uses(cli: &Cli, a: &WhoUsesArgs) -> Result<(), longtail::LongtailError> {
+    let target = match (a.block_hash, a.chunk_hash, &a.asset_path) {
+        (Some(h), _, _) => longtail::UsageTarget::Block(h),
+        (_, Some(h), _) => longtail::UsageTarget::Chunk(h),
+        (_, _, Some(p)) => longtail::UsageTarget::Path(p.clone()),
+        // clap's `target` group requires one.
+        _ => unreachable!(),
+    };
+    if a.source_paths.is_none() && a.versions_uri.is_none() {
+        return Err(longtail::LongtailError::InvalidArgument(
+            "who-uses needs versions to search: --source-paths or --versions-uri".into(),
+        ));
+    }
+    let sources = match &a.source_paths {
+        Some(p) => read_lines_file(p)?,
+        None => Vec::new(),
+    };
+    let mut opts = longtail::WhoUsesOptions::new(a.storage_uri.clone(), target, sources);
+    opts.versions_uri = a.versions_uri.clone();
+    opts.versions_prefix = a.versions_prefix.clone();
+    opts.remote_worker_count = cli.remote_worker_count;
+    #[cfg(feature = "s3")]
+    cli.apply_s3(a.s3_endpoint_resolver_uri.as_ref(), &mut opts.s3_options);
+    let report = longtail::who_uses(opts).await?;
+    if cli.json() {
+        output::print_result(cli.command.name(), &report);
+        return Ok(());
+    }
+    for s in &report.skipped {
+        eprintln!("warning: skipped {}: {}", s.path, s.error);
+    }
+    println!(
+        "{} of {} versions depend on it",
+        report.users.len(),
+        report.versions_checked
+    );
+    for u in &report.users {
+        let blocks: Vec<String> = u.blocks.iter().map(|b| format!("0x{b:016x}")).collect();
+        println!();
+        println!("{}", u.path);
+        if !blocks.is_empty() {
+            println!("  blocks: {}", blocks.join(", "));
+        }
+        for asset in &u.assets {
+            println!("  {asset}");
+        }
+    }
+    Ok(())
+}
+
+fn run_config_show(cli: &Cli, a: &ConfigShowArgs) -> Result<(), longtail::LongtailError> {
+    let applied = &cli.applied;
+    let flags = applied
+        .effective(&Cli::command(), a.subcommand.as_deref())
+        .map_err(longtail::LongtailError::InvalidArgument)?;
+    if cli.json() {
+        output::print_result(
+            cli.command.name(),
+            &ConfigDoc {
+                config_path: applied.path.as_ref().map(|p| p.display().to_string()),
+                profile: applied.profile.as_deref(),
+                subcommand: a.subcommand.as_deref(),
+                flags,
+            },
+        );
+        return Ok(());
+    }
+    match &applied.path {
+        Some(path) => println!("config file: {}", path.display()),
+        None => println!("config file: none"),
+    }
+    println!(
+        "profile:     {}",
+        applied.profile.as_deref().unwrap_or("none")
+    );
+    for f in &flags {
+        for value in &f.values {
+            println!("--{:<36} {value}  ({})", f.flag, f.source);
+        }
+    }
+    Ok(())
 }
 
 // ---- ls / print-version formatting (golongtail-compatible) ----
@@ -1245,8 +2272,46 @@ fn hash_identifier_string(id: u32) -> String {
     }
 }
 
+fn compression_string(id: u32) -> String {
+    longtail::compression_name_for_type(id).map_or_else(|| format!("{id:#x}"), str::to_string)
+}
+
+/// `secs` since the Unix epoch as `YYYY-MM-DD HH:MM:SS`, UTC.
+fn utc_timestamp(secs: u64) -> String {
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+    // Days to a civil date (Howard Hinnant's `civil_from_days`).
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}",
+        rem / 3_600,
+        rem % 3_600 / 60,
+        rem % 60
+    )
+}
+
 /// `GetDetailsString` (longtailutils/stats.go:48): `{rwx-bits} {size:>pad} {name}`.
 fn details_string(name: &str, size: u64, perms: u16, is_dir: bool, pad: usize) -> String {
+    let bits = mode_string(perms, is_dir);
+    let size_s = size.to_string();
+    let size_padded = if size_s.len() < pad {
+        format!("{}{}", " ".repeat(pad - size_s.len()), size_s)
+    } else {
+        size_s
+    };
+    let name = name.trim_end_matches('/');
+    format!("{bits} {size_padded} {name}")
+}
+
+/// `ls -l`-style mode bits: `drwxr-xr-x`.
+fn mode_string(perms: u16, is_dir: bool) -> String {
     let mut bits = String::with_capacity(10);
     bits.push(if is_dir { 'd' } else { '-' });
     const MASKS: [(u16, char); 9] = [
@@ -1263,42 +2328,30 @@ fn details_string(name: &str, size: u64, perms: u16, is_dir: bool, pad: usize) -
     for (m, c) in MASKS {
         bits.push(if perms & m != 0 { c } else { '-' });
     }
-    let size_s = size.to_string();
-    let size_padded = if size_s.len() < pad {
-        format!("{}{}", " ".repeat(pad - size_s.len()), size_s)
-    } else {
-        size_s
-    };
-    let name = name.trim_end_matches('/');
-    format!("{bits} {size_padded} {name}")
+    bits
 }
 
-/// List the single directory level under `search` inside `vi`.
-fn ls_entries(vi: &VersionIndex, search: &str) -> Result<Vec<String>, longtail::LongtailError> {
+/// List the directory level under `search` inside `vi`, or with `recursive`
+/// everything below it, each named by its path relative to `search`.
+fn ls_entries(
+    vi: &VersionIndex,
+    search: &str,
+    recursive: bool,
+) -> Result<Vec<AssetEntry>, longtail::LongtailError> {
+    let prefix = format!("{search}/");
     let mut out = Vec::new();
     for i in 0..vi.asset_count() as usize {
-        let raw = vi.path(i)?;
-        let is_dir = raw.ends_with('/');
-        let stripped = raw.trim_end_matches('/');
-        let name = if search.is_empty() {
-            if stripped.contains('/') {
-                continue;
-            }
-            stripped
+        let entry = AssetEntry::of(vi, i)?;
+        let rem = if search.is_empty() {
+            Some(entry.path.as_str())
         } else {
-            let prefix = format!("{search}/");
-            match stripped.strip_prefix(&prefix) {
-                Some(rem) if !rem.contains('/') => rem,
-                _ => continue,
-            }
+            entry.path.strip_prefix(&prefix)
         };
-        out.push(details_string(
-            name,
-            vi.asset_sizes[i],
-            vi.permissions[i].bits(),
-            is_dir,
-            16,
-        ));
+        let name = match rem {
+            Some(rem) if recursive || !rem.contains('/') => rem.to_string(),
+            _ => continue,
+        };
+        out.push(AssetEntry { name, ..entry });
     }
     Ok(out)
 }
@@ -1337,28 +2390,18 @@ fn byte_count_binary(n: u64) -> String {
     format!("{:.1} {}B", n as f64 / div as f64, suffix)
 }
 
-fn print_version_index(path: &str, vi: &VersionIndex, compact: bool) {
-    let version = longtail_core::VERSION_INDEX_VERSION;
-    let hash_str = hash_identifier_string(vi.hash_identifier);
-    let tcs = vi.target_chunk_size;
-    let asset_count = vi.asset_count();
-    let total_asset_size: u64 = vi.asset_sizes.iter().sum();
-    let chunk_count = vi.chunk_count();
-    let total_chunk_size: u64 = vi.chunk_sizes.iter().map(|&s| s as u64).sum();
-    let (mut smallest, mut largest) = (u32::MAX, 0u32);
-    for &s in &vi.chunk_sizes {
-        smallest = smallest.min(s);
-        largest = largest.max(s);
-    }
-    if vi.chunk_sizes.is_empty() {
-        smallest = 0;
-    }
-    let avg = if chunk_count == 0 {
-        0
-    } else {
-        (total_chunk_size / chunk_count as u64) as u32
-    };
-
+fn print_version_index(v: &VersionSummary, compact: bool) {
+    let path = &v.path;
+    let version = v.version;
+    let hash_str = &v.hash;
+    let tcs = v.target_chunk_size;
+    let asset_count = v.asset_count;
+    let total_asset_size = v.total_asset_size;
+    let chunk_count = v.chunk_count;
+    let total_chunk_size = v.total_chunk_size;
+    let avg = v.average_chunk_size;
+    let smallest = v.smallest_chunk_size;
+    let largest = v.largest_chunk_size;
     if compact {
         println!(
             "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
@@ -1409,6 +2452,53 @@ fn print_version_index(path: &str, vi: &VersionIndex, compact: bool) {
     }
 }
 
+/// The `--dry-run` summary for `downsync`/`get`, on stdout.
+fn print_plan(cli: &Cli, plan: &longtail::DownsyncPlan) {
+    if cli.json() {
+        output::print_result(cli.command.name(), plan);
+        return;
+    }
+    let delta = if plan.disk_delta < 0 {
+        format!("-{}", byte_count_binary(plan.disk_delta.unsigned_abs()))
+    } else {
+        format!("+{}", byte_count_binary(plan.disk_delta as u64))
+    };
+    println!("Target:            {}", plan.target_path);
+    println!(
+        "Assets to write:   {} ({} added, {} modified)",
+        plan.assets_to_write,
+        plan.diff.added.len(),
+        plan.diff.modified.len()
+    );
+    println!("Assets to delete:  {}", plan.assets_to_delete);
+    println!(
+        "Permission fixes:  {}",
+        plan.diff.permissions_modified.len()
+    );
+    println!(
+        "Blocks required:   {} ({} cached)",
+        plan.required_blocks.len(),
+        plan.cached_blocks
+    );
+    println!(
+        "Bytes to fetch:    {}   ({} from cache)",
+        byte_count_binary(plan.fetch_bytes),
+        byte_count_binary(plan.cached_bytes)
+    );
+    println!("Bytes to write:    {}", byte_count_binary(plan.write_bytes));
+    println!("Disk delta:        {delta}");
+}
+
+/// A finished `downsync`/`get`: the whole report in JSON mode, the
+/// `--show-stats` summary on stderr otherwise.
+fn print_report(cli: &Cli, report: &longtail::DownsyncReport) {
+    if cli.json() {
+        output::print_result(cli.command.name(), report);
+    } else if cli.wants_stats() {
+        print_stats(report);
+    }
+}
+
 fn print_stats(report: &longtail::DownsyncReport) {
     eprintln!(
         "downsync complete: {} assets written, {} removed, {} bytes, {} blocks fetched",
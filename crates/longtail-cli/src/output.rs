@@ -0,0 +1,241 @@
+//! `--output json`: one JSON document on stdout per invocation, for pipelines
+//! that would otherwise scrape the human text.
+//!
+//! Every document is an envelope —
+//! `{"schema_version", "command", "ok", "result" | "error"}` — so a consumer can
+//! dispatch on `ok` before it knows the command's result shape. The result is
+//! the facade's own report type (`DownsyncReport`, `StoreIndexStats`, …) as
+//! serde writes it. `schema_version` changes only when an existing field is
+//! renamed, retyped or removed; added fields do not bump it.
+//!
+//! Progress bars, warnings and logs stay on stderr in both formats, so stdout
+//! holds the document and nothing else.
+
+use clap::ValueEnum;
+use longtail_core::VersionIndex;
+use serde::Serialize;
+
+/// Bumped on any incompatible change to an envelope or a result shape.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// `--output`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// The golongtail-compatible text.
+    #[default]
+    Text,
+    /// One versioned JSON document on stdout.
+    Json,
+}
+
+#[derive(Serialize)]
+struct Envelope<'a, T: Serialize> {
+    schema_version: u32,
+    command: &'a str,
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<ErrorDoc>,
+}
+
+/// A failed command, classified the way a caller should act on it.
+#[derive(Serialize)]
+struct ErrorDoc {
+    /// What to do about it; see [`longtail::ErrorClass`].
+    class: longtail::ErrorClass,
+    /// The whole chain on one line, as text mode prints it.
+    message: String,
+    /// Each level of the `source()` chain, outermost first.
+    chain: Vec<String>,
+}
+
+fn emit<T: Serialize>(envelope: &Envelope<'_, T>) {
+    match serde_json::to_string(envelope) {
+        Ok(s) => println!("{s}"),
+        // Only a non-string map key or a failing `Serialize` impl gets here;
+        // neither is reachable from the report types.
+        Err(e) => eprintln!("error: serializing --output json: {e}"),
+    }
+}
+
+/// Print `result` as the successful outcome of `command`.
+pub fn print_result(command: &str, result: &impl Serialize) {
+    emit(&Envelope {
+        schema_version: SCHEMA_VERSION,
+        command,
+        ok: true,
+        result: Some(result),
+        error: None,
+    });
+}
+
+/// Print `e` as the outcome of `command`.
+pub fn print_error(command: &str, e: &longtail::LongtailError) {
+    let mut chain = Vec::new();
+    let mut level: Option<&dyn std::error::Error> = Some(e);
+    while let Some(err) = level {
+        chain.push(err.to_string());
+        level = err.source();
+    }
+    emit(&Envelope::<()> {
+        schema_version: SCHEMA_VERSION,
+        command,
+        ok: false,
+        result: None,
+        error: Some(ErrorDoc {
+            class: e.class(),
+            message: e.full_chain(),
+            chain,
+        }),
+    });
+}
+
+// ---- result shapes for the commands whose text has no facade type behind it ----
+
+/// One `ls` / `dump-version-assets` entry.
+#[derive(Debug, Clone, Serialize)]
+pub struct AssetEntry {
+    /// `/`-joined path from the version root, without a trailing `/`.
+    pub path: String,
+    /// The last component of `path`, or the path below the listed directory
+    /// for `ls`.
+    pub name: String,
+    pub size: u64,
+    /// POSIX permission bits.
+    pub permissions: u16,
+    pub is_dir: bool,
+    pub chunk_count: u32,
+    /// The asset's content hash; a directory's and an empty file's are the
+    /// hash of no bytes.
+    pub content_hash: u64,
+}
+
+impl AssetEntry {
+    pub fn of(vi: &VersionIndex, i: usize) -> Result<AssetEntry, longtail::LongtailError> {
+        let raw = vi.path(i)?;
+        let path = raw.trim_end_matches('/');
+        Ok(AssetEntry {
+            path: path.to_string(),
+            name: path.rsplit('/').next().unwrap_or(path).to_string(),
+            size: vi.asset_sizes[i],
+            permissions: vi.permissions[i].bits(),
+            is_dir: raw.ends_with('/'),
+            chunk_count: vi.asset_chunk_counts[i],
+            content_hash: vi.content_hashes[i],
+        })
+    }
+}
+
+/// `print-version`'s numbers.
+#[derive(Debug, Clone, Serialize)]
+pub struct VersionSummary {
+    pub path: String,
+    pub version: u32,
+    /// `blake3`, `blake2` or `meow`; the numeric id for anything else.
+    pub hash: String,
+    pub target_chunk_size: u32,
+    pub asset_count: u32,
+    pub total_asset_size: u64,
+    pub chunk_count: u32,
+    pub total_chunk_size: u64,
+    pub average_chunk_size: u32,
+    pub smallest_chunk_size: u32,
+    pub largest_chunk_size: u32,
+}
+
+impl VersionSummary {
+    pub fn of(path: &str, vi: &VersionIndex) -> VersionSummary {
+        let chunk_count = vi.chunk_count();
+        let total_chunk_size: u64 = vi.chunk_sizes.iter().map(|&s| s as u64).sum();
+        VersionSummary {
+            path: path.to_string(),
+            version: longtail_core::VERSION_INDEX_VERSION,
+            hash: crate::hash_identifier_string(vi.hash_identifier),
+            target_chunk_size: vi.target_chunk_size,
+            asset_count: vi.asset_count(),
+            total_asset_size: vi.asset_sizes.iter().sum(),
+            chunk_count,
+            total_chunk_size,
+            average_chunk_size: if chunk_count == 0 {
+                0
+            } else {
+                (total_chunk_size / chunk_count as u64) as u32
+            },
+            smallest_chunk_size: vi.chunk_sizes.iter().copied().min().unwrap_or(0),
+            largest_chunk_size: vi.chunk_sizes.iter().copied().max().unwrap_or(0),
+        }
+    }
+}
+
+/// `print-store`: the facade's stats plus what the text output shows beside them.
+#[derive(Serialize)]
+pub struct StoreDoc<'a> {
+    pub store_index_path: &'a str,
+    pub hash: String,
+    #[serde(flatten)]
+    pub stats: longtail::StoreIndexStats,
+}
+
+/// One `list-versions` row: the facade's numbers plus the names the text
+/// output shows for its IDs.
+#[derive(Serialize)]
+pub struct PublishedVersionDoc<'a> {
+    #[serde(flatten)]
+    pub version: &'a longtail::PublishedVersion,
+    pub hash: String,
+    pub compression: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct VersionDoc {
+    pub version: &'static str,
+}
+
+#[derive(Serialize)]
+pub struct ValidateDoc<'a> {
+    pub version_index_path: &'a str,
+    pub valid: bool,
+}
+
+/// `init-remote-store`: the rebuilt store index's block count.
+#[derive(Serialize)]
+pub struct InitDoc {
+    pub block_count: u32,
+}
+
+/// A command whose result is one file it wrote.
+#[derive(Serialize)]
+pub struct WrittenDoc<'a> {
+    pub path: &'a str,
+}
+
+#[derive(Serialize)]
+pub struct CloneDoc {
+    pub versions_cloned: u32,
+}
+
+#[derive(Serialize)]
+pub struct CpDoc<'a> {
+    pub source_path: &'a str,
+    pub target_path: &'a str,
+}
+
+/// `serve-version`: where the version is being served, printed once the
+/// listener is bound.
+#[derive(Serialize)]
+pub struct ServeDoc<'a> {
+    pub version_index_path: &'a str,
+    /// The bound address, with the port picked when `--listen` gave 0.
+    pub address: String,
+    pub url: String,
+}
+
+/// `config show`: the file and profile read, and each flag's effective value.
+#[derive(Serialize)]
+pub struct ConfigDoc<'a> {
+    pub config_path: Option<String>,
+    pub profile: Option<&'a str>,
+    pub subcommand: Option<&'a str>,
+    pub flags: Vec<crate::config::Effective>,
+}
@@ -9,6 +9,10 @@
 //! Phases that report no progress (reading indexes, validating) show a plain
 //! spinner + phase name. When stderr is not a terminal (piped / CI) it falls back
 //! to a single throttled line carrying both metrics.
+//!
+//! `--progress-format jsonl` is for a process wrapping the CLI: one JSON object
+//! per line — phases, the same throttled counter samples with a rate and ETA,
+//! and per-asset events — on stderr or on the descriptor `--progress-fd` names.
 
 use std::io::{self, IsTerminal, Write};
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -16,8 +20,10 @@ use std::sync::{Mutex, OnceLock};
 use std::time::{Duration, Instant};
 
 use bytesize::ByteSize;
+use clap::ValueEnum;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
-use longtail::{Progress, ProgressSink};
+use longtail::{BlockSource, Event, Progress, ProgressSink};
+use serde::Serialize;
 use tracing_subscriber::fmt::MakeWriter;
 
 /// Fixed phase-label column width (the longest label, "Reading full store
@@ -69,6 +75,18 @@ impl Write for SuspendedStderr {
     }
 }
 
+/// `--progress-format`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ProgressFormat {
+    /// A bar on a terminal, plain lines otherwise.
+    #[default]
+    Auto,
+    /// Throttled plain-text lines, even on a terminal.
+    Plain,
+    /// Newline-delimited JSON objects, for a wrapping process.
+    Jsonl,
+}
+
 /// A terminal-aware progress sink for the CLI.
 pub enum CliProgress {
     /// Interactive: one indicatif bar carrying phase + item count + byte rate.
@@ -80,19 +98,211 @@ pub enum CliProgress {
     },
     /// Non-interactive: throttled plain-text lines on stderr.
     Plain(Mutex<PlainState>),
+    /// Machine-readable: throttled JSON lines on `out`.
+    Jsonl(Mutex<JsonlState>),
 }
 
 /// State for the non-TTY fallback (guarded by a `Mutex` since `ProgressSink`
 /// takes `&self`).
 pub struct PlainState {
     phase: String,
-    /// Last printed decile of the driving dimension, or `-1` before the first
-    /// print.
+    throttle: Throttle,
+}
+
+/// State for the JSON-lines renderer.
+pub struct JsonlState {
+    out: Box<dyn Write + Send>,
+    phase: String,
+    throttle: Throttle,
+    /// When the run began; every line carries the time since.
+    start: Instant,
+}
+
+/// The plain and JSON-lines cadence: one sample per decile of the driving
+/// dimension (items when known, else bytes), plus the terminal one.
+struct Throttle {
+    /// Last emitted decile, or `-1` before the phase's first sample.
     last_decile: i32,
-    /// When the current phase began (for the average byte rate).
+    /// When the current phase began (for the average rate).
     phase_start: Instant,
 }
 
+impl Throttle {
+    fn new() -> Throttle {
+        Throttle {
+            last_decile: -1,
+            phase_start: Instant::now(),
+        }
+    }
+
+    /// Start a new phase.
+    fn reset(&mut self) {
+        *self = Throttle::new();
+    }
+
+    /// Whether `p` should be shown.
+    fn admit(&mut self, p: &Progress) -> bool {
+        let (done, total) = if p.total_items != 0 {
+            (p.done_items, p.total_items)
+        } else {
+            (p.done_bytes, p.total_bytes)
+        };
+        let terminal = total != 0 && done >= total;
+        let decile = done
+            .checked_mul(10)
+            .and_then(|x| x.checked_div(total))
+            .unwrap_or(0) as i32;
+        if self.last_decile != decile || terminal {
+            self.last_decile = decile;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Average bytes per second since the phase began.
+    fn byte_rate(&self, p: &Progress) -> u64 {
+        let secs = self.phase_start.elapsed().as_secs_f64();
+        if secs > 0.0 {
+            (p.done_bytes as f64 / secs) as u64
+        } else {
+            0
+        }
+    }
+
+    /// Seconds left at the phase's average pace so far, by whichever dimension
+    /// is known (bytes first); `None` before there is a pace to go by.
+    fn eta_secs(&self, p: &Progress) -> Option<u64> {
+        let (done, total) = if p.total_bytes != 0 {
+            (p.done_bytes, p.total_bytes)
+        } else {
+            (p.done_items, p.total_items)
+        };
+        if total == 0 || done == 0 {
+            return None;
+        }
+        let elapsed = self.phase_start.elapsed().as_secs_f64();
+        let left = total.saturating_sub(done) as f64 * elapsed / done as f64;
+        Some(left.ceil() as u64)
+    }
+}
+
+/// One line of `--progress-format jsonl`.
+#[derive(Serialize)]
+struct JsonLine<'a> {
+    /// Milliseconds since the run began.
+    elapsed_ms: u64,
+    #[serde(flatten)]
+    event: JsonEvent<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum JsonEvent<'a> {
+    Phase {
+        phase: &'a str,
+    },
+    Progress {
+        phase: &'a str,
+        done_items: u64,
+        total_items: u64,
+        done_bytes: u64,
+        total_bytes: u64,
+        bytes_per_sec: u64,
+        eta_secs: Option<u64>,
+    },
+    AssetReady {
+        path: &'a str,
+    },
+    AssetStarted {
+        path: &'a str,
+    },
+    AssetCompleted {
+        path: &'a str,
+        bytes: u64,
+    },
+    AssetFailed {
+        path: &'a str,
+        error: String,
+    },
+    AssetDeleted {
+        path: &'a str,
+    },
+    AssetHashed {
+        path: &'a str,
+        bytes: u64,
+    },
+    BlockFetched {
+        block_hash: String,
+        from_cache: bool,
+        wire_bytes: u64,
+    },
+    Retry {
+        block_hash: String,
+        attempt: u32,
+    },
+    Warning {
+        message: &'a str,
+    },
+}
+
+/// A block hash as the store names its file (`0x…`): as a JSON number it would
+/// exceed the integers a double-based reader holds exactly.
+fn hex_hash(block_hash: u64) -> String {
+    format!("0x{block_hash:016x}")
+}
+
+impl<'a> JsonEvent<'a> {
+    fn of(event: &Event<'a>) -> Option<JsonEvent<'a>> {
+        Some(match *event {
+            Event::AssetStarted { path } => JsonEvent::AssetStarted { path },
+            Event::AssetCompleted { path, bytes } => JsonEvent::AssetCompleted { path, bytes },
+            Event::AssetFailed { path, error } => JsonEvent::AssetFailed {
+                path,
+                error: error.full_chain(),
+            },
+            Event::AssetDeleted { path } => JsonEvent::AssetDeleted { path },
+            Event::AssetHashed { path, bytes } => JsonEvent::AssetHashed { path, bytes },
+            Event::BlockFetched {
+                block_hash,
+                source,
+                wire_bytes,
+            } => JsonEvent::BlockFetched {
+                block_hash: hex_hash(block_hash),
+                from_cache: source == BlockSource::Cache,
+                wire_bytes,
+            },
+            Event::Retry {
+                block_hash,
+                attempt,
+            } => JsonEvent::Retry {
+                block_hash: hex_hash(block_hash),
+                attempt,
+            },
+            Event::Warning { message } => JsonEvent::Warning { message },
+            // `Event` is non-exhaustive; a kind this renderer has not met is
+            // left out rather than guessed at.
+            _ => return None,
+        })
+    }
+}
+
+impl JsonlState {
+    fn emit(&mut self, event: JsonEvent<'_>) {
+        let line = JsonLine {
+            elapsed_ms: self.start.elapsed().as_millis() as u64,
+            event,
+        };
+        // A reader that went away must not fail the transfer; it only loses
+        // the progress it stopped reading.
+        if let Ok(mut text) = serde_json::to_string(&line) {
+            text.push('\n');
+            let _ = self.out.write_all(text.as_bytes());
+            let _ = self.out.flush();
+        }
+    }
+}
+
 /// Plain spinner + phase name, for phases that report no per-item progress.
 fn spinner_style() -> ProgressStyle {
     ProgressStyle::with_template("{spinner:.green} {prefix}")
@@ -121,9 +331,18 @@ fn finished_style() -> ProgressStyle {
 
 impl CliProgress {
     /// Build a sink, choosing the bar or plain renderer from whether stderr is a
-    /// terminal.
-    pub fn new() -> CliProgress {
-        if std::io::stderr().is_terminal() {
+    /// terminal. `--progress-format` overrides the choice. JSON lines go to `jsonl_out`
+    /// when given, else to stderr; the other formats always draw on stderr.
+    pub fn new(format: ProgressFormat, jsonl_out: Option<Box<dyn Write + Send>>) -> CliProgress {
+        if format == ProgressFormat::Jsonl {
+            return CliProgress::Jsonl(Mutex::new(JsonlState {
+                out: jsonl_out.unwrap_or_else(|| Box::new(io::stderr())),
+                phase: String::new(),
+                throttle: Throttle::new(),
+                start: Instant::now(),
+            }));
+        }
+        if format == ProgressFormat::Auto && std::io::stderr().is_terminal() {
             // Added to the registry rather than built with its own draw target,
             // so log output can suspend it (see `bars`).
             let bar = bars().add(ProgressBar::new_spinner());
@@ -136,8 +355,7 @@ impl CliProgress {
         } else {
             CliProgress::Plain(Mutex::new(PlainState {
                 phase: String::new(),
-                last_decile: -1,
-                phase_start: Instant::now(),
+                throttle: Throttle::new(),
             }))
         }
     }
@@ -178,19 +396,7 @@ impl ProgressSink for CliProgress {
             }
             CliProgress::Plain(state) => {
                 let mut s = state.lock().expect("progress mutex poisoned");
-                // Drive the decile off whichever dimension is known (prefer items).
-                let (done, total) = if p.total_items != 0 {
-                    (p.done_items, p.total_items)
-                } else {
-                    (p.done_bytes, p.total_bytes)
-                };
-                let terminal = total != 0 && done >= total;
-                let decile = done
-                    .checked_mul(10)
-                    .and_then(|x| x.checked_div(total))
-                    .unwrap_or(0) as i32;
-                if s.last_decile != decile || terminal {
-                    s.last_decile = decile;
+                if s.throttle.admit(&p) {
                     let mut line = format!("  {}:", s.phase);
                     if p.total_items != 0 {
                         let pct = p
@@ -201,12 +407,7 @@ impl ProgressSink for CliProgress {
                         line.push_str(&format!(" {}/{} ({pct}%)", p.done_items, p.total_items));
                     }
                     if p.total_bytes != 0 {
-                        let secs = s.phase_start.elapsed().as_secs_f64();
-                        let rate = if secs > 0.0 {
-                            (p.done_bytes as f64 / secs) as u64
-                        } else {
-                            0
-                        };
+                        let rate = s.throttle.byte_rate(&p);
                         line.push_str(&format!(
                             " {} ({}/s)",
                             ByteSize(p.done_bytes),
@@ -216,6 +417,24 @@ impl ProgressSink for CliProgress {
                     eprintln!("{line}");
                 }
             }
+            CliProgress::Jsonl(state) => {
+                let mut s = state.lock().expect("progress mutex poisoned");
+                if s.throttle.admit(&p) {
+                    let phase = std::mem::take(&mut s.phase);
+                    let bytes_per_sec = s.throttle.byte_rate(&p);
+                    let eta_secs = s.throttle.eta_secs(&p);
+                    s.emit(JsonEvent::Progress {
+                        phase: &phase,
+                        done_items: p.done_items,
+                        total_items: p.total_items,
+                        done_bytes: p.done_bytes,
+                        total_bytes: p.total_bytes,
+                        bytes_per_sec,
+                        eta_secs,
+                    });
+                    s.phase = phase;
+                }
+            }
         }
     }
 
@@ -233,10 +452,41 @@ impl ProgressSink for CliProgress {
             CliProgress::Plain(state) => {
                 let mut s = state.lock().expect("progress mutex poisoned");
                 s.phase = phase.to_string();
-                s.last_decile = -1;
-                s.phase_start = Instant::now();
+                s.throttle.reset();
                 eprintln!("{phase}...");
             }
+            CliProgress::Jsonl(state) => {
+                let mut s = state.lock().expect("progress mutex poisoned");
+                s.phase = phase.to_string();
+                s.throttle.reset();
+                s.emit(JsonEvent::Phase { phase });
+            }
+        }
+    }
+
+    fn on_asset_ready(&self, path: &str) {
+        if let CliProgress::Jsonl(state) = self {
+            let mut s = state.lock().expect("progress mutex poisoned");
+            s.emit(JsonEvent::AssetReady { path });
+            return;
+        }
+        // Above the bar, like a log line (see `bars`).
+        bars().suspend(|| eprintln!("Ready: {path}"));
+    }
+
+    fn on_event(&self, event: &Event<'_>) {
+        if let CliProgress::Jsonl(state) = self {
+            // Every event, unthrottled: a wrapper tracking files needs each one.
+            if let Some(e) = JsonEvent::of(event) {
+                let mut s = state.lock().expect("progress mutex poisoned");
+                s.emit(e);
+            }
+            return;
+        }
+        // The run's error names one cause; this names every file it left
+        // incomplete.
+        if let Event::AssetFailed { path, error } = *event {
+            tracing::warn!(path, error = %error, "asset left incomplete");
         }
     }
 }
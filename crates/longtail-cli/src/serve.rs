@@ -0,0 +1,458 @@
+//! `serve-version`: a version's files over HTTP/1.1, read through a
+//! [`VersionFs`] so only the blocks a request touches are fetched.
+//!
+//! Deliberately small: `GET` and `HEAD`, keep-alive, a single `Range`
+//! (`bytes=a-b`, `bytes=a-`, `bytes=-n`) and HTML directory listings. A
+//! multi-range request is answered with the whole file, which RFC 9110 allows.
+//! A connection that sends nothing for [`IDLE_TIMEOUT`], or takes nothing of
+//! a response for as long, is closed.
+
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+
+use longtail::{CancellationToken, LongtailError, VersionFs, VersionMetadata};
+use percent_encoding::{AsciiSet, CONTROLS, percent_decode_str, utf8_percent_encode};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinSet;
+
+/// Largest request head read before answering 431.
+const MAX_HEAD_BYTES: usize = 16 << 10;
+/// Bytes read from the version per write, so a large response is streamed.
+const BODY_PIECE_BYTES: u64 = 1 << 20;
+/// How long a connection may wait for its next request, for the rest of one,
+/// or for the client to take more of a response, before it is closed.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Characters escaped in one path segment of a link: controls, `/`, and what a
+/// URL path cannot carry literally.
+const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}')
+    .add(b'/')
+    .add(b'\\')
+    .add(b'^')
+    .add(b'|')
+    .add(b'[')
+    .add(b']');
+
+/// Accept connections on `listener` until `cancel` fires, answering each from
+/// `fs`. Connections still open then are dropped, and joined before this
+/// returns.
+pub async fn serve(fs: Arc<VersionFs>, listener: TcpListener, cancel: CancellationToken) {
+    let mut connections = JoinSet::new();
+    loop {
+        let (stream, peer) = tokio::select! {
+            _ = cancel.cancelled() => break,
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    tracing::warn!("accept failed: {e}");
+                    continue;
+                }
+            },
+        };
+        while connections.try_join_next().is_some() {}
+        let fs = fs.clone();
+        connections.spawn(async move {
+            if let Err(e) = connection(&fs, stream).await {
+                tracing::debug!(%peer, "connection ended: {e}");
+            }
+        });
+    }
+    connections.shutdown().await;
+}
+
+/// What [`respond`] needs from a request head.
+struct Request {
+    method: String,
+    /// The target's path as sent, still percent-encoded, without the query.
+    raw_path: String,
+    range: Option<String>,
+    keep_alive: bool,
+}
+
+/// Answer requests on `stream` until the client closes it or asks to.
+async fn connection(fs: &VersionFs, mut stream: TcpStream) -> io::Result<()> {
+    let mut buf = Vec::with_capacity(4096);
+    loop {
+        let (request, head_len) = loop {
+            let mut headers = [httparse::EMPTY_HEADER; 64];
+            let mut req = httparse::Request::new(&mut headers);
+            match req.parse(&buf) {
+                Ok(httparse::Status::Complete(n)) => break (parse_request(&req), n),
+                Ok(httparse::Status::Partial) if buf.len() < MAX_HEAD_BYTES => {}
+                Ok(httparse::Status::Partial) => {
+                    return plain(&mut stream, 431, false).await;
+                }
+                Err(_) => return plain(&mut stream, 400, false).await,
+            }
+            let mut chunk = [0u8; 4096];
+            let n = match tokio::time::timeout(IDLE_TIMEOUT, stream.read(&mut chunk)).await {
+                Ok(read) => read?,
+                // Idle between requests: hang up. Stalled inside one: say so.
+                Err(_) if buf.is_empty() => return Ok(()),
+                Err(_) => return plain(&mut stream, 408, false).await,
+            };
+            if n == 0 {
+                return Ok(());
+            }
+            buf.extend_from_slice(&chunk[..n]);
+        };
+        buf.drain(..head_len);
+        let Some(request) = request else {
+            // A request body (`GET` has none worth reading) would be taken as
+            // the next request: answer and hang up instead.
+            return plain(&mut stream, 400, false).await;
+        };
+        respond(fs, &mut stream, &request).await?;
+        if !request.keep_alive {
+            return Ok(());
+        }
+    }
+}
+
+/// The parts of a parsed head [`respond`] uses; `None` for a request carrying
+/// a body.
+fn parse_request(req: &httparse::Request<'_, '_>) -> Option<Request> {
+    let header = |name: &str| {
+        req.headers
+            .iter()
+            .find(|h| h.name.eq_ignore_ascii_case(name))
+            .and_then(|h| std::str::from_utf8(h.value).ok())
+            .map(str::trim)
+    };
+    let has_body =
+        header("content-length").is_some_and(|v| v != "0") || header("transfer-encoding").is_some();
+    if has_body {
+        return None;
+    }
+    let connection = header("connection").unwrap_or("");
+    let keep_alive = if req.version == Some(0) {
+        connection.eq_ignore_ascii_case("keep-alive")
+    } else {
+        !connection.eq_ignore_ascii_case("close")
+    };
+    let target = req.path.unwrap_or("/");
+    Some(Request {
+        method: req.method.unwrap_or("").to_string(),
+        raw_path: target.split(['?', '#']).next().unwrap_or("").to_string(),
+        range: header("range").map(str::to_string),
+        keep_alive,
+    })
+}
+
+async fn respond(fs: &VersionFs, stream: &mut TcpStream, req: &Request) -> io::Result<()> {
+    let keep_alive = req.keep_alive;
+    let head_only = match req.method.as_str() {
+        "GET" => false,
+        "HEAD" => true,
+        _ => {
+            let body = "Method Not Allowed\n";
+            let headers = [
+                ("Allow", "GET, HEAD".to_string()),
+                ("Content-Type", "text/plain; charset=utf-8".to_string()),
+            ];
+            send_head(stream, 405, &headers, body.len() as u64, keep_alive).await?;
+            return write(stream, body.as_bytes()).await;
+        }
+    };
+    let Some(path) = req
+        .raw_path
+        .strip_prefix('/')
+        .and_then(|p| percent_decode_str(p).decode_utf8().ok())
+    else {
+        return plain(stream, 400, keep_alive).await;
+    };
+    if path.split('/').any(|c| c == "..") {
+        return plain(stream, 400, keep_alive).await;
+    }
+    let meta = match fs.metadata(&path) {
+        Ok(meta) => meta,
+        Err(e) => return error(stream, e, keep_alive).await,
+    };
+    if meta.is_dir {
+        if !path.is_empty() && !req.raw_path.ends_with('/') {
+            // Relative links in the listing resolve against the directory.
+            // Built from the decoded path, not the raw one: `//folder` would
+            // otherwise redirect to the host `folder`.
+            let encoded: Vec<String> = path
+                .split('/')
+                .filter(|c| !c.is_empty())
+                .map(|c| utf8_percent_encode(c, PATH_SEGMENT).to_string())
+                .collect();
+            let headers = [("Location", format!("/{}/", encoded.join("/")))];
+            return send_head(stream, 301, &headers, 0, keep_alive).await;
+        }
+        let entries = match fs.read_dir(&path) {
+            Ok(entries) => entries,
+            Err(e) => return error(stream, e, keep_alive).await,
+        };
+        let body = listing(&path, &entries);
+        let headers = [("Content-Type", "text/html; charset=utf-8".to_string())];
+        send_head(stream, 200, &headers, body.len() as u64, keep_alive).await?;
+        if !head_only {
+            write(stream, body.as_bytes()).await?;
+        }
+        return Ok(());
+    }
+    file(fs, stream, &path, &meta, req, head_only).await
+}
+
+async fn file(
+    fs: &VersionFs,
+    stream: &mut TcpStream,
+    path: &str,
+    meta: &VersionMetadata,
+    req: &Request,
+    head_only: bool,
+) -> io::Result<()> {
+    let size = meta.size;
+    let mut headers = vec![
+        ("Accept-Ranges", "bytes".to_string()),
+        ("Content-Type", content_type(path).to_string()),
+        ("ETag", format!("\"{:016x}\"", meta.content_hash)),
+    ];
+    let (status, start, end) = match byte_range(req.range.as_deref(), size) {
+        Span::Whole => (200, 0, size),
+        Span::Part(start, end) => {
+            headers.push(("Content-Range", format!("bytes {start}-{}/{size}", end - 1)));
+            (206, start, end)
+        }
+        Span::Unsatisfiable => {
+            headers.push(("Content-Range", format!("bytes */{size}")));
+            return send_head(stream, 416, &headers, 0, req.keep_alive).await;
+        }
+    };
+    send_head(stream, status, &headers, end - start, req.keep_alive).await?;
+    if head_only {
+        return Ok(());
+    }
+    let mut offset = start;
+    while offset < end {
+        let len = (end - offset).min(BODY_PIECE_BYTES);
+        // The status line is out: a failed read can only end the connection
+        // short of its Content-Length.
+        let bytes = fs
+            .read_at(path, offset, len)
+            .await
+            .map_err(io::Error::other)?;
+        if bytes.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("`{path}` ended at {offset} of {size} bytes"),
+            ));
+        }
+        write(stream, &bytes).await?;
+        offset += bytes.len() as u64;
+    }
+    Ok(())
+}
+
+/// The part of a file a `Range` header asks for.
+enum Span {
+    /// No range, or one this server answers with the whole file.
+    Whole,
+    /// `[start, end)`, never empty.
+    Part(u64, u64),
+    Unsatisfiable,
+}
+
+/// Resolve `header` against a `size`-byte file. Malformed and multi-range
+/// headers are ignored, as RFC 9110 permits.
+fn byte_range(header: Option<&str>, size: u64) -> Span {
+    let Some(spec) = header.and_then(|h| h.trim().strip_prefix("bytes=")) else {
+        return Span::Whole;
+    };
+    if spec.contains(',') {
+        return Span::Whole;
+    }
+    let Some((first, last)) = spec.trim().split_once('-') else {
+        return Span::Whole;
+    };
+    let (first, last) = (first.trim(), last.trim());
+    if first.is_empty() {
+        // A suffix: the last `n` bytes.
+        return match last.parse::<u64>() {
+            Ok(0) => Span::Unsatisfiable,
+            Ok(_) if size == 0 => Span::Unsatisfiable,
+            Ok(n) => Span::Part(size - n.min(size), size),
+            Err(_) => Span::Whole,
+        };
+    }
+    let Ok(start) = first.parse::<u64>() else {
+        return Span::Whole;
+    };
+    let end = if last.is_empty() {
+        size
+    } else {
+        match last.parse::<u64>() {
+            Ok(last) if last >= start => last.saturating_add(1).min(size),
+            _ => return Span::Whole,
+        }
+    };
+    if start >= size {
+        Span::Unsatisfiable
+    } else {
+        Span::Part(start, end)
+    }
+}
+
+/// An HTML page linking `entries`, the contents of directory `path`.
+fn listing(path: &str, entries: &[VersionMetadata]) -> String {
+    let title = escape_html(&format!("Index of /{path}"));
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{title}</title></head>\n\
+         <body><h1>{title}</h1>\n<table>\n"
+    );
+    if !path.is_empty() {
+        html.push_str("<tr><td><a href=\"../\">../</a></td><td></td></tr>\n");
+    }
+    for e in entries {
+        let slash = if e.is_dir { "/" } else { "" };
+        let size = if e.is_dir {
+            String::new()
+        } else {
+            e.size.to_string()
+        };
+        html.push_str(&format!(
+            "<tr><td><a href=\"{}{slash}\">{}{slash}</a></td><td align=\"right\">{size}</td></tr>\n",
+            utf8_percent_encode(e.name(), PATH_SEGMENT),
+            escape_html(e.name()),
+        ));
+    }
+    html.push_str("</table>\n</body></html>\n");
+    html
+}
+
+fn escape_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// A `Content-Type` from the file extension; the common text, image and
+/// archive types only.
+fn content_type(path: &str) -> &'static str {
+    let ext = path
+        .rsplit_once('.')
+        .map(|(_, e)| e.to_ascii_lowercase())
+        .unwrap_or_default();
+    match ext.as_str() {
+        "txt" | "log" | "ini" | "cfg" | "md" => "text/plain; charset=utf-8",
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        "tar" => "application/x-tar",
+        "pdf" => "application/pdf",
+        "wasm" => "application/wasm",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Answer a failed lookup: a path missing from the version is a 404, a path
+/// through a file a 404 too, anything else a 500.
+async fn error(stream: &mut TcpStream, e: LongtailError, keep_alive: bool) -> io::Result<()> {
+    match &e {
+        LongtailError::Io { source, .. }
+            if matches!(
+                source.kind(),
+                io::ErrorKind::NotFound | io::ErrorKind::NotADirectory
+            ) =>
+        {
+            plain(stream, 404, keep_alive).await
+        }
+        _ => {
+            tracing::warn!("serving a request failed: {}", e.full_chain());
+            plain(stream, 500, keep_alive).await
+        }
+    }
+}
+
+/// A response whose body is its reason phrase.
+async fn plain(stream: &mut TcpStream, status: u16, keep_alive: bool) -> io::Result<()> {
+    let body = format!("{}\n", reason(status));
+    let headers = [("Content-Type", "text/plain; charset=utf-8".to_string())];
+    send_head(stream, status, &headers, body.len() as u64, keep_alive).await?;
+    write(stream, body.as_bytes()).await
+}
+
+async fn send_head(
+    stream: &mut TcpStream,
+    status: u16,
+    headers: &[(&str, String)],
+    content_length: u64,
+    keep_alive: bool,
+) -> io::Result<()> {
+    let mut head = format!("HTTP/1.1 {status} {}\r\n", reason(status));
+    for (name, value) in headers {
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+    let connection = if keep_alive { "keep-alive" } else { "close" };
+    head.push_str(&format!(
+        "Content-Length: {content_length}\r\nConnection: {connection}\r\n\r\n"
+    ));
+    write(stream, head.as_bytes()).await
+}
+
+/// Write all of `bytes`, giving up once the client has taken none of them for
+/// [`IDLE_TIMEOUT`].
+async fn write(stream: &mut TcpStream, mut bytes: &[u8]) -> io::Result<()> {
+    while !bytes.is_empty() {
+        let n = match tokio::time::timeout(IDLE_TIMEOUT, stream.write(bytes)).await {
+            Ok(written) => written?,
+            Err(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "the client stopped reading",
+                ));
+            }
+        };
+        if n == 0 {
+            return Err(io::ErrorKind::WriteZero.into());
+        }
+        bytes = &bytes[n..];
+    }
+    Ok(())
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        206 => "Partial Content",
+        301 => "Moved Permanently",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        416 => "Range Not Satisfiable",
+        431 => "Request Header Fields Too Large",
+        _ => "Internal Server Error",
+    }
+}
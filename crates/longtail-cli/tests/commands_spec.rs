@@ -31,8 +31,16 @@ fn pin_umask() {
 #[cfg(not(unix))]
 fn pin_umask() {}
 
-fn run(args: &[&str], cwd: Option<&Path>) -> Output {
+/// The binary, with any config file or profile of the developer's own kept out.
+fn command() -> Command {
     let mut cmd = Command::new(bin());
+    cmd.env("LONGTAIL_CONFIG", "")
+        .env_remove("LONGTAIL_PROFILE");
+    cmd
+}
+
+fn run(args: &[&str], cwd: Option<&Path>) -> Output {
+    let mut cmd = command();
     cmd.args(args);
     if let Some(d) = cwd {
         cmd.current_dir(d);
@@ -108,6 +116,27 @@ fn downsync() {
         .expect("zoo tree matches manifest");
 }
 
+/// `--dry-run` prints what the run would do and leaves the target alone.
+#[test]
+fn downsync_dry_run() {
+    let tmp = tempfile::tempdir().unwrap();
+    let target = tmp.path().join("out");
+    let out = run_ok(&[
+        "downsync",
+        "--storage-uri",
+        store().to_str().unwrap(),
+        "--source-path",
+        lvi("zoo.lvi").to_str().unwrap(),
+        "--target-path",
+        target.to_str().unwrap(),
+        "--dry-run",
+    ]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("Assets to write:"), "stdout={stdout}");
+    assert!(stdout.contains("Bytes to fetch:"), "stdout={stdout}");
+    assert!(!target.exists(), "a dry run must not create the target");
+}
+
 /// cmd_downsync_test.go::TestDownsyncNoTargetPath — the target folder is derived
 /// from the source version name (basename before the first dot).
 #[test]
@@ -747,6 +776,64 @@ fn ls() {
     );
 }
 
+/// `ls -R`, `-l`, `--glob` and `--sort size` read everything from the version
+/// index: no store is given.
+#[test]
+fn ls_recursive_long_glob_and_sorted() {
+    let v2 = lvi("chain-v2.lvi");
+    let v2 = v2.to_str().unwrap();
+
+    let out = run_ok(&["ls", "--version-index-path", v2, "-R", "-l", "folder"]);
+    let listing = String::from_utf8_lossy(&out.stdout);
+    let lines: Vec<&str> = listing.lines().collect();
+    assert_eq!(lines.len(), 2, "{listing}");
+    // Mode, size, chunk count, content hash, then the path below `folder`.
+    let fields: Vec<&str> = lines[0].split_whitespace().collect();
+    assert_eq!(fields.len(), 5, "{listing}");
+    assert_eq!((fields[0], fields[2]), ("-rw-r--r--", "1"));
+    assert_eq!(fields[3].len(), 16, "{listing}");
+    assert!(
+        lines
+            .iter()
+            .any(|l| l.ends_with(" abitoftextinasubfolder.txt"))
+    );
+
+    let doc = json_doc(&run_ok(&[
+        "ls",
+        "--output",
+        "json",
+        "--version-index-path",
+        v2,
+        "--glob",
+        "**/*.txt",
+        "--sort",
+        "size",
+    ]));
+    let entries = doc["result"].as_array().unwrap();
+    let paths: Vec<&str> = entries
+        .iter()
+        .map(|e| e["path"].as_str().unwrap())
+        .collect();
+    assert!(
+        paths.contains(&"folder2/anotherabitoftextinasubfolder2.txt"),
+        "{doc}"
+    );
+    assert!(paths.iter().all(|p| p.ends_with(".txt")), "{doc}");
+    assert!(!paths.contains(&"script.sh"), "{doc}");
+    let sizes: Vec<u64> = entries
+        .iter()
+        .map(|e| e["size"].as_u64().unwrap())
+        .collect();
+    assert!(sizes.windows(2).all(|w| w[0] >= w[1]), "{sizes:?}");
+    assert!(entries.iter().all(|e| e["chunk_count"] == 1), "{doc}");
+
+    // A glob only matches within one directory level without `**`.
+    let out = run_ok(&["ls", "--version-index-path", v2, "--glob", "*.txt"]);
+    let listing = String::from_utf8_lossy(&out.stdout);
+    assert!(listing.contains("stuff.txt"), "{listing}");
+    assert!(!listing.contains("folder/"), "{listing}");
+}
+
 /// cmd_validateversion_test.go::TestValidateVersion — valid store passes; a store
 /// with the blocks removed fails.
 #[test]
@@ -1499,6 +1586,238 @@ fn cp() {
     );
 }
 
+/// `cp -r` copies a subtree into a folder and a glob picks assets across it,
+/// with their recorded permissions.
+#[test]
+fn cp_subtree_and_glob() {
+    pin_umask();
+    let tmp = tempfile::tempdir().unwrap();
+    let src = tmp.path().join("src");
+    make_v2(&src);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let exec = std::fs::Permissions::from_mode(0o755);
+        std::fs::set_permissions(src.join("a.txt"), exec).unwrap();
+    }
+    let store = tmp.path().join("store");
+    let lvi = tmp.path().join("v.lvi");
+    run_upsync(&store, &src, &lvi, &[]);
+    let args = [
+        "--storage-uri",
+        store.to_str().unwrap(),
+        "--version-index-path",
+        lvi.to_str().unwrap(),
+    ];
+
+    let dst = tmp.path().join("folder-copy");
+    run_ok(&[&["cp", "-r"], &args[..], &["folder", dst.to_str().unwrap()]].concat());
+    assert_eq!(
+        std::fs::read(dst.join("b.txt")).unwrap(),
+        std::fs::read(src.join("folder/b.txt")).unwrap()
+    );
+
+    let dst = tmp.path().join("globbed");
+    let doc = json_doc(&run_ok(
+        &[
+            &["cp", "--output", "json"],
+            &args[..],
+            &["**/*.txt", dst.to_str().unwrap()],
+        ]
+        .concat(),
+    ));
+    let report = &doc["result"];
+    assert_eq!(report["file_count"], 3, "{doc}");
+    assert_eq!(report["directory_count"], 0, "{doc}");
+    assert!(dst.join("folder/b.txt").is_file());
+    for rel in ["a.txt", "c.txt", "folder/b.txt"] {
+        assert_eq!(
+            std::fs::read(dst.join(rel)).unwrap(),
+            std::fs::read(src.join(rel)).unwrap(),
+            "{rel}"
+        );
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(dst.join("a.txt"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o755);
+    }
+}
+
+/// `cat` writes an asset's bytes to stdout, the same bytes `cp` writes to a
+/// file.
+#[test]
+fn cat_streams_an_asset_to_stdout() {
+    let tmp = tempfile::tempdir().unwrap();
+    let store = store();
+    let v2 = lvi("chain-v2.lvi");
+    let args = [
+        "--storage-uri",
+        store.to_str().unwrap(),
+        "--version-index-path",
+        v2.to_str().unwrap(),
+    ];
+    let copied = tmp.path().join("copied.txt");
+    let source = "folder/anotherabitoftextinasubfolder.txt";
+    run_ok(&[&["cp"], &args[..], &[source, copied.to_str().unwrap()]].concat());
+    let out = run_ok(&[&["cat", "--read-ahead", "1"], &args[..], &[source]].concat());
+    assert_eq!(out.stdout, std::fs::read(&copied).unwrap());
+
+    let out = run(&[&["cat"], &args[..], &["no/such/asset"]].concat(), None);
+    assert!(!out.status.success());
+    assert!(out.stdout.is_empty());
+}
+
+/// A `serve-version` child, killed when the test ends however it ends.
+struct Served(std::process::Child);
+
+impl Drop for Served {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+/// One request on its own connection: the status, the head and the body.
+fn http(addr: &str, method: &str, path: &str, headers: &str) -> (u16, String, Vec<u8>) {
+    use std::io::{Read, Write};
+
+    let mut conn = std::net::TcpStream::connect(addr).unwrap();
+    write!(
+        conn,
+        "{method} {path} HTTP/1.1\r\nHost: {addr}\r\n{headers}Connection: close\r\n\r\n"
+    )
+    .unwrap();
+    let mut response = Vec::new();
+    conn.read_to_end(&mut response).unwrap();
+    let split = response.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+    let head = String::from_utf8(response[..split].to_vec()).unwrap();
+    let status = head[9..12].parse().unwrap();
+    (status, head, response[split + 4..].to_vec())
+}
+
+#[test]
+fn serve_version_answers_listings_ranges_and_misses() {
+    use std::io::BufRead;
+
+    let tmp = tempfile::tempdir().unwrap();
+    let store = store();
+    let v2 = lvi("chain-v2.lvi");
+    let args = [
+        "--storage-uri",
+        store.to_str().unwrap(),
+        "--version-index-path",
+        v2.to_str().unwrap(),
+    ];
+    let source = "folder/anotherabitoftextinasubfolder.txt";
+    let copied = tmp.path().join("copied.txt");
+    run_ok(&[&["cp"], &args[..], &[source, copied.to_str().unwrap()]].concat());
+    let want = std::fs::read(&copied).unwrap();
+    assert!(want.len() > 10);
+
+    let mut child = command()
+        .args([&["serve-version", "--listen", "127.0.0.1:0"], &args[..]].concat())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let stdout = child.stdout.take().unwrap();
+    let served = Served(child);
+    let mut line = String::new();
+    std::io::BufReader::new(stdout)
+        .read_line(&mut line)
+        .unwrap();
+    let addr = line
+        .trim()
+        .rsplit("http://")
+        .next()
+        .unwrap()
+        .trim_end_matches('/')
+        .to_string();
+
+    let (status, _, body) = http(&addr, "GET", "/", "");
+    assert_eq!(status, 200);
+    let listing = String::from_utf8(body).unwrap();
+    assert!(listing.contains("href=\"folder/\""), "{listing}");
+    assert!(listing.contains("href=\"abitoftext.txt\""), "{listing}");
+    let (status, head, _) = http(&addr, "GET", "/folder", "");
+    assert_eq!(status, 301);
+    assert!(head.contains("Location: /folder/"), "{head}");
+    // Not `//folder/`, which a browser reads as the host `folder`.
+    let (status, head, _) = http(&addr, "GET", "//folder", "");
+    assert_eq!(status, 301);
+    assert!(head.contains("Location: /folder/\r\n"), "{head}");
+
+    let path = format!("/{source}");
+    let (status, head, body) = http(&addr, "GET", &path, "");
+    assert_eq!(status, 200);
+    assert!(
+        head.contains(&format!("Content-Length: {}", want.len())),
+        "{head}"
+    );
+    assert!(head.contains("Accept-Ranges: bytes"), "{head}");
+    assert_eq!(body, want);
+    let (status, head, body) = http(&addr, "HEAD", &path, "");
+    assert_eq!(status, 200);
+    assert!(
+        head.contains(&format!("Content-Length: {}", want.len())),
+        "{head}"
+    );
+    assert!(body.is_empty());
+
+    let (status, head, body) = http(&addr, "GET", &path, "Range: bytes=2-6\r\n");
+    assert_eq!(status, 206);
+    assert!(
+        head.contains(&format!("Content-Range: bytes 2-6/{}", want.len())),
+        "{head}"
+    );
+    assert_eq!(body, want[2..7]);
+    let (status, _, body) = http(&addr, "GET", &path, "Range: bytes=-4\r\n");
+    assert_eq!(status, 206);
+    assert_eq!(body, want[want.len() - 4..]);
+    let range = format!("Range: bytes={}-\r\n", want.len());
+    let (status, head, _) = http(&addr, "GET", &path, &range);
+    assert_eq!(status, 416);
+    assert!(
+        head.contains(&format!("Content-Range: bytes */{}", want.len())),
+        "{head}"
+    );
+
+    assert_eq!(http(&addr, "GET", "/no/such/asset", "").0, 404);
+    assert_eq!(http(&addr, "POST", &path, "").0, 405);
+    drop(served);
+
+    // With `--output json` the first line is a document naming the address.
+    let mut child = command()
+        .args([
+            "--output",
+            "json",
+            "serve-version",
+            "--listen",
+            "127.0.0.1:0",
+        ])
+        .args(args)
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let stdout = child.stdout.take().unwrap();
+    let served = Served(child);
+    let mut line = String::new();
+    std::io::BufReader::new(stdout)
+        .read_line(&mut line)
+        .unwrap();
+    let doc: serde_json::Value = serde_json::from_str(&line).unwrap();
+    assert_eq!(doc["command"], "serve-version", "{line}");
+    let result = &doc["result"];
+    let addr = result["address"].as_str().unwrap();
+    assert_eq!(result["url"], format!("http://{addr}/"), "{line}");
+    assert_eq!(http(addr, "GET", &path, "").2, want);
+    drop(served);
+}
+
 // ---- pack/unpack + ArchiveIndex (archive feature — not yet implemented) ----
 
 /// Source: cmd_pack_test.go::TestPack.
@@ -1540,7 +1859,7 @@ fn s3_endpoint_flag_reaches_the_inspection_commands() {
         ("print-store", "--store-index-path", "s3://bucket/store.lsi"),
     ];
     for (cmd, flag, uri) in cases {
-        let out = Command::new(bin())
+        let out = command()
             .args([
                 cmd,
                 flag,
@@ -1977,3 +2296,682 @@ fn downsync_no_delete_removed_repairs_without_touching_user_files() {
         "the default must still remove what the version does not contain"
     );
 }
+
+fn json_doc(out: &Output) -> serde_json::Value {
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(
+        stdout.lines().count(),
+        1,
+        "one document on stdout: {stdout}"
+    );
+    serde_json::from_str(&stdout).unwrap_or_else(|e| panic!("not JSON ({e}): {stdout}"))
+}
+
+/// `--output json` replaces the text with one versioned envelope carrying the
+/// command's result, for the inspection commands and a real transfer alike.
+#[test]
+fn output_json_wraps_each_result_in_a_versioned_envelope() {
+    pin_umask();
+    let v1 = lvi("chain-v1.lvi");
+    let v1 = v1.to_str().unwrap();
+
+    let doc = json_doc(&run_ok(&[
+        "print-version",
+        "--output",
+        "json",
+        "--version-index-path",
+        v1,
+    ]));
+    assert_eq!(doc["schema_version"], 1);
+    assert_eq!(doc["command"], "print-version");
+    assert_eq!(doc["ok"], true);
+    assert_eq!(doc["result"]["hash"], "blake3");
+    assert!(doc["result"]["asset_count"].as_u64().unwrap() > 0, "{doc}");
+
+    let doc = json_doc(&run_ok(&[
+        "ls",
+        "--output",
+        "json",
+        "--version-index-path",
+        v1,
+        "folder",
+    ]));
+    let names: Vec<&str> = doc["result"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["name"].as_str().unwrap())
+        .collect();
+    assert!(names.contains(&"abitoftextinasubfolder.txt"), "{doc}");
+    assert!(!names.contains(&"empty-file"), "{doc}");
+
+    let doc = json_doc(&run_ok(&[
+        "print-store",
+        "--output",
+        "json",
+        "--store-index-path",
+        lvi("chain-v1-store.lsi").to_str().unwrap(),
+    ]));
+    assert_eq!(doc["result"]["hash"], "blake3");
+    assert!(doc["result"]["block_count"].as_u64().unwrap() > 0, "{doc}");
+
+    let doc = json_doc(&run_ok(&[
+        "validate-version",
+        "--output",
+        "json",
+        "--storage-uri",
+        store().to_str().unwrap(),
+        "--version-index-path",
+        v1,
+    ]));
+    assert_eq!(doc["result"]["valid"], true);
+
+    // A transfer reports its `DownsyncReport`; the progress bar stays off stdout.
+    let tmp = tempfile::tempdir().unwrap();
+    let target = tmp.path().join("out");
+    let doc = json_doc(&run_ok(&[
+        "downsync",
+        "--output",
+        "json",
+        "--storage-uri",
+        store().to_str().unwrap(),
+        "--source-path",
+        v1,
+        "--target-path",
+        target.to_str().unwrap(),
+        "--no-cache-target-index",
+    ]));
+    assert_eq!(doc["command"], "downsync");
+    assert!(
+        doc["result"]["assets_written"].as_u64().unwrap() > 0,
+        "{doc}"
+    );
+    assert!(doc["result"]["phases"].is_array(), "{doc}");
+}
+
+/// A failure is a document too: classified, with the whole cause chain, and
+/// still a non-zero exit.
+#[test]
+fn output_json_renders_errors_with_their_class_and_chain() {
+    let tmp = tempfile::tempdir().unwrap();
+    let missing = tmp.path().join("missing.lvi");
+    let out = run(
+        &[
+            "print-version",
+            "--output",
+            "json",
+            "--version-index-path",
+            missing.to_str().unwrap(),
+        ],
+        None,
+    );
+    assert!(!out.status.success());
+    let doc = json_doc(&out);
+    assert_eq!(doc["schema_version"], 1);
+    assert_eq!(doc["ok"], false);
+    assert!(doc.get("result").is_none(), "{doc}");
+    let error = &doc["error"];
+    assert!(error["class"].is_string(), "{doc}");
+    let chain = error["chain"].as_array().unwrap();
+    assert!(chain.len() > 1, "the cause hangs off the category: {doc}");
+    assert_eq!(
+        error["message"].as_str().unwrap(),
+        chain
+            .iter()
+            .map(|l| l.as_str().unwrap())
+            .collect::<Vec<_>>()
+            .join(": ")
+    );
+}
+
+fn jsonl_lines(text: &[u8]) -> Vec<serde_json::Value> {
+    String::from_utf8_lossy(text)
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap_or_else(|e| panic!("not JSON ({e}): {l}")))
+        .collect()
+}
+
+/// `--progress-format jsonl` reports phases, throttled counters with a rate and
+/// ETA, and one event per asset, each line a JSON object on its own.
+#[test]
+fn progress_format_jsonl_reports_phases_counters_and_assets() {
+    pin_umask();
+    let tmp = tempfile::tempdir().unwrap();
+    let target = tmp.path().join("out");
+    let out = run_ok(&[
+        "downsync",
+        "--progress-format",
+        "jsonl",
+        "--no-log-to-console",
+        "--storage-uri",
+        store().to_str().unwrap(),
+        "--source-path",
+        lvi("chain-v1.lvi").to_str().unwrap(),
+        "--target-path",
+        target.to_str().unwrap(),
+        "--no-cache-target-index",
+    ]);
+    let lines = jsonl_lines(&out.stderr);
+    let of = |kind: &str| -> Vec<&serde_json::Value> {
+        lines.iter().filter(|l| l["event"] == kind).collect()
+    };
+    assert_eq!(lines[0]["event"], "phase", "{lines:?}");
+    assert!(lines.iter().all(|l| l["elapsed_ms"].is_u64()), "{lines:?}");
+
+    let progress = of("progress");
+    assert!(!progress.is_empty(), "{lines:?}");
+    for p in &progress {
+        assert!(p["phase"].is_string() && p["bytes_per_sec"].is_u64(), "{p}");
+        assert!(p.get("eta_secs").is_some(), "{p}");
+    }
+
+    let completed: Vec<&str> = of("asset_completed")
+        .iter()
+        .map(|l| l["path"].as_str().unwrap())
+        .collect();
+    assert!(completed.contains(&"abitoftext.txt"), "{completed:?}");
+}
+
+/// `--progress-fd` moves the lines off stderr onto a descriptor the parent
+/// handed down — here stdout, which `downsync` otherwise leaves empty.
+#[cfg(unix)]
+#[test]
+fn progress_fd_writes_the_lines_to_that_descriptor() {
+    pin_umask();
+    let tmp = tempfile::tempdir().unwrap();
+    let target = tmp.path().join("out");
+    let out = run_ok(&[
+        "downsync",
+        "--progress-format",
+        "jsonl",
+        "--progress-fd",
+        "1",
+        "--storage-uri",
+        store().to_str().unwrap(),
+        "--source-path",
+        lvi("chain-v1.lvi").to_str().unwrap(),
+        "--target-path",
+        target.to_str().unwrap(),
+        "--no-cache-target-index",
+    ]);
+    let lines = jsonl_lines(&out.stdout);
+    assert!(lines.iter().any(|l| l["event"] == "phase"), "{lines:?}");
+    assert!(
+        !String::from_utf8_lossy(&out.stderr).contains("\"event\""),
+        "nothing on stderr"
+    );
+
+    // Without jsonl there is nothing to send there.
+    let refused = run(
+        &[
+            "downsync",
+            "--progress-fd",
+            "1",
+            "--storage-uri",
+            store().to_str().unwrap(),
+            "--source-path",
+            lvi("chain-v1.lvi").to_str().unwrap(),
+            "--target-path",
+            tmp.path().join("refused").to_str().unwrap(),
+        ],
+        None,
+    );
+    assert!(!refused.status.success());
+    assert!(
+        String::from_utf8_lossy(&refused.stderr).contains("--progress-fd needs"),
+        "{}",
+        String::from_utf8_lossy(&refused.stderr)
+    );
+}
+
+/// Runs with `config` as the config file.
+fn run_with_config(config: &Path, args: &[&str]) -> Output {
+    command()
+        .env("LONGTAIL_CONFIG", config)
+        .args(args)
+        .output()
+        .expect("spawn longtail binary")
+}
+
+/// A profile supplies flags a command would otherwise need typed, a
+/// subcommand's table applies to that subcommand only, and a typed flag wins.
+#[test]
+fn config_profile_supplies_flag_defaults_and_typed_flags_win() {
+    pin_umask();
+    let tmp = tempfile::tempdir().unwrap();
+    let config = tmp.path().join("config.toml");
+    std::fs::write(
+        &config,
+        format!(
+            r#"
+[profiles.default]
+storage-uri = {store:?}
+worker-count = 3
+
+[profiles.default.downsync]
+source-path = {v1:?}
+no-cache-target-index = true
+
+[profiles.elsewhere]
+storage-uri = "/nowhere"
+"#,
+            store = store().to_str().unwrap(),
+            v1 = lvi("chain-v1.lvi").to_str().unwrap(),
+        ),
+    )
+    .unwrap();
+
+    let target = tmp.path().join("out");
+    let out = run_with_config(
+        &config,
+        &["downsync", "--target-path", target.to_str().unwrap()],
+    );
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    capture(&target)
+        .compare(&manifest("chain-v1.json"), cfg!(windows))
+        .unwrap();
+
+    let out = run_with_config(
+        &config,
+        &[
+            "--worker-count",
+            "5",
+            "--output",
+            "json",
+            "config",
+            "show",
+            "downsync",
+        ],
+    );
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    let doc = json_doc(&out);
+    let result = &doc["result"];
+    assert_eq!(result["profile"], "default");
+    let flag = |name: &str| {
+        let flags = result["flags"].as_array().unwrap();
+        flags
+            .iter()
+            .find(|f| f["flag"] == name)
+            .cloned()
+            .unwrap_or_default()
+    };
+    assert_eq!(flag("worker-count")["values"][0], "5");
+    assert_eq!(flag("worker-count")["source"], "command line");
+    assert_eq!(flag("source-path")["source"], "profile");
+    assert_eq!(flag("no-cache-target-index")["values"][0], "true");
+    assert_eq!(flag("log-level")["source"], "default");
+
+    // The subcommand table stays with its subcommand.
+    let out = run_with_config(&config, &["--output", "json", "config", "show", "ls"]);
+    assert!(
+        json_doc(&out)["result"]["flags"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|f| f["flag"] != "source-path")
+    );
+
+    // Another profile, named by flag or environment.
+    let out = run_with_config(
+        &config,
+        &["--profile", "elsewhere", "config", "show", "downsync"],
+    );
+    let text = String::from_utf8_lossy(&out.stdout);
+    assert!(text.contains("/nowhere"), "{text}");
+    let out = command()
+        .env("LONGTAIL_CONFIG", &config)
+        .env("LONGTAIL_PROFILE", "elsewhere")
+        .args(["config", "show", "downsync"])
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&out.stdout).contains("/nowhere"));
+}
+
+/// A profile that names a missing profile, an unknown flag or a bad value is
+/// refused before anything runs, and the message says where.
+#[test]
+fn config_profile_mistakes_are_usage_errors() {
+    let tmp = tempfile::tempdir().unwrap();
+    let config = tmp.path().join("config.toml");
+    let refused = |text: &str, args: &[&str], expect: &str| {
+        std::fs::write(&config, text).unwrap();
+        let out = run_with_config(&config, args);
+        assert_eq!(out.status.code(), Some(2), "{text}");
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        assert!(stderr.contains(expect), "{text}: {stderr}");
+    };
+    refused(
+        "[profiles.default]\n",
+        &["--profile", "ci", "version"],
+        "no profile named `ci`",
+    );
+    refused(
+        "[profiles.default]\nstorge-uri = \"x\"\n",
+        &["version"],
+        "--storge-uri",
+    );
+    refused(
+        "[profiles.default.ls]\ncache-path = \"x\"\n",
+        &["version"],
+        "`ls` has no --cache-path",
+    );
+    refused(
+        "[profiles.default]\ndry-run = \"yes\"\n",
+        &["version"],
+        "is a switch",
+    );
+    refused(
+        "[profiles.default]\nprofile = \"x\"\n",
+        &["version"],
+        "cannot be set from a profile",
+    );
+
+    // A config file someone named must exist; the default location need not.
+    let missing = tmp.path().join("missing.toml");
+    let out = run_with_config(&missing, &["version"]);
+    assert_eq!(out.status.code(), Some(2));
+    let out = command()
+        .env_remove("LONGTAIL_CONFIG")
+        .env("XDG_CONFIG_HOME", tmp.path())
+        .arg("version")
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+
+    // Under `--output json` the refusal is the error document.
+    std::fs::write(&config, "[profiles.default]\n").unwrap();
+    let out = run_with_config(&config, &["--output", "json", "--profile", "ci", "ls"]);
+    assert_eq!(out.status.code(), Some(2));
+    let doc = json_doc(&out);
+    assert_eq!(doc["ok"], false);
+    assert_eq!(doc["command"], "ls");
+    let message = doc["error"]["message"].as_str().unwrap();
+    assert!(message.contains("no profile named `ci`"), "{message}");
+    assert!(
+        out.stderr.is_empty(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+}
+
+/// A switch a profile turns on is turned off again by typing `--flag=false`.
+#[test]
+fn config_profile_switches_yield_to_typed_false() {
+    let tmp = tempfile::tempdir().unwrap();
+    let config = tmp.path().join("config.toml");
+    std::fs::write(&config, "[profiles.default.ls]\nrecursive = true\n").unwrap();
+    let v2 = lvi("chain-v2.lvi");
+    let v2 = v2.to_str().unwrap();
+    let listing = |args: &[&str]| {
+        let out = run_with_config(&config, args);
+        assert!(
+            out.status.success(),
+            "{args:?}: {}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+        String::from_utf8_lossy(&out.stdout).into_owned()
+    };
+    let plain = run_ok(&["ls", "--version-index-path", v2]);
+    let plain = String::from_utf8_lossy(&plain.stdout);
+    let recursive = listing(&["ls", "--version-index-path", v2]);
+    assert_ne!(recursive, plain, "the profile lists recursively");
+    assert_eq!(
+        listing(&["ls", "--recursive=false", "--version-index-path", v2]),
+        plain
+    );
+    assert_eq!(
+        listing(&["ls", "--version-index-path", v2, "--recursive=true"]),
+        recursive
+    );
+}
+
+/// A short flag typed on the command line counts as its long name, so a
+/// profile that sets the same switch does not add it a second time.
+#[test]
+fn config_profile_yields_to_typed_short_flags() {
+    let tmp = tempfile::tempdir().unwrap();
+    let config = tmp.path().join("config.toml");
+    std::fs::write(
+        &config,
+        "[profiles.default.ls]\nrecursive = true\nlong = true\n",
+    )
+    .unwrap();
+    let v2 = lvi("chain-v2.lvi");
+    let v2 = v2.to_str().unwrap();
+    for typed in [&["-R", "-l"][..], &["-Rl"], &["-l"]] {
+        let mut args = vec!["ls", "--version-index-path", v2];
+        args.extend(typed);
+        args.push("folder");
+        let out = run_with_config(&config, &args);
+        assert!(
+            out.status.success(),
+            "{typed:?}: {}",
+            String::from_utf8_lossy(&out.stderr)
+        );
+        assert_eq!(String::from_utf8_lossy(&out.stdout).lines().count(), 2);
+    }
+}
+
+/// `diff-versions` lists each kind of change and, given the store, what the
+/// update downloads.
+#[test]
+fn diff_versions_lists_changes_and_the_download() {
+    let from = lvi("chain-v1.lvi");
+    let to = lvi("chain-v2.lvi");
+    let out = run_ok(&[
+        "diff-versions",
+        "--from-path",
+        from.to_str().unwrap(),
+        "--to-path",
+        to.to_str().unwrap(),
+    ]);
+    let text = String::from_utf8_lossy(&out.stdout);
+    assert!(
+        text.contains("+ -rw-r--r--") && text.contains("stuff.txt"),
+        "{text}"
+    );
+    assert!(
+        text.contains("- -rw-r--r--") && text.contains("to-delete.txt"),
+        "{text}"
+    );
+    assert!(text.contains("abitoftext.txt (was 19)"), "{text}");
+    assert!(text.contains("script.sh (was -rw-r--r--)"), "{text}");
+    assert!(!text.contains("Download:"), "no store, no estimate: {text}");
+
+    let out = run_ok(&[
+        "diff-versions",
+        "--output",
+        "json",
+        "--from-path",
+        from.to_str().unwrap(),
+        "--to-path",
+        to.to_str().unwrap(),
+        "--storage-uri",
+        store().to_str().unwrap(),
+    ]);
+    let result = &json_doc(&out)["result"];
+    assert_eq!(result["added"].as_array().unwrap().len(), 4, "{result}");
+    assert_eq!(result["removed"][0]["path"], "to-delete.txt");
+    assert_eq!(result["content_modified"][0]["previous_size"], 19);
+    assert_eq!(
+        result["permissions_modified"][0]["previous_permissions"],
+        0o644
+    );
+    assert!(result["new_chunk_bytes"].as_u64().unwrap() > 0, "{result}");
+    let download = &result["download"];
+    assert!(
+        download["compressed_bytes"].as_u64().unwrap() > 0,
+        "{result}"
+    );
+    assert_eq!(download["missing_chunks"], 0);
+}
+
+#[test]
+fn explain_update_attributes_the_download() {
+    let from = lvi("chain-v1.lvi");
+    let to = lvi("chain-v2.lvi");
+    let store = store();
+    let args = [
+        "--from-path",
+        from.to_str().unwrap(),
+        "--to-path",
+        to.to_str().unwrap(),
+        "--storage-uri",
+        store.to_str().unwrap(),
+    ];
+    let out = run_ok(&[&["explain-update", "--top", "1"], &args[..]].concat());
+    let text = String::from_utf8_lossy(&out.stdout);
+    assert!(
+        text.contains("Download:") && text.contains("Unneeded:"),
+        "{text}"
+    );
+    assert!(text.contains("Assets (top 1 of 4):"), "{text}");
+    assert!(text.contains("Blocks by unneeded bytes:"), "{text}");
+
+    let out = run_ok(&[&["explain-update", "--output", "json"], &args[..]].concat());
+    let result = &json_doc(&out)["result"];
+    let assets = result["assets"].as_array().unwrap();
+    assert_eq!(assets.len(), 4, "{result}");
+    let summed: u64 = assets
+        .iter()
+        .map(|a| a["compressed_bytes"].as_u64().unwrap())
+        .sum();
+    assert_eq!(summed, result["compressed_bytes"].as_u64().unwrap());
+    assert_eq!(result["missing_chunks"], 0);
+}
+
+#[test]
+fn list_versions_shows_put_layouts_sorted_and_filtered() {
+    pin_umask();
+    let tmp = tempfile::tempdir().unwrap();
+    let src = tmp.path().join("src");
+    make_v2(&src);
+    let work = tmp.path().join("work");
+    std::fs::create_dir_all(&work).unwrap();
+    let config = work.join("game.json");
+    run_ok(&[
+        "put",
+        "--target-path",
+        config.to_str().unwrap(),
+        "--source-path",
+        src.to_str().unwrap(),
+    ]);
+    std::fs::copy(lvi("zoo.lvi"), work.join("zoo.lvi")).unwrap();
+
+    let out = run_ok(&[
+        "list-versions",
+        "--source-path",
+        work.to_str().unwrap(),
+        "--sort",
+        "assets",
+    ]);
+    let text = String::from_utf8_lossy(&out.stdout);
+    let rows: Vec<&str> = text.lines().skip(1).collect();
+    assert_eq!(rows.len(), 2, "{text}");
+    assert!(rows[0].ends_with("zoo.lvi"), "most assets first: {text}");
+    assert!(
+        rows[1].contains("blake3  zstd")
+            && rows[1].ends_with("version-data/version-index/game.lvi (game.json)"),
+        "{text}"
+    );
+
+    let out = run_ok(&[
+        "list-versions",
+        "--output",
+        "json",
+        "--source-path",
+        work.to_str().unwrap(),
+        "--prefix",
+        "version-data/",
+    ]);
+    let result = &json_doc(&out)["result"];
+    let versions = result.as_array().unwrap();
+    assert_eq!(versions.len(), 1, "{result}");
+    assert_eq!(versions[0]["hash"], "blake3");
+    assert_eq!(versions[0]["compression"][0], "zstd");
+    assert!(versions[0]["last_modified"].as_u64().unwrap() > 0);
+}
+
+#[test]
+fn store_du_splits_unique_and_shared_bytes() {
+    let tmp = tempfile::tempdir().unwrap();
+    let list = tmp.path().join("versions.txt");
+    let versions = [lvi("chain-v2.lvi"), lvi("chain-v3.lvi")];
+    std::fs::write(
+        &list,
+        format!("{}\n{}\n", versions[0].display(), versions[1].display()),
+    )
+    .unwrap();
+    let store = store();
+    let args = [
+        "--storage-uri",
+        store.to_str().unwrap(),
+        "--source-paths",
+        list.to_str().unwrap(),
+    ];
+
+    let out = run_ok(&[&["store-du"], &args[..]].concat());
+    let text = String::from_utf8_lossy(&out.stdout);
+    assert!(text.contains("Shared between:"), "{text}");
+    assert!(text.contains("chain-v2.lvi and "), "{text}");
+
+    let out = run_ok(&[&["store-du", "--output", "json"], &args[..]].concat());
+    let result = &json_doc(&out)["result"];
+    let v2 = &result["versions"][0];
+    // chain-v3 extends chain-v2, so nothing of v2's is its alone.
+    assert_eq!(v2["unique_bytes"], 0, "{result}");
+    assert_eq!(v2["shared_bytes"], v2["bytes"], "{result}");
+    assert_eq!(result["overlaps"][0]["first"], 0);
+    assert_eq!(result["overlaps"][0]["second"], 1);
+}
+
+#[test]
+fn who_uses_finds_versions_by_block_path_or_listing() {
+    let stores = lvi("chain-v1.lvi").parent().unwrap().to_path_buf();
+    let store = store();
+    let out = run_ok(&[
+        "who-uses",
+        "--output",
+        "json",
+        "--storage-uri",
+        store.to_str().unwrap(),
+        "--versions-uri",
+        stores.to_str().unwrap(),
+        "--versions-prefix",
+        "chain",
+        "--asset-path",
+        "stuff.txt",
+    ]);
+    let result = &json_doc(&out)["result"];
+    assert_eq!(result["versions_checked"], 3, "{result}");
+    let users = result["users"].as_array().unwrap();
+    assert_eq!(users.len(), 2, "{result}");
+    let block = users[0]["blocks"][0].as_u64().unwrap();
+
+    // The block file's path, as a corrupt-block report names it.
+    let block_file = store.join(format!("chunks/{:04x}/0x{block:016x}.lsb", block >> 48));
+    let tmp = tempfile::tempdir().unwrap();
+    let list = tmp.path().join("versions.txt");
+    std::fs::write(&list, format!("{}\n", lvi("chain-v2.lvi").display())).unwrap();
+    let out = run_ok(&[
+        "who-uses",
+        "--storage-uri",
+        store.to_str().unwrap(),
+        "--source-paths",
+        list.to_str().unwrap(),
+        "--block-hash",
+        block_file.to_str().unwrap(),
+    ]);
+    let text = String::from_utf8_lossy(&out.stdout);
+    assert!(text.starts_with("1 of 1 versions depend on it"), "{text}");
+    assert!(text.contains(&format!("0x{block:016x}")), "{text}");
+    assert!(text.contains("  stuff.txt"), "{text}");
+}
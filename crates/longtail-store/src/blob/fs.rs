@@ -164,6 +164,7 @@ fn get_objects_sync(root: &Path, prefix: &str) -> Result<Vec<BlobProperties>, St
                 out.push(BlobProperties {
                     size: meta.len(),
                     name: leaf,
+                    last_modified: meta.modified().ok(),
                 });
             }
         }
@@ -7,6 +7,7 @@
 
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 
 use async_trait::async_trait;
 use bytes::Bytes;
@@ -18,6 +19,7 @@ use crate::error::StoreError;
 struct MemBlob {
     generation: i64,
     data: Vec<u8>,
+    modified: SystemTime,
 }
 
 #[derive(Debug, Default)]
@@ -81,6 +83,7 @@ impl BlobClient for MemBlobClient {
                 out.push(BlobProperties {
                     name: key.clone(),
                     size: blob.data.len() as u64,
+                    last_modified: Some(blob.modified),
                 });
             }
         }
@@ -154,12 +157,14 @@ impl BlobObject for MemBlobObject {
                 MemBlob {
                     generation: 0,
                     data: data.to_vec(),
+                    modified: SystemTime::now(),
                 },
             );
         } else {
             let blob = state.blobs.get_mut(&self.path).unwrap();
             blob.data = data.to_vec();
             blob.generation += 1;
+            blob.modified = SystemTime::now();
         }
         Ok(true)
     }
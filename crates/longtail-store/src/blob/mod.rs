@@ -12,6 +12,8 @@
 //! - `write` returns `bool`: `true` = written, `false` = the conditional write
 //!   lost its generation CAS (no error) — mirrors Go's `(ok, nil)`.
 
+use std::time::SystemTime;
+
 use async_trait::async_trait;
 use bytes::Bytes;
 
@@ -28,10 +30,33 @@ pub use mem::MemBlobStore;
 pub use s3::{S3BlobStore, S3Options};
 
 /// One listed object: its store-relative name and byte size (`BlobProperties`).
+/// Non-exhaustive so a field can be added without breaking an out-of-tree
+/// [`BlobClient`]; build one with [`BlobProperties::new`].
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub struct BlobProperties {
     pub size: u64,
     pub name: String,
+    /// When the object was last written, if the backend reports it. Go's
+    /// `BlobProperties` has no such field; nothing in a sync reads it.
+    pub last_modified: Option<SystemTime>,
+}
+
+impl BlobProperties {
+    /// A listed object with no modification time.
+    pub fn new(name: impl Into<String>, size: u64) -> BlobProperties {
+        BlobProperties {
+            size,
+            name: name.into(),
+            last_modified: None,
+        }
+    }
+
+    /// Record when the object was last written.
+    pub fn with_last_modified(mut self, at: SystemTime) -> BlobProperties {
+        self.last_modified = Some(at);
+        self
+    }
 }
 
 /// Default ceiling on a single blob read.
@@ -129,6 +154,7 @@ pub trait BlobObject: Send + Sync {
 ///   deliberate divergence from Go, which constructs a GCS store).
 /// - `abfs://`/`abfss://` → [`StoreError::NotSupported`] (Azure, matching Go's
 ///   "not yet implemented" error).
+/// - a scheme registered with [`crate::register_blob_scheme`] → its factory.
 pub fn create_blob_store_for_uri(uri: &str) -> Result<Box<dyn BlobStore>, StoreError> {
     // Special-case: filepaths do not always parse as URLs (Go checks fsblob://
     // and UNC prefixes before url.Parse).
@@ -177,6 +203,9 @@ pub fn create_blob_store_for_uri(uri: &str) -> Result<Box<dyn BlobStore>, StoreE
                 }
             }
             _ => {
+                if let Some(factory) = crate::scheme::registered_blob_scheme(uri) {
+                    return factory(uri);
+                }
                 // Unknown scheme (Go falls through to a filesystem store; we keep
                 // the fs fallback for `c:\...`-style paths but reject genuine
                 // unknown schemes to surface typos as the spec intends).
@@ -196,13 +225,13 @@ pub fn create_blob_store_for_uri(uri: &str) -> Result<Box<dyn BlobStore>, StoreE
     Ok(Box::new(FsBlobStore::new(uri, false)))
 }
 
-/// Split `scheme://rest`. Returns `None` if there is no `://` separator. The
-/// scheme is lowercased for matching. A bare `c:\path` has no `//` so returns
-/// `None` (treated as a path).
+/// Split `scheme://rest`. Returns `None` if there is no `://` separator or what
+/// precedes it is not a scheme name (RFC 3986: `studio-cas` is one). A bare
+/// `c:\path` has no `//` so returns `None` (treated as a path).
 pub(crate) fn split_scheme(uri: &str) -> Option<(&str, &str)> {
     let idx = uri.find("://")?;
     let scheme = &uri[..idx];
-    if scheme.is_empty() || !scheme.bytes().all(|b| b.is_ascii_alphanumeric()) {
+    if !crate::scheme::is_scheme_name(scheme) {
         return None;
     }
     Some((scheme, &uri[idx + 3..]))
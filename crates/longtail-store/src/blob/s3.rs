@@ -14,6 +14,8 @@
 //! `ListObjectsV2` result (Go reads only the first page, s3Store.go:92-103) — a
 //! correctness fix for stores with > 1000 objects (Init rebuild).
 
+use std::time::SystemTime;
+
 use async_trait::async_trait;
 use aws_config::BehaviorVersion;
 use aws_credential_types::provider::SharedCredentialsProvider;
@@ -73,8 +75,9 @@ where
 }
 
 /// How to obtain an S3 client. Highest precedence first: an explicit `client`,
-/// then a `sdk_config`, then piecewise `credentials_provider`/`region`, each
-/// overlaid with `endpoint_url` / `transfer_acceleration` / `force_path_style`.
+/// then a `sdk_config`, then piecewise `credentials_provider`/`region`/
+/// `profile_name`, each overlaid with `endpoint_url` / `transfer_acceleration`
+/// / `force_path_style`.
 #[derive(Clone)]
 pub struct S3Options {
     /// A fully-built client (holds its own provider). Used verbatim.
@@ -86,6 +89,9 @@ pub struct S3Options {
     pub credentials_provider: Option<SharedCredentialsProvider>,
     /// Region override (else the default region chain / `us-east-1`).
     pub region: Option<String>,
+    /// Named profile from the shared AWS config/credentials files (else
+    /// `AWS_PROFILE` / `default`). Ignored when `sdk_config` or `client` is set.
+    pub profile_name: Option<String>,
     /// Custom endpoint (S3-compatible stores, minio).
     pub endpoint_url: Option<String>,
     /// S3 Transfer Acceleration. **Defaults to `false`** — a deliberate
@@ -117,6 +123,7 @@ impl Default for S3Options {
             sdk_config: None,
             credentials_provider: None,
             region: None,
+            profile_name: None,
             endpoint_url: None,
             transfer_acceleration: false,
             force_path_style: false,
@@ -132,6 +139,7 @@ impl std::fmt::Debug for S3Options {
             .field("sdk_config", &self.sdk_config.is_some())
             .field("credentials_provider", &self.credentials_provider.is_some())
             .field("region", &self.region)
+            .field("profile_name", &self.profile_name)
             .field("endpoint_url", &self.endpoint_url)
             .field("transfer_acceleration", &self.transfer_acceleration)
             .field("force_path_style", &self.force_path_style)
@@ -205,6 +213,9 @@ impl S3BlobStore {
             Some(cfg) => cfg.clone(),
             None => {
                 let mut loader = aws_config::defaults(BehaviorVersion::latest());
+                if let Some(profile) = &self.options.profile_name {
+                    loader = loader.profile_name(profile);
+                }
                 if let Some(provider) = &self.options.credentials_provider {
                     loader = loader.credentials_provider(provider.clone());
                 }
@@ -306,6 +317,9 @@ impl BlobClient for S3BlobClient {
                 out.push(BlobProperties {
                     size: object.size().unwrap_or_default() as u64,
                     name,
+                    last_modified: object
+                        .last_modified()
+                        .and_then(|t| SystemTime::try_from(*t).ok()),
                 });
             }
             if resp.is_truncated().unwrap_or(false) {
@@ -410,6 +424,8 @@ impl BlobObject for S3BlobObject {
                 self.key, self.max_read_bytes
             )));
         }
+        // Dropping this future mid-collect (a cancelled block read) drops the
+        // body stream and with it the connection; the bytes so far go too.
         let data = resp
             .body
             .collect()
@@ -6,10 +6,12 @@
 //! Runtime composition (`Compress(Cache(Remote(…)))`) works because the trait is
 //! object-safe (via `async_trait`).
 
+use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
 
 use async_trait::async_trait;
 use longtail_core::{StoreIndex, StoredBlock};
+use tokio_util::sync::CancellationToken;
 
 use crate::error::StoreError;
 
@@ -27,6 +29,37 @@ pub trait BlockStore: Send + Sync {
     /// Hint that these blocks will be fetched soon (starts prefetching).
     async fn preflight_get(&self, block_hashes: &[u64]) -> Result<(), StoreError>;
 
+    /// [`get_stored_block`](Self::get_stored_block) on behalf of one operation:
+    /// once `cancel` fires the read fails with [`StoreError::Cancelled`].
+    /// Other operations' reads of the same block are not affected. The default
+    /// stops waiting; stores with reads worth aborting (the remote) override it
+    /// to drop the read itself.
+    async fn get_stored_block_cancellable(
+        &self,
+        block_hash: u64,
+        cancel: &CancellationToken,
+    ) -> Result<StoredBlock, StoreError> {
+        tokio::select! {
+            biased;
+            _ = cancel.cancelled() => Err(StoreError::Cancelled),
+            res = self.get_stored_block(block_hash) => res,
+        }
+    }
+
+    /// [`preflight_get`](Self::preflight_get) on behalf of one operation: the
+    /// prefetches it starts are abandoned once `cancel` fires. The default
+    /// ignores the token after the call.
+    async fn preflight_get_cancellable(
+        &self,
+        block_hashes: &[u64],
+        cancel: &CancellationToken,
+    ) -> Result<(), StoreError> {
+        if cancel.is_cancelled() {
+            return Err(StoreError::Cancelled);
+        }
+        self.preflight_get(block_hashes).await
+    }
+
     /// The subset of the store index covering `chunk_hashes`, honoring
     /// `min_block_usage_percent` (`GetExistingStoreIndex`).
     async fn get_existing_content(
@@ -46,6 +79,14 @@ pub trait BlockStore: Send + Sync {
     /// Flush and stop the store's background tasks. Idempotent.
     async fn close(&self) -> Result<(), StoreError>;
 
+    /// Read the store index again from the backend, so blocks written by
+    /// someone else since it was loaded become visible — for a reader that
+    /// outlives one operation. Stores that hold no index have nothing to do
+    /// (the default).
+    async fn refresh_index(&self) -> Result<(), StoreError> {
+        Ok(())
+    }
+
     /// A snapshot of the running counters.
     fn stats(&self) -> StatsSnapshot;
 }
@@ -103,3 +144,33 @@ pub struct StatsSnapshot {
     pub put_retry_count: u64,
     pub put_fail_count: u64,
 }
+
+/// Where a fetched block came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockSource {
+    /// The local block cache ([`crate::cache::CacheBlockStore`]).
+    Cache,
+    /// The store itself ([`crate::remote::RemoteBlockStore`]).
+    Remote,
+}
+
+/// A per-operation event a store layer reports as it happens — the detail
+/// [`BlockStoreStats`] sums away. Delivered through a [`StoreEventSink`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StoreEvent {
+    /// A block was read. `wire_bytes` is its stored (compressed) size: what
+    /// crossed the network for [`BlockSource::Remote`], what was read from
+    /// disk for [`BlockSource::Cache`].
+    BlockFetched {
+        block_hash: u64,
+        source: BlockSource,
+        wire_bytes: u64,
+    },
+    /// A block read failed and is about to be retried; `attempt` counts from 1.
+    Retry { block_hash: u64, attempt: u32 },
+}
+
+/// A callback for [`StoreEvent`]s. Called on the store's tasks, so it must be
+/// cheap and must not block.
+pub type StoreEventSink = Arc<dyn Fn(&StoreEvent) + Send + Sync>;
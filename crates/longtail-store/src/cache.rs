@@ -24,9 +24,10 @@ use std::time::SystemTime;
 
 use async_trait::async_trait;
 use longtail_core::{StoreIndex, StoredBlock};
+use tokio_util::sync::CancellationToken;
 
 use crate::blob::{BlobClient, BlobStore, FsBlobStore};
-use crate::block_store::{BlockStore, StatsSnapshot};
+use crate::block_store::{BlockSource, BlockStore, StatsSnapshot, StoreEvent, StoreEventSink};
 use crate::error::StoreError;
 
 /// A local filesystem cache in front of a remote [`BlockStore`].
@@ -41,6 +42,7 @@ pub struct CacheBlockStore {
     /// `None` = unbounded (no tracking, zero extra overhead).
     size_limit: Option<u64>,
     remote: Arc<dyn BlockStore>,
+    events: Option<StoreEventSink>,
 }
 
 impl CacheBlockStore {
@@ -61,8 +63,16 @@ impl CacheBlockStore {
             cache_root,
             size_limit,
             remote,
+            events: None,
         })
     }
+
+    /// Report every cache hit to `sink` as a [`StoreEvent::BlockFetched`] from
+    /// [`BlockSource::Cache`]. Misses are the remote's to report.
+    pub fn with_events(mut self, sink: StoreEventSink) -> CacheBlockStore {
+        self.events = Some(sink);
+        self
+    }
 }
 
 /// Cache block path: `chunks/<first-4-hex>/0x<16-hex>.lrb` (C's FSBlockStore
@@ -73,6 +83,14 @@ fn cache_block_path(block_hash: u64) -> String {
     format!("chunks/{sub}/{file_name}")
 }
 
+/// Whether `cache_root` holds a file for `block_hash` — a presence probe only.
+/// The cache also rejects a file that does not parse, so a corrupt entry counts
+/// here and is still fetched on the read path; callers use this to estimate,
+/// never to decide what to skip.
+pub fn is_block_cached(cache_root: &Path, block_hash: u64) -> bool {
+    cache_root.join(cache_block_path(block_hash)).is_file()
+}
+
 #[async_trait]
 impl BlockStore for CacheBlockStore {
     async fn put_stored_block(&self, block: StoredBlock) -> Result<(), StoreError> {
@@ -86,6 +104,15 @@ impl BlockStore for CacheBlockStore {
     }
 
     async fn get_stored_block(&self, block_hash: u64) -> Result<StoredBlock, StoreError> {
+        self.get_stored_block_cancellable(block_hash, &CancellationToken::new())
+            .await
+    }
+
+    async fn get_stored_block_cancellable(
+        &self,
+        block_hash: u64,
+        cancel: &CancellationToken,
+    ) -> Result<StoredBlock, StoreError> {
         let key = cache_block_path(block_hash);
         // Probe the cache file directly.
         // Cache hit only when the file exists, parses, and matches the hash;
@@ -114,10 +141,20 @@ impl BlockStore for CacheBlockStore {
                 })
                 .await;
             }
+            if let Some(sink) = &self.events {
+                sink(&StoreEvent::BlockFetched {
+                    block_hash,
+                    source: BlockSource::Cache,
+                    wire_bytes: data.len() as u64,
+                });
+            }
             return Ok(block);
         }
         // Miss → fetch from remote and write back to the cache.
-        let block = self.remote.get_stored_block(block_hash).await?;
+        let block = self
+            .remote
+            .get_stored_block_cancellable(block_hash, cancel)
+            .await?;
         let mut wb = self.cache_client.new_object(&key).await?;
         if present {
             // The file is there and did not parse, or named a different block.
@@ -137,6 +174,15 @@ impl BlockStore for CacheBlockStore {
     }
 
     async fn preflight_get(&self, block_hashes: &[u64]) -> Result<(), StoreError> {
+        self.preflight_get_cancellable(block_hashes, &CancellationToken::new())
+            .await
+    }
+
+    async fn preflight_get_cancellable(
+        &self,
+        block_hashes: &[u64],
+        cancel: &CancellationToken,
+    ) -> Result<(), StoreError> {
         // Only prefetch blocks not already cached (best-effort filter).
         let mut missing = Vec::new();
         for &h in block_hashes {
@@ -149,7 +195,9 @@ impl BlockStore for CacheBlockStore {
                 missing.push(h);
             }
         }
-        self.remote.preflight_get(&missing).await
+        self.remote
+            .preflight_get_cancellable(&missing, cancel)
+            .await
     }
 
     async fn get_existing_content(
@@ -170,6 +218,10 @@ impl BlockStore for CacheBlockStore {
         self.remote.flush().await
     }
 
+    async fn refresh_index(&self) -> Result<(), StoreError> {
+        self.remote.refresh_index().await
+    }
+
     async fn close(&self) -> Result<(), StoreError> {
         self.remote.close().await?;
         // Post-run LRU eviction: after the store closes (all write-backs done),
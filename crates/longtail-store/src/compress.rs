@@ -16,6 +16,7 @@ use async_trait::async_trait;
 use longtail_core::compress::{decode_block_payload, encode_block_payload};
 use longtail_core::{StoreIndex, StoredBlock};
 use tokio::sync::oneshot;
+use tokio_util::sync::CancellationToken;
 
 use crate::block_store::{BlockStore, StatsSnapshot};
 use crate::error::StoreError;
@@ -73,7 +74,19 @@ impl BlockStore for CompressBlockStore {
     }
 
     async fn get_stored_block(&self, block_hash: u64) -> Result<StoredBlock, StoreError> {
-        let block = self.inner.get_stored_block(block_hash).await?;
+        self.get_stored_block_cancellable(block_hash, &CancellationToken::new())
+            .await
+    }
+
+    async fn get_stored_block_cancellable(
+        &self,
+        block_hash: u64,
+        cancel: &CancellationToken,
+    ) -> Result<StoredBlock, StoreError> {
+        let block = self
+            .inner
+            .get_stored_block_cancellable(block_hash, cancel)
+            .await?;
         let StoredBlock {
             block_index,
             payload,
@@ -112,6 +125,16 @@ impl BlockStore for CompressBlockStore {
         self.inner.preflight_get(block_hashes).await
     }
 
+    async fn preflight_get_cancellable(
+        &self,
+        block_hashes: &[u64],
+        cancel: &CancellationToken,
+    ) -> Result<(), StoreError> {
+        self.inner
+            .preflight_get_cancellable(block_hashes, cancel)
+            .await
+    }
+
     async fn get_existing_content(
         &self,
         chunk_hashes: &[u64],
@@ -130,6 +153,10 @@ impl BlockStore for CompressBlockStore {
         self.inner.flush().await
     }
 
+    async fn refresh_index(&self) -> Result<(), StoreError> {
+        self.inner.refresh_index().await
+    }
+
     async fn close(&self) -> Result<(), StoreError> {
         self.inner.close().await
     }
@@ -82,6 +82,11 @@ pub enum StoreError {
     #[error("network error: {0}")]
     Network(String),
 
+    /// The operation's cancellation token fired while a block read was in
+    /// flight; the read was dropped and nothing it had received was kept.
+    #[error("cancelled")]
+    Cancelled,
+
     /// A backend-specific error (S3 SDK, etc.) that has no more specific variant.
     #[error("backend error: {0}")]
     Backend(String),
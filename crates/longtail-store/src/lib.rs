@@ -16,6 +16,10 @@
 //! - [`cache`] / [`compress`] — the `.lrb` cache and rayon-bridged compression
 //!   decorators.
 //! - [`uri`] — the block-level URI dispatcher (`Compress(Cache(Remote(…)))`).
+//! - [`pause`] — [`pause::PauseToken`], which parks new work without tearing
+//!   an operation down.
+//! - [`scheme`] — the registry both dispatchers consult for application-defined
+//!   URI schemes.
 #![forbid(unsafe_code)]
 
 pub mod blob;
@@ -23,7 +27,9 @@ pub mod block_store;
 pub mod cache;
 pub mod compress;
 pub mod error;
+pub mod pause;
 pub mod remote;
+pub mod scheme;
 pub mod sync;
 pub mod uri;
 
@@ -31,11 +37,17 @@ pub use blob::{
     BlobClient, BlobObject, BlobProperties, BlobStore, FsBlobStore, MemBlobStore,
     create_blob_store_for_uri,
 };
-pub use block_store::{BlockStore, BlockStoreStats, StatsSnapshot};
-pub use cache::{CacheBlockStore, EvictionReport, evict_cache_dir};
+pub use block_store::{
+    BlockSource, BlockStore, BlockStoreStats, StatsSnapshot, StoreEvent, StoreEventSink,
+};
+pub use cache::{CacheBlockStore, EvictionReport, evict_cache_dir, is_block_cached};
 pub use compress::CompressBlockStore;
 pub use error::StoreError;
+pub use pause::PauseToken;
 pub use remote::{DEFAULT_MAX_PREFETCH_BYTES, RemoteBlockStore};
+pub use scheme::{
+    BlobStoreFactory, register_blob_scheme, registered_blob_scheme, unregister_blob_scheme,
+};
 pub use sync::{
     AccessType, add_to_remote_store_index, block_path, overwrite_remote_store_index,
     read_merged_store_index,
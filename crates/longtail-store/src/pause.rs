@@ -0,0 +1,163 @@
+//! [`PauseToken`]: park new work without tearing an operation down.
+//!
+//! Cancelling ends an operation; resuming one means starting it again, which
+//! re-scans the target and re-reads the store index. Pausing keeps all of that
+//! in memory: whoever holds the token stops *starting* work — a block fetch, a
+//! block write, a file hash — while the work already in flight finishes, and
+//! picks up where it left off on [`resume`](PauseToken::resume).
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::sync::Notify;
+
+/// A cloneable pause switch. Clones share state; pausing one pauses all.
+#[derive(Clone, Default)]
+pub struct PauseToken {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    /// Mirrors `clock.since.is_some()` for the lock-free fast path.
+    paused: AtomicBool,
+    clock: Mutex<Clock>,
+    /// Wakes threads parked in [`PauseToken::wait_blocking`].
+    resumed_cv: Condvar,
+    /// Wakes tasks parked in [`PauseToken::resumed`].
+    resumed: Notify,
+}
+
+/// Pause bookkeeping: when the current pause began, and the total of the
+/// finished ones.
+#[derive(Default)]
+struct Clock {
+    since: Option<Instant>,
+    total: Duration,
+}
+
+impl std::fmt::Debug for PauseToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PauseToken")
+            .field("paused", &self.is_paused())
+            .finish()
+    }
+}
+
+impl PauseToken {
+    /// A token that is not paused.
+    pub fn new() -> PauseToken {
+        PauseToken::default()
+    }
+
+    /// Stop starting new work. Idempotent.
+    pub fn pause(&self) {
+        let mut clock = self.lock();
+        if clock.since.is_none() {
+            clock.since = Some(Instant::now());
+            self.inner.paused.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// Let parked work continue. Idempotent.
+    pub fn resume(&self) {
+        {
+            let mut clock = self.lock();
+            let Some(since) = clock.since.take() else {
+                return;
+            };
+            clock.total += since.elapsed();
+            self.inner.paused.store(false, Ordering::SeqCst);
+        }
+        self.inner.resumed_cv.notify_all();
+        self.inner.resumed.notify_waiters();
+    }
+
+    /// Whether the token is paused right now.
+    pub fn is_paused(&self) -> bool {
+        self.inner.paused.load(Ordering::SeqCst)
+    }
+
+    /// Total time spent paused since the token was made, including a pause
+    /// still in progress. Two readings bracket an operation's idle time.
+    pub fn paused_for(&self) -> Duration {
+        let clock = self.lock();
+        clock.total + clock.since.map_or(Duration::ZERO, |s| s.elapsed())
+    }
+
+    /// Returns once the token is not paused — immediately if it is not.
+    /// Cancel-safe; to stop waiting on cancellation, `select!` it against the
+    /// cancellation future.
+    pub async fn resumed(&self) {
+        loop {
+            let notified = self.inner.resumed.notified();
+            tokio::pin!(notified);
+            // Registered before the check, so a resume in between is not missed.
+            notified.as_mut().enable();
+            if !self.is_paused() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Block the current thread until the token is not paused or
+    /// `is_cancelled` returns true, whichever comes first. For synchronous
+    /// workers (a rayon scan); async code uses [`resumed`](Self::resumed).
+    pub fn wait_blocking(&self, is_cancelled: &dyn Fn() -> bool) {
+        let mut clock = self.lock();
+        while clock.since.is_some() && !is_cancelled() {
+            // Cancellation has no way to wake this thread, so poll for it.
+            clock = self
+                .inner
+                .resumed_cv
+                .wait_timeout(clock, Duration::from_millis(100))
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Clock> {
+        self.inner.clock.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn a_parked_task_runs_on_resume_and_the_pause_is_timed() {
+        let token = PauseToken::new();
+        token.resumed().await; // not paused: returns at once
+        token.pause();
+        let parked = tokio::spawn({
+            let token = token.clone();
+            async move { token.resumed().await }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!parked.is_finished());
+        token.resume();
+        parked.await.unwrap();
+        assert!(token.paused_for() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn a_blocked_thread_wakes_on_resume_or_cancel() {
+        let token = PauseToken::new();
+        token.pause();
+        let waiter = std::thread::spawn({
+            let token = token.clone();
+            move || token.wait_blocking(&|| false)
+        });
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(!waiter.is_finished());
+        token.resume();
+        waiter.join().unwrap();
+
+        token.pause();
+        token.wait_blocking(&|| true);
+        assert!(token.is_paused());
+    }
+}
@@ -60,10 +60,14 @@ use futures_util::FutureExt;
 use futures_util::future::{BoxFuture, Shared};
 use longtail_core::{BlockIndex, StoreIndex, StoredBlock};
 use tokio::sync::{Mutex, OwnedSemaphorePermit, Semaphore, mpsc, oneshot};
+use tokio_util::sync::CancellationToken;
 
 use crate::blob::BlobStore;
-use crate::block_store::{BlockStore, BlockStoreStats, StatsSnapshot};
+use crate::block_store::{
+    BlockSource, BlockStore, BlockStoreStats, StatsSnapshot, StoreEvent, StoreEventSink,
+};
 use crate::error::StoreError;
+use crate::pause::PauseToken;
 use crate::sync::{self, AccessType};
 
 /// Default prefetch memory budget (`maxPrefetchMemory`, remotestore.go:992).
@@ -79,6 +83,9 @@ const PUT_RETRY_DELAYS: [std::time::Duration; 3] = [
     std::time::Duration::from_millis(2000),
 ];
 
+/// How often a prefetch parked by a pause checks its claim is still wanted.
+const PAUSE_CLAIM_POLL: std::time::Duration = std::time::Duration::from_millis(250);
+
 type FetchResult = Result<Arc<StoredBlock>, Arc<StoreError>>;
 type SharedFetch = Shared<BoxFuture<'static, FetchResult>>;
 
@@ -128,6 +135,11 @@ enum IndexCommand {
     Flush {
         reply: oneshot::Sender<Result<(), StoreError>>,
     },
+    /// Replace the loaded index with a fresh read (a version-local override
+    /// included: afterwards the store answers from its own index).
+    Refresh {
+        reply: oneshot::Sender<Result<(), StoreError>>,
+    },
     Shutdown {
         reply: oneshot::Sender<Result<(), StoreError>>,
     },
@@ -144,6 +156,8 @@ pub struct RemoteBlockStore {
     stats: Arc<BlockStoreStats>,
     index_tx: mpsc::Sender<IndexCommand>,
     closed: AtomicBool,
+    events: Option<StoreEventSink>,
+    pause: Option<PauseToken>,
 }
 
 impl std::fmt::Debug for RemoteBlockStore {
@@ -235,6 +249,99 @@ impl RemoteBlockStore {
             stats,
             index_tx,
             closed: AtomicBool::new(false),
+            events: None,
+            pause: None,
+        })
+    }
+
+    /// Report every block read and read retry to `sink` (see [`StoreEvent`]).
+    pub fn with_events(mut self, sink: StoreEventSink) -> RemoteBlockStore {
+        self.events = Some(sink);
+        self
+    }
+
+    /// Hold back prefetches not yet started while `token` is paused; fetches
+    /// already on the wire, and demand gets, carry on.
+    pub fn with_pause(mut self, token: PauseToken) -> RemoteBlockStore {
+        self.pause = Some(token);
+        self
+    }
+
+    /// One attempt at a demand get: coalesce onto an in-flight fetch of
+    /// `block_hash`, or claim it and fetch. `None` when `cancel` fired first.
+    async fn claim_or_coalesce(
+        &self,
+        block_hash: u64,
+        cancel: &CancellationToken,
+    ) -> Option<Result<StoredBlock, StoreError>> {
+        // Coalesce with an in-flight (dispatched or demand-claimed) fetch if an
+        // entry exists; otherwise claim the hash and fetch inline. A demand get
+        // NEVER waits on a budget-parked (queued, undispatched) prefetch —
+        // demand fetches always proceed, budget or not (Go's `fetchBlock` has
+        // no budget check and runs in every worker branch including
+        // over-budget, remotestore.go:504-506/:533-534/:560-561). Demand-fetch
+        // memory is bounded by the worker semaphore, exactly like Go.
+        let fut = {
+            let mut st = self.prefetch.lock().await;
+            if let Some(e) = st.entries.get(&block_hash) {
+                e.fut.clone()
+            } else {
+                // Claim the hash (Go's placeholder insert, remotestore.go:
+                // 283-284): remove any undispatched claim so its parked
+                // dispatch task abandons, and register a permit-less entry so
+                // a later-dispatched background prefetch skips it (Go's
+                // later-prefetch no-op, :361-366). Concurrent demand gets for
+                // the same block coalesce on this entry.
+                st.queued.remove(&block_hash);
+                let fut: SharedFetch = fetch_stored_block(
+                    self.client.clone(),
+                    self.worker_sem.clone(),
+                    self.stats.clone(),
+                    self.events.clone(),
+                    block_hash,
+                )
+                .map(|r| r.map(Arc::new).map_err(Arc::new))
+                .boxed()
+                .shared();
+                st.entries.insert(
+                    block_hash,
+                    PrefetchEntry {
+                        fut: fut.clone(),
+                        _permit: None,
+                    },
+                );
+                fut
+            }
+        };
+        // The await consumes our Shared clone; removing the entry then drops
+        // the map's clone (releasing a dispatched prefetch's budget permit).
+        // Cancelled first, a demand-claimed entry no other get is waiting on
+        // is removed instead, which drops its read.
+        let result = tokio::select! {
+            biased;
+            _ = cancel.cancelled() => None,
+            res = fut => Some(res),
+        };
+        let mut st = self.prefetch.lock().await;
+        let Some(result) = result else {
+            if let Some(e) = st.entries.get(&block_hash)
+                && e._permit.is_none()
+                && e.fut.strong_count() == Some(1)
+            {
+                st.entries.remove(&block_hash);
+            }
+            return None;
+        };
+        // A cancelled prefetch has already taken its entry out; what is there
+        // now belongs to someone else.
+        if !matches!(&result, Err(e) if matches!(**e, StoreError::Cancelled)) {
+            st.entries.remove(&block_hash);
+        }
+        drop(st);
+        Some(match result {
+            // Sole holder (the common demand case) → move the block out copy-free.
+            Ok(block) => Ok(Arc::try_unwrap(block).unwrap_or_else(|arc| (*arc).clone())),
+            Err(e) => Err(clone_store_error(&e)),
         })
     }
 
@@ -277,15 +384,41 @@ async fn dispatch_prefetch(
     client: Arc<dyn crate::blob::BlobClient>,
     worker_sem: Arc<Semaphore>,
     stats: Arc<BlockStoreStats>,
+    events: Option<StoreEventSink>,
+    pause: Option<PauseToken>,
+    cancel: CancellationToken,
     hash: u64,
     permits: u32,
 ) {
+    let cancelled = cancel.cancelled();
+    tokio::pin!(cancelled);
+    // Paused: park before taking budget, so a paused store holds none for
+    // blocks it has not started. A claim consumed meanwhile (a demand get, or
+    // flush/close draining the queue) ends the wait.
+    if let Some(pause) = &pause {
+        while pause.is_paused() {
+            tokio::select! {
+                _ = pause.resumed() => {}
+                _ = &mut cancelled => return abandon_claim(&prefetch, hash).await,
+                _ = tokio::time::sleep(PAUSE_CLAIM_POLL) => {
+                    if !prefetch.lock().await.queued.contains(&hash) {
+                        return;
+                    }
+                }
+            }
+        }
+    }
     // Budget acquired at dispatch, not enqueue. Parks under budget pressure;
     // nothing awaits this task (map entries are created only below, after
     // acquisition), so parking here can never block a demand fetch.
-    let permit = match prefetch_sem.acquire_many_owned(permits).await {
-        Ok(p) => p,
-        Err(_) => return, // semaphore closed — store torn down
+    // A prefetch cancelled before it started gives its claim up, so a later
+    // preflight of the same block enqueues it again.
+    let permit = tokio::select! {
+        p = prefetch_sem.acquire_many_owned(permits) => match p {
+            Ok(p) => p,
+            Err(_) => return, // semaphore closed — store torn down
+        },
+        _ = &mut cancelled => return abandon_claim(&prefetch, hash).await,
     };
     let tx = {
         let mut st = prefetch.lock().await;
@@ -324,16 +457,32 @@ async fn dispatch_prefetch(
     // Drive the fetch to completion; the result stays in the entry — holding
     // the budget permit — until consumed or flushed. A failed send means the
     // entry was flushed away with no consumer waiting: drop the block.
-    let res = fetch_stored_block(client, worker_sem, stats, hash).await;
+    // Cancellation drops the read wherever it is — waiting for a worker,
+    // mid-body or in a retry sleep — and takes the entry out before waking
+    // its consumers, which fetch again unless their own token fired too.
+    let res = tokio::select! {
+        biased;
+        _ = &mut cancelled => {
+            prefetch.lock().await.entries.remove(&hash);
+            Err(StoreError::Cancelled)
+        }
+        res = fetch_stored_block(client, worker_sem, stats, events, hash) => res,
+    };
     let _ = tx.send(res.map(Arc::new).map_err(Arc::new));
 }
 
+/// Give up an undispatched prefetch claim whose operation was cancelled.
+async fn abandon_claim(prefetch: &Mutex<PrefetchState>, hash: u64) {
+    prefetch.lock().await.queued.remove(&hash);
+}
+
 /// Fetch + parse + validate a stored block by hash, bounded by `worker_sem`.
 /// Shared by direct gets and prefetch.
 async fn fetch_stored_block(
     client: Arc<dyn crate::blob::BlobClient>,
     worker_sem: Arc<Semaphore>,
     stats: Arc<BlockStoreStats>,
+    events: Option<StoreEventSink>,
     block_hash: u64,
 ) -> Result<StoredBlock, StoreError> {
     let _permit = worker_sem
@@ -342,7 +491,15 @@ async fn fetch_stored_block(
         .map_err(|_| StoreError::WorkerGone)?;
     stats.add(&stats.get_count, 1);
     let key = sync::block_path("chunks", block_hash);
-    let (data, retries) = match sync::read_blob_with_retry(&*client, &key).await {
+    let on_retry = |attempt| {
+        if let Some(sink) = &events {
+            sink(&StoreEvent::Retry {
+                block_hash,
+                attempt,
+            });
+        }
+    };
+    let (data, retries) = match sync::read_blob_with_retry(&*client, &key, &on_retry).await {
         Ok(v) => v,
         Err(e) => {
             stats.add(&stats.get_fail_count, 1);
@@ -370,6 +527,13 @@ async fn fetch_stored_block(
         &stats.get_chunk_count,
         block.block_index.chunk_count() as u64,
     );
+    if let Some(sink) = &events {
+        sink(&StoreEvent::BlockFetched {
+            block_hash,
+            source: BlockSource::Remote,
+            wire_bytes: data.len() as u64,
+        });
+    }
     Ok(block)
 }
 
@@ -442,56 +606,42 @@ impl BlockStore for RemoteBlockStore {
     }
 
     async fn get_stored_block(&self, block_hash: u64) -> Result<StoredBlock, StoreError> {
-        // Coalesce with an in-flight (dispatched or demand-claimed) fetch if an
-        // entry exists; otherwise claim the hash and fetch inline. A demand get
-        // NEVER waits on a budget-parked (queued, undispatched) prefetch —
-        // demand fetches always proceed, budget or not (Go's `fetchBlock` has
-        // no budget check and runs in every worker branch including
-        // over-budget, remotestore.go:504-506/:533-534/:560-561). Demand-fetch
-        // memory is bounded by the worker semaphore, exactly like Go.
-        let fut = {
-            let mut st = self.prefetch.lock().await;
-            if let Some(e) = st.entries.get(&block_hash) {
-                e.fut.clone()
-            } else {
-                // Claim the hash (Go's placeholder insert, remotestore.go:
-                // 283-284): remove any undispatched claim so its parked
-                // dispatch task abandons, and register a permit-less entry so
-                // a later-dispatched background prefetch skips it (Go's
-                // later-prefetch no-op, :361-366). Concurrent demand gets for
-                // the same block coalesce on this entry.
-                st.queued.remove(&block_hash);
-                let fut: SharedFetch = fetch_stored_block(
-                    self.client.clone(),
-                    self.worker_sem.clone(),
-                    self.stats.clone(),
-                    block_hash,
-                )
-                .map(|r| r.map(Arc::new).map_err(Arc::new))
-                .boxed()
-                .shared();
-                st.entries.insert(
-                    block_hash,
-                    PrefetchEntry {
-                        fut: fut.clone(),
-                        _permit: None,
-                    },
-                );
-                fut
+        self.get_stored_block_cancellable(block_hash, &CancellationToken::new())
+            .await
+    }
+
+    async fn get_stored_block_cancellable(
+        &self,
+        block_hash: u64,
+        cancel: &CancellationToken,
+    ) -> Result<StoredBlock, StoreError> {
+        loop {
+            if cancel.is_cancelled() {
+                return Err(StoreError::Cancelled);
+            }
+            match self.claim_or_coalesce(block_hash, cancel).await {
+                // A prefetch this get coalesced onto was cancelled by the
+                // operation that started it, not by ours: fetch again.
+                Some(Err(StoreError::Cancelled)) if !cancel.is_cancelled() => continue,
+                Some(res) => return res,
+                None => return Err(StoreError::Cancelled),
             }
-        };
-        // The await consumes our Shared clone; removing the entry then drops
-        // the map's clone (releasing a dispatched prefetch's budget permit).
-        let result = fut.await;
-        self.prefetch.lock().await.entries.remove(&block_hash);
-        match result {
-            // Sole holder (the common demand case) → move the block out copy-free.
-            Ok(block) => Ok(Arc::try_unwrap(block).unwrap_or_else(|arc| (*arc).clone())),
-            Err(e) => Err(clone_store_error(&e)),
         }
     }
 
     async fn preflight_get(&self, block_hashes: &[u64]) -> Result<(), StoreError> {
+        self.preflight_get_cancellable(block_hashes, &CancellationToken::new())
+            .await
+    }
+
+    async fn preflight_get_cancellable(
+        &self,
+        block_hashes: &[u64],
+        cancel: &CancellationToken,
+    ) -> Result<(), StoreError> {
+        if cancel.is_cancelled() {
+            return Err(StoreError::Cancelled);
+        }
         if block_hashes.is_empty() {
             return Ok(());
         }
@@ -524,6 +674,9 @@ impl BlockStore for RemoteBlockStore {
                 self.client.clone(),
                 self.worker_sem.clone(),
                 self.stats.clone(),
+                self.events.clone(),
+                self.pause.clone(),
+                cancel.clone(),
                 hash,
                 permits,
             ));
@@ -619,6 +772,15 @@ impl BlockStore for RemoteBlockStore {
         rx.await.map_err(|_| StoreError::WorkerGone)?
     }
 
+    async fn refresh_index(&self) -> Result<(), StoreError> {
+        let (reply, rx) = oneshot::channel();
+        self.index_tx
+            .send(IndexCommand::Refresh { reply })
+            .await
+            .map_err(|_| StoreError::WorkerGone)?;
+        rx.await.map_err(|_| StoreError::WorkerGone)?
+    }
+
     fn stats(&self) -> StatsSnapshot {
         self.stats.snapshot()
     }
@@ -661,6 +823,7 @@ fn clone_store_error(e: &StoreError) -> StoreError {
             reason: reason.clone(),
         },
         StoreError::WorkerGone => StoreError::WorkerGone,
+        StoreError::Cancelled => StoreError::Cancelled,
         StoreError::NotAuthorized(s) => StoreError::NotAuthorized(s.clone()),
         StoreError::Network(s) => StoreError::Network(s.clone()),
         StoreError::Backend(s) => StoreError::Backend(s.clone()),
@@ -758,6 +921,14 @@ async fn index_owner(
                 let r = persist(&mut index, &mut added, &*client, access_type, false).await;
                 let _ = reply.send(r);
             }
+            IndexCommand::Refresh { reply } => {
+                // Blocks this store added and has not persisted yet stay in
+                // `added`, so they are merged over the fresh read as before.
+                let r = sync::read_remote_store_index(&*blob_store, &*client, access_type)
+                    .await
+                    .map(|loaded| index = Some(loaded));
+                let _ = reply.send(r);
+            }
             IndexCommand::Shutdown { reply } => {
                 let r = persist(&mut index, &mut added, &*client, access_type, true).await;
                 let _ = reply.send(r);
@@ -779,7 +950,9 @@ fn fail_command(cmd: IndexCommand, err: impl Fn() -> StoreError) {
         IndexCommand::GetExistingContent { reply, .. } => {
             let _ = reply.send(Err(err()));
         }
-        IndexCommand::Flush { reply } | IndexCommand::Shutdown { reply } => {
+        IndexCommand::Flush { reply }
+        | IndexCommand::Refresh { reply }
+        | IndexCommand::Shutdown { reply } => {
             let _ = reply.send(Err(err()));
         }
     }
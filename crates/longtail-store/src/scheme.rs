@@ -0,0 +1,96 @@
+//! The process-wide registry of application-defined URI schemes.
+//!
+//! The built-in schemes (`fsblob`, `file`, `s3`, and the rejected `gs`/`abfs`/
+//! `abfss`) are dispatched by a `match` in [`crate::blob::create_blob_store_for_uri`]
+//! and [`crate::uri::create_block_store_for_uri`]. Any other scheme is looked up
+//! here, so in-house storage (`studio-cas://…`) plugs into every dispatcher —
+//! and into the facade's `.lvi`/`.lsi` reads and writes — by registering a
+//! factory for it once at startup:
+//!
+//! ```no_run
+//! use longtail_store::{MemBlobStore, register_blob_scheme};
+//!
+//! register_blob_scheme("studio-cas", |uri: &str| {
+//!     Ok(Box::new(MemBlobStore::new(uri, false)))
+//! })
+//! .unwrap();
+//! ```
+//!
+//! The factory is handed the whole URI (`studio-cas://bucket/prefix`) and
+//! returns a [`BlobStore`] rooted there; the block store, the index sync and
+//! the blob-level readers are all built over that. A registered store is treated
+//! as networked: `worker_count = 0` resolves to `min(NumCPU, 8)`, as for `s3`.
+
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, RwLock};
+
+use crate::blob::BlobStore;
+use crate::error::StoreError;
+
+/// Builds a [`BlobStore`] for a URI of a registered scheme.
+pub type BlobStoreFactory =
+    Arc<dyn Fn(&str) -> Result<Box<dyn BlobStore>, StoreError> + Send + Sync>;
+
+/// Schemes the dispatchers handle themselves; registering one is refused so a
+/// factory cannot silently shadow (or be shadowed by) the built-in arm.
+const BUILT_IN: [&str; 6] = ["fsblob", "file", "s3", "gs", "abfs", "abfss"];
+
+static REGISTRY: LazyLock<RwLock<HashMap<String, BlobStoreFactory>>> =
+    LazyLock::new(Default::default);
+
+/// Register `factory` for `scheme` (without the `://`; matched
+/// case-insensitively), replacing any factory registered for it before.
+///
+/// Refused with [`StoreError::InvalidUri`] for a built-in scheme, a
+/// single-letter scheme (those are Windows drive letters, always paths), or a
+/// name that is not `ALPHA *( ALPHA / DIGIT / "+" / "-" / "." )`.
+pub fn register_blob_scheme<F>(scheme: &str, factory: F) -> Result<(), StoreError>
+where
+    F: Fn(&str) -> Result<Box<dyn BlobStore>, StoreError> + Send + Sync + 'static,
+{
+    let scheme = scheme.to_ascii_lowercase();
+    let refuse = |reason: &str| StoreError::InvalidUri {
+        uri: format!("{scheme}://"),
+        reason: reason.to_string(),
+    };
+    if !is_scheme_name(&scheme) {
+        return Err(refuse("not a valid uri scheme name"));
+    }
+    if scheme.len() == 1 {
+        return Err(refuse("a single-letter scheme is a drive letter"));
+    }
+    if BUILT_IN.contains(&scheme.as_str()) {
+        return Err(refuse("a built-in scheme cannot be re-registered"));
+    }
+    REGISTRY
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(scheme, Arc::new(factory));
+    Ok(())
+}
+
+/// Remove the factory for `scheme`; returns whether there was one.
+pub fn unregister_blob_scheme(scheme: &str) -> bool {
+    REGISTRY
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(&scheme.to_ascii_lowercase())
+        .is_some()
+}
+
+/// The factory registered for `uri`'s scheme, if any.
+pub fn registered_blob_scheme(uri: &str) -> Option<BlobStoreFactory> {
+    let (scheme, _) = crate::blob::split_scheme(uri)?;
+    REGISTRY
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&scheme.to_ascii_lowercase())
+        .cloned()
+}
+
+/// RFC 3986 §3.1: a letter, then letters, digits, `+`, `-` or `.`.
+pub(crate) fn is_scheme_name(s: &str) -> bool {
+    let mut bytes = s.bytes();
+    bytes.next().is_some_and(|b| b.is_ascii_alphabetic())
+        && bytes.all(|b| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'-' | b'.'))
+}
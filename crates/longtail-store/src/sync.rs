@@ -68,9 +68,11 @@ pub fn block_path(base: &str, block_hash: u64) -> String {
 /// `ReadBlobWithRetry` (longtailutils.go:401): exists-precheck (not-found → no
 /// retry), then read with the fixed ladder; a mid-loop not-found also
 /// short-circuits. Returns the bytes and the retry count (for stats).
+/// `on_retry` is called with the attempt number (from 1) before each retry.
 pub(crate) async fn read_blob_with_retry(
     client: &dyn BlobClient,
     key: &str,
+    on_retry: &(dyn Fn(u32) + Sync),
 ) -> Result<(Vec<u8>, u32), StoreError> {
     let obj = client.new_object(key).await?;
     if !obj.exists().await? {
@@ -95,6 +97,7 @@ pub(crate) async fn read_blob_with_retry(
                     error = %e,
                     "blob read failed; retrying"
                 );
+                on_retry(retry_count + 1);
                 sleep(READ_RETRY_DELAYS[retry_count as usize]).await;
                 retry_count += 1;
             }
@@ -442,7 +445,7 @@ async fn get_store_index_from_blocks(
 ) -> Result<StoreIndex, StoreError> {
     let mut blocks: Vec<BlockIndex> = Vec::with_capacity(block_keys.len());
     for key in block_keys {
-        let (data, _retries) = match read_blob_with_retry(client, key).await {
+        let (data, _retries) = match read_blob_with_retry(client, key, &|_| {}).await {
             Ok(v) => v,
             Err(e) if e.is_not_found() => continue,
             Err(e) => return Err(e),
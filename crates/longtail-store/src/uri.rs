@@ -13,8 +13,9 @@
 //! subsumed by the prefetch coalescing in [`RemoteBlockStore`].)
 //!
 //! Worker-count defaults (`CreateBlockStoreForURI` :1977-2032, documented at
-//! commands/commands.go:12): fsblob → `NumCPU` (uncapped); networked (s3) →
-//! `min(NumCPU, 8)`. A caller `worker_count` of `0` requests the default.
+//! commands/commands.go:12): fsblob → `NumCPU` (uncapped); networked (s3, and
+//! any scheme registered in [`crate::scheme`]) → `min(NumCPU, 8)`. A caller
+//! `worker_count` of `0` requests the default.
 
 use std::path::PathBuf;
 use std::sync::Arc;
@@ -22,10 +23,11 @@ use std::sync::Arc;
 use longtail_core::StoreIndex;
 
 use crate::blob::{BlobStore, FsBlobStore};
-use crate::block_store::BlockStore;
+use crate::block_store::{BlockStore, StoreEventSink};
 use crate::cache::CacheBlockStore;
 use crate::compress::CompressBlockStore;
 use crate::error::StoreError;
+use crate::pause::PauseToken;
 use crate::remote::RemoteBlockStore;
 use crate::sync::AccessType;
 
@@ -54,6 +56,12 @@ pub struct BlockStoreOpts {
     /// genuinely writes blocks larger than the default — it exists so a store
     /// cannot choose this process's memory use.
     pub max_block_bytes: Option<u64>,
+    /// Receives a [`crate::StoreEvent`] per block read (from the cache or the
+    /// store) and per read retry. `None` = no events.
+    pub events: Option<StoreEventSink>,
+    /// While paused, the remote store starts no new prefetch (see
+    /// [`RemoteBlockStore::with_pause`]). `None` = never paused.
+    pub pause: Option<PauseToken>,
     /// S3 credential/endpoint options (feature `s3`).
     #[cfg(feature = "s3")]
     pub s3_options: S3Options,
@@ -69,6 +77,8 @@ impl BlockStoreOpts {
             pool,
             version_local_store_index: None,
             max_block_bytes: None,
+            events: None,
+            pause: None,
             #[cfg(feature = "s3")]
             s3_options: S3Options::default(),
         }
@@ -96,11 +106,12 @@ fn local_worker_count(requested: usize) -> usize {
 /// concurrency (e.g. the facade's concurrent block apply) to
 /// the same value without introducing a second knob.
 pub fn resolved_worker_count(uri: &str, requested: usize) -> usize {
-    // s3:// is the only networked scheme (gs/abfs are rejected by
+    // s3:// and registered schemes are networked (gs/abfs are rejected by
     // `resolve_backend`); every other accepted form is a filesystem store.
     let is_networked = crate::blob::split_scheme(uri)
         .map(|(scheme, _)| scheme == "s3")
-        .unwrap_or(false);
+        .unwrap_or(false)
+        || crate::scheme::registered_blob_scheme(uri).is_some();
     if is_networked {
         networked_worker_count(requested)
     } else {
@@ -145,19 +156,30 @@ pub async fn create_block_store_for_uri_with_budget(
         None
     };
 
-    let remote: Arc<dyn BlockStore> = Arc::new(
-        RemoteBlockStore::with_prefetch_budget(
-            blob_store,
-            opts.access_type,
-            worker_count,
-            max_prefetch_bytes.unwrap_or(crate::remote::DEFAULT_MAX_PREFETCH_BYTES),
-            override_index,
-        )
-        .await?,
-    );
+    let mut remote = RemoteBlockStore::with_prefetch_budget(
+        blob_store,
+        opts.access_type,
+        worker_count,
+        max_prefetch_bytes.unwrap_or(crate::remote::DEFAULT_MAX_PREFETCH_BYTES),
+        override_index,
+    )
+    .await?;
+    if let Some(sink) = &opts.events {
+        remote = remote.with_events(sink.clone());
+    }
+    if let Some(token) = &opts.pause {
+        remote = remote.with_pause(token.clone());
+    }
+    let remote: Arc<dyn BlockStore> = Arc::new(remote);
 
     let base: Arc<dyn BlockStore> = match &opts.cache_dir {
-        Some(dir) => Arc::new(CacheBlockStore::new(dir, remote, cache_size_limit).await?),
+        Some(dir) => {
+            let mut cache = CacheBlockStore::new(dir, remote, cache_size_limit).await?;
+            if let Some(sink) = &opts.events {
+                cache = cache.with_events(sink.clone());
+            }
+            Arc::new(cache)
+        }
         None => remote,
     };
 
@@ -231,6 +253,12 @@ fn resolve_backend(
                 }
             }
             _ => {
+                if let Some(factory) = crate::scheme::registered_blob_scheme(uri) {
+                    return Ok((
+                        Arc::from(factory(uri)?),
+                        networked_worker_count(opts.worker_count),
+                    ));
+                }
                 if scheme.len() == 1 {
                     // Windows drive letter `c:\...` — a path.
                     return Ok((
@@ -8,7 +8,9 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 use async_trait::async_trait;
 use longtail_core::{BlockIndex, StoredBlock};
 use longtail_store::blob::{BlobClient, BlobObject, BlobProperties, BlobStore, MemBlobStore};
-use longtail_store::{AccessType, BlockStore, RemoteBlockStore, block_path};
+use longtail_store::{
+    AccessType, BlockSource, BlockStore, RemoteBlockStore, StoreEvent, block_path,
+};
 
 fn make_block(seed: u8) -> StoredBlock {
     let s = seed as u64;
@@ -155,6 +157,48 @@ async fn retry_ladder_recovers_under_paused_time() {
     store.close().await.unwrap();
 }
 
+/// Each retry and the fetch that ends the ladder reach an attached event sink.
+#[tokio::test(start_paused = true)]
+async fn retries_and_the_fetch_are_reported_as_events() {
+    let mem = MemBlobStore::new("", true);
+    let block = make_block(1);
+    seed_block(&mem, &block).await;
+    let wire = block.to_bytes().len() as u64;
+    let hash = block.block_index.block_hash;
+
+    let flaky = Arc::new(FlakyStore {
+        inner: mem,
+        fails_remaining: Arc::new(AtomicUsize::new(2)),
+    });
+    let events: Arc<std::sync::Mutex<Vec<StoreEvent>>> = Arc::default();
+    let sink = events.clone();
+    let store = RemoteBlockStore::new(flaky, AccessType::ReadOnly, 2)
+        .await
+        .unwrap()
+        .with_events(Arc::new(move |e| sink.lock().unwrap().push(*e)));
+
+    store.get_stored_block(hash).await.expect("recovers");
+    assert_eq!(
+        events.lock().unwrap().as_slice(),
+        [
+            StoreEvent::Retry {
+                block_hash: hash,
+                attempt: 1
+            },
+            StoreEvent::Retry {
+                block_hash: hash,
+                attempt: 2
+            },
+            StoreEvent::BlockFetched {
+                block_hash: hash,
+                source: BlockSource::Remote,
+                wire_bytes: wire,
+            },
+        ]
+    );
+    store.close().await.unwrap();
+}
+
 /// A not-found short-circuits the ladder with zero retries.
 #[tokio::test(start_paused = true)]
 async fn not_found_does_not_retry() {
@@ -296,3 +296,57 @@ async fn fs_get_objects() {
     let nested = client.get_objects("nest").await.unwrap();
     assert_eq!(nested.len(), 2);
 }
+
+// --- application-defined schemes ---
+
+/// A registered scheme reaches both dispatchers; built-in and drive-letter
+/// schemes cannot be registered, and an unregistered one is still a typo.
+#[tokio::test]
+async fn a_registered_scheme_dispatches_to_its_factory() {
+    use longtail_store::uri::{BlockStoreOpts, create_block_store_for_uri};
+    use longtail_store::{
+        AccessType, FsBlobStore, register_blob_scheme, resolved_worker_count,
+        unregister_blob_scheme,
+    };
+
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().to_path_buf();
+    register_blob_scheme("Studio-CAS", move |uri: &str| {
+        let rest = uri.split_once("://").unwrap().1;
+        Ok(Box::new(FsBlobStore::new(root.join(rest), false)))
+    })
+    .unwrap();
+
+    let store = create_blob_store_for_uri("studio-cas://project/store").unwrap();
+    let client = store.new_client().await.unwrap();
+    let mut obj = client.new_object("hello.bin").await.unwrap();
+    assert!(obj.write(Bytes::from_static(b"hi")).await.unwrap());
+    assert_eq!(
+        std::fs::read(dir.path().join("project/store/hello.bin")).unwrap(),
+        b"hi"
+    );
+
+    let pool = std::sync::Arc::new(rayon::ThreadPoolBuilder::new().build().unwrap());
+    let blocks = create_block_store_for_uri(
+        "studio-cas://project/store",
+        BlockStoreOpts::new(AccessType::ReadOnly, pool),
+    )
+    .await
+    .unwrap();
+    blocks.close().await.unwrap();
+    assert_eq!(
+        resolved_worker_count("studio-cas://project/store", 0),
+        resolved_worker_count("s3://bucket", 0),
+        "a registered store is networked"
+    );
+
+    for refused in ["s3", "file", "c", "9p", "bad_name"] {
+        assert!(
+            register_blob_scheme(refused, |_: &str| unreachable!()).is_err(),
+            "{refused}"
+        );
+    }
+
+    assert!(unregister_blob_scheme("studio-cas"));
+    assert!(create_blob_store_for_uri("studio-cas://project/store").is_err());
+}
@@ -20,6 +20,7 @@ use async_trait::async_trait;
 use longtail_core::{BlockIndex, StoredBlock};
 use longtail_store::blob::{BlobClient, BlobObject, BlobProperties, BlobStore, MemBlobStore};
 use longtail_store::{AccessType, BlockStore, RemoteBlockStore, StoreError, block_path};
+use tokio_util::sync::CancellationToken;
 
 /// Same shape as the actor tests: three chunks of 10+20+30 = 60 bytes.
 fn make_block(seed: u8) -> StoredBlock {
@@ -441,3 +442,76 @@ async fn concurrent_gets_coalesce_with_dispatched_prefetch() {
     );
     store.close().await.unwrap();
 }
+
+/// Cancellation is per operation: cancelling the token a prefetch was started
+/// under drops its read and fails that operation's coalesced get at once,
+/// while another operation's get of the same block fetches again and succeeds.
+/// A get under the cancelled token afterwards fails without touching the store.
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn cancel_aborts_only_its_own_operations_reads() {
+    let mem = MemBlobStore::new("", true);
+    let block = make_block(6);
+    seed_block(&mem, &block).await;
+
+    let (gate_tx, gate_rx) = tokio::sync::watch::channel(false);
+    let gated: Arc<dyn BlobStore> = Arc::new(GatedStore {
+        inner: mem,
+        gate: gate_rx,
+    });
+    let store = Arc::new(
+        RemoteBlockStore::with_prefetch_budget(gated, AccessType::ReadOnly, 4, 1024, None)
+            .await
+            .unwrap(),
+    );
+    let hash = block.block_index.block_hash;
+    let cancelled = CancellationToken::new();
+    let other = CancellationToken::new();
+
+    store
+        .preflight_get_cancellable(&[hash], &cancelled)
+        .await
+        .unwrap();
+    let s = store.clone();
+    tokio::time::timeout(GUARD, async move {
+        while s.stats().get_count < 1 {
+            tokio::time::sleep(Duration::from_millis(2)).await;
+        }
+    })
+    .await
+    .expect("prefetch should dispatch");
+    let (s, token) = (store.clone(), cancelled.clone());
+    let doomed = tokio::spawn(async move { s.get_stored_block_cancellable(hash, &token).await });
+    let (s, token) = (store.clone(), other.clone());
+    let survivor = tokio::spawn(async move { s.get_stored_block_cancellable(hash, &token).await });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(!doomed.is_finished(), "the gated read holds the get");
+
+    cancelled.cancel();
+    let res = tokio::time::timeout(GUARD, doomed)
+        .await
+        .expect("a cancelled read must not wait for its body")
+        .unwrap();
+    assert!(matches!(res, Err(StoreError::Cancelled)), "{res:?}");
+
+    gate_tx.send(true).unwrap();
+    let got = tokio::time::timeout(GUARD, survivor)
+        .await
+        .expect("the other operation's get must complete")
+        .unwrap()
+        .unwrap();
+    assert_eq!(got, block);
+    assert_eq!(
+        store.stats().get_count,
+        2,
+        "the survivor read the block again"
+    );
+
+    let res = store.get_stored_block_cancellable(hash, &cancelled).await;
+    assert!(matches!(res, Err(StoreError::Cancelled)), "{res:?}");
+    assert_eq!(
+        store.stats().get_count,
+        2,
+        "no read under a cancelled token"
+    );
+    store.close().await.unwrap();
+}
@@ -156,6 +156,35 @@ async fn get_existing_content() {
     store.close().await.unwrap();
 }
 
+/// A reader that loaded the index before a writer flushed sees the writer's
+/// blocks only after `refresh_index` — the long-lived-session shape.
+#[tokio::test]
+async fn refresh_index_picks_up_another_writers_blocks() {
+    let blob_store: Arc<dyn BlobStore> = Arc::new(MemBlobStore::new("the_path", true));
+    let reader = RemoteBlockStore::new(blob_store.clone(), AccessType::ReadOnly, 4)
+        .await
+        .unwrap();
+    let before = reader.get_existing_content(&[1, 2, 3], 0).await.unwrap();
+    assert_eq!(before.block_count(), 0);
+
+    let writer = RemoteBlockStore::new(blob_store, AccessType::ReadWrite, 4)
+        .await
+        .unwrap();
+    put(&writer, 0).await;
+    writer.close().await.unwrap();
+
+    let stale = reader.get_existing_content(&[1, 2, 3], 0).await.unwrap();
+    assert_eq!(
+        stale.block_count(),
+        0,
+        "the loaded index is kept between queries"
+    );
+    reader.refresh_index().await.unwrap();
+    let fresh = reader.get_existing_content(&[1, 2, 3], 0).await.unwrap();
+    assert_eq!(fresh.block_count(), 1);
+    reader.close().await.unwrap();
+}
+
 /// Source: remotestore_test.go::TestRestoreStore — index survives a close/reopen
 /// through the persisted `store.lsi`.
 #[tokio::test]
@@ -23,12 +23,15 @@ use std::sync::Arc;
 use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 
 use longtail_core::{StoreIndex, StoredBlock, VersionDiff, VersionIndex};
+use longtail_store::PauseToken;
 use longtail_store::block_store::BlockStore;
 use tokio_util::sync::CancellationToken;
 
 use crate::error::LongtailError;
 use crate::fs_util;
-use crate::progress::{Progress, RateLimited};
+use crate::journal::{ApplyJournal, JournalAsset, JournalPlan};
+use crate::path_filter::RegexPathFilter;
+use crate::progress::{Event, Progress, RateLimited};
 
 /// Byte/asset counters produced by an apply.
 #[derive(Debug, Default, Clone, Copy)]
@@ -61,6 +64,13 @@ fn asset_path(vi: &VersionIndex, idx: u32) -> Result<String, LongtailError> {
 /// content from `store`, using the pre-retargetted `store_index`.
 /// `apply_concurrency` bounds the in-flight block tasks (the caller passes the
 /// resolved remote worker count — `longtail_store::resolved_worker_count`).
+/// With a `journal`, the apply journal is written before the first write of
+/// step 5b and each block is marked in it once its writes are synced. With a
+/// `priority` filter, the blocks of the matching assets are preflighted and
+/// written first, and each matching file is reported ready (see
+/// [`crate::ProgressSink::on_asset_ready`]) as soon as it is. Every asset
+/// started, completed, failed or deleted is reported as an [`Event`]. While
+/// `pause` is paused no further block is launched.
 #[allow(clippy::too_many_arguments)]
 pub(crate) async fn change_version2(
     store: &Arc<dyn BlockStore>,
@@ -73,8 +83,11 @@ pub(crate) async fn change_version2(
     delete_removed: bool,
     verify: Option<Arc<dyn longtail_core::Hash + Send + Sync>>,
     apply_concurrency: usize,
+    journal: Option<&JournalPlan>,
+    priority: Option<&RegexPathFilter>,
     progress: &Arc<RateLimited>,
     cancel: &CancellationToken,
+    pause: Option<&PauseToken>,
 ) -> Result<ApplyStats, LongtailError> {
     let mut stats = ApplyStats::default();
 
@@ -82,8 +95,43 @@ pub(crate) async fn change_version2(
     std::fs::create_dir_all(target_root)
         .map_err(|e| LongtailError::io(format!("mkdir {target_root:?}"), e))?;
 
-    // 2. Preflight ALL retargetted store-index blocks (longtail.c:8780).
-    store.preflight_get(&store_index.block_hashes).await?;
+    let chunk_to_block = build_chunk_block_map(store_index);
+    let mut write_asset_indexes: Vec<u32> = diff.target_added_asset_indexes.clone();
+    write_asset_indexes.extend_from_slice(&diff.target_content_modified_asset_indexes);
+    let priority = match priority {
+        Some(filter) => PriorityPlan::new(
+            filter,
+            desired,
+            &write_asset_indexes,
+            &chunk_to_block,
+            retain_permissions,
+        )?,
+        None => PriorityPlan::default(),
+    };
+    // Store-index order, with the priority blocks moved to the front.
+    let block_order: Vec<u64> = {
+        let first: std::collections::HashSet<u64> = priority.blocks.iter().copied().collect();
+        let mut seen = std::collections::HashSet::new();
+        priority
+            .blocks
+            .iter()
+            .copied()
+            .chain(
+                store_index
+                    .block_hashes
+                    .iter()
+                    .copied()
+                    .filter(|h| !first.contains(h)),
+            )
+            .filter(|h| seen.insert(*h))
+            .collect()
+    };
+
+    // 2. Preflight ALL retargetted store-index blocks (longtail.c:8780), the
+    //    priority ones first so their fetches are the first dispatched.
+    store
+        .preflight_get_cancellable(&block_order, cancel)
+        .await?;
 
     // 3. Deletes FIRST (CleanUpRemoveAssets, longtail.c:8787 / :7758) — removed
     //    indexes are already sorted long-to-short; 10-retry loop lets a dir be
@@ -96,17 +144,18 @@ pub(crate) async fn change_version2(
     //    construction — a path present in both versions is content- or
     //    permissions-modified, never "removed" (`create_version_diff`).
     stats.assets_removed = if delete_removed {
-        delete_assets(target_root, current, diff, cancel)?
+        delete_assets(target_root, current, diff, progress, cancel)?
     } else {
         0
     };
 
     // 4. Build the write set = added + content-modified (longtail.c:8587).
-    let chunk_to_block = build_chunk_block_map(store_index);
     let mut zero_assets: Vec<ZeroAsset> = Vec::new();
     let mut block_writes: HashMap<u64, Vec<BlockWrite>> = HashMap::new();
-    let mut write_asset_indexes: Vec<u32> = diff.target_added_asset_indexes.clone();
-    write_asset_indexes.extend_from_slice(&diff.target_content_modified_asset_indexes);
+    // Per content asset, the blocks carrying it, in first-use order (journal only).
+    let mut asset_blocks: Vec<JournalAsset> = Vec::new();
+    let mut tracker = AssetTracker::default();
+    let mut priority_files = priority.files;
 
     for &idx in &write_asset_indexes {
         let ai = idx as usize;
@@ -119,6 +168,7 @@ pub(crate) async fn change_version2(
             continue;
         }
         let mut asset_offset: u64 = 0;
+        let mut blocks: Vec<u64> = Vec::new();
         for k in 0..count {
             let cidx = desired.asset_chunk_indexes[start + k] as usize;
             let chunk_hash = desired.chunk_hashes[cidx];
@@ -128,6 +178,9 @@ pub(crate) async fn change_version2(
                     "chunk {chunk_hash:#018x} required by `{rel}` not in the store index"
                 )))
             })?;
+            if !blocks.contains(&block_hash) {
+                blocks.push(block_hash);
+            }
             block_writes
                 .entry(block_hash)
                 .or_default()
@@ -139,6 +192,11 @@ pub(crate) async fn change_version2(
                 });
             asset_offset += chunk_size as u64;
         }
+        let ready_mode = priority_files.remove(&rel);
+        tracker.add(&rel, desired.asset_sizes[ai], &blocks, ready_mode);
+        if journal.is_some() {
+            asset_blocks.push(JournalAsset { path: rel, blocks });
+        }
     }
 
     // Modes relaxed below so a write could land, to be put back before step 7.
@@ -154,6 +212,7 @@ pub(crate) async fn change_version2(
         if cancel.is_cancelled() {
             return Err(LongtailError::Cancelled);
         }
+        progress.event(Event::AssetStarted { path: &z.rel });
         if z.is_dir {
             relaxed.unlock_parents(&z.rel)?;
             fs_util::create_dir(target_root, &z.rel)?;
@@ -163,8 +222,41 @@ pub(crate) async fn change_version2(
             let _ = fs_util::create_file_sized(target_root, &z.rel, 0)?;
             stats.assets_written += 1;
         }
+        progress.event(Event::AssetCompleted {
+            path: &z.rel,
+            bytes: 0,
+        });
     }
 
+    // The journal goes down after the deletes and the zero-size job and before
+    // the first write that can leave an asset half-done (I3). It names every
+    // asset from here on — the content writes, the zero-size assets just made,
+    // and the step-7 permission targets — so a resumed run need check nothing
+    // outside it.
+    let journal: Option<Arc<ApplyJournal>> = match journal {
+        Some(plan) => {
+            let mut assets = asset_blocks;
+            let mut named: std::collections::HashSet<String> =
+                assets.iter().map(|a| a.path.clone()).collect();
+            let zero = zero_assets.iter().map(|z| z.rel.clone());
+            let perms = diff
+                .target_permissions_modified_asset_indexes
+                .iter()
+                .map(|&idx| asset_path(desired, idx))
+                .collect::<Result<Vec<_>, _>>()?;
+            for rel in zero.chain(perms) {
+                if named.insert(rel.clone()) {
+                    assets.push(JournalAsset {
+                        path: rel,
+                        blocks: Vec::new(),
+                    });
+                }
+            }
+            ApplyJournal::create(plan, assets).map(Arc::new)
+        }
+        None => None,
+    };
+
     // 5b. Pre-create + truncate all write files to their final size (first-touch
     //     semantic, concurrentchunkwrite.c:108), so positional writes fill them.
     let mut created: std::collections::HashSet<String> = std::collections::HashSet::new();
@@ -189,6 +281,19 @@ pub(crate) async fn change_version2(
         }
     }
 
+    // Priority files the block loop will not touch — already current, or empty
+    // and just created — are ready now.
+    let mut made_ready: Vec<String> = Vec::new();
+    for &idx in &priority.ready_now {
+        let rel = asset_path(desired, idx)?;
+        if retain_permissions {
+            fs_util::set_permissions(target_root, &rel, desired.permissions[idx as usize])?;
+        }
+        progress.asset_ready(&rel);
+        made_ready.push(rel);
+    }
+    let tracker = Arc::new(std::sync::Mutex::new(tracker));
+
     // 6. Per-block positional writes (longtail.c:8347), N block tasks in flight
     //    (Fix 2). Preflight enqueued the background fetches; each task's demand
     //    get coalesces with (or claims ahead of) its prefetch. First-error-wins:
@@ -215,9 +320,27 @@ pub(crate) async fn change_version2(
     let mut tasks: tokio::task::JoinSet<Result<(), LongtailError>> = tokio::task::JoinSet::new();
     let mut first_err: Option<LongtailError> = None;
 
-    for (block_hash, writes) in block_writes {
+    // Priority blocks first, then the rest in store-index order.
+    let mut ordered: Vec<(u64, Vec<BlockWrite>)> = block_order
+        .iter()
+        .filter_map(|h| block_writes.remove_entry(h))
+        .collect();
+    ordered.extend(block_writes);
+
+    for (block_hash, writes) in ordered {
+        // Paused: launch nothing until resumed or cancelled; in-flight blocks
+        // complete meanwhile, and the plan stays as it is.
+        if let Some(pause) = pause
+            && pause.is_paused()
+        {
+            tokio::select! {
+                _ = pause.resumed() => {}
+                _ = cancel.cancelled() => {}
+            }
+        }
         // Cancellation honored between blocks (pre-Fix-2 granularity): stop
-        // launching; in-flight blocks complete whole (resumable target).
+        // launching. In flight, the store drops a block still being read and
+        // a block already read is written whole (resumable target).
         if cancel.is_cancelled() {
             first_err.get_or_insert(LongtailError::Cancelled);
         }
@@ -243,24 +366,75 @@ pub(crate) async fn change_version2(
         let bytes_written = bytes_written.clone();
         let report_lock = report_lock.clone();
         let verify = verify.clone();
+        let journal = journal.clone();
+        let tracker = tracker.clone();
+        let cancel = cancel.clone();
         tasks.spawn(async move {
             let _permit = permit;
-            let block = store.get_stored_block(block_hash).await?;
-            // Full decompressed block payload we just fetched — the download
-            // byte dimension (captured before `block` moves into the writer).
-            let payload_len = block.payload.len() as u64;
-            // The sync positional writes run on the blocking pool — N tasks
-            // doing sync file I/O on the tokio workers is a known footgun.
-            let n = tokio::task::spawn_blocking(move || {
-                write_block_chunks(&target_root, block_hash, &block, &writes, verify.as_deref())
-            })
-            .await
-            .map_err(|e| {
-                LongtailError::io(
-                    "apply block-write task",
-                    std::io::Error::other(format!("join error: {e}")),
-                )
-            })??;
+            let landed = async {
+                let block = store
+                    .get_stored_block_cancellable(block_hash, &cancel)
+                    .await?;
+                // Full decompressed block payload we just fetched — the download
+                // byte dimension (captured before `block` moves into the writer).
+                let payload_len = block.payload.len() as u64;
+                let progress = progress.clone();
+                let tracker = tracker.clone();
+                // The sync positional writes run on the blocking pool — N tasks
+                // doing sync file I/O on the tokio workers is a known footgun.
+                let n = tokio::task::spawn_blocking(move || {
+                    for rel in tracker.lock().unwrap().block_started(block_hash) {
+                        progress.event(Event::AssetStarted { path: &rel });
+                    }
+                    let durable = journal.is_some();
+                    let n = write_block_chunks(
+                        &target_root,
+                        block_hash,
+                        &block,
+                        &writes,
+                        verify.as_deref(),
+                        durable,
+                    )?;
+                    if let Some(journal) = &journal {
+                        journal.mark_block(block_hash);
+                    }
+                    let completed = tracker.lock().unwrap().block_done(block_hash);
+                    for done in completed {
+                        progress.event(Event::AssetCompleted {
+                            path: &done.rel,
+                            bytes: done.size,
+                        });
+                        if let Some(mode) = done.priority {
+                            if let Some(mode) = mode {
+                                fs_util::set_permissions(&target_root, &done.rel, mode)?;
+                            }
+                            progress.asset_ready(&done.rel);
+                        }
+                    }
+                    Ok::<_, LongtailError>(n)
+                })
+                .await
+                .map_err(|e| {
+                    LongtailError::io(
+                        "apply block-write task",
+                        std::io::Error::other(format!("join error: {e}")),
+                    )
+                })??;
+                Ok::<_, LongtailError>((n, payload_len))
+            }
+            .await;
+            let (n, payload_len) = match landed {
+                Ok(v) => v,
+                Err(e) => {
+                    for rel in tracker.lock().unwrap().block_failed(block_hash) {
+                        progress.event(Event::AssetFailed {
+                            path: &rel,
+                            error: &e,
+                        });
+                    }
+                    return Err(e);
+                }
+            };
             bytes_written.fetch_add(n, Ordering::Relaxed);
             done_bytes.fetch_add(payload_len, Ordering::Relaxed);
             done_blocks.fetch_add(1, Ordering::Relaxed);
@@ -293,7 +467,15 @@ pub(crate) async fn change_version2(
     }
     // Before step 7, and before the error check, so a failed or cancelled run
     // does not leave an asset more permissive than it found it — the target
-    // survives both, and the next run resumes over it.
+    // survives both, and the next run resumes over it. A priority file already
+    // given its recorded mode keeps it: putting the prior one back would take
+    // it away from a launcher that may already be running it.
+    if retain_permissions {
+        made_ready.extend(tracker.lock().unwrap().take_ready());
+        for rel in &made_ready {
+            relaxed.forget(rel);
+        }
+    }
     relaxed.restore();
 
     if let Some(e) = first_err {
@@ -384,6 +566,11 @@ impl<'a> RelaxedModes<'a> {
         Ok(())
     }
 
+    /// Drop the obligation for `rel`, whose mode has been set deliberately.
+    fn forget(&mut self, rel: &str) {
+        self.entries.retain(|(r, _)| r != rel);
+    }
+
     /// Put every recorded mode back. Draining leaves the drop with nothing to do,
     /// so calling this and then unwinding cannot restore twice.
     fn restore(&mut self) {
@@ -405,6 +592,172 @@ impl Drop for RelaxedModes<'_> {
     }
 }
 
+/// The assets a priority filter picks out of an apply, and the blocks that
+/// carry them.
+#[derive(Default)]
+struct PriorityPlan {
+    /// Blocks carrying a matching asset's content, in first-use order.
+    blocks: Vec<u64>,
+    /// Matching files ready before any block lands: not in the write set, or
+    /// in it with no content.
+    ready_now: Vec<u32>,
+    /// The rest: ready once their last block lands, then given this mode if
+    /// permissions are retained.
+    files: HashMap<String, Option<longtail_core::Permissions>>,
+}
+
+impl PriorityPlan {
+    fn new(
+        filter: &RegexPathFilter,
+        desired: &VersionIndex,
+        write_asset_indexes: &[u32],
+        chunk_to_block: &HashMap<u64, u64>,
+        retain_permissions: bool,
+    ) -> Result<PriorityPlan, LongtailError> {
+        let writing: std::collections::HashSet<u32> = write_asset_indexes.iter().copied().collect();
+        let mut plan = PriorityPlan::default();
+        for idx in 0..desired.asset_count() {
+            let ai = idx as usize;
+            if desired.is_dir(ai).unwrap_or(false) {
+                continue;
+            }
+            let rel = asset_path(desired, idx)?;
+            if !filter.include(&rel, false) {
+                continue;
+            }
+            let count = desired.asset_chunk_counts[ai] as usize;
+            if !writing.contains(&idx) || count == 0 {
+                plan.ready_now.push(idx);
+                continue;
+            }
+            let start = desired.asset_chunk_index_starts[ai] as usize;
+            for k in 0..count {
+                let chunk = desired.chunk_hashes[desired.asset_chunk_indexes[start + k] as usize];
+                // A chunk the store lacks fails step 4 with the error that says so.
+                if let Some(&b) = chunk_to_block.get(&chunk)
+                    && !plan.blocks.contains(&b)
+                {
+                    plan.blocks.push(b);
+                }
+            }
+            let mode = retain_permissions.then(|| desired.permissions[ai]);
+            plan.files.insert(rel, mode);
+        }
+        Ok(plan)
+    }
+}
+
+/// Counts down each content asset's outstanding blocks as they land, for the
+/// per-asset events and the priority files' ready notifications.
+#[derive(Default)]
+struct AssetTracker {
+    assets: HashMap<String, TrackedAsset>,
+    by_block: HashMap<u64, Vec<String>>,
+    /// Priority files reported ready so far.
+    ready: Vec<String>,
+}
+
+struct TrackedAsset {
+    /// Blocks still to land.
+    left: usize,
+    size: u64,
+    /// Started or failed: reported once, whichever of its blocks comes first.
+    started: bool,
+    failed: bool,
+    /// `Some` for a priority file: the mode to give it when complete, if any.
+    priority: Option<Option<longtail_core::Permissions>>,
+}
+
+/// An asset whose last block just landed.
+struct CompletedAsset {
+    rel: String,
+    size: u64,
+    priority: Option<Option<longtail_core::Permissions>>,
+}
+
+impl AssetTracker {
+    /// Track `rel`, carried by the distinct `blocks`.
+    fn add(
+        &mut self,
+        rel: &str,
+        size: u64,
+        blocks: &[u64],
+        priority: Option<Option<longtail_core::Permissions>>,
+    ) {
+        if self.assets.contains_key(rel) {
+            return;
+        }
+        for &b in blocks {
+            self.by_block.entry(b).or_default().push(rel.to_string());
+        }
+        self.assets.insert(
+            rel.to_string(),
+            TrackedAsset {
+                left: blocks.len(),
+                size,
+                started: false,
+                failed: false,
+                priority,
+            },
+        );
+    }
+
+    /// The assets `block_hash` is the first of their blocks to write.
+    fn block_started(&mut self, block_hash: u64) -> Vec<String> {
+        let mut out = Vec::new();
+        for rel in self.by_block.get(&block_hash).into_iter().flatten() {
+            if let Some(a) = self.assets.get_mut(rel)
+                && !a.started
+            {
+                a.started = true;
+                out.push(rel.clone());
+            }
+        }
+        out
+    }
+
+    /// Record `block_hash` as landed; the assets that makes complete.
+    fn block_done(&mut self, block_hash: u64) -> Vec<CompletedAsset> {
+        let mut out = Vec::new();
+        for rel in self.by_block.remove(&block_hash).unwrap_or_default() {
+            if let Some(a) = self.assets.get_mut(&rel) {
+                a.left -= 1;
+                if a.left == 0 {
+                    if a.priority.is_some() {
+                        self.ready.push(rel.clone());
+                    }
+                    out.push(CompletedAsset {
+                        rel,
+                        size: a.size,
+                        priority: a.priority,
+                    });
+                }
+            }
+        }
+        out
+    }
+
+    /// Record `block_hash` as lost; the assets it leaves incomplete, each
+    /// named once however many of its blocks fail.
+    fn block_failed(&mut self, block_hash: u64) -> Vec<String> {
+        let mut out = Vec::new();
+        for rel in self.by_block.remove(&block_hash).unwrap_or_default() {
+            if let Some(a) = self.assets.get_mut(&rel)
+                && !a.failed
+            {
+                a.failed = true;
+                out.push(rel);
+            }
+        }
+        out
+    }
+
+    /// Every priority file reported ready so far.
+    fn take_ready(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.ready)
+    }
+}
+
 /// Write one fetched block's chunk occurrences into their pre-created target
 /// files (positional writes, longtail.c:8347). Sync — runs under
 /// `spawn_blocking`. Returns the bytes written. Safe to run concurrently with
@@ -414,12 +767,17 @@ impl Drop for RelaxedModes<'_> {
 /// and runs that are contiguous in BOTH the asset and the block payload merge
 /// into a single `pwrite` — chunks usually sit consecutively in both, so this
 /// collapses tens of thousands of open/pwrite/close syscalls into a handful.
+///
+/// `durable` syncs each file written before returning, for a caller about to
+/// record the block as landed (the apply journal; a mark that outlives the
+/// bytes it vouches for would be believed).
 fn write_block_chunks(
     target_root: &Path,
     block_hash: u64,
     block: &StoredBlock,
     writes: &[BlockWrite],
     verify: Option<&(dyn longtail_core::Hash + Send + Sync)>,
+    durable: bool,
 ) -> Result<u64, LongtailError> {
     let mut verified: std::collections::HashSet<u64> = std::collections::HashSet::new();
     // chunk_hash -> (offset_in_block, size) over the decoded payload.
@@ -509,6 +867,10 @@ fn write_block_chunks(
             written += len as u64;
             i = j;
         }
+        if durable {
+            file.sync_data()
+                .map_err(|e| LongtailError::io(format!("sync `{rel}`"), e))?;
+        }
     }
     Ok(written)
 }
@@ -577,6 +939,7 @@ fn delete_assets(
     target_root: &Path,
     current: &VersionIndex,
     diff: &VersionDiff,
+    progress: &RateLimited,
     cancel: &CancellationToken,
 ) -> Result<u32, LongtailError> {
     let mut remove: Vec<Option<u32>> = diff
@@ -607,6 +970,7 @@ fn delete_assets(
                 Ok(true) => {
                     *slot = None;
                     removed_count += 1;
+                    progress.event(Event::AssetDeleted { path: &rel });
                 }
                 Ok(false) => {
                     // Still present (likely a non-empty dir); leave for a retry.
@@ -651,7 +1015,9 @@ mod tests {
     use tokio_util::sync::CancellationToken;
 
     use super::change_version2;
-    use crate::progress::{NullProgress, RateLimited};
+    use crate::journal::{JournalKey, JournalPlan};
+    use crate::path_filter::RegexPathFilter;
+    use crate::progress::{NullProgress, Progress, ProgressSink, RateLimited};
 
     type BoxFut<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
 
@@ -943,10 +1309,41 @@ mod tests {
         sc: &Scenario,
         target: &Path,
         concurrency: usize,
+    ) -> Result<super::ApplyStats, crate::LongtailError> {
+        run_apply_journaled(mock, sc, target, concurrency, None).await
+    }
+
+    async fn run_apply_journaled(
+        mock: Arc<MockStore>,
+        sc: &Scenario,
+        target: &Path,
+        concurrency: usize,
+        journal: Option<&JournalPlan>,
+    ) -> Result<super::ApplyStats, crate::LongtailError> {
+        run_apply_with(
+            mock,
+            sc,
+            target,
+            concurrency,
+            journal,
+            None,
+            Arc::new(NullProgress),
+        )
+        .await
+    }
+
+    async fn run_apply_with(
+        mock: Arc<MockStore>,
+        sc: &Scenario,
+        target: &Path,
+        concurrency: usize,
+        journal: Option<&JournalPlan>,
+        priority: Option<&RegexPathFilter>,
+        sink: Arc<dyn ProgressSink>,
     ) -> Result<super::ApplyStats, crate::LongtailError> {
         let store: Arc<dyn BlockStore> = mock;
         let diff = create_version_diff(&sc.current, &sc.desired);
-        let progress = Arc::new(RateLimited::new(Arc::new(NullProgress)));
+        let progress = Arc::new(RateLimited::new(sink));
         let cancel = CancellationToken::new();
         change_version2(
             &store,
@@ -959,8 +1356,11 @@ mod tests {
             true,  // delete_removed
             None,  // verify
             concurrency,
+            journal,
+            priority,
             &progress,
             &cancel,
+            None,
         )
         .await
     }
@@ -1099,6 +1499,207 @@ mod tests {
         );
     }
 
+    /// **Apply journal**: read mid-run, the journal names every asset the apply
+    /// touches and counts an asset complete exactly when all its blocks landed.
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn the_journal_marks_assets_complete_as_their_blocks_land() {
+        let sc = scenario();
+        let tmp = tempfile::tempdir().unwrap();
+        let target = tmp.path().join("out");
+        let plan = JournalPlan {
+            path: tmp.path().join("journal"),
+            key: JournalKey {
+                version_hash: 1,
+                delete_removed: true,
+                include: None,
+                exclude: None,
+            },
+        };
+
+        // Hold B2 back; B1 and B3 are free to land.
+        let mut mock = MockStore::new(sc.blocks.clone());
+        let (tx, rx) = tokio::sync::watch::channel(false);
+        mock.gates.insert(B2, rx);
+        let mock = Arc::new(mock);
+
+        let observer = async {
+            let state = loop {
+                if let Some(state) = crate::journal::read(&plan.path, &plan.key)
+                    && state.done.len() == 2
+                {
+                    break state;
+                }
+                tokio::time::sleep(Duration::from_millis(2)).await;
+            };
+            tx.send(true).unwrap();
+            state
+        };
+        let apply = run_apply_journaled(mock, &sc, &target, 8, Some(&plan));
+        let (stats, state) =
+            tokio::time::timeout(Duration::from_secs(60), futures_join(apply, observer))
+                .await
+                .expect("journaled apply must not hang");
+        stats.expect("apply");
+
+        assert_eq!(state.done, HashSet::from([B1, B3]));
+        let mut complete: Vec<&str> = state
+            .assets
+            .iter()
+            .filter(|a| state.is_complete(a))
+            .map(|a| a.path.as_str())
+            .collect();
+        complete.sort_unstable();
+        assert_eq!(
+            complete,
+            ["c.bin", "empty.txt"],
+            "a.bin and sub/b.bin wait on B2"
+        );
+        assert_eq!(
+            state.assets.len(),
+            4,
+            "every asset the apply touches is named"
+        );
+        assert_eq!(capture_tree(&target, &sc.expected), sc.expected);
+    }
+
+    /// **Priority**: the blocks of a priority asset are fetched first, and the
+    /// asset is reported ready the moment they have landed — not at the end.
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn priority_assets_are_written_first_and_reported_ready() {
+        /// Records each ready file with how many blocks had landed by then.
+        struct Ready {
+            mock: Arc<MockStore>,
+            seen: StdMutex<Vec<(String, usize)>>,
+        }
+        impl ProgressSink for Ready {
+            fn on_progress(&self, _p: Progress) {}
+            fn on_asset_ready(&self, path: &str) {
+                let landed = self.mock.completed.lock().unwrap().len();
+                self.seen.lock().unwrap().push((path.to_string(), landed));
+            }
+        }
+
+        let sc = scenario();
+        let tmp = tempfile::tempdir().unwrap();
+        let target = tmp.path().join("out");
+        let mock = Arc::new(MockStore::new(sc.blocks.clone()));
+        let sink = Arc::new(Ready {
+            mock: mock.clone(),
+            seen: StdMutex::new(Vec::new()),
+        });
+        // `c.bin` lives in B3 alone; `empty.txt` has no blocks at all.
+        let priority = RegexPathFilter::new(Some(r"^c\.bin$**^empty"), None).unwrap();
+
+        // One block in flight at a time, so fetch order is launch order.
+        run_apply_with(
+            mock.clone(),
+            &sc,
+            &target,
+            1,
+            None,
+            Some(&priority),
+            sink.clone(),
+        )
+        .await
+        .expect("apply");
+
+        assert_eq!(
+            mock.arrived.lock().unwrap()[0],
+            B3,
+            "the priority block goes first"
+        );
+        assert_eq!(
+            sink.seen.lock().unwrap().as_slice(),
+            [("empty.txt".to_string(), 0), ("c.bin".to_string(), 1)],
+            "each priority file is ready as soon as its own blocks are"
+        );
+        assert_eq!(capture_tree(&target, &sc.expected), sc.expected);
+    }
+
+    /// Records every asset event as `(kind, path, bytes)`.
+    struct Events(StdMutex<Vec<(&'static str, String, u64)>>);
+
+    impl ProgressSink for Events {
+        fn on_progress(&self, _p: Progress) {}
+        fn on_event(&self, event: &crate::Event<'_>) {
+            use crate::Event::*;
+            let row = match *event {
+                AssetStarted { path } => ("started", path, 0),
+                AssetCompleted { path, bytes } => ("completed", path, bytes),
+                AssetFailed { path, .. } => ("failed", path, 0),
+                AssetDeleted { path } => ("deleted", path, 0),
+                _ => return,
+            };
+            self.0
+                .lock()
+                .unwrap()
+                .push((row.0, row.1.to_string(), row.2));
+        }
+    }
+
+    #[tokio::test]
+    async fn each_asset_is_started_and_completed_once_with_its_size() {
+        let sc = scenario();
+        let tmp = tempfile::tempdir().unwrap();
+        let target = tmp.path().join("out");
+        let mock = Arc::new(MockStore::new(sc.blocks.clone()));
+        let sink = Arc::new(Events(StdMutex::new(Vec::new())));
+        run_apply_with(mock, &sc, &target, 4, None, None, sink.clone())
+            .await
+            .expect("apply");
+
+        let events = sink.0.lock().unwrap().clone();
+        for (rel, bytes) in &sc.expected {
+            let of = |kind: &str| {
+                events
+                    .iter()
+                    .filter(|(k, p, _)| *k == kind && p == rel)
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(of("started").len(), 1, "{rel} started once");
+            let completed = of("completed");
+            assert_eq!(completed.len(), 1, "{rel} completed once");
+            assert_eq!(completed[0].2, bytes.len() as u64, "{rel} completed whole");
+            let started_at = events.iter().position(|e| e.0 == "started" && &e.1 == rel);
+            let completed_at = events
+                .iter()
+                .position(|e| e.0 == "completed" && &e.1 == rel);
+            assert!(
+                started_at < completed_at,
+                "{rel} starts before it completes"
+            );
+        }
+        assert!(!events.iter().any(|e| e.0 == "failed"));
+    }
+
+    /// A lost block fails every asset it carries that is not already complete,
+    /// and only those.
+    #[tokio::test]
+    async fn a_lost_block_reports_the_assets_it_leaves_incomplete() {
+        let sc = scenario();
+        let tmp = tempfile::tempdir().unwrap();
+        let target = tmp.path().join("out");
+        let mut mock = MockStore::new(sc.blocks.clone());
+        mock.missing.insert(B3);
+        let sink = Arc::new(Events(StdMutex::new(Vec::new())));
+        run_apply_with(Arc::new(mock), &sc, &target, 1, None, None, sink.clone())
+            .await
+            .expect_err("B3 is missing");
+
+        let events = sink.0.lock().unwrap().clone();
+        let mut failed: Vec<&str> = events
+            .iter()
+            .filter(|e| e.0 == "failed")
+            .map(|e| e.1.as_str())
+            .collect();
+        failed.sort_unstable();
+        assert_eq!(failed, ["c.bin", "sub/b.bin"]);
+        assert!(
+            events.iter().any(|e| e.0 == "completed" && e.1 == "a.bin"),
+            "a.bin needs nothing from B3"
+        );
+    }
+
     /// A block whose own index sizes a chunk differently from the version index
     /// must be refused, in every profile.
     ///
@@ -1136,7 +1737,7 @@ mod tests {
             chunk_size: 64,
         }];
 
-        let err = write_block_chunks(root, 0xABCD, &block, &writes, None)
+        let err = write_block_chunks(root, 0xABCD, &block, &writes, None, false)
             .expect_err("a size disagreement must not be written through");
         assert!(
             format!("{err:?}").contains("sizes chunk"),
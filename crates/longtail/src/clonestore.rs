@@ -158,6 +158,8 @@ pub async fn clone_store(opts: CloneStoreOptions) -> Result<u32, LongtailError>
                 pool: Arc::new(crate::version::build_pool(opts.worker_count)?),
                 version_local_store_index: None,
                 max_block_bytes: None,
+                events: None,
+                pause: None,
                 #[cfg(feature = "s3")]
                 s3_options: opts.target_s3_options.clone(),
             },
@@ -257,6 +259,8 @@ async fn validate_target_covers(
             pool: Arc::new(crate::version::build_pool(1)?),
             version_local_store_index: None,
             max_block_bytes: None,
+            events: None,
+            pause: None,
             #[cfg(feature = "s3")]
             s3_options: opts.target_s3_options.clone(),
         },
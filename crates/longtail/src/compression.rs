@@ -1,7 +1,7 @@
 //! CLI-name → compression-ID mapping (golongtail's `compressionTypeMap`,
 //! longtailutils.go:458-472), including the `zstd_low`/`zstd_high` → `zstd_max`
 //! alias quirk. Used by the byte-gate (upsync-equivalent uniform tag) and
-//! by upsync.
+//! by upsync; the inverse names a store's block tags for `list-versions`.
 
 use longtail_core::compress::{BROTLI_FAMILY, LZ4_ID, ZSTD_FAMILY};
 
@@ -29,6 +29,28 @@ pub fn compression_type_for_name(name: &str) -> Option<u32> {
     })
 }
 
+/// The CLI name for a compression ID: the inverse of
+/// [`compression_type_for_name`], answering `zstd_max` for the ID the
+/// `zstd_low`/`zstd_high` aliases share. `None` for an unknown ID.
+pub fn compression_name_for_type(id: u32) -> Option<&'static str> {
+    const CANONICAL: [&str; 11] = [
+        "none",
+        "brotli",
+        "brotli_min",
+        "brotli_max",
+        "brotli_text",
+        "brotli_text_min",
+        "brotli_text_max",
+        "lz4",
+        "zstd",
+        "zstd_min",
+        "zstd_max",
+    ];
+    CANONICAL
+        .into_iter()
+        .find(|name| compression_type_for_name(name) == Some(id))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -47,4 +69,12 @@ mod tests {
         assert_eq!(compression_type_for_name("brotli_text"), Some(0x6274_6c62));
         assert_eq!(compression_type_for_name("nope"), None);
     }
+
+    #[test]
+    fn names_roundtrip() {
+        assert_eq!(compression_name_for_type(0x7a74_6433), Some("zstd_max"));
+        assert_eq!(compression_name_for_type(LZ4_ID), Some("lz4"));
+        assert_eq!(compression_name_for_type(NO_COMPRESSION), Some("none"));
+        assert_eq!(compression_name_for_type(0x1234), None);
+    }
 }
@@ -1,17 +1,24 @@
 //! `cp` (`cmd_cp.go`): extract a single asset from a version by fetching only
 //! the blocks that cover its chunks and assembling the file. No
 //! blockstorestorage port — a targeted block fetch + assemble.
+//!
+//! [`cp_tree`] does the same for a directory subtree or a glob of assets,
+//! fetching each block the selection needs once.
 
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
 
+use longtail_core::{Hash, StoreIndex, VersionIndex};
 use longtail_store::AccessType;
 use longtail_store::block_store::BlockStore;
 use longtail_store::uri::{BlockStoreOpts, create_block_store_for_uri};
+use serde::{Deserialize, Serialize};
 
 use crate::error::LongtailError;
 use crate::fs_util::{self, S3OptionsArg};
+use crate::hash_util::make_hasher;
+use crate::path_filter::PathGlob;
 
 #[cfg(feature = "s3")]
 fn default_s3() -> S3OptionsArg {
@@ -63,9 +70,314 @@ pub async fn cp(opts: CpOptions) -> Result<(), LongtailError> {
         &crate::s3_arg!(opts),
     )
     .await?;
+    let (chunk_hashes, chunk_sizes) = asset_chunks(&vi, &opts.source_path)?;
 
+    #[cfg(feature = "s3")]
+    let s3: S3OptionsArg = opts.s3_options.clone();
+    #[cfg(not(feature = "s3"))]
+    let s3: S3OptionsArg = ();
+
+    let store_opts = BlockStoreOpts {
+        access_type: AccessType::ReadOnly,
+        worker_count: opts.remote_worker_count,
+        cache_dir: opts.cache_path.clone(),
+        pool: Arc::new(crate::version::build_pool(1)?),
+        version_local_store_index: None,
+        max_block_bytes: None,
+        events: None,
+        pause: None,
+        #[cfg(feature = "s3")]
+        s3_options: opts.s3_options.clone(),
+    };
+    let store: Arc<dyn BlockStore> =
+        create_block_store_for_uri(&opts.storage_uri, store_opts).await?;
+
+    // Fallible work runs inside `assemble` so the close below happens on a
+    // failure too; `cp` shares the block cache and write-back obligations of
+    // any other read.
+    let assembled = assemble(&store, &chunk_hashes, &chunk_sizes, &opts.source_path).await;
+    let out = crate::store_lifecycle::finish_store(&store, assembled).await?;
+
+    fs_util::write_to_uri(&opts.target_path, out.into(), &s3).await?;
+    Ok(())
+}
+
+/// Options for [`cp_tree`].
+#[non_exhaustive]
+pub struct CpTreeOptions {
+    pub storage_uri: String,
+    pub version_index_path: String,
+    /// What to copy: a directory inside the version (`""` or `.` for all of
+    /// it), or a [`PathGlob`] over in-version paths.
+    pub source_path: String,
+    /// The local folder to copy into. Each asset lands at its path below the
+    /// source directory, or for a glob below the glob's leading literal
+    /// directories: `config` and `config/**` both put `config/game.ini` at
+    /// `<target>/game.ini`.
+    pub target_path: PathBuf,
+    /// Apply the permissions the version records (default true).
+    pub retain_permissions: bool,
+    pub cache_path: Option<PathBuf>,
+    pub remote_worker_count: usize,
+    #[cfg(feature = "s3")]
+    pub s3_options: S3OptionsArg,
+}
+
+impl CpTreeOptions {
+    pub fn new(
+        storage_uri: impl Into<String>,
+        version_index_path: impl Into<String>,
+        source_path: impl Into<String>,
+        target_path: impl Into<PathBuf>,
+    ) -> Self {
+        CpTreeOptions {
+            storage_uri: storage_uri.into(),
+            version_index_path: version_index_path.into(),
+            source_path: source_path.into(),
+            target_path: target_path.into(),
+            retain_permissions: true,
+            cache_path: None,
+            remote_worker_count: 0,
+            #[cfg(feature = "s3")]
+            s3_options: default_s3(),
+        }
+    }
+}
+
+/// What [`cp_tree`] wrote.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct CpTreeReport {
+    /// Files written, each verified against its content hash.
+    pub file_count: u32,
+    /// Directory assets created.
+    pub directory_count: u32,
+    pub bytes: u64,
+    /// Blocks fetched: each block the selection needs, once.
+    pub block_count: u32,
+}
+
+/// Copy a directory subtree or a glob of assets out of a version into a local
+/// folder.
+///
+/// The blocks covering every selected file are announced to the store with
+/// `preflight_get`, then fetched once each and held only until the last file
+/// needing them is written. Each written file is read back and its chunks and
+/// content hash checked against the version index; a mismatch is
+/// [`LongtailError::ValidationMismatch`]. A selection matching nothing is
+/// [`LongtailError::InvalidArgument`].
+pub async fn cp_tree(opts: CpTreeOptions) -> Result<CpTreeReport, LongtailError> {
+    let vi = crate::inspect::read_version_index_from_uri(
+        &opts.version_index_path,
+        &crate::s3_arg!(opts),
+    )
+    .await?;
+    let selected = select(&vi, &opts.source_path)?;
+    if selected.is_empty() {
+        return Err(LongtailError::InvalidArgument(format!(
+            "nothing in the version index matches `{}`",
+            opts.source_path
+        )));
+    }
+    let hasher = make_hasher(vi.hash_identifier)?;
+    let mut files = Vec::new();
+    let mut all_chunks = Vec::new();
+    for &(a, ref rel) in &selected {
+        if !vi.path(a)?.ends_with('/') {
+            let (hashes, sizes) = chunks_of(&vi, a, rel)?;
+            all_chunks.extend_from_slice(&hashes);
+            files.push((a, rel.as_str(), hashes, sizes));
+        }
+    }
+
+    let store_opts = BlockStoreOpts {
+        access_type: AccessType::ReadOnly,
+        worker_count: opts.remote_worker_count,
+        cache_dir: opts.cache_path.clone(),
+        pool: Arc::new(crate::version::build_pool(1)?),
+        version_local_store_index: None,
+        max_block_bytes: None,
+        events: None,
+        pause: None,
+        #[cfg(feature = "s3")]
+        s3_options: opts.s3_options.clone(),
+    };
+    let store: Arc<dyn BlockStore> =
+        create_block_store_for_uri(&opts.storage_uri, store_opts).await?;
+    let root = &opts.target_path;
+    let copied = async {
+        std::fs::create_dir_all(root)
+            .map_err(|e| LongtailError::io(format!("mkdir {root:?}"), e))?;
+        let store_index = store.get_existing_content(&all_chunks, 0).await?;
+        let location = chunk_locations(&store_index);
+        // Each block's remaining uses across the selection, and the order the
+        // copy first needs them in.
+        let mut uses: HashMap<u64, usize> = HashMap::new();
+        let mut order = Vec::new();
+        for (_, rel, hashes, _) in &files {
+            for ch in hashes {
+                let (block_hash, _) = *location.get(ch).ok_or_else(|| {
+                    LongtailError::InvalidArgument(format!(
+                        "chunk {ch:#018x} of `{rel}` is not present in the store"
+                    ))
+                })?;
+                let n = uses.entry(block_hash).or_insert(0);
+                if *n == 0 {
+                    order.push(block_hash);
+                }
+                *n += 1;
+            }
+        }
+        store.preflight_get(&order).await?;
+
+        let mut report = CpTreeReport::default();
+        let mut held: HashMap<u64, Vec<u8>> = HashMap::new();
+        for (a, rel, hashes, sizes) in &files {
+            let mut out = Vec::new();
+            for (ch, &size) in hashes.iter().zip(sizes) {
+                let (block_hash, within) = location[ch];
+                if let std::collections::hash_map::Entry::Vacant(e) = held.entry(block_hash) {
+                    let sb = store.get_stored_block(block_hash).await?;
+                    e.insert(sb.payload);
+                    report.block_count += 1;
+                }
+                out.extend_from_slice(chunk_bytes(&held[&block_hash], block_hash, within, size)?);
+                let left = uses.get_mut(&block_hash).expect("counted above");
+                *left -= 1;
+                if *left == 0 {
+                    held.remove(&block_hash);
+                }
+            }
+            let file = fs_util::create_file_sized(root, rel, out.len() as u64)?;
+            fs_util::write_at(&file, 0, &out)?;
+            drop(file);
+            verify_file(root, rel, hashes, sizes, vi.content_hashes[*a], &*hasher)?;
+            report.file_count += 1;
+            report.bytes += out.len() as u64;
+        }
+
+        if opts.retain_permissions {
+            for (a, rel, _, _) in &files {
+                fs_util::set_permissions(root, rel, vi.permissions[*a])?;
+            }
+        }
+        // Directories last, deepest first, so a read-only one does not stop
+        // the writes below it.
+        let mut dirs: Vec<&(usize, String)> = selected
+            .iter()
+            .filter(|(a, rel)| !rel.is_empty() && vi.path(*a).is_ok_and(|p| p.ends_with('/')))
+            .collect();
+        dirs.sort_by(|x, y| y.1.cmp(&x.1));
+        for (a, rel) in dirs {
+            fs_util::create_dir(root, rel)?;
+            report.directory_count += 1;
+            if opts.retain_permissions {
+                fs_util::set_permissions(root, rel, vi.permissions[*a])?;
+            }
+        }
+        Ok(report)
+    }
+    .await;
+    crate::store_lifecycle::finish_store(&store, copied).await
+}
+
+/// The assets of `vi` that `source` selects, each with its path below the
+/// selection's base directory, in index order.
+fn select(vi: &VersionIndex, source: &str) -> Result<Vec<(usize, String)>, LongtailError> {
+    let source = source.trim_start_matches("./").trim_end_matches('/');
+    let glob = if PathGlob::is_glob(source) {
+        Some(PathGlob::new(source)?)
+    } else {
+        None
+    };
+    let base = match &glob {
+        // The glob's leading components with no glob character in them.
+        Some(_) => {
+            let literal: Vec<&str> = source
+                .split('/')
+                .take_while(|c| !PathGlob::is_glob(c))
+                .collect();
+            literal.join("/")
+        }
+        None if source == "." => String::new(),
+        None => source.to_string(),
+    };
+    let mut out = Vec::new();
+    for a in 0..vi.asset_count() as usize {
+        let path = fs_util::strip_trailing_slash(vi.path(a)?);
+        let wanted = match &glob {
+            Some(g) => g.matches(path),
+            None => base.is_empty() || path == base || path.starts_with(&format!("{base}/")),
+        };
+        if !wanted {
+            continue;
+        }
+        let rel = if base.is_empty() {
+            path
+        } else if path == base {
+            // The source names a file: it lands under its own name.
+            match vi.path(a)?.ends_with('/') {
+                true => "",
+                false => path.rsplit('/').next().unwrap_or(path),
+            }
+        } else {
+            &path[base.len() + 1..]
+        };
+        out.push((a, rel.to_string()));
+    }
+    Ok(out)
+}
+
+/// Read `root/rel` back and check it holds exactly the chunks the version
+/// index lists for it, and so hashes to `content_hash`.
+fn verify_file(
+    root: &std::path::Path,
+    rel: &str,
+    chunk_hashes: &[u64],
+    chunk_sizes: &[u32],
+    content_hash: u64,
+    hasher: &dyn Hash,
+) -> Result<(), LongtailError> {
+    let path = root.join(rel);
+    let written =
+        std::fs::read(&path).map_err(|e| LongtailError::io(format!("read {path:?}"), e))?;
+    let mismatch = |why: String| LongtailError::ValidationMismatch(format!("`{rel}`: {why}"));
+    let expected: u64 = chunk_sizes.iter().map(|&s| s as u64).sum();
+    if written.len() as u64 != expected {
+        return Err(mismatch(format!(
+            "wrote {} bytes, the version index lists {expected}",
+            written.len()
+        )));
+    }
+    let mut offset = 0usize;
+    let mut content = Vec::with_capacity(chunk_hashes.len() * 8);
+    for (&want, &size) in chunk_hashes.iter().zip(chunk_sizes) {
+        let got = hasher.hash(&written[offset..offset + size as usize]);
+        if got != want {
+            return Err(mismatch(format!(
+                "bytes at {offset} hash to {got:#018x}, not chunk {want:#018x}"
+            )));
+        }
+        content.extend_from_slice(&got.to_le_bytes());
+        offset += size as usize;
+    }
+    let got = hasher.hash(&content);
+    if got != content_hash {
+        return Err(mismatch(format!(
+            "content hash {got:#018x}, the version index records {content_hash:#018x}"
+        )));
+    }
+    Ok(())
+}
+
+/// The chunk list (hashes + sizes, in asset order) of the asset at
+/// `source_path` inside `vi`.
+pub(crate) fn asset_chunks(
+    vi: &VersionIndex,
+    source_path: &str,
+) -> Result<(Vec<u64>, Vec<u32>), LongtailError> {
     // Locate the asset by its in-version path.
-    let want = opts.source_path.trim_end_matches('/');
+    let want = source_path.trim_end_matches('/');
     let mut asset: Option<usize> = None;
     for i in 0..vi.asset_count() as usize {
         let p = vi.path(i)?;
@@ -75,12 +387,18 @@ pub async fn cp(opts: CpOptions) -> Result<(), LongtailError> {
         }
     }
     let asset = asset.ok_or_else(|| {
-        LongtailError::InvalidArgument(format!(
-            "asset `{}` not found in version index",
-            opts.source_path
-        ))
+        LongtailError::InvalidArgument(format!("asset `{source_path}` not found in version index"))
     })?;
+    chunks_of(vi, asset, source_path)
+}
 
+/// The chunk list (hashes + sizes, in asset order) of asset `asset` of `vi`.
+/// `source_path` is for error messages only.
+fn chunks_of(
+    vi: &VersionIndex,
+    asset: usize,
+    source_path: &str,
+) -> Result<(Vec<u64>, Vec<u32>), LongtailError> {
     // The asset's chunk list (hashes + sizes), in asset order.
     //
     // Sized and iterated from the map itself rather than from the header count.
@@ -92,8 +410,7 @@ pub async fn cp(opts: CpOptions) -> Result<(), LongtailError> {
     let count = vi.asset_chunk_counts[asset] as usize;
     let bad_map = || {
         LongtailError::InvalidArgument(format!(
-            "version index asset `{}` names chunks outside the index",
-            opts.source_path
+            "version index asset `{source_path}` names chunks outside the index"
         ))
     };
     let indexes = vi
@@ -108,89 +425,88 @@ pub async fn cp(opts: CpOptions) -> Result<(), LongtailError> {
         chunk_hashes.push(*vi.chunk_hashes.get(ci).ok_or_else(bad_map)?);
         chunk_sizes.push(*vi.chunk_sizes.get(ci).ok_or_else(bad_map)?);
     }
+    Ok((chunk_hashes, chunk_sizes))
+}
 
-    #[cfg(feature = "s3")]
-    let s3: S3OptionsArg = opts.s3_options.clone();
-    #[cfg(not(feature = "s3"))]
-    let s3: S3OptionsArg = ();
-
-    let store_opts = BlockStoreOpts {
-        access_type: AccessType::ReadOnly,
-        worker_count: opts.remote_worker_count,
-        cache_dir: opts.cache_path.clone(),
-        pool: Arc::new(crate::version::build_pool(1)?),
-        version_local_store_index: None,
-        max_block_bytes: None,
-        #[cfg(feature = "s3")]
-        s3_options: opts.s3_options.clone(),
-    };
-    let store: Arc<dyn BlockStore> =
-        create_block_store_for_uri(&opts.storage_uri, store_opts).await?;
+/// Fetch the blocks covering `chunk_hashes` from `store` and assemble them, in
+/// order, into the asset's bytes. `source_path` is for error messages only.
+pub(crate) async fn assemble(
+    store: &Arc<dyn BlockStore>,
+    chunk_hashes: &[u64],
+    chunk_sizes: &[u32],
+    source_path: &str,
+) -> Result<Vec<u8>, LongtailError> {
+    // Retarget: the store index limited to the blocks covering these chunks.
+    let store_index = store.get_existing_content(chunk_hashes, 0).await?;
+    let location = chunk_locations(&store_index);
 
-    // Fallible work runs inside this block so the close below happens on a
-    // failure too; `cp` shares the block cache and write-back obligations of
-    // any other read.
-    let assembled = async {
-        // Retarget: the store index limited to the blocks covering these chunks.
-        let store_index = store.get_existing_content(&chunk_hashes, 0).await?;
-
-        // chunk_hash → (block_hash, byte offset within the decompressed block).
-        //
-        // A block whose chunk range runs off the arrays is skipped, matching the
-        // same walk in `apply.rs`. Both are fed `get_existing_content`, whose
-        // output is canonical by construction, so neither guard should ever fire
-        // — but the two walks are the same shape over the same public-fielded
-        // struct, and having one checked and the other bare reads as an oversight
-        // in whichever file you open first.
-        let mut location: HashMap<u64, (u64, u64)> = HashMap::new();
-        for b in 0..store_index.block_count() as usize {
-            let bcount = store_index.block_chunk_counts[b] as usize;
-            let boff = store_index.block_chunks_offsets[b] as usize;
-            let Some(end) = boff.checked_add(bcount) else {
-                continue;
-            };
-            if end > store_index.chunk_hashes.len() || end > store_index.chunk_sizes.len() {
-                continue;
-            }
-            let mut within: u64 = 0;
-            for k in boff..end {
-                let ch = store_index.chunk_hashes[k];
-                location
-                    .entry(ch)
-                    .or_insert((store_index.block_hashes[b], within));
-                within += store_index.chunk_sizes[k] as u64;
-            }
+    // Fetch each needed block once (decompressed), then assemble in asset order.
+    let mut block_cache: HashMap<u64, Arc<Vec<u8>>> = HashMap::new();
+    let mut out: Vec<u8> = Vec::new();
+    for (k, &ch) in chunk_hashes.iter().enumerate() {
+        let (block_hash, within) = *location.get(&ch).ok_or_else(|| {
+            LongtailError::InvalidArgument(format!(
+                "chunk {ch:#018x} of `{source_path}` is not present in the store"
+            ))
+        })?;
+        if let std::collections::hash_map::Entry::Vacant(e) = block_cache.entry(block_hash) {
+            let sb = store.get_stored_block(block_hash).await?;
+            e.insert(Arc::new(sb.payload));
         }
+        out.extend_from_slice(chunk_bytes(
+            &block_cache[&block_hash],
+            block_hash,
+            within,
+            chunk_sizes[k],
+        )?);
+    }
+    Ok(out)
+}
 
-        // Fetch each needed block once (decompressed), then assemble in asset order.
-        let mut block_cache: HashMap<u64, Arc<Vec<u8>>> = HashMap::new();
-        let mut out: Vec<u8> = Vec::new();
-        for (k, &ch) in chunk_hashes.iter().enumerate() {
-            let (block_hash, within) = *location.get(&ch).ok_or_else(|| {
-                LongtailError::InvalidArgument(format!(
-                    "chunk {ch:#018x} of `{}` is not present in the store",
-                    opts.source_path
-                ))
-            })?;
-            if let std::collections::hash_map::Entry::Vacant(e) = block_cache.entry(block_hash) {
-                let sb = store.get_stored_block(block_hash).await?;
-                e.insert(Arc::new(sb.payload));
-            }
-            let payload = &block_cache[&block_hash];
-            let s = within as usize;
-            let e = s + chunk_sizes[k] as usize;
-            if e > payload.len() {
-                return Err(LongtailError::InvalidArgument(format!(
-                    "block {block_hash:#018x} shorter than indexed chunk range"
-                )));
-            }
-            out.extend_from_slice(&payload[s..e]);
+/// chunk_hash → (block_hash, byte offset within the decompressed block), first
+/// block holding a chunk winning.
+///
+/// A block whose chunk range runs off the arrays is skipped, matching the same
+/// walk in `apply.rs`. Both are fed `get_existing_content`, whose output is
+/// canonical by construction, so neither guard should ever fire — but the two
+/// walks are the same shape over the same public-fielded struct, and having one
+/// checked and the other bare reads as an oversight in whichever file you open
+/// first.
+pub(crate) fn chunk_locations(store_index: &StoreIndex) -> HashMap<u64, (u64, u64)> {
+    let mut location: HashMap<u64, (u64, u64)> = HashMap::new();
+    for b in 0..store_index.block_count() as usize {
+        let bcount = store_index.block_chunk_counts[b] as usize;
+        let boff = store_index.block_chunks_offsets[b] as usize;
+        let Some(end) = boff.checked_add(bcount) else {
+            continue;
+        };
+        if end > store_index.chunk_hashes.len() || end > store_index.chunk_sizes.len() {
+            continue;
+        }
+        let mut within: u64 = 0;
+        for k in boff..end {
+            let ch = store_index.chunk_hashes[k];
+            location
+                .entry(ch)
+                .or_insert((store_index.block_hashes[b], within));
+            within += store_index.chunk_sizes[k] as u64;
         }
-        Ok::<_, LongtailError>(out)
     }
-    .await;
-    let out = crate::store_lifecycle::finish_store(&store, assembled).await?;
+    location
+}
 
-    fs_util::write_to_uri(&opts.target_path, out.into(), &s3).await?;
-    Ok(())
+/// The `size` bytes at `within` in the decompressed `payload` of `block_hash`.
+pub(crate) fn chunk_bytes(
+    payload: &[u8],
+    block_hash: u64,
+    within: u64,
+    size: u32,
+) -> Result<&[u8], LongtailError> {
+    let s = within as usize;
+    let e = s + size as usize;
+    payload.get(s..e).ok_or_else(|| {
+        LongtailError::InvalidArgument(format!(
+            "block {block_hash:#018x} shorter than indexed chunk range"
+        ))
+    })
 }
@@ -0,0 +1,293 @@
+//! `diff-versions`: what changed between two published versions, and what an
+//! update from one to the other costs.
+//!
+//! The asset lists come from [`longtail_core::create_version_diff`], the same
+//! diff a downsync applies, so "modified" here means exactly what the update
+//! would rewrite. The chunk numbers come from
+//! [`longtail_core::get_required_chunk_hashes`].
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use longtail_core::{StoreIndex, VersionIndex, create_version_diff, get_required_chunk_hashes};
+use longtail_store::AccessType;
+use longtail_store::uri::{BlockStoreOpts, create_block_store_for_uri};
+use serde::{Deserialize, Serialize};
+
+use crate::error::LongtailError;
+#[cfg(feature = "s3")]
+use crate::fs_util::default_s3;
+use crate::fs_util::{self, S3OptionsArg};
+use crate::inspect::read_version_index_from_uri;
+
+/// Options for [`diff_versions`].
+#[non_exhaustive]
+pub struct DiffVersionsOptions {
+    /// The older version index URI.
+    pub from_path: String,
+    /// The newer version index URI.
+    pub to_path: String,
+    /// A store holding `to`'s blocks. When set, the report carries a
+    /// [`DownloadEstimate`].
+    pub storage_uri: Option<String>,
+    pub remote_worker_count: usize,
+    #[cfg(feature = "s3")]
+    pub s3_options: S3OptionsArg,
+}
+
+impl DiffVersionsOptions {
+    pub fn new(from_path: impl Into<String>, to_path: impl Into<String>) -> Self {
+        DiffVersionsOptions {
+            from_path: from_path.into(),
+            to_path: to_path.into(),
+            storage_uri: None,
+            remote_worker_count: 0,
+            #[cfg(feature = "s3")]
+            s3_options: default_s3(),
+        }
+    }
+}
+
+/// One asset in a [`VersionDiffReport`] list.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct AssetChange {
+    /// The path as the version index records it (directories end in `/`).
+    pub path: String,
+    /// Size in the newer version; in the older one for a removed asset.
+    pub size: u64,
+    /// POSIX permission bits, on the same side as `size`.
+    pub permissions: u16,
+    /// Size in the older version, for a content-modified asset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_size: Option<u64>,
+    /// Permissions in the older version, for a permission-modified asset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_permissions: Option<u16>,
+}
+
+/// What an update from one version to the other would download.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DownloadEstimate {
+    /// Blocks holding at least one required chunk; each is fetched whole.
+    pub block_count: u32,
+    /// Their payload (decompressed) bytes.
+    pub payload_bytes: u64,
+    /// Their size as stored: the bytes on the wire.
+    pub compressed_bytes: u64,
+    /// Required blocks the store listing did not show, and so are not in
+    /// `compressed_bytes`. Non-zero means the store index names blocks that
+    /// are gone.
+    pub unlisted_blocks: u32,
+    /// Required chunks no block in the store holds. Non-zero means the update
+    /// would fail.
+    pub missing_chunks: u32,
+}
+
+/// The outcome of [`diff_versions`]. Each list is sorted by path.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct VersionDiffReport {
+    /// In the newer version only.
+    pub added: Vec<AssetChange>,
+    /// In the older version only.
+    pub removed: Vec<AssetChange>,
+    /// On both sides with different content.
+    pub content_modified: Vec<AssetChange>,
+    /// On both sides with different permissions (content may differ too).
+    pub permissions_modified: Vec<AssetChange>,
+    /// Distinct chunks of the added and content-modified assets: what an
+    /// update fetches.
+    pub required_chunk_count: u32,
+    pub required_chunk_bytes: u64,
+    /// Of those, the chunks the older version does not have anywhere: content
+    /// that is new in this version, not moved or duplicated.
+    pub new_chunk_count: u32,
+    pub new_chunk_bytes: u64,
+    /// Set when a store was given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download: Option<DownloadEstimate>,
+}
+
+/// Compare two version indexes already in memory. Both must be hashed with
+/// the same algorithm: content and chunk hashes mean nothing across two.
+pub fn diff_version_indexes(
+    from: &VersionIndex,
+    to: &VersionIndex,
+) -> Result<VersionDiffReport, LongtailError> {
+    if from.hash_identifier != to.hash_identifier {
+        return Err(LongtailError::InvalidArgument(format!(
+            "the versions are hashed differently ({:#x} and {:#x}); their content cannot be \
+             compared",
+            from.hash_identifier, to.hash_identifier
+        )));
+    }
+    let diff = create_version_diff(from, to);
+    let change = |vi: &VersionIndex, i: u32| -> Result<AssetChange, LongtailError> {
+        let i = i as usize;
+        Ok(AssetChange {
+            path: vi.path(i)?.to_string(),
+            size: vi.asset_sizes[i],
+            permissions: vi.permissions[i].bits(),
+            previous_size: None,
+            previous_permissions: None,
+        })
+    };
+    let list = |vi: &VersionIndex, idx: &[u32]| -> Result<Vec<AssetChange>, LongtailError> {
+        let mut out = idx
+            .iter()
+            .map(|&i| change(vi, i))
+            .collect::<Result<Vec<_>, _>>()?;
+        out.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(out)
+    };
+    let mut report = VersionDiffReport {
+        added: list(to, &diff.target_added_asset_indexes)?,
+        removed: list(from, &diff.source_removed_asset_indexes)?,
+        ..VersionDiffReport::default()
+    };
+    for (&s, &t) in diff
+        .source_content_modified_asset_indexes
+        .iter()
+        .zip(&diff.target_content_modified_asset_indexes)
+    {
+        let mut c = change(to, t)?;
+        c.previous_size = Some(from.asset_sizes[s as usize]);
+        report.content_modified.push(c);
+    }
+    for (&s, &t) in diff
+        .source_permissions_modified_asset_indexes
+        .iter()
+        .zip(&diff.target_permissions_modified_asset_indexes)
+    {
+        let mut c = change(to, t)?;
+        c.previous_permissions = Some(from.permissions[s as usize].bits());
+        report.permissions_modified.push(c);
+    }
+    report.content_modified.sort_by(|a, b| a.path.cmp(&b.path));
+    report
+        .permissions_modified
+        .sort_by(|a, b| a.path.cmp(&b.path));
+
+    let sizes: HashMap<u64, u32> = to
+        .chunk_hashes
+        .iter()
+        .copied()
+        .zip(to.chunk_sizes.iter().copied())
+        .collect();
+    let old: HashSet<u64> = from.chunk_hashes.iter().copied().collect();
+    for h in get_required_chunk_hashes(to, &diff) {
+        let size = sizes.get(&h).copied().unwrap_or(0) as u64;
+        report.required_chunk_count += 1;
+        report.required_chunk_bytes += size;
+        if !old.contains(&h) {
+            report.new_chunk_count += 1;
+            report.new_chunk_bytes += size;
+        }
+    }
+    Ok(report)
+}
+
+/// `diff-versions`: read both version indexes and compare them; with a store,
+/// also estimate the download an update between them would make.
+pub async fn diff_versions(opts: DiffVersionsOptions) -> Result<VersionDiffReport, LongtailError> {
+    let s3 = crate::s3_arg!(opts);
+    let from = read_version_index_from_uri(&opts.from_path, &s3).await?;
+    let to = read_version_index_from_uri(&opts.to_path, &s3).await?;
+    let mut report = diff_version_indexes(&from, &to)?;
+    if let Some(storage_uri) = &opts.storage_uri {
+        let required = get_required_chunk_hashes(&to, &create_version_diff(&from, &to));
+        report.download =
+            Some(estimate_download(storage_uri, &required, opts.remote_worker_count, &s3).await?);
+    }
+    Ok(report)
+}
+
+/// The blocks of `storage_uri` that hold `chunks`, and what fetching them
+/// costs.
+async fn estimate_download(
+    storage_uri: &str,
+    chunks: &[u64],
+    remote_worker_count: usize,
+    s3: &S3OptionsArg,
+) -> Result<DownloadEstimate, LongtailError> {
+    let existing = required_blocks(storage_uri, chunks, remote_worker_count, s3).await?;
+    let blocks = unique_blocks(&existing);
+    let stored: HashSet<u64> = existing.chunk_hashes.iter().copied().collect();
+    let compressed = stored_block_sizes(storage_uri, &blocks, s3).await?;
+    Ok(DownloadEstimate {
+        block_count: blocks.len() as u32,
+        payload_bytes: existing.block_payload_sizes(&blocks).values().sum(),
+        compressed_bytes: compressed.values().sum(),
+        unlisted_blocks: blocks
+            .iter()
+            .filter(|h| !compressed.contains_key(h))
+            .count() as u32,
+        missing_chunks: chunks.iter().filter(|h| !stored.contains(h)).count() as u32,
+    })
+}
+
+/// The store index of `storage_uri` narrowed to the blocks holding `chunks`:
+/// the blocks an update needing those chunks fetches. Chunks no block holds
+/// are absent from it.
+pub(crate) async fn required_blocks(
+    storage_uri: &str,
+    chunks: &[u64],
+    remote_worker_count: usize,
+    #[allow(unused)] s3: &S3OptionsArg,
+) -> Result<StoreIndex, LongtailError> {
+    let store_opts = BlockStoreOpts {
+        access_type: AccessType::ReadOnly,
+        worker_count: remote_worker_count,
+        cache_dir: None,
+        pool: Arc::new(crate::version::build_pool(1)?),
+        version_local_store_index: None,
+        max_block_bytes: None,
+        events: None,
+        pause: None,
+        #[cfg(feature = "s3")]
+        s3_options: s3.clone(),
+    };
+    let store = create_block_store_for_uri(storage_uri, store_opts).await?;
+    let fetched = store
+        .get_existing_content(chunks, 0)
+        .await
+        .map_err(LongtailError::from);
+    crate::store_lifecycle::finish_store(&store, fetched).await
+}
+
+/// `si`'s block hashes, first occurrence of each.
+pub(crate) fn unique_blocks(si: &StoreIndex) -> Vec<u64> {
+    let mut seen = HashSet::new();
+    si.block_hashes
+        .iter()
+        .copied()
+        .filter(|h| seen.insert(*h))
+        .collect()
+}
+
+/// The stored (compressed) size of each of `blocks` that the store lists.
+///
+/// The store index records only payload sizes, so this lists the store's
+/// `chunks/` prefix once — one request per thousand blocks on S3, however few
+/// are wanted.
+pub(crate) async fn stored_block_sizes(
+    storage_uri: &str,
+    blocks: &[u64],
+    s3: &S3OptionsArg,
+) -> Result<HashMap<u64, u64>, LongtailError> {
+    let wanted: HashMap<String, u64> = blocks
+        .iter()
+        .map(|&h| (longtail_store::block_path("chunks", h), h))
+        .collect();
+    let client = fs_util::blob_store_for_uri(storage_uri, s3)?
+        .new_client()
+        .await?;
+    Ok(client
+        .get_objects("chunks/")
+        .await?
+        .into_iter()
+        .filter_map(|o| wanted.get(&o.name).map(|&h| (h, o.size)))
+        .collect())
+}
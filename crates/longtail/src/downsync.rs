@@ -1,27 +1,40 @@
 //! The async download-path orchestration (`ChangeVersion2` semantics; mirrors
 //! `cmd_downsync.go` + the ffi `commands.rs` map).
+//!
+//! A run is split into a read-only half ([`DownsyncRun::resolve`] through
+//! [`DownsyncRun::diff_and_retarget`]) and a mutating half
+//! ([`DownsyncRun::apply`] + [`DownsyncRun::complete`]). [`downsync`] does both
+//! back to back over one store; `crate::plan` stops after the first half and
+//! resumes from it later.
 
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use longtail_core::{
-    StoreIndex, VersionIndex, create_version_diff, get_required_chunk_hashes, merge_version_index,
+    StoreIndex, VersionDiff, VersionIndex, create_version_diff, get_required_chunk_hashes,
+    merge_version_index,
 };
-use longtail_store::AccessType;
-use longtail_store::block_store::BlockStore;
+use longtail_store::block_store::{BlockStore, StatsSnapshot};
 use longtail_store::uri::{BlockStoreOpts, create_block_store_for_uri_with_budget};
+use longtail_store::{AccessType, PauseToken};
 use tokio_util::sync::CancellationToken;
 
-use crate::apply::change_version2;
+use crate::apply::{ApplyStats, change_version2};
 use crate::error::LongtailError;
+use crate::fingerprint::{self, FingerprintCache};
 use crate::fs_util::{self, S3OptionsArg};
-use crate::hash_util::make_hasher;
+use crate::hash_util::{SyncHasher, make_hasher};
+use crate::journal::{JournalKey, JournalPlan};
 use crate::options::{DownsyncOptions, DownsyncReport, PhaseTiming};
-use crate::path_filter::{RegexPathFilter, TARGET_INDEX_CACHE_NAME, relative_within};
-use crate::progress::{NullProgress, ProgressSink, RateLimited};
-use crate::version::create_version_index_from_folder;
+use crate::path_filter::{
+    APPLY_JOURNAL_NAME, FINGERPRINT_CACHE_NAME, RegexPathFilter, TARGET_INDEX_CACHE_NAME,
+    relative_within,
+};
+use crate::progress::{Event, NullProgress, ProgressSink, RateLimited};
+use crate::store_lifecycle;
+use crate::version::{create_version_index_from_folder, create_version_index_with_fingerprints};
 
 /// Downsync one or more source versions into a target folder. See
 /// [`DownsyncOptions`]. Runs on the caller's ambient tokio runtime.
@@ -36,198 +49,371 @@ use crate::version::create_version_index_from_folder;
     )
 )]
 pub async fn downsync(opts: DownsyncOptions) -> Result<DownsyncReport, LongtailError> {
-    if opts.use_legacy_write {
-        return Err(LongtailError::LegacyWriteUnsupported);
-    }
+    let mut run = DownsyncRun::resolve(opts).await?;
+    let override_index = run.read_store_index_override().await?;
+    let store = run.open_store(override_index).await?;
 
-    // Non-empty source paths (cmd_downsync.go:87-94).
-    let sources: Vec<String> = opts
-        .source_paths
-        .iter()
-        .filter(|s| !s.is_empty())
-        .cloned()
-        .collect();
-    if sources.is_empty() {
-        return Err(LongtailError::InvalidArgument(
-            "please provide at least one source path uri".into(),
-        ));
+    // Everything that can fail between opening the store and closing it runs
+    // inside this block, so the flush + close below happen on a cancel or a
+    // failure too — the store's write-backs and the cache-budget sweep both hang
+    // off `close()`, and cancelling is a routine way to end a download.
+    let applied = async {
+        let retargeted = run.diff_and_retarget(&store).await?;
+        run.apply(&store, &retargeted).await
     }
+    .await;
 
-    // Resolve the target folder (cmd_downsync.go:101).
-    let target_string = match opts.target_path.as_deref() {
-        Some(t) if !t.is_empty() => t.to_string(),
-        _ => derive_target_path(&sources[0])?,
-    };
-    let target_root = PathBuf::from(&target_string);
-
-    // Target-index caching four-step semantics (cmd_downsync.go:120-135).
-    let mut cache_target_index = opts.cache_target_index;
-    let explicit_target_index = opts
-        .target_index_path
-        .as_deref()
-        .filter(|s| !s.is_empty())
-        .map(|s| s.to_string());
-    if explicit_target_index.is_some() {
-        cache_target_index = false;
-    }
-    let cache_index_path = target_root.join(TARGET_INDEX_CACHE_NAME);
-    // Effective target index: explicit path, or the cache file if it exists. The
-    // two are not equally trusted — see `read_target_index` below.
-    let effective_target_index: Option<String> = if let Some(t) = &explicit_target_index {
-        Some(t.clone())
-    } else if cache_target_index && fs_util::file_exists(&cache_index_path) {
-        Some(cache_index_path.to_string_lossy().into_owned())
-    } else {
-        None
-    };
-    let target_index_is_cache = explicit_target_index.is_none();
-
-    // A target index is a description of the target, so it is never part of it —
-    // neither scanned into one nor written out of one. That covers the cache and
-    // an explicitly supplied path that happens to live inside the target.
-    let mut never_content = vec![TARGET_INDEX_CACHE_NAME.to_string()];
-    if let Some(t) = &explicit_target_index
-        && let Some(rel) = relative_within(&target_root, t)
-    {
-        never_content.push(rel);
-    }
-    let filter = RegexPathFilter::new(
-        opts.include_filter_regex.as_deref(),
-        opts.exclude_filter_regex.as_deref(),
-    )?
-    .never_paths(never_content.clone());
-
-    let progress: Arc<dyn ProgressSink> = opts
-        .progress
-        .clone()
-        .unwrap_or_else(|| Arc::new(NullProgress));
-    let progress = Arc::new(RateLimited::new(progress));
-    let cancel = opts.cancel.clone().unwrap_or_default();
-
-    let pool = match &opts.pool {
-        Some(p) => p.clone(),
-        None => Arc::new(crate::version::build_pool(opts.worker_count)?),
-    };
+    // Flush + close the store chain before resolving (obligation #6; warm-cache
+    // write-backs must complete — cmd_downsync.go:324).
+    let apply_stats = run.finish_store(&store, applied).await?;
+    run.complete(apply_stats, store.stats())
+}
+
+/// The diff a run applies and the store index retargetted to its required
+/// chunks.
+pub(crate) struct Retargeted {
+    pub(crate) diff: VersionDiff,
+    pub(crate) store_index: StoreIndex,
+}
+
+/// One downsync, resolved: options, target, filter, the merged source version
+/// and the target's current index. Nothing in here has touched the target.
+pub(crate) struct DownsyncRun {
+    pub(crate) opts: DownsyncOptions,
+    pub(crate) target_string: String,
+    target_root: PathBuf,
+    cache_target_index: bool,
+    cache_index_path: PathBuf,
+    journal_path: PathBuf,
+    fingerprint_path: PathBuf,
+    /// The journal this run keeps, when `resume_journal` is on.
+    journal: Option<JournalPlan>,
+    effective_target_index: Option<String>,
+    target_index_is_cache: bool,
+    filter: RegexPathFilter,
+    /// Assets to write first; `None` when no priority filter was given.
+    priority: Option<RegexPathFilter>,
+    pub(crate) progress: Arc<RateLimited>,
+    pub(crate) cancel: CancellationToken,
+    pool: Arc<rayon::ThreadPool>,
+    s3: S3OptionsArg,
+    /// The store's counters when this run opened it; non-zero only for a
+    /// caller-built store that earlier operations have already used.
+    stats_base: StatsSnapshot,
+    pub(crate) source_version: VersionIndex,
+    pub(crate) hasher: SyncHasher,
+    pub(crate) target_index: VersionIndex,
+    phases: Vec<PhaseTiming>,
+    phase: PhaseTimer,
+}
+
+impl DownsyncRun {
+    /// Validate the options, resolve the target, read + merge the sources and
+    /// build the target's current index.
+    pub(crate) async fn resolve(opts: DownsyncOptions) -> Result<DownsyncRun, LongtailError> {
+        if opts.use_legacy_write {
+            return Err(LongtailError::LegacyWriteUnsupported);
+        }
+
+        // Non-empty source paths (cmd_downsync.go:87-94).
+        let sources: Vec<String> = opts
+            .source_paths
+            .iter()
+            .filter(|s| !s.is_empty())
+            .cloned()
+            .collect();
+        if sources.is_empty() {
+            return Err(LongtailError::InvalidArgument(
+                "please provide at least one source path uri".into(),
+            ));
+        }
+
+        // Resolve the target folder (cmd_downsync.go:101).
+        let target_string = match opts.target_path.as_deref() {
+            Some(t) if !t.is_empty() => t.to_string(),
+            _ => derive_target_path(&sources[0])?,
+        };
+        let target_root = PathBuf::from(&target_string);
+
+        // Target-index caching four-step semantics (cmd_downsync.go:120-135).
+        let mut cache_target_index = opts.cache_target_index;
+        let explicit_target_index = opts
+            .target_index_path
+            .as_deref()
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string());
+        if explicit_target_index.is_some() {
+            cache_target_index = false;
+        }
+        let cache_index_path = target_root.join(TARGET_INDEX_CACHE_NAME);
+        // Effective target index: explicit path, or the cache file if it exists. The
+        // two are not equally trusted — see `read_target_index` below.
+        let effective_target_index: Option<String> = if let Some(t) = &explicit_target_index {
+            Some(t.clone())
+        } else if cache_target_index && fs_util::file_exists(&cache_index_path) {
+            Some(cache_index_path.to_string_lossy().into_owned())
+        } else {
+            None
+        };
+        let target_index_is_cache = explicit_target_index.is_none();
 
-    #[cfg(feature = "s3")]
-    let s3: S3OptionsArg = opts.s3_options.clone();
-    #[cfg(not(feature = "s3"))]
-    let s3: S3OptionsArg = ();
-
-    let mut phases: Vec<PhaseTiming> = Vec::new();
-    let mut phase = PhaseTimer::new();
-
-    // Read + merge source version index(es) (cmd_downsync.go:142-164). This is
-    // the first (often remote) fetch, so label it — otherwise the run appears to
-    // hang here with no phase shown.
-    check_cancel(&cancel)?;
-    progress.phase("Reading version index");
-    let source_version = read_merged_source(&sources, &s3).await?;
-    let hash_id = source_version.hash_identifier;
-    let target_chunk_size = source_version.target_chunk_size;
-    let hasher = make_hasher(hash_id)?;
-    phases.push(phase.lap("read_source_index"));
-
-    // Build the current target index (explicit/cached file, scan, or empty).
-    progress.phase("Indexing version");
-    let preloaded = read_target_index(effective_target_index.as_deref(), target_index_is_cache)?;
-    let used_preloaded = preloaded.is_some();
-    let target_index = if let Some(vi) = preloaded {
-        vi
-    } else if opts.scan_target {
-        let on_scan = crate::version::scan_progress_forwarder(progress.clone());
-        create_version_index_from_folder(
-            &target_root,
-            &filter,
-            hasher.as_ref(),
-            target_chunk_size,
-            0, // NoCompressionType for target scanning (cmd_downsync.go:176)
-            &pool,
-            &cancel,
-            Some(&on_scan),
+        // A target index is a description of the target, so it is never part of it —
+        // neither scanned into one nor written out of one. That covers the cache and
+        // an explicitly supplied path that happens to live inside the target.
+        // The apply journal and the fingerprint sidecar are the same kind of file.
+        let mut never_content = vec![
+            TARGET_INDEX_CACHE_NAME.to_string(),
+            APPLY_JOURNAL_NAME.to_string(),
+            FINGERPRINT_CACHE_NAME.to_string(),
+        ];
+        if let Some(t) = &explicit_target_index
+            && let Some(rel) = relative_within(&target_root, t)
+        {
+            never_content.push(rel);
+        }
+        let filter = RegexPathFilter::new(
+            opts.include_filter_regex.as_deref(),
+            opts.exclude_filter_regex.as_deref(),
         )?
-    } else {
-        empty_version_index(hash_id, target_chunk_size)
-    };
-    phases.push(phase.lap("build_target_index"));
-
-    // A repair that reads a cached target index cannot repair anything: the cache
-    // is trusted as the target's state, so the diff is empty and the run exits 0
-    // having written nothing. The two options are orthogonal and the combination
-    // is legal — a no-delete upgrade wants exactly this — but it is also the shape
-    // a "repair" button gets wired as by accident, and the failure is silent.
-    // Keyed on the index actually being used, so a cache that was rejected above
-    // does not draw a warning about a trust that is no longer being placed.
-    if !opts.delete_removed && used_preloaded {
-        tracing::warn!(
-            "delete_removed is off while a cached or explicit target index is in use; damage on \
-             disk will not be detected — set cache_target_index = false to scan the target"
-        );
+        .never_paths(never_content);
+        let priority = match opts.priority_filter_regex.as_deref() {
+            Some(p) if !p.is_empty() => Some(RegexPathFilter::new(Some(p), None)?),
+            _ => None,
+        };
+
+        let progress: Arc<dyn ProgressSink> = opts
+            .progress
+            .clone()
+            .unwrap_or_else(|| Arc::new(NullProgress));
+        let progress = Arc::new(RateLimited::new(progress));
+        let cancel = opts.cancel.clone().unwrap_or_default();
+
+        let pool = match &opts.pool {
+            Some(p) => p.clone(),
+            None => Arc::new(crate::version::build_pool(opts.worker_count)?),
+        };
+
+        #[cfg(feature = "s3")]
+        let s3: S3OptionsArg = opts.s3_options.clone();
+        #[cfg(not(feature = "s3"))]
+        let s3: S3OptionsArg = ();
+
+        let mut phases: Vec<PhaseTiming> = Vec::new();
+        let mut phase = PhaseTimer::new(opts.pause.clone());
+
+        // Read + merge source version index(es) (cmd_downsync.go:142-164). This is
+        // the first (often remote) fetch, so label it — otherwise the run appears to
+        // hang here with no phase shown.
+        check_cancel(&cancel)?;
+        progress.phase("Reading version index");
+        let source_version = read_merged_source(&sources, &s3).await?;
+        let hasher = make_hasher(source_version.hash_identifier)?;
+        phases.push(phase.lap("read_source_index"));
+
+        let journal_path = target_root.join(APPLY_JOURNAL_NAME);
+        let fingerprint_path = target_root.join(FINGERPRINT_CACHE_NAME);
+        let journal = opts.resume_journal.then(|| JournalPlan {
+            path: journal_path.clone(),
+            key: JournalKey {
+                version_hash: hasher.hash(&source_version.to_bytes()),
+                delete_removed: opts.delete_removed,
+                include: opts.include_filter_regex.clone(),
+                exclude: opts.exclude_filter_regex.clone(),
+            },
+        });
+
+        let mut run = DownsyncRun {
+            opts,
+            target_string,
+            target_root,
+            cache_target_index,
+            cache_index_path,
+            journal_path,
+            journal,
+            fingerprint_path,
+            effective_target_index,
+            target_index_is_cache,
+            filter,
+            priority,
+            progress,
+            cancel,
+            pool,
+            s3,
+            source_version,
+            hasher,
+            // Placeholder; built just below, which needs the rest of the run.
+            target_index: empty_version_index(0, 0),
+            phases,
+            phase,
+            stats_base: StatsSnapshot::default(),
+        };
+
+        // Build the current target index (explicit/cached file, scan, or empty).
+        run.progress.phase("Indexing version");
+        let (target_index, used_preloaded) = run.build_target_index()?;
+        run.target_index = target_index;
+        run.lap("build_target_index");
+
+        // A repair that reads a cached target index cannot repair anything: the cache
+        // is trusted as the target's state, so the diff is empty and the run exits 0
+        // having written nothing. The two options are orthogonal and the combination
+        // is legal — a no-delete upgrade wants exactly this — but it is also the shape
+        // a "repair" button gets wired as by accident, and the failure is silent.
+        // Keyed on the index actually being used, so a cache that was rejected above
+        // does not draw a warning about a trust that is no longer being placed.
+        if !run.opts.delete_removed && used_preloaded {
+            let message = "delete_removed is off while a cached or explicit target index is in \
+                           use; damage on disk will not be detected — set cache_target_index = \
+                           false to scan the target";
+            tracing::warn!("{message}");
+            run.progress.warn(message);
+        }
+        Ok(run)
     }
 
-    // Build the ReadOnly store-index override from version-local-store-index
-    // paths (remotestore.go:1897; on any failure → None → the store reads its
-    // own index instead). Reading the override is itself a (possibly remote)
-    // step, so label it rather than leaving the stale "Indexing version" up.
-    check_cancel(&cancel)?;
-    progress.phase("Reading store index");
-    let override_index =
-        load_store_index_override(&opts.version_local_store_index_paths, &s3).await;
-
-    // Without an override the store reads its own index — a list of `store*.lsi`
-    // and a merge of every shard — on the first block query below, and this phase
-    // is still the one on screen when that happens. Re-label so the slow branch
-    // is named rather than hiding behind a label that also covers the cheap one.
-    // Fires whether the override failed or was never supplied: the work, and so
-    // the honest label, is the same either way.
-    if override_index.is_none() {
-        progress.phase("Reading full store index");
+    /// The target's current index — the explicit or cached file, a scan (or
+    /// the part of one an apply journal leaves to do), or empty — and whether
+    /// it came from a file.
+    pub(crate) fn build_target_index(&self) -> Result<(VersionIndex, bool), LongtailError> {
+        let preloaded = read_target_index(
+            self.effective_target_index.as_deref(),
+            self.target_index_is_cache,
+        )?;
+        if let Some(vi) = preloaded {
+            return Ok((vi, true));
+        }
+        let hash_id = self.source_version.hash_identifier;
+        let target_chunk_size = self.source_version.target_chunk_size;
+        // An explicit target index is the caller's account of the target, and
+        // is used even when it failed to read; the journal stands in for a
+        // scan only.
+        if self.opts.scan_target
+            && self.target_index_is_cache
+            && let Some(plan) = &self.journal
+            && let Some(state) = crate::journal::read(&plan.path, &plan.key)
+        {
+            let on_check = crate::version::scan_progress_forwarder(self.progress.clone());
+            if let Some(vi) = crate::journal::resume_index(
+                &self.target_root,
+                &state,
+                &self.source_version,
+                &self.hasher,
+                &self.pool,
+                &self.cancel,
+                Some(&on_check),
+            )? {
+                return Ok((vi, false));
+            }
+            let message =
+                "the apply journal does not match the source version; scanning the target";
+            tracing::warn!("{message}");
+            self.progress.warn(message);
+        }
+        let vi = if self.opts.scan_target {
+            let on_scan = crate::version::scan_progress_forwarder(self.progress.clone());
+            let known = if self.opts.fingerprint_cache {
+                FingerprintCache::read(&self.fingerprint_path, hash_id, target_chunk_size)
+            } else {
+                None
+            };
+            create_version_index_with_fingerprints(
+                &self.target_root,
+                &self.filter,
+                self.hasher.as_ref(),
+                target_chunk_size,
+                0, // NoCompressionType for target scanning (cmd_downsync.go:176)
+                &self.pool,
+                &self.cancel,
+                self.opts.pause.as_ref(),
+                Some(&on_scan),
+                known.as_ref(),
+                Some(&|path, bytes| {
+                    self.progress.event(Event::AssetHashed { path, bytes });
+                }),
+            )?
+        } else {
+            empty_version_index(hash_id, target_chunk_size)
+        };
+        Ok((vi, false))
     }
 
-    // Compose the block store (Compress(Cache(Remote))), ReadOnly. The apply
-    // loop's block-task concurrency shares the store's resolved worker count
-    // (one knob — no separate apply setting).
-    let apply_concurrency =
-        longtail_store::resolved_worker_count(&opts.storage_uri, opts.remote_worker_count);
-    let opts_store = BlockStoreOpts {
-        access_type: AccessType::ReadOnly,
-        worker_count: opts.remote_worker_count,
-        cache_dir: opts.cache_path.clone(),
-        pool: pool.clone(),
-        version_local_store_index: override_index,
-        max_block_bytes: None,
-        #[cfg(feature = "s3")]
-        s3_options: opts.s3_options.clone(),
-    };
-    // `max_prefetch_bytes` is the deadlock-regression test knob (None in
-    // production → the 512 MiB default). Liveness must never depend on it.
-    let store: Arc<dyn BlockStore> = create_block_store_for_uri_with_budget(
-        &opts.storage_uri,
-        opts_store,
-        opts.max_prefetch_bytes,
-        opts.cache_size_limit,
-    )
-    .await?;
-    phases.push(phase.lap("open_store"));
-
-    // A second hasher instance for the opt-in chunk verification: the apply tasks
-    // are spawned, so it has to be shared rather than borrowed. Constructing one is
-    // trivial (a unit struct), so this is cheaper than reshaping the scan's hasher.
-    let verify_hasher: Option<Arc<dyn longtail_core::Hash + Send + Sync>> = if opts.verify_chunks {
-        Some(Arc::from(make_hasher(hash_id)?))
-    } else {
-        None
-    };
+    /// Build the ReadOnly store-index override from version-local-store-index
+    /// paths (remotestore.go:1897; on any failure → None → the store reads its
+    /// own index instead). Reading the override is itself a (possibly remote)
+    /// step, so label it rather than leaving the stale "Indexing version" up.
+    pub(crate) async fn read_store_index_override(
+        &self,
+    ) -> Result<Option<StoreIndex>, LongtailError> {
+        check_cancel(&self.cancel)?;
+        // A caller-built store is its own index source; there is nothing to
+        // seed it with.
+        if self.opts.block_store.is_some() {
+            return Ok(None);
+        }
+        self.progress.phase("Reading store index");
+        let paths = &self.opts.version_local_store_index_paths;
+        let loaded = load_store_index_override(paths, &self.s3).await;
+        // Each reason is logged where it happened; the sink gets the outcome.
+        if loaded.is_none() && paths.iter().any(|p| !p.is_empty()) {
+            self.progress
+                .warn("could not use the version-local store index; reading the whole store index");
+        }
+        Ok(loaded)
+    }
 
-    // Everything that can fail between opening the store and closing it runs
-    // inside this block, so the flush + close below happen on a cancel or a
-    // failure too — the store's write-backs and the cache-budget sweep both hang
-    // off `close()`, and cancelling is a routine way to end a download.
-    let applied = async {
-        // Diff (from = current target, to = desired source), required chunks,
-        // retargetted store index (min_block_usage_percent = 0,
-        // cmd_downsync.go:266).
-        let mut diff = create_version_diff(&target_index, &source_version);
+    /// Compose the block store (Compress(Cache(Remote))), ReadOnly, seeded with
+    /// `override_index` when there is one — or take the caller's
+    /// [`DownsyncOptions::block_store`], which ignores the override.
+    pub(crate) async fn open_store(
+        &mut self,
+        override_index: Option<StoreIndex>,
+    ) -> Result<Arc<dyn BlockStore>, LongtailError> {
+        if let Some(store) = &self.opts.block_store {
+            self.stats_base = store.stats();
+            return Ok(store.clone());
+        }
+        // Without an override the store reads its own index — a list of
+        // `store*.lsi` and a merge of every shard — on the first block query, and
+        // the previous phase is still the one on screen when that happens.
+        // Re-label so the slow branch is named rather than hiding behind a label
+        // that also covers the cheap one. Fires whether the override failed or was
+        // never supplied: the work, and so the honest label, is the same either way.
+        if override_index.is_none() {
+            self.progress.phase("Reading full store index");
+        }
+        let opts_store = BlockStoreOpts {
+            access_type: AccessType::ReadOnly,
+            worker_count: self.opts.remote_worker_count,
+            cache_dir: self.opts.cache_path.clone(),
+            pool: self.pool.clone(),
+            version_local_store_index: override_index,
+            max_block_bytes: None,
+            events: Some(self.progress.store_events()),
+            pause: self.opts.pause.clone(),
+            #[cfg(feature = "s3")]
+            s3_options: self.opts.s3_options.clone(),
+        };
+        // `max_prefetch_bytes` is the deadlock-regression test knob (None in
+        // production → the 512 MiB default). Liveness must never depend on it.
+        let store: Arc<dyn BlockStore> = create_block_store_for_uri_with_budget(
+            &self.opts.storage_uri,
+            opts_store,
+            self.opts.max_prefetch_bytes,
+            self.opts.cache_size_limit,
+        )
+        .await?;
+        self.lap("open_store");
+        Ok(store)
+    }
+
+    /// Diff (from = current target, to = desired source), required chunks,
+    /// retargetted store index (min_block_usage_percent = 0,
+    /// cmd_downsync.go:266). Reads the store index only; writes nothing.
+    pub(crate) async fn diff_and_retarget(
+        &mut self,
+        store: &Arc<dyn BlockStore>,
+    ) -> Result<Retargeted, LongtailError> {
+        let mut diff = create_version_diff(&self.target_index, &self.source_version);
         // Existing stores already hold versions that name a target index as an
         // asset — golongtail indexes it too, so upsyncing a downsynced folder has
         // always propagated one. Keeping it out of the scan stops new ones; this
@@ -235,82 +421,164 @@ pub async fn downsync(opts: DownsyncOptions) -> Result<DownsyncReport, LongtailE
         // where a run that fails after it lands leaves a valid-looking index that
         // the next run trusts and diffs against, writing nothing and reporting
         // success over an incomplete tree.
-        let dropped = strip_never_content(&mut diff, &source_version, &filter);
+        let dropped = strip_never_content(&mut diff, &self.source_version, &self.filter);
         if dropped > 0 {
-            tracing::warn!(
-                count = dropped,
-                "the source version names a target index as content; not writing it to the target"
-            );
+            let message =
+                "the source version names a target index as content; not writing it to the target";
+            tracing::warn!(count = dropped, "{message}");
+            self.progress.warn(message);
         }
-        let required = get_required_chunk_hashes(&source_version, &diff);
+        let required = get_required_chunk_hashes(&self.source_version, &diff);
         let store_index = store.get_existing_content(&required, 0).await?;
-        phases.push(phase.lap("diff_and_retarget"));
+        self.lap("diff_and_retarget");
+        Ok(Retargeted { diff, store_index })
+    }
+
+    /// Mutate the target: drop the cache index (I1) and any stale apply journal
+    /// (I3), then `ChangeVersion2`, then drop the journal it kept.
+    pub(crate) async fn apply(
+        &mut self,
+        store: &Arc<dyn BlockStore>,
+        retargeted: &Retargeted,
+    ) -> Result<ApplyStats, LongtailError> {
+        // A second hasher instance for the opt-in chunk verification: the apply
+        // tasks are spawned, so it has to be shared rather than borrowed.
+        // Constructing one is trivial (a unit struct), so this is cheaper than
+        // reshaping the scan's hasher.
+        let verify_hasher: Option<Arc<dyn longtail_core::Hash + Send + Sync>> =
+            if self.opts.verify_chunks {
+                Some(Arc::from(make_hasher(self.source_version.hash_identifier)?))
+            } else {
+                None
+            };
 
         // Delete the cache index before mutating the target (cmd_downsync.go:274).
-        if cache_target_index {
-            fs_util::delete_local(&cache_index_path)?;
+        if self.cache_target_index {
+            fs_util::delete_local(&self.cache_index_path)?;
         }
+        // A journal from an earlier run describes a plan this one is about to
+        // overwrite, whether or not this run keeps one of its own. The
+        // fingerprint sidecar vouches for the tree as it is, so it goes under
+        // I1 like the cache does — and regardless of the option, since a stale
+        // one would be believed by the next run that turns it on.
+        fs_util::delete_local(&self.journal_path)?;
+        fs_util::delete_local(&self.fingerprint_path)?;
 
-        // Apply.
+        // The apply loop's block-task concurrency shares the store's resolved
+        // worker count (one knob — no separate apply setting).
+        let apply_concurrency = longtail_store::resolved_worker_count(
+            &self.opts.storage_uri,
+            self.opts.remote_worker_count,
+        );
         let apply_stats = change_version2(
-            &store,
-            &target_root,
-            &source_version,
-            &target_index,
-            &diff,
-            &store_index,
-            opts.retain_permissions,
-            opts.delete_removed,
+            store,
+            &self.target_root,
+            &self.source_version,
+            &self.target_index,
+            &retargeted.diff,
+            &retargeted.store_index,
+            self.opts.retain_permissions,
+            self.opts.delete_removed,
             verify_hasher,
             apply_concurrency,
-            &progress,
-            &cancel,
+            self.journal.as_ref(),
+            self.priority.as_ref(),
+            &self.progress,
+            &self.cancel,
+            self.opts.pause.as_ref(),
         )
         .await?;
-        phases.push(phase.lap("apply"));
-        Ok::<_, LongtailError>(apply_stats)
+        // Before validation and the cache write: the journal covers the apply
+        // only, and a validation failure is reported, not resumed.
+        fs_util::delete_local(&self.journal_path)?;
+        self.lap("apply");
+        Ok(apply_stats)
     }
-    .await;
 
-    // Flush + close the store chain before resolving (obligation #6; warm-cache
-    // write-backs must complete — cmd_downsync.go:324).
-    let apply_stats = crate::store_lifecycle::finish_store(&store, applied).await?;
-    let store_stats = store.stats();
-    phases.push(phase.lap("flush"));
-
-    // Optional post-downsync validation (cmd_downsync.go:380-456).
-    if opts.validate {
-        progress.phase("Validating version");
-        validate_target(
-            &target_root,
-            &filter,
-            hasher.as_ref(),
-            target_chunk_size,
-            &source_version,
-            opts.retain_permissions,
-            &pool,
-            &cancel,
-        )?;
-        phases.push(phase.lap("validate"));
+    /// Flush and close a store this run composed; only flush a caller-built
+    /// one, which outlives the run and is the caller's to close.
+    pub(crate) async fn finish_store<T>(
+        &self,
+        store: &Arc<dyn BlockStore>,
+        outcome: Result<T, LongtailError>,
+    ) -> Result<T, LongtailError> {
+        if self.opts.block_store.is_some() {
+            store_lifecycle::flush_store(store, outcome).await
+        } else {
+            store_lifecycle::finish_store(store, outcome).await
+        }
+    }
+
+    /// After the store is closed: the optional validation, the cache index
+    /// rewrite, and the report.
+    pub(crate) fn complete(
+        mut self,
+        apply_stats: ApplyStats,
+        store_stats: StatsSnapshot,
+    ) -> Result<DownsyncReport, LongtailError> {
+        self.lap("flush");
+        let store_stats = store_lifecycle::stats_since(self.stats_base, store_stats);
+
+        // Optional post-downsync validation (cmd_downsync.go:380-456).
+        if self.opts.validate {
+            self.progress.phase("Validating version");
+            validate_target(
+                &self.target_root,
+                &self.filter,
+                self.hasher.as_ref(),
+                self.source_version.target_chunk_size,
+                &self.source_version,
+                self.opts.retain_permissions,
+                &self.pool,
+                &self.cancel,
+            )?;
+            self.lap("validate");
+        }
+
+        // Cache the SOURCE version index for next time (cmd_downsync.go:458).
+        if self.cache_target_index {
+            fs_util::write_local(&self.cache_index_path, &self.source_version.to_bytes())?;
+        }
+        // Best-effort: without it the next scan hashes everything, as it would
+        // have with the option off.
+        if self.opts.fingerprint_cache
+            && let Err(e) = fingerprint::write(
+                &self.fingerprint_path,
+                &self.target_root,
+                &self.source_version,
+            )
+        {
+            let message =
+                "could not write the fingerprint cache; the next scan will hash the whole target";
+            tracing::warn!(path = %self.fingerprint_path.display(), error = %e, "{message}");
+            self.progress.warn(&format!("{message}: {e}"));
+        }
+
+        Ok(DownsyncReport {
+            target_path: self.target_string,
+            phases: self.phases,
+            store_stats: store_stats.into(),
+            bytes_written: apply_stats.bytes_written,
+            assets_written: apply_stats.assets_written,
+            assets_removed: apply_stats.assets_removed,
+            blocks_fetched: store_stats.get_count,
+        })
     }
 
-    // Cache the SOURCE version index for next time (cmd_downsync.go:458).
-    if cache_target_index {
-        fs_util::write_local(&cache_index_path, &source_version.to_bytes())?;
+    /// Record the time since the previous lap as phase `name`.
+    pub(crate) fn lap(&mut self, name: &str) {
+        let t = self.phase.lap(name);
+        self.phases.push(t);
     }
 
-    Ok(DownsyncReport {
-        target_path: target_string,
-        phases,
-        store_stats: store_stats.into(),
-        bytes_written: apply_stats.bytes_written,
-        assets_written: apply_stats.assets_written,
-        assets_removed: apply_stats.assets_removed,
-        blocks_fetched: store_stats.get_count,
-    })
+    /// Restart the phase clock without recording a phase — for a run resumed
+    /// after an arbitrary pause, whose wait is nobody's phase.
+    pub(crate) fn restart_clock(&mut self) {
+        self.phase = PhaseTimer::new(self.opts.pause.clone());
+    }
 }
 
-fn check_cancel(cancel: &CancellationToken) -> Result<(), LongtailError> {
+pub(crate) fn check_cancel(cancel: &CancellationToken) -> Result<(), LongtailError> {
     if cancel.is_cancelled() {
         Err(LongtailError::Cancelled)
     } else {
@@ -626,24 +894,38 @@ fn permissions_disagree(rescanned: u16, recorded: u16, windows: bool) -> bool {
     }
 }
 
-/// A simple sequential phase timer.
+/// A simple sequential phase timer, splitting each lap into working and
+/// paused time.
 struct PhaseTimer {
     last: Instant,
+    pause: Option<PauseToken>,
+    /// `pause.paused_for()` at `last`.
+    paused_at_last: Duration,
 }
 
 impl PhaseTimer {
-    fn new() -> PhaseTimer {
+    fn new(pause: Option<PauseToken>) -> PhaseTimer {
+        let paused_at_last = pause.as_ref().map_or(Duration::ZERO, |p| p.paused_for());
         PhaseTimer {
             last: Instant::now(),
+            pause,
+            paused_at_last,
         }
     }
     fn lap(&mut self, name: &str) -> PhaseTiming {
         let now = Instant::now();
-        let millis = now.duration_since(self.last).as_millis() as u64;
+        let paused = self
+            .pause
+            .as_ref()
+            .map_or(Duration::ZERO, |p| p.paused_for());
+        let wall = now.duration_since(self.last);
+        let idle = paused.saturating_sub(self.paused_at_last).min(wall);
         self.last = now;
+        self.paused_at_last = paused;
         PhaseTiming {
             phase: name.to_string(),
-            millis,
+            millis: (wall - idle).as_millis() as u64,
+            idle_millis: idle.as_millis() as u64,
         }
     }
 }
@@ -0,0 +1,217 @@
+//! `store-du`: what each version in a store costs.
+//!
+//! A version references the blocks holding its chunks — the same
+//! `GetExistingContent(chunks, 0)` resolution a downsync makes. A block only one
+//! of the listed versions references is that version's alone: pruning the
+//! version frees it. A block several reference is shared, and stays while any
+//! of them is kept. Sizes are block payload sizes from the store index, so they
+//! are decompressed bytes, not what the block files occupy.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use longtail_store::AccessType;
+use longtail_store::uri::{BlockStoreOpts, create_block_store_for_uri};
+use serde::{Deserialize, Serialize};
+
+use crate::diff::unique_blocks;
+use crate::error::LongtailError;
+#[cfg(feature = "s3")]
+use crate::fs_util::{S3OptionsArg, default_s3};
+use crate::inspect::read_version_index_from_uri;
+
+/// Options for [`store_du`].
+#[non_exhaustive]
+pub struct StoreDuOptions {
+    pub storage_uri: String,
+    /// The versions to account for. "Only this version" means only this one
+    /// of these: a version left off the list does not hold its blocks.
+    pub version_index_paths: Vec<String>,
+    pub remote_worker_count: usize,
+    #[cfg(feature = "s3")]
+    pub s3_options: S3OptionsArg,
+}
+
+impl StoreDuOptions {
+    pub fn new(storage_uri: impl Into<String>, version_index_paths: Vec<String>) -> Self {
+        StoreDuOptions {
+            storage_uri: storage_uri.into(),
+            version_index_paths,
+            remote_worker_count: 0,
+            #[cfg(feature = "s3")]
+            s3_options: default_s3(),
+        }
+    }
+}
+
+/// One version's share of the store.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct VersionStorage {
+    pub path: String,
+    /// Blocks holding at least one of its chunks.
+    pub block_count: u32,
+    pub bytes: u64,
+    /// Of those, the blocks no other listed version references: freed if
+    /// this version is pruned.
+    pub unique_block_count: u32,
+    pub unique_bytes: u64,
+    /// `bytes - unique_bytes`: kept while any other listed version is.
+    pub shared_bytes: u64,
+    /// Its chunks no block in the store holds. Non-zero means the version is
+    /// already broken.
+    pub missing_chunks: u32,
+}
+
+/// The blocks two versions both reference.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct VersionOverlap {
+    /// Index into [`StoreUsageReport::versions`].
+    pub first: usize,
+    /// Index into [`StoreUsageReport::versions`]; always above `first`.
+    pub second: usize,
+    pub block_count: u32,
+    pub bytes: u64,
+}
+
+/// The outcome of [`store_du`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct StoreUsageReport {
+    /// In the order given; a path given twice is listed once.
+    pub versions: Vec<VersionStorage>,
+    /// Every pair sharing at least one block, by `(first, second)`.
+    pub overlaps: Vec<VersionOverlap>,
+    /// Blocks any listed version references, each counted once.
+    pub block_count: u32,
+    pub bytes: u64,
+}
+
+/// `store-du`: attribute the store's blocks to the versions referencing them.
+pub async fn store_du(opts: StoreDuOptions) -> Result<StoreUsageReport, LongtailError> {
+    let s3 = crate::s3_arg!(opts);
+    let store_opts = BlockStoreOpts {
+        access_type: AccessType::ReadOnly,
+        worker_count: opts.remote_worker_count,
+        cache_dir: None,
+        pool: Arc::new(crate::version::build_pool(1)?),
+        version_local_store_index: None,
+        max_block_bytes: None,
+        events: None,
+        pause: None,
+        #[cfg(feature = "s3")]
+        s3_options: s3.clone(),
+    };
+    // A path listed twice would share every block with itself.
+    let mut seen = HashSet::new();
+    let paths: Vec<String> = opts
+        .version_index_paths
+        .iter()
+        .filter(|p| seen.insert(p.as_str()))
+        .cloned()
+        .collect();
+    let store = create_block_store_for_uri(&opts.storage_uri, store_opts).await?;
+    let referenced = async {
+        let mut out = Vec::with_capacity(paths.len());
+        for path in &paths {
+            let vi = read_version_index_from_uri(path, &s3).await?;
+            let si = store.get_existing_content(&vi.chunk_hashes, 0).await?;
+            let stored: HashSet<u64> = si.chunk_hashes.iter().copied().collect();
+            let missing = vi
+                .chunk_hashes
+                .iter()
+                .filter(|h| !stored.contains(h))
+                .count();
+            let blocks = unique_blocks(&si);
+            out.push((si.block_payload_sizes(&blocks), missing as u32));
+        }
+        Ok(out)
+    }
+    .await;
+    let referenced = crate::store_lifecycle::finish_store(&store, referenced).await?;
+    Ok(account(&paths, &referenced))
+}
+
+/// The report for versions `paths` referencing `referenced[i].0` (block hash to
+/// payload size) with `referenced[i].1` chunks missing.
+fn account(paths: &[String], referenced: &[(HashMap<u64, u64>, u32)]) -> StoreUsageReport {
+    let mut users: HashMap<u64, (u32, u64)> = HashMap::new();
+    for (blocks, _) in referenced {
+        for (&h, &size) in blocks {
+            users.entry(h).or_insert((0, size)).0 += 1;
+        }
+    }
+    let mut report = StoreUsageReport {
+        block_count: users.len() as u32,
+        bytes: users.values().map(|&(_, size)| size).sum(),
+        ..StoreUsageReport::default()
+    };
+    for (path, (blocks, missing)) in paths.iter().zip(referenced) {
+        let mut v = VersionStorage {
+            path: path.clone(),
+            block_count: blocks.len() as u32,
+            bytes: blocks.values().sum(),
+            missing_chunks: *missing,
+            ..VersionStorage::default()
+        };
+        for (h, &size) in blocks {
+            if users[h].0 == 1 {
+                v.unique_block_count += 1;
+                v.unique_bytes += size;
+            }
+        }
+        v.shared_bytes = v.bytes - v.unique_bytes;
+        report.versions.push(v);
+    }
+    for first in 0..referenced.len() {
+        for second in first + 1..referenced.len() {
+            let (a, b) = (&referenced[first].0, &referenced[second].0);
+            let (small, large) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+            let mut overlap = VersionOverlap {
+                first,
+                second,
+                ..VersionOverlap::default()
+            };
+            for (h, &size) in small {
+                if large.contains_key(h) {
+                    overlap.block_count += 1;
+                    overlap.bytes += size;
+                }
+            }
+            if overlap.block_count > 0 {
+                report.overlaps.push(overlap);
+            }
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::account;
+
+    #[test]
+    fn unique_shared_and_pairwise_bytes() {
+        let blocks = |list: &[(u64, u64)]| list.iter().copied().collect::<HashMap<_, _>>();
+        let paths = ["a".to_string(), "b".to_string(), "c".to_string()];
+        let report = account(
+            &paths,
+            &[
+                (blocks(&[(1, 10), (2, 20)]), 0),
+                (blocks(&[(2, 20), (3, 30)]), 0),
+                (blocks(&[(4, 40)]), 2),
+            ],
+        );
+        assert_eq!((report.block_count, report.bytes), (4, 100));
+        let v = &report.versions;
+        assert_eq!((v[0].unique_bytes, v[0].shared_bytes), (10, 20));
+        assert_eq!((v[1].unique_bytes, v[1].shared_bytes), (30, 20));
+        assert_eq!((v[2].unique_block_count, v[2].missing_chunks), (1, 2));
+        assert_eq!(report.overlaps.len(), 1);
+        let o = &report.overlaps[0];
+        assert_eq!((o.first, o.second, o.block_count, o.bytes), (0, 1, 1, 20));
+    }
+}
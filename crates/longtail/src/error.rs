@@ -8,6 +8,7 @@ use longtail_core::{
     ChunkerError, CompressError, FormatError, HashError, MergeVersionError, ValidateError,
 };
 use longtail_store::StoreError;
+use serde::{Deserialize, Serialize};
 
 /// The unified error surfaced by the download-path facade API.
 ///
@@ -43,15 +44,21 @@ pub enum LongtailError {
     #[error("store does not cover version")]
     Validate(#[from] ValidateError),
 
-    /// A block/blob store error.
+    /// A block/blob store error. A store read aborted by cancellation arrives
+    /// as [`LongtailError::Cancelled`] instead.
     #[error("store error")]
-    Store(#[from] StoreError),
+    Store(#[source] StoreError),
 
     /// The `--validate` post-downsync target rescan disagreed with the source
     /// version index.
     #[error("downsync validation failed: {0}")]
     ValidationMismatch(String),
 
+    /// [`crate::execute_plan`] found the target no longer matches the state the
+    /// plan was computed against. Nothing was written; plan again.
+    #[error("target changed since the plan was made: {0}")]
+    TargetChanged(String),
+
     /// The operation was cancelled via the caller's `CancellationToken`. The
     /// target is left resumable (a follow-up downsync completes and matches).
     #[error("operation cancelled")]
@@ -97,6 +104,15 @@ pub enum LongtailError {
     Internal(String),
 }
 
+impl From<StoreError> for LongtailError {
+    fn from(e: StoreError) -> LongtailError {
+        match e {
+            StoreError::Cancelled => LongtailError::Cancelled,
+            e => LongtailError::Store(e),
+        }
+    }
+}
+
 /// What a caller should *do* about a [`LongtailError`], as opposed to what it
 /// says.
 ///
@@ -108,7 +124,8 @@ pub enum LongtailError {
 /// `#[non_exhaustive]`: new classes are expected as the error tree grows, so
 /// callers must carry a fallback arm.
 #[non_exhaustive]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ErrorClass {
     /// The caller asked to stop. Not a failure; nothing to report or retry.
     Cancelled,
@@ -160,7 +177,8 @@ impl LongtailError {
             | LongtailError::InvalidGetConfig(_)
             | LongtailError::InvalidArgument(_)
             | LongtailError::UnsupportedUri { .. }
-            | LongtailError::UnsafeAssetPath { .. } => ErrorClass::InvalidInput,
+            | LongtailError::UnsafeAssetPath { .. }
+            | LongtailError::TargetChanged(_) => ErrorClass::InvalidInput,
 
             LongtailError::Io { .. } => ErrorClass::Io,
             LongtailError::Internal(_) => ErrorClass::Internal,
@@ -179,6 +197,7 @@ impl LongtailError {
                 | StoreError::AccessViolation => ErrorClass::InvalidInput,
                 StoreError::Io { .. } => ErrorClass::Io,
                 StoreError::WorkerGone => ErrorClass::Internal,
+                StoreError::Cancelled => ErrorClass::Cancelled,
                 // Unclassified by the backend; it carries a message but no
                 // decision. Treated as a bug rather than silently "retryable".
                 StoreError::Backend(_) => ErrorClass::Internal,
@@ -234,6 +253,8 @@ mod tests {
     fn classes_map_to_the_response_they_call_for() {
         let cases: &[(LongtailError, ErrorClass)] = &[
             (LongtailError::Cancelled, ErrorClass::Cancelled),
+            // An aborted block read is the caller's cancellation, not a store fault.
+            (StoreError::Cancelled.into(), ErrorClass::Cancelled),
             (
                 LongtailError::Store(StoreError::NotFound("v.lvi".into())),
                 ErrorClass::NotFound,
@@ -273,6 +294,10 @@ mod tests {
                 },
                 ErrorClass::InvalidInput,
             ),
+            (
+                LongtailError::TargetChanged("asset added".into()),
+                ErrorClass::InvalidInput,
+            ),
             (
                 LongtailError::ValidationMismatch("tree differs".into()),
                 ErrorClass::Corrupt,
@@ -0,0 +1,432 @@
+//! The pull-based alternative to a [`ProgressSink`]: run a downsync or upsync on
+//! a spawned task and read what it reports from an [`EventStream`], so a GUI
+//! can `select!` over progress, phase changes and completion.
+//!
+//! The stream is bounded, and the run never waits for the consumer. A consumer
+//! that falls behind loses detail, not correctness: progress samples coalesce
+//! into the latest, which is always delivered, and per-asset and per-block
+//! events beyond [`EVENT_STREAM_CAPACITY`] are counted into one
+//! [`StreamEvent::Lagged`]. Phases, ready assets, failures and warnings queue
+//! on past that bound, to twice it; beyond that they are counted too, except
+//! the latest phase, which is kept. The final word is the task's
+//! [`JoinHandle`], not the stream.
+
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+use futures_core::Stream;
+use longtail_store::BlockSource;
+use tokio::task::JoinHandle;
+
+use crate::error::LongtailError;
+use crate::options::{DownsyncOptions, DownsyncReport, UpsyncOptions, UpsyncReport};
+use crate::progress::{Event, Progress, ProgressSink};
+
+/// How many droppable events an [`EventStream`] holds before it starts
+/// counting them into [`StreamEvent::Lagged`] instead. Other events are
+/// counted from twice this.
+pub const EVENT_STREAM_CAPACITY: usize = 1024;
+
+/// An owned copy of what a [`ProgressSink`] is told, one per callback.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StreamEvent {
+    /// [`ProgressSink::on_phase`].
+    Phase { phase: String },
+    /// [`ProgressSink::on_progress`]; consecutive samples coalesce.
+    Progress(Progress),
+    /// [`ProgressSink::on_asset_ready`].
+    AssetReady { path: String },
+    /// [`Event::AssetStarted`].
+    AssetStarted { path: String },
+    /// [`Event::AssetCompleted`].
+    AssetCompleted { path: String, bytes: u64 },
+    /// [`Event::AssetFailed`], with the error rendered.
+    AssetFailed { path: String, error: String },
+    /// [`Event::AssetDeleted`].
+    AssetDeleted { path: String },
+    /// [`Event::AssetHashed`].
+    AssetHashed { path: String, bytes: u64 },
+    /// [`Event::BlockFetched`].
+    BlockFetched {
+        block_hash: u64,
+        source: BlockSource,
+        wire_bytes: u64,
+    },
+    /// [`Event::Retry`].
+    Retry { block_hash: u64, attempt: u32 },
+    /// [`Event::Warning`].
+    Warning { message: String },
+    /// The consumer fell behind and `skipped` events were dropped here.
+    Lagged { skipped: u64 },
+}
+
+impl StreamEvent {
+    fn from_event(event: &Event<'_>) -> StreamEvent {
+        match *event {
+            Event::AssetStarted { path } => StreamEvent::AssetStarted { path: path.into() },
+            Event::AssetCompleted { path, bytes } => StreamEvent::AssetCompleted {
+                path: path.into(),
+                bytes,
+            },
+            Event::AssetFailed { path, error } => StreamEvent::AssetFailed {
+                path: path.into(),
+                error: error.to_string(),
+            },
+            Event::AssetDeleted { path } => StreamEvent::AssetDeleted { path: path.into() },
+            Event::AssetHashed { path, bytes } => StreamEvent::AssetHashed {
+                path: path.into(),
+                bytes,
+            },
+            Event::BlockFetched {
+                block_hash,
+                source,
+                wire_bytes,
+            } => StreamEvent::BlockFetched {
+                block_hash,
+                source,
+                wire_bytes,
+            },
+            Event::Retry {
+                block_hash,
+                attempt,
+            } => StreamEvent::Retry {
+                block_hash,
+                attempt,
+            },
+            Event::Warning { message } => StreamEvent::Warning {
+                message: message.into(),
+            },
+        }
+    }
+
+    /// Whether a lagging consumer may lose this event: the fine-grained ones,
+    /// of which a run has one per asset or block.
+    fn droppable(&self) -> bool {
+        matches!(
+            self,
+            StreamEvent::Progress(_)
+                | StreamEvent::AssetStarted { .. }
+                | StreamEvent::AssetCompleted { .. }
+                | StreamEvent::AssetDeleted { .. }
+                | StreamEvent::AssetHashed { .. }
+                | StreamEvent::BlockFetched { .. }
+                | StreamEvent::Retry { .. }
+        )
+    }
+}
+
+#[derive(Default)]
+struct State {
+    queue: VecDeque<StreamEvent>,
+    /// The latest progress sample not yet queued. Outside the bound: it is
+    /// overwritten by the next sample but never dropped, and it enters the
+    /// queue ahead of whatever event comes after it.
+    progress: Option<Progress>,
+    /// Likewise the latest phase that arrived past the hard bound, queued
+    /// ahead of `progress`.
+    phase: Option<String>,
+    closed: bool,
+    waker: Option<Waker>,
+}
+
+/// The queue between the run (through [`ChannelSink`]) and the [`EventStream`].
+struct Channel {
+    state: Mutex<State>,
+    capacity: usize,
+}
+
+impl Channel {
+    fn new(capacity: usize) -> Arc<Channel> {
+        Arc::new(Channel {
+            state: Mutex::new(State::default()),
+            capacity,
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn push(&self, event: StreamEvent) {
+        let waker = {
+            let mut state = self.lock();
+            let held = state.queue.len()
+                + usize::from(state.progress.is_some())
+                + usize::from(state.phase.is_some());
+            let lags = |e: &StreamEvent| {
+                let bound = if e.droppable() {
+                    self.capacity
+                } else {
+                    2 * self.capacity
+                };
+                held >= bound
+            };
+            match (state.queue.back_mut(), event) {
+                (_, StreamEvent::Progress(p)) => state.progress = Some(p),
+                (_, StreamEvent::Phase { phase }) if held >= 2 * self.capacity => {
+                    state.phase = Some(phase)
+                }
+                (Some(StreamEvent::Lagged { skipped }), e) if lags(&e) => *skipped += 1,
+                (_, e) => {
+                    let e = if lags(&e) {
+                        StreamEvent::Lagged { skipped: 1 }
+                    } else {
+                        e
+                    };
+                    if let Some(phase) = state.phase.take() {
+                        state.queue.push_back(StreamEvent::Phase { phase });
+                    }
+                    if let Some(p) = state.progress.take() {
+                        state.queue.push_back(StreamEvent::Progress(p));
+                    }
+                    state.queue.push_back(e);
+                }
+            }
+            state.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    /// No more events: the stream ends once the queue drains.
+    fn close(&self) {
+        let waker = {
+            let mut state = self.lock();
+            state.closed = true;
+            state.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+/// The run's [`ProgressSink`]: every callback becomes a [`StreamEvent`].
+struct ChannelSink(Arc<Channel>);
+
+impl ProgressSink for ChannelSink {
+    fn on_progress(&self, p: Progress) {
+        self.0.push(StreamEvent::Progress(p));
+    }
+    fn on_phase(&self, phase: &str) {
+        self.0.push(StreamEvent::Phase {
+            phase: phase.into(),
+        });
+    }
+    fn on_asset_ready(&self, path: &str) {
+        self.0.push(StreamEvent::AssetReady { path: path.into() });
+    }
+    fn on_event(&self, event: &Event<'_>) {
+        self.0.push(StreamEvent::from_event(event));
+    }
+}
+
+struct CloseOnDrop(Arc<Channel>);
+
+impl Drop for CloseOnDrop {
+    fn drop(&mut self) {
+        self.0.close();
+    }
+}
+
+/// What a run started by [`downsync_with_events`] / [`upsync_with_events`]
+/// reports, in order. Ends after the run does. Usable as a
+/// [`Stream`](futures_core::Stream) or through [`recv`](Self::recv).
+pub struct EventStream {
+    channel: Arc<Channel>,
+}
+
+impl EventStream {
+    /// The next event, or `None` once the run has finished and every event
+    /// has been read.
+    pub async fn recv(&mut self) -> Option<StreamEvent> {
+        std::future::poll_fn(|cx| self.poll_recv(cx)).await
+    }
+
+    fn poll_recv(&self, cx: &mut Context<'_>) -> Poll<Option<StreamEvent>> {
+        let mut state = self.channel.lock();
+        if let Some(event) = state.queue.pop_front() {
+            return Poll::Ready(Some(event));
+        }
+        if let Some(phase) = state.phase.take() {
+            return Poll::Ready(Some(StreamEvent::Phase { phase }));
+        }
+        if let Some(p) = state.progress.take() {
+            return Poll::Ready(Some(StreamEvent::Progress(p)));
+        }
+        if state.closed {
+            return Poll::Ready(None);
+        }
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Stream for EventStream {
+    type Item = StreamEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<StreamEvent>> {
+        self.poll_recv(cx)
+    }
+}
+
+/// Spawn `run` with a sink feeding a fresh stream, closing the stream when the
+/// run returns.
+fn spawn_with_events<T, F>(
+    run: impl FnOnce(Arc<dyn ProgressSink>) -> F,
+) -> (EventStream, JoinHandle<Result<T, LongtailError>>)
+where
+    T: Send + 'static,
+    F: Future<Output = Result<T, LongtailError>> + Send + 'static,
+{
+    let channel = Channel::new(EVENT_STREAM_CAPACITY);
+    let fut = run(Arc::new(ChannelSink(channel.clone())));
+    // Closed on drop, so a run that panics still ends the stream.
+    let closer = CloseOnDrop(channel.clone());
+    let handle = tokio::spawn(async move {
+        let _closer = closer;
+        fut.await
+    });
+    (EventStream { channel }, handle)
+}
+
+/// [`crate::downsync`] on a spawned task, reporting through a stream instead
+/// of a sink: `opts.progress` is replaced. Must be called from within a tokio
+/// runtime, like [`tokio::spawn`].
+pub fn downsync_with_events(
+    mut opts: DownsyncOptions,
+) -> (
+    EventStream,
+    JoinHandle<Result<DownsyncReport, LongtailError>>,
+) {
+    spawn_with_events(|sink| {
+        opts.progress = Some(sink);
+        crate::downsync::downsync(opts)
+    })
+}
+
+/// [`crate::upsync`] on a spawned task; see [`downsync_with_events`].
+pub fn upsync_with_events(
+    mut opts: UpsyncOptions,
+) -> (EventStream, JoinHandle<Result<UpsyncReport, LongtailError>>) {
+    spawn_with_events(|sink| {
+        opts.progress = Some(sink);
+        crate::upsync::upsync(opts)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// What a consumer would read now, in order.
+    fn drain(channel: &Channel) -> Vec<StreamEvent> {
+        let mut state = channel.lock();
+        let mut events: Vec<StreamEvent> = state.queue.drain(..).collect();
+        events.extend(state.phase.take().map(|phase| StreamEvent::Phase { phase }));
+        events.extend(state.progress.take().map(StreamEvent::Progress));
+        events
+    }
+
+    #[test]
+    fn a_lagging_consumer_loses_detail_but_not_phases_or_failures() {
+        let channel = Channel::new(2);
+        let sink = ChannelSink(channel.clone());
+        sink.on_phase("Writing");
+        for done_items in 1..=3 {
+            sink.on_progress(Progress {
+                done_items,
+                ..Progress::default()
+            });
+        }
+        for path in ["a", "b", "c", "d"] {
+            sink.on_event(&Event::AssetHashed { path, bytes: 1 });
+        }
+        sink.on_event(&Event::Warning { message: "slow" });
+        sink.on_event(&Event::AssetHashed {
+            path: "e",
+            bytes: 1,
+        });
+
+        assert_eq!(
+            drain(&channel),
+            [
+                StreamEvent::Phase {
+                    phase: "Writing".into()
+                },
+                StreamEvent::Progress(Progress {
+                    done_items: 3,
+                    ..Progress::default()
+                }),
+                StreamEvent::Lagged { skipped: 4 },
+                StreamEvent::Warning {
+                    message: "slow".into()
+                },
+                StreamEvent::Lagged { skipped: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn the_latest_progress_sample_outlasts_a_full_queue() {
+        let channel = Channel::new(1);
+        let sink = ChannelSink(channel.clone());
+        for path in ["a", "b", "c"] {
+            sink.on_event(&Event::AssetHashed { path, bytes: 1 });
+        }
+        for done_items in [50, 100] {
+            sink.on_progress(Progress {
+                done_items,
+                ..Progress::default()
+            });
+        }
+        channel.close();
+
+        assert_eq!(
+            drain(&channel),
+            [
+                StreamEvent::AssetHashed {
+                    path: "a".into(),
+                    bytes: 1
+                },
+                StreamEvent::Lagged { skipped: 2 },
+                StreamEvent::Progress(Progress {
+                    done_items: 100,
+                    ..Progress::default()
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn phases_and_warnings_are_bounded_too() {
+        let channel = Channel::new(1);
+        let sink = ChannelSink(channel.clone());
+        for message in ["w1", "w2", "w3"] {
+            sink.on_event(&Event::Warning { message });
+        }
+        sink.on_phase("Reading");
+        sink.on_phase("Writing");
+        sink.on_event(&Event::Warning { message: "w4" });
+
+        assert_eq!(
+            drain(&channel),
+            [
+                StreamEvent::Warning {
+                    message: "w1".into()
+                },
+                StreamEvent::Warning {
+                    message: "w2".into()
+                },
+                StreamEvent::Lagged { skipped: 2 },
+                StreamEvent::Phase {
+                    phase: "Writing".into()
+                },
+            ]
+        );
+    }
+}
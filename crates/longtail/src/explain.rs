@@ -0,0 +1,402 @@
+//! `explain-update`: where an update's download bytes go.
+//!
+//! An update fetches whole blocks. Each block holds some chunks the update
+//! needs — chunks of assets it adds or rewrites — and usually some it does
+//! not, which ride along because the upsync packed them into the same block.
+//! This attributes every fetched block to the assets whose chunks caused the
+//! fetch, and keeps the ride-along bytes apart, so a large update can be traced
+//! to the files that changed or to how the store packed them.
+//!
+//! A chunk several changed assets share is split evenly between them, and a
+//! block's unneeded bytes and compressed size are split in proportion to what
+//! each asset needed from it, so the per-asset numbers add up to the totals.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use longtail_core::{StoreIndex, VersionIndex, create_version_diff, get_required_chunk_hashes};
+use serde::{Deserialize, Serialize};
+
+use crate::diff::{required_blocks, stored_block_sizes, unique_blocks};
+use crate::error::LongtailError;
+#[cfg(feature = "s3")]
+use crate::fs_util::{S3OptionsArg, default_s3};
+use crate::inspect::read_version_index_from_uri;
+
+/// Options for [`explain_update`].
+#[non_exhaustive]
+pub struct ExplainUpdateOptions {
+    /// The installed version's index URI.
+    pub from_path: String,
+    /// The version being updated to.
+    pub to_path: String,
+    /// The store holding `to`'s blocks.
+    pub storage_uri: String,
+    pub remote_worker_count: usize,
+    #[cfg(feature = "s3")]
+    pub s3_options: S3OptionsArg,
+}
+
+impl ExplainUpdateOptions {
+    pub fn new(
+        from_path: impl Into<String>,
+        to_path: impl Into<String>,
+        storage_uri: impl Into<String>,
+    ) -> Self {
+        ExplainUpdateOptions {
+            from_path: from_path.into(),
+            to_path: to_path.into(),
+            storage_uri: storage_uri.into(),
+            remote_worker_count: 0,
+            #[cfg(feature = "s3")]
+            s3_options: default_s3(),
+        }
+    }
+}
+
+/// What fetching some blocks costs, and why. Payload bytes are decompressed;
+/// `compressed_bytes` is what crosses the wire.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct UpdateCost {
+    /// Payload bytes of chunks the update needs.
+    pub needed_bytes: u64,
+    /// Payload bytes fetched only because they share a block with needed
+    /// chunks.
+    pub unneeded_bytes: u64,
+    /// The stored size of the blocks, or the share of it attributed here.
+    pub compressed_bytes: u64,
+}
+
+impl UpdateCost {
+    fn add(&mut self, other: &UpdateCost) {
+        self.needed_bytes += other.needed_bytes;
+        self.unneeded_bytes += other.unneeded_bytes;
+        self.compressed_bytes += other.compressed_bytes;
+    }
+}
+
+/// One added or rewritten asset's share of the download.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct AssetUpdateCost {
+    pub path: String,
+    /// The asset's size in the new version.
+    pub size: u64,
+    /// Blocks it needed chunks from.
+    pub blocks: u32,
+    #[serde(flatten)]
+    pub cost: UpdateCost,
+}
+
+/// The summed cost of the assets under one directory, at any depth.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DirectoryUpdateCost {
+    /// Ends in `/`.
+    pub path: String,
+    pub assets: u32,
+    #[serde(flatten)]
+    pub cost: UpdateCost,
+}
+
+/// One fetched block: how much of it the update uses, and for what.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct BlockUpdateCost {
+    pub block_hash: u64,
+    pub chunk_count: u32,
+    pub needed_chunk_count: u32,
+    #[serde(flatten)]
+    pub cost: UpdateCost,
+    /// The assets that needed it, by path.
+    pub assets: Vec<String>,
+}
+
+impl BlockUpdateCost {
+    /// Percent of the block's payload the update uses.
+    pub fn usage_percent(&self) -> u32 {
+        let payload = self.cost.needed_bytes + self.cost.unneeded_bytes;
+        (100 * self.cost.needed_bytes)
+            .checked_div(payload)
+            .map_or(100, |p| p as u32)
+    }
+}
+
+/// The outcome of [`explain_update`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct UpdateExplanation {
+    /// Blocks the update fetches.
+    pub block_count: u32,
+    /// The whole download.
+    #[serde(flatten)]
+    pub total: UpdateCost,
+    /// Needed chunks no block in the store holds; non-zero means the update
+    /// would fail.
+    pub missing_chunks: u32,
+    /// Most expensive first, by compressed bytes.
+    pub assets: Vec<AssetUpdateCost>,
+    /// Most expensive first, by compressed bytes.
+    pub directories: Vec<DirectoryUpdateCost>,
+    /// Most unneeded bytes first: the packing that costs the most.
+    pub blocks: Vec<BlockUpdateCost>,
+}
+
+/// `explain-update`: attribute the blocks an update from `from` to `to`
+/// fetches to the assets that need them.
+pub async fn explain_update(
+    opts: ExplainUpdateOptions,
+) -> Result<UpdateExplanation, LongtailError> {
+    let s3 = crate::s3_arg!(opts);
+    let from = read_version_index_from_uri(&opts.from_path, &s3).await?;
+    let to = read_version_index_from_uri(&opts.to_path, &s3).await?;
+    if from.hash_identifier != to.hash_identifier {
+        return Err(LongtailError::InvalidArgument(
+            "the versions are hashed differently; an update between them fetches everything".into(),
+        ));
+    }
+    let diff = create_version_diff(&from, &to);
+    let required = get_required_chunk_hashes(&to, &diff);
+    let existing =
+        required_blocks(&opts.storage_uri, &required, opts.remote_worker_count, &s3).await?;
+    let compressed = stored_block_sizes(&opts.storage_uri, &unique_blocks(&existing), &s3).await?;
+
+    // Each needed chunk's users among the assets the update writes.
+    let changed: Vec<u32> = diff
+        .target_added_asset_indexes
+        .iter()
+        .chain(&diff.target_content_modified_asset_indexes)
+        .copied()
+        .collect();
+    let mut users: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (slot, &a) in changed.iter().enumerate() {
+        for h in asset_chunks(&to, a as usize) {
+            users.entry(h).or_default().push(slot);
+        }
+    }
+
+    let mut out = UpdateExplanation::default();
+    let attributed = attribute(&existing, &compressed, &users, changed.len());
+    for (mut block, slots) in attributed.blocks {
+        for slot in slots {
+            block
+                .assets
+                .push(to.path(changed[slot] as usize)?.to_string());
+        }
+        block.assets.sort();
+        out.block_count += 1;
+        out.total.add(&block.cost);
+        out.blocks.push(block);
+    }
+    out.missing_chunks = required
+        .iter()
+        .filter(|h| !attributed.found.contains(h))
+        .count() as u32;
+
+    let mut directories: BTreeMap<String, DirectoryUpdateCost> = BTreeMap::new();
+    for (slot, (cost, blocks)) in attributed.per_asset.into_iter().enumerate() {
+        if blocks == 0 {
+            continue;
+        }
+        let a = changed[slot] as usize;
+        let path = to.path(a)?.to_string();
+        for (i, _) in path.match_indices('/') {
+            let dir = directories
+                .entry(path[..=i].to_string())
+                .or_insert_with(|| DirectoryUpdateCost {
+                    path: path[..=i].to_string(),
+                    assets: 0,
+                    cost: UpdateCost::default(),
+                });
+            dir.assets += 1;
+            dir.cost.add(&cost);
+        }
+        out.assets.push(AssetUpdateCost {
+            path,
+            size: to.asset_sizes[a],
+            blocks,
+            cost,
+        });
+    }
+    out.directories = directories.into_values().collect();
+
+    let key = |c: &UpdateCost| (c.compressed_bytes, c.needed_bytes + c.unneeded_bytes);
+    out.assets
+        .sort_by(|x, y| key(&y.cost).cmp(&key(&x.cost)).then(x.path.cmp(&y.path)));
+    out.directories
+        .sort_by(|x, y| key(&y.cost).cmp(&key(&x.cost)).then(x.path.cmp(&y.path)));
+    out.blocks.sort_by(|x, y| {
+        (y.cost.unneeded_bytes, y.cost.compressed_bytes)
+            .cmp(&(x.cost.unneeded_bytes, x.cost.compressed_bytes))
+            .then(x.block_hash.cmp(&y.block_hash))
+    });
+    Ok(out)
+}
+
+/// The fetched blocks of an update, and who they are fetched for.
+struct Attribution {
+    /// Each fetched block, with the slots of the assets that need it.
+    blocks: Vec<(BlockUpdateCost, Vec<usize>)>,
+    /// Each slot's share of the blocks, and how many blocks it needed.
+    per_asset: Vec<(UpdateCost, u32)>,
+    /// The needed chunks some block holds.
+    found: HashSet<u64>,
+}
+
+/// Attribute the blocks of `existing` to the `slots` changed assets, given
+/// each needed chunk's `users` and each block's `compressed` size.
+fn attribute(
+    existing: &StoreIndex,
+    compressed: &HashMap<u64, u64>,
+    users: &HashMap<u64, Vec<usize>>,
+    slots: usize,
+) -> Attribution {
+    let mut out = Attribution {
+        blocks: Vec::new(),
+        per_asset: vec![(UpdateCost::default(), 0); slots],
+        found: HashSet::new(),
+    };
+    let mut seen_blocks: HashSet<u64> = HashSet::new();
+    for b in 0..existing.block_hashes.len() {
+        let block_hash = existing.block_hashes[b];
+        if !seen_blocks.insert(block_hash) {
+            continue;
+        }
+        let start = existing.block_chunks_offsets[b] as usize;
+        let count = existing.block_chunk_counts[b] as usize;
+        let mut block = BlockUpdateCost {
+            block_hash,
+            chunk_count: count as u32,
+            needed_chunk_count: 0,
+            cost: UpdateCost {
+                compressed_bytes: compressed.get(&block_hash).copied().unwrap_or(0),
+                ..UpdateCost::default()
+            },
+            assets: Vec::new(),
+        };
+        // What each asset needs from this block, by slot.
+        let mut needs: BTreeMap<usize, u64> = BTreeMap::new();
+        for k in start..start + count {
+            let (h, size) = (existing.chunk_hashes[k], existing.chunk_sizes[k] as u64);
+            // A chunk stored twice is needed from the first block only.
+            match users.get(&h) {
+                Some(slots) if out.found.insert(h) => {
+                    block.needed_chunk_count += 1;
+                    block.cost.needed_bytes += size;
+                    let weights = vec![1; slots.len()];
+                    for (slot, share) in slots.iter().zip(split(size, &weights)) {
+                        *needs.entry(*slot).or_default() += share;
+                    }
+                }
+                _ => block.cost.unneeded_bytes += size,
+            }
+        }
+        if needs.is_empty() {
+            // Every chunk the update needs from it comes from an earlier
+            // block, so the update does not fetch it.
+            continue;
+        }
+        let weights: Vec<u64> = needs.values().copied().collect();
+        let unneeded = split(block.cost.unneeded_bytes, &weights);
+        let wire = split(block.cost.compressed_bytes, &weights);
+        for (i, (&slot, &needed)) in needs.iter().enumerate() {
+            let (cost, blocks) = &mut out.per_asset[slot];
+            cost.add(&UpdateCost {
+                needed_bytes: needed,
+                unneeded_bytes: unneeded[i],
+                compressed_bytes: wire[i],
+            });
+            *blocks += 1;
+        }
+        out.blocks.push((block, needs.into_keys().collect()));
+    }
+    out
+}
+
+/// The distinct chunk hashes of asset `a`.
+fn asset_chunks(vi: &VersionIndex, a: usize) -> HashSet<u64> {
+    let start = vi.asset_chunk_index_starts[a] as usize;
+    let count = vi.asset_chunk_counts[a] as usize;
+    vi.asset_chunk_indexes[start..start + count]
+        .iter()
+        .map(|&c| vi.chunk_hashes[c as usize])
+        .collect()
+}
+
+/// `total` split in proportion to `weights`, summing to exactly `total`: the
+/// rounding remainder goes to the heaviest weight. All zero if every weight is.
+fn split(total: u64, weights: &[u64]) -> Vec<u64> {
+    let sum: u64 = weights.iter().sum();
+    if sum == 0 {
+        return vec![0; weights.len()];
+    }
+    let mut out: Vec<u64> = weights
+        .iter()
+        .map(|&w| (total as u128 * w as u128 / sum as u128) as u64)
+        .collect();
+    let rest = total - out.iter().sum::<u64>();
+    if let Some(heaviest) = (0..weights.len()).max_by_key(|&i| (weights[i], std::cmp::Reverse(i))) {
+        out[heaviest] += rest;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use longtail_core::StoreIndex;
+
+    use super::{UpdateCost, attribute, split};
+
+    #[test]
+    fn split_is_proportional_and_exact() {
+        assert_eq!(split(100, &[1, 1, 2]), [25, 25, 50]);
+        assert_eq!(split(10, &[1, 1, 1]), [4, 3, 3]);
+        assert_eq!(split(7, &[0, 3]), [0, 7]);
+        assert_eq!(split(5, &[0, 0]), [0, 0]);
+        assert_eq!(split(0, &[]), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn a_block_holding_only_chunks_found_earlier_is_not_fetched() {
+        // Chunk 10 is stored in both blocks; the second adds only chunk 12,
+        // which nothing needs.
+        let existing = StoreIndex {
+            block_hashes: vec![1, 2],
+            chunk_hashes: vec![10, 11, 10, 12],
+            block_chunks_offsets: vec![0, 2],
+            block_chunk_counts: vec![2, 2],
+            block_tags: vec![0, 0],
+            chunk_sizes: vec![100, 50, 100, 30],
+            ..StoreIndex::empty(0)
+        };
+        let compressed = HashMap::from([(1, 120), (2, 90)]);
+        let users = HashMap::from([(10, vec![0, 1])]);
+        let attributed = attribute(&existing, &compressed, &users, 2);
+
+        let blocks: Vec<u64> = attributed
+            .blocks
+            .iter()
+            .map(|(b, _)| b.block_hash)
+            .collect();
+        assert_eq!(blocks, [1]);
+        let mut total = UpdateCost::default();
+        for (block, _) in &attributed.blocks {
+            total.add(&block.cost);
+        }
+        let mut shared = UpdateCost::default();
+        for (cost, _) in &attributed.per_asset {
+            shared.add(cost);
+        }
+        assert_eq!(shared, total);
+        assert_eq!(
+            total,
+            UpdateCost {
+                needed_bytes: 100,
+                unneeded_bytes: 50,
+                compressed_bytes: 120,
+            }
+        );
+    }
+}
@@ -0,0 +1,235 @@
+//! The fingerprint sidecar: per-asset `(size, mtime, inode, ctime)` beside the
+//! chunk list a successful apply left on disk, so a later target scan re-hashes
+//! only the files whose fingerprint moved.
+//!
+//! It answers the question the cached target index cannot — "has anything been
+//! touched since?" — at a stat per file rather than a hash. It lives under the
+//! same rules as that cache (`docs/rust-port.md` §Resume invariants, I1):
+//! deleted before the target is mutated, written only after an apply succeeds.
+//! A torn file from an interrupted run therefore never has a fingerprint to
+//! match, and anything that writes a file afterwards moves its ctime.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use longtail_core::VersionIndex;
+use serde::{Deserialize, Serialize};
+
+use crate::fs_util;
+
+/// Bumped on any change to the file layout; a sidecar of another format is
+/// ignored.
+const FORMAT: u32 = 1;
+
+/// What the filesystem says about a file without reading it. Any write moves
+/// `ctime`; a replacement moves `inode`; `mtime` and `size` catch the rest,
+/// and everything on a platform without the first two.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Fingerprint {
+    pub size: u64,
+    pub mtime_ns: i64,
+    pub inode: u64,
+    pub ctime_ns: i64,
+}
+
+impl Fingerprint {
+    #[cfg(unix)]
+    pub fn of(meta: &fs::Metadata) -> Fingerprint {
+        use std::os::unix::fs::MetadataExt;
+        Fingerprint {
+            size: meta.len(),
+            mtime_ns: meta.mtime() * 1_000_000_000 + meta.mtime_nsec(),
+            inode: meta.ino(),
+            ctime_ns: meta.ctime() * 1_000_000_000 + meta.ctime_nsec(),
+        }
+    }
+
+    /// No stable inode or change time through `std` here; size and mtime only.
+    #[cfg(not(unix))]
+    pub fn of(meta: &fs::Metadata) -> Fingerprint {
+        let mtime_ns = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_nanos() as i64);
+        Fingerprint {
+            size: meta.len(),
+            mtime_ns,
+            inode: 0,
+            ctime_ns: 0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    path: String,
+    fingerprint: Fingerprint,
+    /// `(chunk_hash, size)` in order — what a scan would have produced.
+    chunks: Vec<(u64, u32)>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct File {
+    format: u32,
+    hash_identifier: u32,
+    target_chunk_size: u32,
+    entries: Vec<Entry>,
+}
+
+/// A loaded sidecar, for one hasher and target chunk size.
+pub(crate) struct FingerprintCache {
+    entries: HashMap<String, (Fingerprint, Vec<(u64, u32)>)>,
+}
+
+impl FingerprintCache {
+    /// Load the sidecar at `path` if it was written for the same hash and
+    /// chunking as the scan about to use it. Anything else — absent,
+    /// unreadable, another configuration — is `None`, and the scan hashes
+    /// everything.
+    pub fn read(path: &Path, hash_identifier: u32, target_chunk_size: u32) -> Option<Self> {
+        let bytes = match fs::read(path) {
+            Ok(b) => b,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                tracing::warn!(path = %path.display(), error = %e, "could not read the fingerprint cache");
+                return None;
+            }
+        };
+        let file: File = match serde_json::from_slice(&bytes) {
+            Ok(f) => f,
+            Err(e) => {
+                tracing::warn!(
+                    path = %path.display(),
+                    error = %e,
+                    "ignoring an unreadable fingerprint cache; the target will be hashed in full"
+                );
+                return None;
+            }
+        };
+        if file.format != FORMAT
+            || file.hash_identifier != hash_identifier
+            || file.target_chunk_size != target_chunk_size
+        {
+            return None;
+        }
+        Some(FingerprintCache {
+            entries: file
+                .entries
+                .into_iter()
+                .map(|e| (e.path, (e.fingerprint, e.chunks)))
+                .collect(),
+        })
+    }
+
+    /// The recorded chunks of `rel_path`, if it still has the fingerprint it
+    /// was recorded with.
+    pub fn lookup(&self, rel_path: &str, meta: &fs::Metadata) -> Option<&[(u64, u32)]> {
+        let (fp, chunks) = self.entries.get(rel_path)?;
+        (*fp == Fingerprint::of(meta)).then_some(chunks.as_slice())
+    }
+}
+
+/// Record every file `vi` names under `root` as it is on disk now. Call only
+/// once `root` is known to hold `vi` — after a successful apply. Files that
+/// are absent (filtered, or never written) are left out.
+pub(crate) fn write(path: &Path, root: &Path, vi: &VersionIndex) -> std::io::Result<()> {
+    let mut entries = Vec::new();
+    for i in 0..vi.asset_count() as usize {
+        if vi.is_dir(i).unwrap_or(true) {
+            continue;
+        }
+        let Ok(rel) = vi.path(i) else { continue };
+        let Some(meta) = fs_util::asset_metadata(root, rel) else {
+            continue;
+        };
+        if !meta.is_file() || meta.len() != vi.asset_sizes[i] {
+            continue;
+        }
+        let start = vi.asset_chunk_index_starts[i] as usize;
+        let count = vi.asset_chunk_counts[i] as usize;
+        let chunks = vi.asset_chunk_indexes[start..start + count]
+            .iter()
+            .map(|&c| (vi.chunk_hashes[c as usize], vi.chunk_sizes[c as usize]))
+            .collect();
+        entries.push(Entry {
+            path: rel.to_string(),
+            fingerprint: Fingerprint::of(&meta),
+            chunks,
+        });
+    }
+    let file = File {
+        format: FORMAT,
+        hash_identifier: vi.hash_identifier,
+        target_chunk_size: vi.target_chunk_size,
+        entries,
+    };
+    let bytes = serde_json::to_vec(&file).map_err(std::io::Error::other)?;
+    fs::write(path, bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use longtail_core::{Blake3, FileEntry, FileInfos, Permissions, assemble_version_index};
+
+    use super::*;
+
+    fn version(size: u64) -> VersionIndex {
+        let fi = FileInfos::from_scanned_entries(vec![FileEntry {
+            relative_path: "a.bin".into(),
+            size,
+            permissions: Permissions(0o644),
+            is_dir: false,
+        }]);
+        assemble_version_index(&fi, &[vec![(0xA1, size as u32)]], &Blake3, 32768, None)
+    }
+
+    #[test]
+    fn an_untouched_file_hits_and_a_rewritten_one_misses() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let sidecar = root.join("fp");
+        fs::write(root.join("a.bin"), b"abcd").unwrap();
+        let vi = version(4);
+        write(&sidecar, root, &vi).unwrap();
+
+        let cache = FingerprintCache::read(&sidecar, vi.hash_identifier, 32768).expect("cache");
+        let meta = fs::symlink_metadata(root.join("a.bin")).unwrap();
+        assert_eq!(cache.lookup("a.bin", &meta), Some(&[(0xA1, 4)][..]));
+
+        // Same length, new bytes: the size matches, the change time does not.
+        fs::write(root.join("a.bin"), b"wxyz").unwrap();
+        let meta = fs::symlink_metadata(root.join("a.bin")).unwrap();
+        assert!(cache.lookup("a.bin", &meta).is_none());
+    }
+
+    /// A file that is not the size the version says was not written by the
+    /// apply being recorded, and must not be vouched for.
+    #[test]
+    fn a_file_of_the_wrong_size_is_not_recorded() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        let sidecar = root.join("fp");
+        fs::write(root.join("a.bin"), b"abc").unwrap();
+        let vi = version(4);
+        write(&sidecar, root, &vi).unwrap();
+
+        let cache = FingerprintCache::read(&sidecar, vi.hash_identifier, 32768).expect("cache");
+        let meta = fs::symlink_metadata(root.join("a.bin")).unwrap();
+        assert!(cache.lookup("a.bin", &meta).is_none());
+    }
+
+    #[test]
+    fn another_chunking_is_ignored() {
+        let tmp = tempfile::tempdir().unwrap();
+        let sidecar = tmp.path().join("fp");
+        let vi = version(4);
+        write(&sidecar, tmp.path(), &vi).unwrap();
+        assert!(FingerprintCache::read(&sidecar, vi.hash_identifier, 65536).is_none());
+        assert!(FingerprintCache::read(&sidecar, vi.hash_identifier + 1, 32768).is_none());
+
+        fs::write(&sidecar, b"{").unwrap();
+        assert!(FingerprintCache::read(&sidecar, vi.hash_identifier, 32768).is_none());
+    }
+}
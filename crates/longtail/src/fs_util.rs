@@ -195,6 +195,12 @@ pub fn open_asset(root: &Path, rel_path: &str) -> Result<fs::File, LongtailError
     fs::File::open(&path).map_err(|e| LongtailError::io(format!("open {path:?}"), e))
 }
 
+/// An asset's own metadata (not following a symlink), or `None` when it is
+/// missing or its path is not one the target can hold.
+pub fn asset_metadata(root: &Path, rel_path: &str) -> Option<fs::Metadata> {
+    fs::symlink_metadata(safe_join(root, rel_path).ok()?).ok()
+}
+
 /// `mkdir -p` for a directory asset.
 pub fn create_dir(root: &Path, rel_path: &str) -> Result<(), LongtailError> {
     let path = safe_join(root, rel_path)?;
@@ -422,7 +428,8 @@ pub fn file_exists(path: &Path) -> bool {
 }
 
 /// Read a `.lvi`/`.lsi`/get-config from a URI: a local path (or `file://`),
-/// or `s3://bucket/key` (feature `s3`). golongtail reads these via its blob
+/// `s3://bucket/key` (feature `s3`), or a scheme registered with
+/// [`longtail_store::register_blob_scheme`]. golongtail reads these via its blob
 /// store abstraction (`ReadFromURI`); local paths never go through a URI parser
 /// (folderscanner.go:115 uses a plain file read for target-index paths).
 pub async fn read_from_uri(
@@ -448,6 +455,9 @@ pub async fn read_from_uri(
             });
         }
     }
+    if let Some(obj) = registered_object(uri).await? {
+        return Ok(obj.read().await?);
+    }
     if let Some((scheme, _)) = split_scheme(uri)
         && scheme.len() > 1
     {
@@ -459,6 +469,22 @@ pub async fn read_from_uri(
     read_local(uri)
 }
 
+/// Open the blob store rooted at `uri` (a store or folder, not one object),
+/// honouring `s3_options` where [`longtail_store::create_blob_store_for_uri`]
+/// would default them.
+pub fn blob_store_for_uri(
+    uri: &str,
+    #[allow(unused)] s3_options: &S3OptionsArg,
+) -> Result<Box<dyn longtail_store::BlobStore>, LongtailError> {
+    #[cfg(feature = "s3")]
+    if uri.starts_with("s3://") {
+        return Ok(Box::new(
+            longtail_store::S3BlobStore::from_uri_with_options(uri, s3_options.clone())?,
+        ));
+    }
+    Ok(longtail_store::create_blob_store_for_uri(uri)?)
+}
+
 fn read_local(path: &str) -> Result<Vec<u8>, LongtailError> {
     fs::read(path).map_err(|e| LongtailError::io(format!("read {path}"), e))
 }
@@ -468,12 +494,42 @@ fn split_scheme(uri: &str) -> Option<(&str, &str)> {
     Some((&uri[..idx], &uri[idx + 3..]))
 }
 
+/// `uri` as an object of a scheme registered with
+/// [`longtail_store::register_blob_scheme`]: the parent URI opened through the
+/// scheme's factory, the basename as the object — the same split the s3 path
+/// makes. `None` when the scheme is not registered.
+async fn registered_object(
+    uri: &str,
+) -> Result<Option<Box<dyn longtail_store::BlobObject>>, LongtailError> {
+    let Some(factory) = longtail_store::registered_blob_scheme(uri) else {
+        return Ok(None);
+    };
+    let (parent, name) = match uri.rfind('/') {
+        Some(pos) if pos > uri.find("://").map_or(0, |i| i + 2) => (&uri[..pos], &uri[pos + 1..]),
+        _ => {
+            return Err(LongtailError::UnsupportedUri {
+                uri: uri.to_string(),
+                reason: "uri missing object key".into(),
+            });
+        }
+    };
+    let store = factory(parent)?;
+    let client = store.new_client().await?;
+    Ok(Some(client.new_object(name).await?))
+}
+
 /// Alias so the s3-feature `read_from_uri` signature is stable either way.
 #[cfg(feature = "s3")]
 pub type S3OptionsArg = longtail_store::S3Options;
 #[cfg(not(feature = "s3"))]
 pub type S3OptionsArg = ();
 
+/// What a new options struct's `s3_options` starts as.
+#[cfg(feature = "s3")]
+pub(crate) fn default_s3() -> S3OptionsArg {
+    longtail_store::S3Options::default()
+}
+
 /// The [`S3OptionsArg`] an options struct carries, or `()` when the feature is
 /// off — for the call sites that pass one to a `read_*_from_uri`.
 ///
@@ -514,10 +570,10 @@ async fn read_s3(uri: &str, options: &longtail_store::S3Options) -> Result<Vec<u
     obj.read().await.map_err(LongtailError::from)
 }
 
-/// Write `bytes` to a URI: a local path (or `file://`), or `s3://bucket/key`
-/// (feature `s3`). Mirrors golongtail's `WriteToURI` (longtailutils.go:342):
-/// split into a parent-directory URI + object basename, then write via the blob
-/// store. Used by upsync/put/clone-store to write `.lvi`/`.lsi`/get-config.
+/// Write `bytes` to a URI: a local path (or `file://`), `s3://bucket/key`
+/// (feature `s3`), or a registered scheme. Mirrors golongtail's `WriteToURI`
+/// (longtailutils.go:342): split into a parent-directory URI + object basename,
+/// then write via the blob store. Used by upsync/put/clone-store to write `.lvi`/`.lsi`/get-config.
 pub async fn write_to_uri(
     uri: &str,
     bytes: Bytes,
@@ -542,6 +598,10 @@ pub async fn write_to_uri(
             });
         }
     }
+    if let Some(mut obj) = registered_object(uri).await? {
+        obj.write(bytes).await?;
+        return Ok(());
+    }
     if let Some((scheme, _)) = split_scheme(uri)
         && scheme.len() > 1
     {
@@ -577,7 +637,8 @@ async fn write_s3(
     Ok(())
 }
 
-/// Delete an object at a URI (local path / `file://` / `s3://`). Best-effort:
+/// Delete an object at a URI (local path / `file://` / `s3://` / a registered
+/// scheme). Best-effort:
 /// a missing object is not an error. Used by clone-store's zip fallback cleanup
 /// and prune paths that operate through URIs.
 #[allow(dead_code)]
@@ -588,6 +649,10 @@ pub async fn delete_uri(
     if let Some(rest) = uri.strip_prefix("file://") {
         return delete_local(Path::new(rest));
     }
+    if let Some(mut obj) = registered_object(uri).await? {
+        let _ = obj.delete().await;
+        return Ok(());
+    }
     if !uri.starts_with("s3://") {
         return delete_local(Path::new(uri));
     }
@@ -9,6 +9,11 @@ use crate::options::{DownsyncOptions, DownsyncReport, GetOptions};
 
 /// Read a get-config JSON and downsync it (see [`GetOptions`]).
 pub async fn get(opts: GetOptions) -> Result<DownsyncReport, LongtailError> {
+    downsync(downsync_options(opts).await?).await
+}
+
+/// Resolve the get-config(s) in `opts` into the equivalent [`DownsyncOptions`].
+pub(crate) async fn downsync_options(opts: GetOptions) -> Result<DownsyncOptions, LongtailError> {
     let configs: Vec<String> = opts
         .get_config_paths
         .iter()
@@ -107,21 +112,25 @@ pub async fn get(opts: GetOptions) -> Result<DownsyncReport, LongtailError> {
     ds.verify_chunks = opts.verify_chunks;
     ds.validate = opts.validate;
     ds.scan_target = opts.scan_target;
+    ds.resume_journal = opts.resume_journal;
+    ds.fingerprint_cache = opts.fingerprint_cache;
     ds.cache_target_index = opts.cache_target_index;
     ds.target_index_path = opts.target_index_path;
     ds.include_filter_regex = opts.include_filter_regex;
     ds.exclude_filter_regex = opts.exclude_filter_regex;
+    ds.priority_filter_regex = opts.priority_filter_regex;
     ds.worker_count = opts.worker_count;
     ds.remote_worker_count = opts.remote_worker_count;
     ds.enable_file_mapping = opts.enable_file_mapping;
     ds.use_legacy_write = opts.use_legacy_write;
     ds.progress = opts.progress;
     ds.cancel = opts.cancel;
+    ds.pause = opts.pause;
     ds.pool = opts.pool;
+    ds.block_store = opts.block_store;
     #[cfg(feature = "s3")]
     {
         ds.s3_options = opts.s3_options;
     }
-
-    downsync(ds).await
+    Ok(ds)
 }
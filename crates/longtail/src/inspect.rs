@@ -9,6 +9,7 @@ use longtail_core::{StoreIndex, VersionIndex, validate_store};
 use longtail_store::AccessType;
 use longtail_store::block_store::BlockStore;
 use longtail_store::uri::{BlockStoreOpts, create_block_store_for_uri};
+use serde::{Deserialize, Serialize};
 
 use crate::error::LongtailError;
 use crate::fs_util::{self, S3OptionsArg};
@@ -69,6 +70,8 @@ pub async fn validate_version(opts: ValidateVersionOptions) -> Result<(), Longta
         pool,
         version_local_store_index: None,
         max_block_bytes: None,
+        events: None,
+        pause: None,
         #[cfg(feature = "s3")]
         s3_options: opts.s3_options,
     };
@@ -100,7 +103,7 @@ pub async fn read_store_index_from_uri(
 }
 
 /// Summary numbers for `print-store` (cmd_printstore.go).
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[non_exhaustive]
 pub struct StoreIndexStats {
     pub version: u32,
@@ -163,6 +166,8 @@ pub async fn init_remote_store(opts: InitRemoteStoreOptions) -> Result<u32, Long
         pool: single_thread_pool()?,
         version_local_store_index: None,
         max_block_bytes: None,
+        events: None,
+        pause: None,
         #[cfg(feature = "s3")]
         s3_options: opts.s3_options,
     };
@@ -228,6 +233,8 @@ pub async fn create_version_store_index(
         pool: single_thread_pool()?,
         version_local_store_index: None,
         max_block_bytes: None,
+        events: None,
+        pause: None,
         #[cfg(feature = "s3")]
         s3_options: opts.s3_options.clone(),
     };
@@ -274,7 +281,7 @@ impl PrintVersionUsageOptions {
 }
 
 /// `print-version-usage` numbers (cmd_printVersionUsage.go:145-181).
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[non_exhaustive]
 pub struct VersionUsageStats {
     pub block_usage_percent: u32,
@@ -298,6 +305,8 @@ pub async fn print_version_usage_stats(
         pool: single_thread_pool()?,
         version_local_store_index: None,
         max_block_bytes: None,
+        events: None,
+        pause: None,
         #[cfg(feature = "s3")]
         s3_options: opts.s3_options.clone(),
     };
@@ -0,0 +1,378 @@
+//! The apply journal: what an interrupted `downsync` had finished writing, so
+//! the next run can check just the rest (`docs/rust-port.md` §Resume
+//! invariants, I3).
+//!
+//! One file inside the target, [`APPLY_JOURNAL_NAME`](crate::APPLY_JOURNAL_NAME),
+//! written as lines. The first is a JSON header: the [`JournalKey`] the run was
+//! made under and every asset the apply touches, each with the blocks that
+//! carry its content. Every later line is the decimal hash of a block whose
+//! writes have all landed (and been synced). An asset is complete once all its
+//! blocks are; an asset with no blocks (an empty file, a directory, a
+//! permissions-only change) is complete as soon as the journal exists.
+//!
+//! Writing it is best-effort: a journal that cannot be created or appended to
+//! costs the next run a full scan, never correctness. Reading it is
+//! conservative: anything it cannot account for — a foreign key, a torn header,
+//! an asset the version does not name — discards it in favour of the scan.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use longtail_core::VersionIndex;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+
+use crate::error::LongtailError;
+use crate::fs_util;
+use crate::hash_util::SyncHasher;
+
+/// Bumped on any change to the file layout; a journal of another format is
+/// ignored.
+const FORMAT: u32 = 1;
+
+/// What a journal is only valid for. A run under a different key diffs against
+/// a different plan, so another run's progress says nothing about it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct JournalKey {
+    /// Hash of the (merged) source version index bytes.
+    pub version_hash: u64,
+    pub delete_removed: bool,
+    pub include: Option<String>,
+    pub exclude: Option<String>,
+}
+
+/// Where to keep the journal for one run, and what it is keyed on.
+pub(crate) struct JournalPlan {
+    pub path: PathBuf,
+    pub key: JournalKey,
+}
+
+/// One asset the apply writes or re-permissions, and the blocks that carry its
+/// content.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct JournalAsset {
+    /// Root-relative, no trailing slash.
+    pub path: String,
+    pub blocks: Vec<u64>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Header {
+    format: u32,
+    key: JournalKey,
+    assets: Vec<JournalAsset>,
+}
+
+/// An open journal, appended to as blocks land.
+pub(crate) struct ApplyJournal {
+    path: PathBuf,
+    file: Mutex<fs::File>,
+}
+
+impl ApplyJournal {
+    /// Write the header. `None` (with a warning) when the file cannot be
+    /// written; the apply carries on without one.
+    pub fn create(plan: &JournalPlan, assets: Vec<JournalAsset>) -> Option<ApplyJournal> {
+        let header = Header {
+            format: FORMAT,
+            key: plan.key.clone(),
+            assets,
+        };
+        let written = (|| -> std::io::Result<fs::File> {
+            let mut line = serde_json::to_vec(&header).map_err(std::io::Error::other)?;
+            line.push(b'\n');
+            let mut file = fs::File::create(&plan.path)?;
+            file.write_all(&line)?;
+            // The header has to be on disk before any mark can be: marks
+            // against a header that was lost are marks against nothing.
+            file.sync_all()?;
+            Ok(file)
+        })();
+        match written {
+            Ok(file) => Some(ApplyJournal {
+                path: plan.path.clone(),
+                file: Mutex::new(file),
+            }),
+            Err(e) => {
+                tracing::warn!(
+                    path = %plan.path.display(),
+                    error = %e,
+                    "could not write the apply journal; an interrupted run will re-scan the target"
+                );
+                let _ = fs::remove_file(&plan.path);
+                None
+            }
+        }
+    }
+
+    /// Record that every write of `block_hash` has landed. The caller has
+    /// already synced them; a mark that is lost only costs a re-check.
+    pub fn mark_block(&self, block_hash: u64) {
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        // One write per line, so a torn append leaves at most one unterminated
+        // tail, which `read` drops.
+        if let Err(e) = file.write_all(format!("{block_hash}\n").as_bytes()) {
+            tracing::debug!(
+                path = %self.path.display(),
+                error = %e,
+                "could not append to the apply journal"
+            );
+        }
+    }
+}
+
+/// What a journal left by an interrupted run says.
+#[derive(Debug)]
+pub(crate) struct ResumeState {
+    pub assets: Vec<JournalAsset>,
+    pub done: HashSet<u64>,
+}
+
+impl ResumeState {
+    /// Whether every block carrying `asset`'s content was marked.
+    pub fn is_complete(&self, asset: &JournalAsset) -> bool {
+        asset.blocks.iter().all(|b| self.done.contains(b))
+    }
+}
+
+/// Read the journal at `path` if there is one written under `key`. `None` when
+/// absent, unreadable, or another run's (the latter two with a log line).
+pub(crate) fn read(path: &Path, key: &JournalKey) -> Option<ResumeState> {
+    let bytes = match fs::read(path) {
+        Ok(b) => b,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(e) => {
+            tracing::warn!(path = %path.display(), error = %e, "could not read the apply journal");
+            return None;
+        }
+    };
+    let state = parse(&bytes, key);
+    if state.is_none() {
+        tracing::info!(
+            path = %path.display(),
+            "ignoring an apply journal from a different version, filter set or format"
+        );
+    }
+    state
+}
+
+fn parse(bytes: &[u8], key: &JournalKey) -> Option<ResumeState> {
+    // Only newline-terminated lines count: an unterminated tail is a torn
+    // append, and a torn number still parses — as the wrong block.
+    let end = bytes.iter().rposition(|&b| b == b'\n')?;
+    let mut lines = bytes[..end].split(|&b| b == b'\n');
+    let header: Header = serde_json::from_slice(lines.next()?).ok()?;
+    if header.format != FORMAT || header.key != *key {
+        return None;
+    }
+    let done = lines
+        .filter_map(|l| std::str::from_utf8(l).ok()?.parse::<u64>().ok())
+        .collect();
+    Some(ResumeState {
+        assets: header.assets,
+        done,
+    })
+}
+
+/// The target's index as a journal describes it, checking only what the
+/// interrupted run had not finished.
+///
+/// Starts from `desired`, since everything the journal does not list already
+/// matched it. A listed asset whose blocks all landed need only exist at its
+/// size; the others are re-chunked and compared. Permissions are read from
+/// disk for every listed asset, since step 7 may not have run. An asset that
+/// does not check out gets a content hash `desired` cannot have, so the diff
+/// rewrites it.
+///
+/// `None` when the journal names an asset `desired` does not — it cannot then
+/// be the run it claims to be.
+pub(crate) fn resume_index(
+    root: &Path,
+    state: &ResumeState,
+    desired: &VersionIndex,
+    hasher: &SyncHasher,
+    pool: &rayon::ThreadPool,
+    cancel: &CancellationToken,
+    on_check: Option<&(dyn Fn(u64, u64, u64, u64) + Sync)>,
+) -> Result<Option<VersionIndex>, LongtailError> {
+    let by_path: HashMap<&str, usize> = (0..desired.asset_count() as usize)
+        .filter_map(|i| {
+            let p = desired.path(i).ok()?;
+            Some((fs_util::strip_trailing_slash(p), i))
+        })
+        .collect();
+    let mut listed = Vec::with_capacity(state.assets.len());
+    for asset in &state.assets {
+        let Some(&i) = by_path.get(asset.path.as_str()) else {
+            return Ok(None);
+        };
+        listed.push((asset, i));
+    }
+
+    let to_hash: Vec<_> = listed
+        .iter()
+        .filter(|(a, i)| !state.is_complete(a) && !desired.is_dir(*i).unwrap_or(false))
+        .collect();
+    let total_files = to_hash.len() as u64;
+    let total_bytes: u64 = to_hash.iter().map(|(_, i)| desired.asset_sizes[*i]).sum();
+    tracing::info!(
+        assets = state.assets.len(),
+        incomplete = total_files,
+        "resuming from the apply journal; verifying only the assets it left incomplete"
+    );
+    let done_files = std::sync::atomic::AtomicU64::new(0);
+    let done_bytes = std::sync::atomic::AtomicU64::new(0);
+
+    let checked: Vec<(usize, bool, Option<u16>)> = pool.install(|| {
+        listed
+            .par_iter()
+            .map(
+                |(asset, i)| -> Result<(usize, bool, Option<u16>), LongtailError> {
+                    if cancel.is_cancelled() {
+                        return Err(LongtailError::Cancelled);
+                    }
+                    let i = *i;
+                    let Some(meta) = fs_util::asset_metadata(root, &asset.path) else {
+                        return Ok((i, false, None));
+                    };
+                    let mode = Some(fs_util::mode_of(&meta));
+                    let is_dir = desired.is_dir(i).unwrap_or(false);
+                    if is_dir || meta.is_dir() {
+                        return Ok((i, is_dir && meta.is_dir(), mode));
+                    }
+                    let size = desired.asset_sizes[i];
+                    if state.is_complete(asset) {
+                        return Ok((i, meta.len() == size, mode));
+                    }
+                    let ok = crate::version::asset_matches_chunks(
+                        root,
+                        &asset.path,
+                        size,
+                        &chunks_of(desired, i),
+                        desired.target_chunk_size,
+                        hasher.as_ref(),
+                    )?;
+                    if let Some(cb) = on_check {
+                        use std::sync::atomic::Ordering;
+                        let f = done_files.fetch_add(1, Ordering::Relaxed) + 1;
+                        let b = done_bytes.fetch_add(size, Ordering::Relaxed) + size;
+                        cb(f, total_files, b, total_bytes);
+                    }
+                    Ok((i, ok, mode))
+                },
+            )
+            .collect::<Result<Vec<_>, _>>()
+    })?;
+
+    let mut vi = desired.clone();
+    for (i, ok, mode) in checked {
+        if !ok {
+            vi.content_hashes[i] = !desired.content_hashes[i];
+        }
+        if let Some(mode) = mode {
+            vi.permissions[i] = longtail_core::Permissions(mode);
+        }
+    }
+    Ok(Some(vi))
+}
+
+/// Asset `i`'s `(chunk_hash, size)` list.
+fn chunks_of(vi: &VersionIndex, i: usize) -> Vec<(u64, u32)> {
+    let start = vi.asset_chunk_index_starts[i] as usize;
+    let count = vi.asset_chunk_counts[i] as usize;
+    vi.asset_chunk_indexes[start..start + count]
+        .iter()
+        .map(|&c| (vi.chunk_hashes[c as usize], vi.chunk_sizes[c as usize]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key() -> JournalKey {
+        JournalKey {
+            version_hash: 0xFEED,
+            delete_removed: true,
+            include: None,
+            exclude: Some(".*\\.log$".into()),
+        }
+    }
+
+    fn journal_with(dir: &Path) -> ApplyJournal {
+        let plan = JournalPlan {
+            path: dir.join("journal"),
+            key: key(),
+        };
+        let assets = vec![
+            JournalAsset {
+                path: "a.bin".into(),
+                blocks: vec![1, 2],
+            },
+            JournalAsset {
+                path: "b.bin".into(),
+                blocks: vec![3],
+            },
+            JournalAsset {
+                path: "dir".into(),
+                blocks: Vec::new(),
+            },
+        ];
+        ApplyJournal::create(&plan, assets).expect("journal")
+    }
+
+    #[test]
+    fn marked_blocks_complete_their_assets() {
+        let tmp = tempfile::tempdir().unwrap();
+        let journal = journal_with(tmp.path());
+        journal.mark_block(2);
+        journal.mark_block(3);
+
+        let state = read(&tmp.path().join("journal"), &key()).expect("same key");
+        let complete: Vec<_> = state
+            .assets
+            .iter()
+            .filter(|a| state.is_complete(a))
+            .map(|a| a.path.as_str())
+            .collect();
+        assert_eq!(complete, ["b.bin", "dir"], "a.bin still waits on block 1");
+    }
+
+    /// A crash mid-append leaves a prefix of a number, which parses as a
+    /// different, wrong block.
+    #[test]
+    fn a_torn_tail_is_dropped() {
+        let tmp = tempfile::tempdir().unwrap();
+        let journal = journal_with(tmp.path());
+        journal.mark_block(3);
+        drop(journal);
+        let path = tmp.path().join("journal");
+        let mut f = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        f.write_all(b"1").unwrap();
+
+        let state = read(&path, &key()).expect("same key");
+        assert_eq!(state.done, HashSet::from([3]));
+    }
+
+    #[test]
+    fn another_runs_journal_is_ignored() {
+        let tmp = tempfile::tempdir().unwrap();
+        journal_with(tmp.path()).mark_block(1);
+        let path = tmp.path().join("journal");
+
+        let mut other = key();
+        other.version_hash += 1;
+        assert!(read(&path, &other).is_none(), "another version");
+        let mut other = key();
+        other.delete_removed = false;
+        assert!(read(&path, &other).is_none(), "another delete mode");
+
+        fs::write(&path, b"{\"format\":1").unwrap();
+        assert!(read(&path, &key()).is_none(), "a torn header");
+        assert!(read(&tmp.path().join("absent"), &key()).is_none());
+    }
+}
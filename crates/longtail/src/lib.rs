@@ -29,32 +29,60 @@
 //! # Ok(())
 //! # }
 //! ```
+//!
+//! A caller that runs many operations against one store (install, repair,
+//! extract, pre-download) opens a [`StoreSession`] instead: it reads the store
+//! index once and keeps the composed store, its cache and the pool across calls.
 #![forbid(unsafe_code)]
 
 mod apply;
 mod clonestore;
 pub mod compression;
 mod cp;
+mod diff;
 mod downsync;
+mod du;
 pub mod error;
+mod events;
+mod explain;
+mod fingerprint;
 mod fs_util;
 mod get;
 mod hash_util;
 mod inspect;
+mod journal;
+mod list;
 pub mod options;
 pub mod path_filter;
+mod plan;
 pub mod progress;
 mod prune;
 mod put;
+mod session;
 mod store_lifecycle;
+mod stream;
 mod upsync;
 mod version;
+mod version_fs;
+mod who_uses;
 
 pub use clonestore::{CloneStoreOptions, clone_store};
-pub use compression::compression_type_for_name;
-pub use cp::{CpOptions, cp};
+pub use compression::{compression_name_for_type, compression_type_for_name};
+pub use cp::{CpOptions, CpTreeOptions, CpTreeReport, cp, cp_tree};
+pub use diff::{
+    AssetChange, DiffVersionsOptions, DownloadEstimate, VersionDiffReport, diff_version_indexes,
+    diff_versions,
+};
 pub use downsync::downsync;
+pub use du::{StoreDuOptions, StoreUsageReport, VersionOverlap, VersionStorage, store_du};
 pub use error::{ErrorClass, LongtailError};
+pub use events::{
+    EVENT_STREAM_CAPACITY, EventStream, StreamEvent, downsync_with_events, upsync_with_events,
+};
+pub use explain::{
+    AssetUpdateCost, BlockUpdateCost, DirectoryUpdateCost, ExplainUpdateOptions, UpdateCost,
+    UpdateExplanation, explain_update,
+};
 pub use get::get;
 pub use hash_util::{SyncHasher, make_hasher};
 pub use inspect::{
@@ -63,29 +91,40 @@ pub use inspect::{
     init_remote_store, print_version_usage_stats, read_store_index_from_uri,
     read_version_index_from_uri, store_index_stats, validate_version,
 };
+pub use list::{ListVersionsOptions, PublishedVersion, list_versions};
 pub use options::{
     DownsyncOptions, DownsyncReport, DownsyncStoreStats, GetOptions, PhaseTiming, UpsyncOptions,
     UpsyncReport,
 };
-pub use path_filter::{RegexPathFilter, TARGET_INDEX_CACHE_NAME};
-pub use progress::{NullProgress, Progress, ProgressSink};
+pub use path_filter::{
+    APPLY_JOURNAL_NAME, FINGERPRINT_CACHE_NAME, PathGlob, RegexPathFilter, TARGET_INDEX_CACHE_NAME,
+};
+pub use plan::{DownsyncPlan, PlanDiff, execute_plan, plan_downsync, plan_get};
+pub use progress::{Event, NullProgress, Progress, ProgressSink};
 // Re-exported so a caller can construct/trigger cancellation without a direct
 // `tokio-util` dependency (or a version-coupling to it). Put a clone in
-// `DownsyncOptions`/`GetOptions::cancel` and call `.cancel()` to stop: in-flight
-// blocks finish, the partial target and its `.lrb` block cache stay valid, and
-// the op returns `LongtailError::Cancelled`. This is also the pause primitive —
-// "pause" = cancel and keep the target folder; "resume" = call `get`/`downsync`
-// again (delta-only; already-fetched blocks come from the cache, not the store,
-// though resume does re-scan the target); "cancel" = the same, then delete the
-// target. Cancellation is block-granular: it stops launching the next block but
-// cannot abort an already-in-flight fetch.
+// `DownsyncOptions`/`GetOptions::cancel` and call `.cancel()` to stop: block
+// reads in flight are dropped (a partly received block is discarded, never
+// written or cached), blocks already received finish writing, the partial
+// target and its `.lrb` block cache stay valid, and the op returns
+// `LongtailError::Cancelled`. A cancelled run resumes by calling
+// `get`/`downsync` again (delta-only; already-fetched blocks come from the cache,
+// not the store, and with `resume_journal` the apply journal limits the re-scan
+// to the assets the interrupted run left incomplete); "cancel" for good = the
+// same, then delete the target.
 pub use tokio_util::sync::CancellationToken;
+// A pause that keeps the run alive (`DownsyncOptions`/`GetOptions::pause`): new
+// blocks, prefetches and scan hashes wait, in-flight ones finish, and `resume()`
+// continues with the store, indexes and plan still in memory — no re-scan.
+pub use longtail_store::PauseToken;
 // Re-exported so a facade-only consumer can match on the store-error classes
 // (`StoreError::NotAuthorized` / `Network` / `NotFound`) reachable through
 // `LongtailError::Store(_)` without a direct `longtail-store` dependency.
 // Prefer `LongtailError::class()` for dispatch: it covers the whole error tree
 // and does not require matching variants across three crates.
 pub use longtail_store::StoreError;
+// Named by `Event::BlockFetched`, for the same reason.
+pub use longtail_store::BlockSource;
 // The S3 configuration surface is re-exported so a crate that depends only on
 // `longtail` can name the type it must construct for `DownsyncOptions`/
 // `GetOptions::s3_options` without adding a direct `longtail-store` dependency.
@@ -99,8 +138,14 @@ pub use prune::{
     prune_store_blocks, prune_store_index,
 };
 pub use put::{PutOptions, put};
+pub use session::{StoreSession, StoreSessionOptions, WarmReport};
+pub use stream::{AssetStream, AssetStreamOptions, DEFAULT_READ_AHEAD, open_asset_stream};
 pub use upsync::upsync;
 pub use version::create_version_index_from_folder;
+pub use version_fs::{DEFAULT_BLOCK_CACHE_BYTES, VersionFs, VersionFsOptions, VersionMetadata};
+pub use who_uses::{
+    SkippedVersion, UsageTarget, VersionUse, WhoUsesOptions, WhoUsesReport, who_uses,
+};
 
 /// Blocking convenience wrapper around [`downsync`]: builds its own multi-thread
 /// tokio runtime. Call from a non-async context (the CLI, or a plain thread).
@@ -0,0 +1,302 @@
+//! `list-versions`: what has been published under a folder, bucket or prefix.
+//!
+//! Discovery is one [`longtail_store::BlobClient::get_objects`] listing: every
+//! `.lvi` object is a version, and a `.json` object that parses as a `put`
+//! get-config is matched to the version its `source-path` names. Each version
+//! index is read once for its numbers. Version indexes do not record
+//! compression, so that comes from the version-local store index `put` writes
+//! beside it (`version-data/version-store-index/<name>.lsi`) or, failing that,
+//! from the blocks of a store the caller names.
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+use longtail_core::{StoreIndex, VersionIndex};
+use longtail_store::AccessType;
+use longtail_store::uri::{BlockStoreOpts, create_block_store_for_uri};
+use longtail_store::{BlobClient, StoreError};
+use serde::{Deserialize, Serialize};
+
+use crate::error::LongtailError;
+use crate::fs_util;
+#[cfg(feature = "s3")]
+use crate::fs_util::{S3OptionsArg, default_s3};
+
+/// Where `put` writes a version's index, relative to its get-config's folder.
+const PUT_VERSION_INDEX_DIR: &str = "version-data/version-index/";
+/// Where `put` writes a version-local store index.
+const PUT_VERSION_STORE_INDEX_DIR: &str = "version-data/version-store-index/";
+
+/// Options for [`list_versions`].
+#[non_exhaustive]
+pub struct ListVersionsOptions {
+    /// The folder, store or bucket prefix to search.
+    pub uri: String,
+    /// Only objects whose name under `uri` starts with this. A store's
+    /// `chunks/` holds no versions, and on S3 listing it costs one request per
+    /// thousand blocks, so a narrow prefix is worth giving.
+    pub prefix: String,
+    /// A store holding the versions' blocks, for the compression of versions
+    /// with no version-local store index.
+    pub storage_uri: Option<String>,
+    pub remote_worker_count: usize,
+    #[cfg(feature = "s3")]
+    pub s3_options: S3OptionsArg,
+}
+
+impl ListVersionsOptions {
+    pub fn new(uri: impl Into<String>) -> Self {
+        ListVersionsOptions {
+            uri: uri.into(),
+            prefix: String::new(),
+            storage_uri: None,
+            remote_worker_count: 0,
+            #[cfg(feature = "s3")]
+            s3_options: default_s3(),
+        }
+    }
+}
+
+/// One published version.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct PublishedVersion {
+    /// The `.lvi` object's name under the listed URI.
+    pub path: String,
+    /// The get-config naming it, if one was listed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub get_config: Option<String>,
+    /// The `.lvi` object's size.
+    pub object_size: u64,
+    /// Seconds since the Unix epoch, when the backend reports it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<u64>,
+    pub hash_identifier: u32,
+    pub asset_count: u32,
+    pub total_asset_size: u64,
+    pub chunk_count: u32,
+    /// The distinct compression IDs of the version's blocks, ascending. Empty
+    /// when neither a version-local store index nor a store said.
+    pub compression_types: Vec<u32>,
+    /// Why the index could not be read; the numbers above are zero then.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// `list-versions`: the version indexes under `opts.uri`, by path. A version
+/// index that cannot be read is listed with its error rather than failing the
+/// listing.
+pub async fn list_versions(
+    opts: ListVersionsOptions,
+) -> Result<Vec<PublishedVersion>, LongtailError> {
+    let s3 = crate::s3_arg!(opts);
+    let client = fs_util::blob_store_for_uri(&opts.uri, &s3)?
+        .new_client()
+        .await?;
+    let mut objects = client.get_objects(&opts.prefix).await?;
+    objects.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut versions: Vec<PublishedVersion> = objects
+        .iter()
+        .filter(|o| o.name.ends_with(".lvi"))
+        .map(|o| PublishedVersion {
+            path: o.name.clone(),
+            object_size: o.size,
+            last_modified: o
+                .last_modified
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs()),
+            ..PublishedVersion::default()
+        })
+        .collect();
+    let by_path: HashMap<String, usize> = versions
+        .iter()
+        .enumerate()
+        .map(|(i, v)| (v.path.clone(), i))
+        .collect();
+    for o in objects.iter().filter(|o| o.name.ends_with(".json")) {
+        let Some(source) = get_config_source(client.as_ref(), &o.name).await else {
+            continue;
+        };
+        if let Some(&i) = find_version(&by_path, &opts.uri, &o.name, &source) {
+            versions[i].get_config.get_or_insert_with(|| o.name.clone());
+        }
+    }
+
+    let store = match &opts.storage_uri {
+        Some(storage_uri) => {
+            let store_opts = BlockStoreOpts {
+                access_type: AccessType::ReadOnly,
+                worker_count: opts.remote_worker_count,
+                cache_dir: None,
+                pool: Arc::new(crate::version::build_pool(1)?),
+                version_local_store_index: None,
+                max_block_bytes: None,
+                events: None,
+                pause: None,
+                #[cfg(feature = "s3")]
+                s3_options: s3.clone(),
+            };
+            Some(create_block_store_for_uri(storage_uri, store_opts).await?)
+        }
+        None => None,
+    };
+    let mut result = Ok(());
+    for v in &mut versions {
+        let vi = match read_object(client.as_ref(), &v.path).await {
+            Ok(bytes) => VersionIndex::from_bytes(&bytes).map_err(LongtailError::from),
+            Err(e) => Err(e.into()),
+        };
+        let vi = match vi {
+            Ok(vi) => vi,
+            Err(e) => {
+                v.error = Some(e.to_string());
+                continue;
+            }
+        };
+        v.hash_identifier = vi.hash_identifier;
+        v.asset_count = vi.asset_count();
+        v.total_asset_size = vi.asset_sizes.iter().sum();
+        v.chunk_count = vi.chunk_count();
+        let local = match put_store_index_path(&v.path) {
+            Some(lsi) => read_object(client.as_ref(), &lsi)
+                .await
+                .ok()
+                .and_then(|bytes| StoreIndex::from_bytes(&bytes).ok()),
+            None => None,
+        };
+        let blocks = match (local, &store) {
+            (Some(si), _) => Some(si),
+            (None, Some(store)) => match store.get_existing_content(&vi.chunk_hashes, 0).await {
+                Ok(si) => Some(si),
+                Err(e) => {
+                    result = Err(LongtailError::from(e));
+                    break;
+                }
+            },
+            (None, None) => None,
+        };
+        if let Some(si) = blocks {
+            v.compression_types = si
+                .block_tags
+                .iter()
+                .copied()
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect();
+        }
+    }
+    if let Some(store) = store {
+        crate::store_lifecycle::finish_store(&store, result).await?;
+    } else {
+        result?;
+    }
+    Ok(versions)
+}
+
+/// The `.lvi` object names under `uri` starting with `prefix`, sorted, with
+/// none of them read.
+pub(crate) async fn list_version_paths(
+    uri: &str,
+    prefix: &str,
+    s3: &fs_util::S3OptionsArg,
+) -> Result<Vec<String>, LongtailError> {
+    let client = fs_util::blob_store_for_uri(uri, s3)?.new_client().await?;
+    let mut names: Vec<String> = client
+        .get_objects(prefix)
+        .await?
+        .into_iter()
+        .map(|o| o.name)
+        .filter(|name| name.ends_with(".lvi"))
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+async fn read_object(client: &dyn BlobClient, name: &str) -> Result<Vec<u8>, StoreError> {
+    client.new_object(name).await?.read().await
+}
+
+/// The `source-path` of the get-config at `name`; `None` for a `.json` that is
+/// not one.
+async fn get_config_source(client: &dyn BlobClient, name: &str) -> Option<String> {
+    let bytes = read_object(client, name).await.ok()?;
+    let doc: serde_json::Value = serde_json::from_slice(&bytes).ok()?;
+    Some(doc.get("source-path")?.as_str()?.to_string())
+}
+
+/// The listed version a get-config at `config` names. `source-path` is a URI
+/// as `put` was given it, so it matches a listed name by URI, then by suffix,
+/// then by the `put` layout beside the get-config.
+fn find_version<'a>(
+    by_path: &'a HashMap<String, usize>,
+    uri: &str,
+    config: &str,
+    source: &str,
+) -> Option<&'a usize> {
+    let source = source.replace('\\', "/");
+    let rel = source
+        .strip_prefix(uri.trim_end_matches('/'))
+        .map(|r| r.trim_start_matches('/'));
+    if let Some(i) = rel.and_then(|r| by_path.get(r)) {
+        return Some(i);
+    }
+    // The longest suffix is the closest match, and the answer must not depend
+    // on the map's iteration order.
+    if let Some((_, i)) = by_path
+        .iter()
+        .filter(|(path, _)| source.ends_with(&format!("/{path}")))
+        .max_by_key(|(path, _)| path.len())
+    {
+        return Some(i);
+    }
+    let (parent, file) = config.rsplit_once('/').unwrap_or(("", config));
+    let stem = file.strip_suffix(".json")?;
+    let sep = if parent.is_empty() { "" } else { "/" };
+    by_path.get(&format!("{parent}{sep}{PUT_VERSION_INDEX_DIR}{stem}.lvi"))
+}
+
+/// `put`'s version-local store index for the version index at `lvi`, if `lvi`
+/// is where `put` writes one.
+fn put_store_index_path(lvi: &str) -> Option<String> {
+    let (dir, file) = lvi.rsplit_once('/')?;
+    let parent = format!("{dir}/")
+        .strip_suffix(PUT_VERSION_INDEX_DIR)?
+        .to_string();
+    let stem = file.strip_suffix(".lvi")?;
+    Some(format!("{parent}{PUT_VERSION_STORE_INDEX_DIR}{stem}.lsi"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{find_version, put_store_index_path};
+
+    #[test]
+    fn put_layout_store_index_sits_beside_the_version_index() {
+        assert_eq!(
+            put_store_index_path("version-data/version-index/game.lvi").as_deref(),
+            Some("version-data/version-store-index/game.lsi")
+        );
+        assert_eq!(
+            put_store_index_path("builds/version-data/version-index/v1.lvi").as_deref(),
+            Some("builds/version-data/version-store-index/v1.lsi")
+        );
+        assert_eq!(put_store_index_path("chain-v1.lvi"), None);
+        assert_eq!(put_store_index_path("other/v1.lvi"), None);
+    }
+
+    #[test]
+    fn a_source_path_suffix_picks_the_longest_listed_path() {
+        let by_path: HashMap<String, usize> = [("v.lvi", 0), ("a/v.lvi", 1), ("b/v.lvi", 2)]
+            .into_iter()
+            .map(|(p, i)| (p.to_string(), i))
+            .collect();
+        let found = |source| find_version(&by_path, "s3://bucket/root", "game.json", source);
+        assert_eq!(found("s3://elsewhere/x/a/v.lvi"), Some(&1));
+        assert_eq!(found("s3://elsewhere/x/b/v.lvi"), Some(&2));
+        assert_eq!(found("s3://elsewhere/x/c/v.lvi"), Some(&0));
+    }
+}
@@ -6,6 +6,8 @@ use std::sync::Arc;
 use serde::{Deserialize, Serialize};
 use tokio_util::sync::CancellationToken;
 
+use longtail_store::{BlockStore, PauseToken};
+
 use crate::progress::ProgressSink;
 
 #[cfg(feature = "s3")]
@@ -39,6 +41,14 @@ pub struct DownsyncOptions {
     pub include_filter_regex: Option<String>,
     /// Exclude filter (multi-regex separated by `**`).
     pub exclude_filter_regex: Option<String>,
+    /// Assets to write first (multi-regex separated by `**`, matched like
+    /// `include_filter_regex`) — the executable, the first level's data — so a
+    /// launcher can start before the download ends. Their blocks are
+    /// preflighted and written ahead of the rest, and the progress sink's
+    /// [`on_asset_ready`](crate::ProgressSink::on_asset_ready) names each
+    /// matching file once it is complete and, with `retain_permissions`, has
+    /// its recorded mode. Directories never match.
+    pub priority_filter_regex: Option<String>,
     /// Apply the source version's POSIX permissions to written files (default
     /// true).
     pub retain_permissions: bool,
@@ -83,6 +93,34 @@ pub struct DownsyncOptions {
     /// Scan the target folder to build its current index (default true). Skipped
     /// when a target index path (or an existing cache) supplies it.
     pub scan_target: bool,
+    /// Keep an apply journal (`<target>/.longtail.apply.journal`) while the
+    /// target is being written, and use one left by an interrupted run (default
+    /// **false**).
+    ///
+    /// The journal records the plan and each block as its writes land. A run
+    /// that finds one for the same version and filters verifies only the assets
+    /// it left incomplete, instead of hashing the whole target. It is deleted
+    /// once the apply succeeds. Only consulted when the target would otherwise
+    /// be scanned. See `docs/rust-port.md` §Resume invariants, I3.
+    ///
+    /// A block is only marked once every file it wrote is synced to disk, so a
+    /// journaled apply pays an `fsync` per file per block. That is a cost worth
+    /// paying where downloads are often interrupted and targets are large, and
+    /// not otherwise, which is why it is opt-in.
+    pub resume_journal: bool,
+    /// Keep a fingerprint sidecar (`<target>/.longtail.fingerprints`) of each
+    /// file's size, mtime, inode and ctime beside its chunks, and let the target
+    /// scan re-hash only the files whose fingerprint changed (default
+    /// **false**).
+    ///
+    /// Written after a successful apply and deleted before the next one
+    /// mutates the target, like the target-index cache. Unlike that cache it
+    /// still notices damage done after the install — a touched file has a new
+    /// fingerprint — so it pairs with `cache_target_index = false` to make a
+    /// full check cheap. It trusts the filesystem's timestamps: a tool that
+    /// rewrites a file and restores all four defeats it, which is why it is
+    /// opt-in.
+    pub fingerprint_cache: bool,
     /// Cache the source version index as `<target>/.longtail.index.cache.lvi`
     /// and reuse it next time (default true).
     pub cache_target_index: bool,
@@ -101,8 +139,33 @@ pub struct DownsyncOptions {
     pub progress: Option<Arc<dyn ProgressSink>>,
     /// Optional cancellation token.
     pub cancel: Option<CancellationToken>,
+    /// Optional pause switch. While it is paused the run starts nothing new —
+    /// no block write, background prefetch or scan hash — and lets what is in
+    /// flight finish; the store, indexes and plan stay in memory, so
+    /// [`resume`](longtail_store::PauseToken::resume) carries on where it
+    /// stopped. Cancelling still works while paused. Time spent paused is
+    /// reported per phase as [`PhaseTiming::idle_millis`].
+    pub pause: Option<PauseToken>,
     /// Optional caller-supplied rayon pool (else one is built per operation).
     pub pool: Option<Arc<rayon::ThreadPool>>,
+    /// A caller-built block store to read from instead of the
+    /// `Compress(Cache(Remote))` stack composed from `storage_uri` — for
+    /// decorators (metrics, fault injection, a bespoke cache) wrapped around the
+    /// public [`longtail_store::RemoteBlockStore`] /
+    /// [`longtail_store::CacheBlockStore`] / [`longtail_store::CompressBlockStore`]
+    /// pieces. It must hand out decompressed blocks, as `CompressBlockStore` does.
+    ///
+    /// The store is its own store-index source: `version_local_store_index_paths`
+    /// is ignored, so seed a `RemoteBlockStore` with
+    /// [`RemoteBlockStore::with_store_index_override`](longtail_store::RemoteBlockStore::with_store_index_override)
+    /// to get the same effect. `cache_path`, `cache_size_limit` and
+    /// `max_prefetch_bytes` are the stack's business and ignored too; store
+    /// events reach the progress sink only if the caller wired them. The run
+    /// flushes the store but does not close it — it may be reused, and closing
+    /// it is the caller's job. `cancel` reaches its reads per call, through
+    /// [`BlockStore::get_stored_block_cancellable`]. `storage_uri` still sizes
+    /// the apply's concurrency when `remote_worker_count` is `0`.
+    pub block_store: Option<Arc<dyn BlockStore>>,
     /// Test-oriented override of the remote store's prefetch byte budget
     /// (`None` → the 512 MiB default). Exists for the deadlock
     /// regression suite — correctness must never depend on this value (the
@@ -131,11 +194,14 @@ impl DownsyncOptions {
             version_local_store_index_paths: Vec::new(),
             include_filter_regex: None,
             exclude_filter_regex: None,
+            priority_filter_regex: None,
             retain_permissions: true,
             delete_removed: true,
             verify_chunks: false,
             validate: false,
             scan_target: true,
+            resume_journal: false,
+            fingerprint_cache: false,
             cache_target_index: true,
             target_index_path: None,
             worker_count: 0,
@@ -144,7 +210,9 @@ impl DownsyncOptions {
             use_legacy_write: false,
             progress: None,
             cancel: None,
+            pause: None,
             pool: None,
+            block_store: None,
             max_prefetch_bytes: None,
             #[cfg(feature = "s3")]
             s3_options: S3Options::default(),
@@ -157,7 +225,11 @@ impl DownsyncOptions {
 #[non_exhaustive]
 pub struct PhaseTiming {
     pub phase: String,
+    /// Time spent working: wall-clock time less `idle_millis`.
     pub millis: u64,
+    /// Time spent paused (see [`DownsyncOptions::pause`]).
+    #[serde(default)]
+    pub idle_millis: u64,
 }
 
 /// Store I/O counters, mirroring `longtail_store::StatsSnapshot` but
@@ -236,17 +308,27 @@ pub struct GetOptions {
     pub verify_chunks: bool,
     pub validate: bool,
     pub scan_target: bool,
+    /// See [`DownsyncOptions::resume_journal`].
+    pub resume_journal: bool,
+    /// See [`DownsyncOptions::fingerprint_cache`].
+    pub fingerprint_cache: bool,
     pub cache_target_index: bool,
     pub target_index_path: Option<String>,
     pub include_filter_regex: Option<String>,
     pub exclude_filter_regex: Option<String>,
+    /// See [`DownsyncOptions::priority_filter_regex`].
+    pub priority_filter_regex: Option<String>,
     pub worker_count: usize,
     pub remote_worker_count: usize,
     pub enable_file_mapping: bool,
     pub use_legacy_write: bool,
     pub progress: Option<Arc<dyn ProgressSink>>,
     pub cancel: Option<CancellationToken>,
+    /// See [`DownsyncOptions::pause`].
+    pub pause: Option<PauseToken>,
     pub pool: Option<Arc<rayon::ThreadPool>>,
+    /// See [`DownsyncOptions::block_store`].
+    pub block_store: Option<Arc<dyn BlockStore>>,
     #[cfg(feature = "s3")]
     pub s3_options: S3Options,
 }
@@ -283,6 +365,12 @@ pub struct UpsyncOptions {
     pub progress: Option<Arc<dyn ProgressSink>>,
     pub cancel: Option<CancellationToken>,
     pub pool: Option<Arc<rayon::ThreadPool>>,
+    /// A caller-built block store to write to instead of the
+    /// `Compress(Remote)` stack composed from `storage_uri`; see
+    /// [`DownsyncOptions::block_store`]. It must accept uncompressed blocks
+    /// and persist its index on `flush`, as the composed stack does: the run
+    /// flushes it but does not close it.
+    pub block_store: Option<Arc<dyn BlockStore>>,
     #[cfg(feature = "s3")]
     pub s3_options: S3Options,
 }
@@ -315,6 +403,7 @@ impl UpsyncOptions {
             progress: None,
             cancel: None,
             pool: None,
+            block_store: None,
             #[cfg(feature = "s3")]
             s3_options: S3Options::default(),
         }
@@ -353,17 +442,22 @@ impl GetOptions {
             verify_chunks: false,
             validate: false,
             scan_target: true,
+            resume_journal: false,
+            fingerprint_cache: false,
             cache_target_index: true,
             target_index_path: None,
             include_filter_regex: None,
             exclude_filter_regex: None,
+            priority_filter_regex: None,
             worker_count: 0,
             remote_worker_count: 0,
             enable_file_mapping: false,
             use_legacy_write: false,
             progress: None,
             cancel: None,
+            pause: None,
             pool: None,
+            block_store: None,
             #[cfg(feature = "s3")]
             s3_options: S3Options::default(),
         }
@@ -47,6 +47,16 @@ pub(crate) fn relative_within(root: &Path, path: &str) -> Option<String> {
 /// and write it, and the filter has to keep it out of every version index.
 pub const TARGET_INDEX_CACHE_NAME: &str = ".longtail.index.cache.lvi";
 
+/// The name of the apply journal `downsync` keeps inside the target folder while
+/// an apply is in progress (see `docs/rust-port.md` §Resume invariants, I3).
+/// Never content, for the same reasons as [`TARGET_INDEX_CACHE_NAME`].
+pub const APPLY_JOURNAL_NAME: &str = ".longtail.apply.journal";
+
+/// The name of the fingerprint sidecar `downsync` writes inside the target
+/// folder when `fingerprint_cache` is on. Never content, for the same reasons as
+/// [`TARGET_INDEX_CACHE_NAME`].
+pub const FINGERPRINT_CACHE_NAME: &str = ".longtail.fingerprints";
+
 /// A compiled include/exclude path filter.
 #[derive(Debug, Default)]
 pub struct RegexPathFilter {
@@ -185,6 +195,151 @@ impl RegexPathFilter {
     }
 }
 
+/// A shell-style glob over in-version paths (root-relative, `/`-separated, no
+/// trailing slash), for picking assets by name rather than by regex.
+///
+/// `*` and `?` stay within one path component, `**` spans any number of them
+/// (`a/**/b` matches `a/b`), and `[...]` is a character class of single
+/// characters and `a-z` ranges (`[!...]` negates) that never matches `/`. The
+/// whole path must match; `\` escapes the next character.
+#[derive(Debug, Clone)]
+pub struct PathGlob {
+    pattern: String,
+    regex: Regex,
+}
+
+impl PathGlob {
+    pub fn new(pattern: &str) -> Result<PathGlob, LongtailError> {
+        let invalid = |why: &str| {
+            LongtailError::InvalidArgument(format!("invalid path glob `{pattern}`: {why}"))
+        };
+        let mut re = String::from("^");
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '*' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    if chars.peek() == Some(&'/') {
+                        chars.next();
+                        re.push_str("(?:.*/)?");
+                    } else {
+                        re.push_str(".*");
+                    }
+                }
+                '*' => re.push_str("[^/]*"),
+                '?' => re.push_str("[^/]"),
+                '[' => {
+                    // Intersected with `[^/]`, so no class crosses a
+                    // component, and every member is escaped but a range's
+                    // `-`, so the regex crate's class syntax (`&&`, `--`,
+                    // `~~`, nested `[...]`) stays literal.
+                    re.push_str("[[");
+                    if chars.peek() == Some(&'!') {
+                        chars.next();
+                        re.push('^');
+                    }
+                    // Each member, and whether it was `\`-escaped.
+                    let mut members: Vec<(char, bool)> = Vec::new();
+                    let mut closed = false;
+                    while let Some(c) = chars.next() {
+                        match c {
+                            ']' => {
+                                closed = true;
+                                break;
+                            }
+                            '\\' => {
+                                let next = chars.next().ok_or_else(|| invalid("unclosed `[`"))?;
+                                members.push((next, true));
+                            }
+                            c => members.push((c, false)),
+                        }
+                    }
+                    if !closed {
+                        return Err(invalid("unclosed `[`"));
+                    }
+                    for (i, &(c, escaped)) in members.iter().enumerate() {
+                        if c == '-' && !escaped && i > 0 && i + 1 < members.len() {
+                            re.push('-');
+                        } else {
+                            re.push_str(&regex::escape(&c.to_string()));
+                        }
+                    }
+                    re.push_str("]&&[^/]]");
+                }
+                '\\' => {
+                    let next = chars.next().ok_or_else(|| invalid("trailing `\\`"))?;
+                    re.push_str(&regex::escape(&next.to_string()));
+                }
+                c => re.push_str(&regex::escape(&c.to_string())),
+            }
+        }
+        re.push('$');
+        let regex = Regex::new(&re).map_err(|e| invalid(&e.to_string()))?;
+        Ok(PathGlob {
+            pattern: pattern.to_string(),
+            regex,
+        })
+    }
+
+    /// Whether any glob character appears in `s`: `cp` and `ls` take a plain
+    /// path literally.
+    pub fn is_glob(s: &str) -> bool {
+        s.contains(['*', '?', '['])
+    }
+
+    pub fn matches(&self, asset_path: &str) -> bool {
+        self.regex.is_match(asset_path.trim_end_matches('/'))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+}
+
+#[cfg(test)]
+mod glob_tests {
+    use super::PathGlob;
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        PathGlob::new(pattern).unwrap().matches(path)
+    }
+
+    #[test]
+    fn stars_stay_in_a_component_and_double_stars_cross_them() {
+        assert!(matches("*.txt", "a.txt"));
+        assert!(!matches("*.txt", "dir/a.txt"));
+        assert!(matches("**/*.txt", "a.txt"));
+        assert!(matches("**/*.txt", "dir/sub/a.txt"));
+        assert!(matches("config/**", "config/game/settings.ini"));
+        assert!(matches("dir/**/b", "dir/b"));
+        assert!(!matches("dir/**/b", "dirb"));
+        assert!(matches("file?.bin", "file1.bin"));
+        assert!(!matches("file?.bin", "file/.bin"));
+    }
+
+    #[test]
+    fn classes_escapes_and_errors() {
+        assert!(matches("v[0-9].pak", "v3.pak"));
+        assert!(!matches("v[!0-9].pak", "v3.pak"));
+        assert!(matches(r"weird\*name", "weird*name"));
+        assert!(!matches(r"weird\*name", "weirdXname"));
+        assert!(matches("a+b(1).txt", "a+b(1).txt"));
+        assert!(PathGlob::new("broken[").is_err());
+        // A class never matches `/`, and the regex crate's class operators
+        // are plain characters in a glob.
+        assert!(!matches("a[!x]b", "a/b"));
+        assert!(!matches("a[/]b", "a/b"));
+        assert!(matches("a[!x]b", "acb"));
+        assert!(!matches("a[a&&b]b", "a/b"));
+        assert!(matches("[a&&b]", "&"));
+        assert!(matches("[a&&b]", "a") && matches("[a&&b]", "b"));
+        assert!(matches("[a-]", "-") && matches("[-a]", "-"));
+        assert!(matches("[~~]", "~") && matches("[[]", "["));
+        assert!(matches(r"[\]]", "]"));
+        assert!(PathGlob::is_glob("*.txt") && !PathGlob::is_glob("plain/path"));
+    }
+}
+
 #[cfg(test)]
 mod never_content_tests {
     use std::path::Path;
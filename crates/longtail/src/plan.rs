@@ -0,0 +1,306 @@
+//! Plan/execute split for `downsync`: compute what an update will do — blocks
+//! to fetch, bytes to write, assets to delete — without touching the target,
+//! then apply exactly that plan once the caller has confirmed it.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use longtail_core::{StoreIndex, VersionDiff, VersionIndex};
+use serde::{Deserialize, Serialize};
+
+use crate::downsync::{DownsyncRun, Retargeted, check_cancel};
+use crate::error::LongtailError;
+use crate::options::{DownsyncOptions, DownsyncReport, GetOptions};
+
+/// What a [`DownsyncPlan`] would change, by path. The serializable face of the
+/// [`VersionDiff`] (whose asset indexes mean nothing without the two version
+/// indexes they point into); [`DownsyncPlan::version_diff`] has the real one.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct PlanDiff {
+    /// Assets the source version has and the target does not.
+    pub added: Vec<String>,
+    /// Assets on both sides whose content differs.
+    pub modified: Vec<String>,
+    /// Assets on both sides whose permissions differ.
+    pub permissions_modified: Vec<String>,
+    /// Assets the target has and the source version does not. Listed whether
+    /// or not the plan deletes them; see [`DownsyncPlan::assets_to_delete`].
+    pub removed: Vec<String>,
+}
+
+/// The outcome of [`plan_downsync`]: what applying it will fetch, write and
+/// delete. Serializable so a launcher can show it (or log it) before the user
+/// confirms.
+///
+/// Byte counts are block **payload** sizes — what lands in memory after
+/// decompression. The compressed transfer is smaller; the store index does not
+/// record it.
+///
+/// A plan carries the resolved run it was computed from, so
+/// [`execute_plan`] can apply it without re-reading the sources or the store
+/// index. That part is not serialized: a deserialized plan is a report only.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DownsyncPlan {
+    /// Resolved target folder path.
+    pub target_path: String,
+    /// The changes, by path.
+    pub diff: PlanDiff,
+    /// Every block the apply reads, fetched or cached.
+    pub required_blocks: Vec<u64>,
+    /// How many of `required_blocks` are already in the local block cache.
+    pub cached_blocks: u32,
+    /// Payload bytes of the required blocks not in the local cache.
+    pub fetch_bytes: u64,
+    /// Payload bytes of the required blocks served from the local cache.
+    pub cached_bytes: u64,
+    /// Bytes written to the target (the full size of every added or modified
+    /// asset).
+    pub write_bytes: u64,
+    /// Assets created or content-rewritten.
+    pub assets_to_write: u32,
+    /// Assets deleted (zero when `delete_removed` is off).
+    pub assets_to_delete: u32,
+    /// Net change in the target's size on disk once applied.
+    pub disk_delta: i64,
+    /// A hash of the target index the plan was computed against. Informational;
+    /// [`execute_plan`] compares the indexes themselves.
+    pub target_fingerprint: u64,
+    #[serde(skip)]
+    state: Option<PlanState>,
+}
+
+/// The resolved run and retargetted store index behind a plan.
+struct PlanState(Box<(DownsyncRun, Retargeted)>);
+
+impl fmt::Debug for PlanState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PlanState { .. }")
+    }
+}
+
+impl DownsyncPlan {
+    /// The diff the plan applies; `None` on a deserialized plan.
+    pub fn version_diff(&self) -> Option<&VersionDiff> {
+        self.state.as_ref().map(|s| &s.0.1.diff)
+    }
+
+    /// Whether applying the plan would change nothing.
+    pub fn is_empty(&self) -> bool {
+        self.assets_to_write == 0
+            && self.assets_to_delete == 0
+            && self.diff.permissions_modified.is_empty()
+    }
+}
+
+/// Resolve a downsync up to, but not including, touching the target: read the
+/// sources, index the target, diff, and query the store for the required
+/// blocks. Nothing is written — neither to the target nor to its cache index.
+///
+/// The options' progress sink and cancellation token apply to both this and
+/// the later [`execute_plan`].
+pub async fn plan_downsync(opts: DownsyncOptions) -> Result<DownsyncPlan, LongtailError> {
+    let mut run = DownsyncRun::resolve(opts).await?;
+    let override_index = run.read_store_index_override().await?;
+    let store = run.open_store(override_index).await?;
+    let retargeted = run.diff_and_retarget(&store).await;
+    let retargeted = run.finish_store(&store, retargeted).await?;
+    Ok(summarize(run, retargeted))
+}
+
+/// [`plan_downsync`] for a get-config (see [`GetOptions`]).
+pub async fn plan_get(opts: GetOptions) -> Result<DownsyncPlan, LongtailError> {
+    plan_downsync(crate::get::downsync_options(opts).await?).await
+}
+
+/// Apply a plan from [`plan_downsync`].
+///
+/// The target is indexed again first, the same way the plan indexed it, and the
+/// run is refused with [`LongtailError::TargetChanged`] if the result differs:
+/// the plan's diff is only correct against the state it was computed from.
+/// Detection is as good as that index — a cached target index sees what the
+/// cache says, not what is on disk, exactly as it does for `downsync`.
+pub async fn execute_plan(plan: DownsyncPlan) -> Result<DownsyncReport, LongtailError> {
+    let Some(PlanState(state)) = plan.state else {
+        return Err(LongtailError::InvalidArgument(
+            "the plan carries no resolved run (it was deserialized); plan again to execute".into(),
+        ));
+    };
+    let (mut run, retargeted) = *state;
+    // The wait between planning and executing is the user's, not a phase.
+    run.restart_clock();
+
+    check_cancel(&run.cancel)?;
+    run.progress.phase("Checking target");
+    let (current, _) = run.build_target_index()?;
+    if let Some(why) = describe_change(&run.target_index, &current) {
+        return Err(LongtailError::TargetChanged(why));
+    }
+    run.lap("check_target");
+
+    // The retargetted index names exactly the blocks this apply reads, so it
+    // doubles as the store-index override and the store need not read its own.
+    let store = run.open_store(Some(retargeted.store_index.clone())).await?;
+    let applied = run.apply(&store, &retargeted).await;
+    let apply_stats = run.finish_store(&store, applied).await?;
+    run.complete(apply_stats, store.stats())
+}
+
+fn summarize(run: DownsyncRun, retargeted: Retargeted) -> DownsyncPlan {
+    let desired = &run.source_version;
+    let current = &run.target_index;
+    let diff = &retargeted.diff;
+    let paths = |vi: &VersionIndex, idx: &[u32]| -> Vec<String> {
+        idx.iter()
+            .filter_map(|&i| vi.path(i as usize).ok().map(str::to_string))
+            .collect()
+    };
+    let size = |vi: &VersionIndex, idx: &[u32]| -> u64 {
+        idx.iter()
+            .filter_map(|&i| vi.asset_sizes.get(i as usize))
+            .sum()
+    };
+
+    let (required_blocks, cached_blocks, fetch_bytes, cached_bytes) =
+        block_costs(&retargeted.store_index, run.opts.cache_path.as_deref());
+
+    let write_bytes = size(desired, &diff.target_added_asset_indexes)
+        + size(desired, &diff.target_content_modified_asset_indexes);
+    let replaced_bytes = size(current, &diff.source_content_modified_asset_indexes);
+    let (assets_to_delete, removed_bytes) = if run.opts.delete_removed {
+        (
+            diff.source_removed_asset_indexes.len() as u32,
+            size(current, &diff.source_removed_asset_indexes),
+        )
+    } else {
+        (0, 0)
+    };
+    let disk_delta = write_bytes as i64 - replaced_bytes as i64 - removed_bytes as i64;
+
+    DownsyncPlan {
+        target_path: run.target_string.clone(),
+        diff: PlanDiff {
+            added: paths(desired, &diff.target_added_asset_indexes),
+            modified: paths(desired, &diff.target_content_modified_asset_indexes),
+            permissions_modified: paths(desired, &diff.target_permissions_modified_asset_indexes),
+            removed: paths(current, &diff.source_removed_asset_indexes),
+        },
+        required_blocks,
+        cached_blocks,
+        fetch_bytes,
+        cached_bytes,
+        write_bytes,
+        assets_to_write: (diff.target_added_asset_indexes.len()
+            + diff.target_content_modified_asset_indexes.len()) as u32,
+        assets_to_delete,
+        disk_delta,
+        target_fingerprint: run.hasher.hash(&current.to_bytes()),
+        state: Some(PlanState(Box::new((run, retargeted)))),
+    }
+}
+
+/// Required block hashes, how many are cached, and the payload bytes to fetch
+/// versus already cached.
+fn block_costs(
+    store_index: &StoreIndex,
+    cache_path: Option<&std::path::Path>,
+) -> (Vec<u64>, u32, u64, u64) {
+    let mut seen = HashSet::new();
+    let blocks: Vec<u64> = store_index
+        .block_hashes
+        .iter()
+        .copied()
+        .filter(|h| seen.insert(*h))
+        .collect();
+    let sizes = store_index.block_payload_sizes(&blocks);
+    let (mut cached, mut fetch_bytes, mut cached_bytes) = (0u32, 0u64, 0u64);
+    for h in &blocks {
+        let size = sizes.get(h).copied().unwrap_or(0);
+        if cache_path.is_some_and(|root| longtail_store::is_block_cached(root, *h)) {
+            cached += 1;
+            cached_bytes += size;
+        } else {
+            fetch_bytes += size;
+        }
+    }
+    (blocks, cached, fetch_bytes, cached_bytes)
+}
+
+/// `None` when `now` describes the same target as `planned`, else a short
+/// account of the first difference found.
+fn describe_change(planned: &VersionIndex, now: &VersionIndex) -> Option<String> {
+    if planned.to_bytes() == now.to_bytes() {
+        return None;
+    }
+    if planned.asset_count() != now.asset_count() {
+        return Some(format!(
+            "{} assets when planned, {} now",
+            planned.asset_count(),
+            now.asset_count()
+        ));
+    }
+    for i in 0..now.asset_count() as usize {
+        if planned.path_hashes.get(i) != now.path_hashes.get(i)
+            || planned.content_hashes.get(i) != now.content_hashes.get(i)
+            || planned.permissions.get(i) != now.permissions.get(i)
+        {
+            let path = now.path(i).unwrap_or("?");
+            return Some(format!("`{path}` differs from when the plan was made"));
+        }
+    }
+    Some("the target index differs from when the plan was made".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use longtail_core::{Permissions, VersionIndex};
+
+    use super::describe_change;
+
+    fn index(assets: &[(&str, u64)]) -> VersionIndex {
+        let mut name_data = Vec::new();
+        let mut name_offsets = Vec::new();
+        for (n, _) in assets {
+            name_offsets.push(name_data.len() as u32);
+            name_data.extend_from_slice(n.as_bytes());
+            name_data.push(0);
+        }
+        let count = assets.len();
+        VersionIndex {
+            hash_identifier: 0,
+            target_chunk_size: 32768,
+            path_hashes: (0..count as u64).collect(),
+            content_hashes: assets.iter().map(|(_, h)| *h).collect(),
+            asset_sizes: vec![1; count],
+            asset_chunk_counts: vec![0; count],
+            asset_chunk_index_starts: vec![0; count],
+            asset_chunk_indexes: Vec::new(),
+            chunk_hashes: Vec::new(),
+            chunk_sizes: Vec::new(),
+            chunk_tags: Vec::new(),
+            permissions: vec![Permissions(0o644); count],
+            name_offsets,
+            name_data,
+        }
+    }
+
+    #[test]
+    fn an_unchanged_target_is_not_a_change() {
+        let a = index(&[("a.bin", 1), ("b.bin", 2)]);
+        assert_eq!(describe_change(&a, &a.clone()), None);
+    }
+
+    /// The refusal names what moved, so "plan again" is not a mystery.
+    #[test]
+    fn a_changed_target_names_the_asset() {
+        let planned = index(&[("a.bin", 1), ("b.bin", 2)]);
+        let rewritten = index(&[("a.bin", 1), ("b.bin", 3)]);
+        let why = describe_change(&planned, &rewritten).unwrap();
+        assert!(why.contains("b.bin"), "{why}");
+
+        let grown = index(&[("a.bin", 1), ("b.bin", 2), ("c.bin", 3)]);
+        let why = describe_change(&planned, &grown).unwrap();
+        assert!(why.contains("2 assets when planned, 3 now"), "{why}");
+    }
+}
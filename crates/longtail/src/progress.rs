@@ -5,6 +5,10 @@
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
 
+use longtail_store::{BlockSource, StoreEvent};
+
+use crate::error::LongtailError;
+
 /// A progress sample carrying two independent dimensions: an **item** count
 /// (blocks for the download phase, files for the indexing phase) and a **byte**
 /// count (data handled). Either dimension is "unknown/indeterminate" when its
@@ -22,6 +26,68 @@ pub struct Progress {
     pub total_bytes: u64,
 }
 
+/// One thing that happened to one asset or block, for a sink that wants more
+/// than the counters: which file is being written, which failed, where each
+/// block came from — and what the run had to work around. Paths are
+/// root-relative and `/`-separated.
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub enum Event<'a> {
+    /// The first block carrying this asset's content is about to be written;
+    /// for an empty file or a directory, it is about to be created.
+    AssetStarted { path: &'a str },
+    /// Every byte of the asset is on disk. `bytes` is its size.
+    AssetCompleted { path: &'a str, bytes: u64 },
+    /// A block carrying the asset failed to arrive or to be written; the run
+    /// fails with `error` (or one that came first).
+    AssetFailed {
+        path: &'a str,
+        error: &'a LongtailError,
+    },
+    /// The asset was removed from the target (it is not in the version).
+    AssetDeleted { path: &'a str },
+    /// A scan read and hashed the asset, `bytes` long. A file whose
+    /// fingerprint let the scan skip it has no event.
+    AssetHashed { path: &'a str, bytes: u64 },
+    /// A block was read: `wire_bytes` is its stored, compressed size.
+    BlockFetched {
+        block_hash: u64,
+        source: BlockSource,
+        wire_bytes: u64,
+    },
+    /// A block read failed and is being retried; `attempt` counts from 1.
+    Retry { block_hash: u64, attempt: u32 },
+    /// Something the run worked around but the caller may want to surface —
+    /// a cache it could not use, a fallback to a slower path. The same text is
+    /// logged at `warn` level; the run carries on.
+    Warning { message: &'a str },
+}
+
+impl<'a> Event<'a> {
+    /// The facade's view of a store-layer event, if it has one.
+    pub(crate) fn from_store(e: &StoreEvent) -> Option<Event<'a>> {
+        match *e {
+            StoreEvent::BlockFetched {
+                block_hash,
+                source,
+                wire_bytes,
+            } => Some(Event::BlockFetched {
+                block_hash,
+                source,
+                wire_bytes,
+            }),
+            StoreEvent::Retry {
+                block_hash,
+                attempt,
+            } => Some(Event::Retry {
+                block_hash,
+                attempt,
+            }),
+            _ => None,
+        }
+    }
+}
+
 /// A progress sink. [`on_progress`](ProgressSink::on_progress) is called as work
 /// completes; a dimension's `total` may grow as phases are entered. `on_phase`
 /// names the current phase.
@@ -35,6 +101,19 @@ pub trait ProgressSink: Send + Sync {
     /// Called when a named phase begins (default: no-op). Phase-aware sinks can
     /// use this to segment; simple sinks ignore it.
     fn on_phase(&self, _phase: &str) {}
+
+    /// Called when a file picked out by the priority filter
+    /// ([`crate::DownsyncOptions::priority_filter_regex`]) is complete on disk,
+    /// with its recorded permissions when those are retained (default: no-op).
+    /// `path` is root-relative and `/`-separated. Fires once per file, from
+    /// whichever worker finished it; the rest of the download carries on.
+    fn on_asset_ready(&self, _path: &str) {}
+
+    /// Called for each [`Event`] (default: no-op). Unlike
+    /// [`on_progress`](ProgressSink::on_progress) it is not rate-limited:
+    /// every event arrives, from whichever task or worker it happened on, so
+    /// keep it cheap.
+    fn on_event(&self, _event: &Event<'_>) {}
 }
 
 /// A no-op sink (the default when the caller supplies none).
@@ -70,6 +149,32 @@ impl RateLimited {
         self.inner.on_phase(phase);
     }
 
+    /// Not rate-limited: each file is reported exactly once.
+    pub(crate) fn asset_ready(&self, path: &str) {
+        self.inner.on_asset_ready(path);
+    }
+
+    /// Not rate-limited, like [`asset_ready`](Self::asset_ready).
+    pub(crate) fn event(&self, event: Event<'_>) {
+        self.inner.on_event(&event);
+    }
+
+    /// An [`Event::Warning`]; the caller still logs it.
+    pub(crate) fn warn(&self, message: &str) {
+        self.event(Event::Warning { message });
+    }
+
+    /// A store-event sink forwarding to [`event`](Self::event), for
+    /// [`longtail_store::uri::BlockStoreOpts::events`].
+    pub(crate) fn store_events(self: &Arc<Self>) -> longtail_store::StoreEventSink {
+        let this = self.clone();
+        Arc::new(move |e| {
+            if let Some(event) = Event::from_store(e) {
+                this.event(event);
+            }
+        })
+    }
+
     pub(crate) fn report(&self, p: Progress) {
         let done = p.done_items;
         let last = self.last_reported.load(Ordering::Relaxed);
@@ -14,6 +14,7 @@ use longtail_core::{StoreIndex, VersionIndex, validate_store};
 use longtail_store::AccessType;
 use longtail_store::blob::create_blob_store_for_uri;
 use longtail_store::uri::{BlockStoreOpts, create_block_store_for_uri};
+use serde::{Deserialize, Serialize};
 
 use crate::error::LongtailError;
 use crate::fs_util::{self, S3OptionsArg};
@@ -144,7 +145,7 @@ impl PruneStoreOptions {
 }
 
 /// The outcome of [`prune_store`].
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[non_exhaustive]
 pub struct PruneStoreResult {
     pub dry_run: bool,
@@ -211,6 +212,8 @@ pub async fn prune_store(opts: PruneStoreOptions) -> Result<PruneStoreResult, Lo
             pool: pool()?,
             version_local_store_index: None,
             max_block_bytes: None,
+            events: None,
+            pause: None,
             #[cfg(feature = "s3")]
             s3_options: opts.s3_options.clone(),
         },
@@ -267,6 +270,8 @@ pub async fn prune_store(opts: PruneStoreOptions) -> Result<PruneStoreResult, Lo
             pool: pool()?,
             version_local_store_index: None,
             max_block_bytes: None,
+            events: None,
+            pause: None,
             #[cfg(feature = "s3")]
             s3_options: opts.s3_options.clone(),
         },
@@ -324,7 +329,7 @@ impl PruneStoreIndexOptions {
 }
 
 /// The outcome of [`prune_store_index`].
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[non_exhaustive]
 pub struct PruneStoreIndexResult {
     pub dry_run: bool,
@@ -423,7 +428,7 @@ impl PruneStoreBlocksOptions {
 }
 
 /// The outcome of [`prune_store_blocks`].
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[non_exhaustive]
 pub struct PruneStoreBlocksResult {
     pub dry_run: bool,
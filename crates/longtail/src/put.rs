@@ -3,6 +3,8 @@
 
 use std::sync::Arc;
 
+use tokio_util::sync::CancellationToken;
+
 use crate::error::LongtailError;
 use crate::fs_util::{self, S3OptionsArg};
 use crate::options::{UpsyncOptions, UpsyncReport};
@@ -44,6 +46,8 @@ pub struct PutOptions {
     pub use_legacy_write: bool,
     /// Optional progress sink (forwarded to the underlying upsync).
     pub progress: Option<Arc<dyn ProgressSink>>,
+    /// Optional cancellation token (forwarded to the underlying upsync).
+    pub cancel: Option<CancellationToken>,
     #[cfg(feature = "s3")]
     pub s3_options: longtail_store::S3Options,
 }
@@ -72,6 +76,7 @@ impl PutOptions {
             enable_file_mapping: false,
             use_legacy_write: false,
             progress: None,
+            cancel: None,
             #[cfg(feature = "s3")]
             s3_options: longtail_store::S3Options::default(),
         }
@@ -156,6 +161,7 @@ pub async fn put(opts: PutOptions) -> Result<UpsyncReport, LongtailError> {
     up.enable_file_mapping = opts.enable_file_mapping;
     up.use_legacy_write = opts.use_legacy_write;
     up.progress = opts.progress.clone();
+    up.cancel = opts.cancel.clone();
     #[cfg(feature = "s3")]
     {
         up.s3_options = opts.s3_options.clone();
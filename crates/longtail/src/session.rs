@@ -0,0 +1,361 @@
+//! A long-lived, read-only handle on one block store: the composed
+//! `Compress(Cache(Remote))` stack, its merged store index and a rayon pool,
+//! opened once and shared by many operations.
+//!
+//! The one-shot entry points ([`crate::downsync`], [`crate::cp`], …) each list
+//! and merge the store index, run, and close the store again. A launcher that
+//! installs, repairs, extracts and pre-fetches against the same store pays that
+//! once per operation; a [`StoreSession`] pays it once, at [`open`], and again
+//! only on [`refresh`].
+//!
+//! [`open`]: StoreSession::open
+//! [`refresh`]: StoreSession::refresh
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use longtail_core::validate_store;
+use longtail_store::AccessType;
+use longtail_store::block_store::BlockStore;
+use longtail_store::uri::{BlockStoreOpts, create_block_store_for_uri_with_budget};
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+
+use crate::downsync::check_cancel;
+use crate::error::LongtailError;
+use crate::fs_util::{self, S3OptionsArg};
+use crate::inspect::read_version_index_from_uri;
+use crate::options::{DownsyncOptions, DownsyncReport, DownsyncStoreStats};
+use crate::progress::{NullProgress, Progress, ProgressSink, RateLimited};
+use crate::store_lifecycle::flush_store;
+use crate::stream::AssetStream;
+use crate::version_fs::VersionFs;
+
+#[cfg(feature = "s3")]
+use longtail_store::S3Options;
+
+/// Options for [`StoreSession::open`]: the store-side half of
+/// [`DownsyncOptions`], fixed for the life of the session.
+#[non_exhaustive]
+pub struct StoreSessionOptions {
+    /// The block store URI (`s3://…`, a path, `file://…`).
+    pub storage_uri: String,
+    /// Optional local cache directory (`.lrb` blocks). Required by
+    /// [`StoreSession::warm`].
+    pub cache_path: Option<PathBuf>,
+    /// Optional cache byte budget, enforced when the session closes. See
+    /// [`DownsyncOptions::cache_size_limit`].
+    pub cache_size_limit: Option<u64>,
+    /// CPU (rayon) worker count for the session's pool; `0` = logical CPUs.
+    /// Ignored when `pool` is set.
+    pub worker_count: usize,
+    /// Remote block-I/O worker count; `0` = the scheme default.
+    pub remote_worker_count: usize,
+    /// Optional caller-supplied rayon pool (else the session builds one).
+    pub pool: Option<Arc<rayon::ThreadPool>>,
+    /// Receives the store's own progress: the index read, [`StoreSession::warm`],
+    /// and every [`crate::Event::BlockFetched`] / [`crate::Event::Retry`],
+    /// whichever operation caused it. Per-operation sinks get the rest.
+    pub progress: Option<Arc<dyn ProgressSink>>,
+    /// Cancels [`StoreSession::warm`], block reads in flight included. Other
+    /// operations take their own token; the store outlives a cancelled warm.
+    pub cancel: Option<CancellationToken>,
+    /// S3 credential/endpoint injection (feature `s3`).
+    #[cfg(feature = "s3")]
+    pub s3_options: S3Options,
+}
+
+impl StoreSessionOptions {
+    /// Minimal options: a storage URI; no cache, default workers.
+    pub fn new(storage_uri: impl Into<String>) -> StoreSessionOptions {
+        StoreSessionOptions {
+            storage_uri: storage_uri.into(),
+            cache_path: None,
+            cache_size_limit: None,
+            worker_count: 0,
+            remote_worker_count: 0,
+            pool: None,
+            progress: None,
+            cancel: None,
+            #[cfg(feature = "s3")]
+            s3_options: S3Options::default(),
+        }
+    }
+}
+
+/// What [`StoreSession::warm`] did.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct WarmReport {
+    /// Blocks the version needs.
+    pub blocks: u32,
+    /// Of those, already in the cache.
+    pub already_cached: u32,
+    /// Fetched from the store into the cache.
+    pub fetched: u32,
+}
+
+/// An open, read-only store. Methods take `&self` and may run concurrently;
+/// store counters in their reports are then shared between them. Call
+/// [`close`](Self::close) when done — it is what trims the cache to its budget.
+pub struct StoreSession {
+    storage_uri: String,
+    remote_worker_count: usize,
+    cache_path: Option<PathBuf>,
+    cache_size_limit: Option<u64>,
+    store: Arc<dyn BlockStore>,
+    pool: Arc<rayon::ThreadPool>,
+    progress: Arc<RateLimited>,
+    cancel: CancellationToken,
+    s3: S3OptionsArg,
+}
+
+impl StoreSession {
+    /// Compose the store stack and read its index.
+    pub async fn open(opts: StoreSessionOptions) -> Result<StoreSession, LongtailError> {
+        let progress: Arc<dyn ProgressSink> = opts
+            .progress
+            .clone()
+            .unwrap_or_else(|| Arc::new(NullProgress));
+        let progress = Arc::new(RateLimited::new(progress));
+        let pool = match &opts.pool {
+            Some(p) => p.clone(),
+            None => Arc::new(crate::version::build_pool(opts.worker_count)?),
+        };
+        let s3: S3OptionsArg = crate::s3_arg!(opts);
+        let cancel = opts.cancel.unwrap_or_default();
+
+        let store_opts = BlockStoreOpts {
+            access_type: AccessType::ReadOnly,
+            worker_count: opts.remote_worker_count,
+            cache_dir: opts.cache_path.clone(),
+            pool: pool.clone(),
+            version_local_store_index: None,
+            max_block_bytes: None,
+            events: Some(progress.store_events()),
+            pause: None,
+            #[cfg(feature = "s3")]
+            s3_options: opts.s3_options,
+        };
+        let store = create_block_store_for_uri_with_budget(
+            &opts.storage_uri,
+            store_opts,
+            None,
+            opts.cache_size_limit,
+        )
+        .await?;
+        let session = StoreSession {
+            storage_uri: opts.storage_uri,
+            remote_worker_count: opts.remote_worker_count,
+            cache_path: opts.cache_path,
+            cache_size_limit: opts.cache_size_limit,
+            store,
+            pool,
+            progress,
+            cancel,
+            s3,
+        };
+        // Read now rather than inside the first operation, whose progress
+        // would otherwise stall on it.
+        if let Err(e) = session.refresh().await {
+            let _ = session.close().await;
+            return Err(e);
+        }
+        Ok(session)
+    }
+
+    /// Read the store index again, picking up blocks written since it was
+    /// last read. Operations already running finish against whichever index
+    /// each query saw.
+    pub async fn refresh(&self) -> Result<(), LongtailError> {
+        self.progress.phase("Reading full store index");
+        self.store.refresh_index().await?;
+        Ok(())
+    }
+
+    /// The session's storage URI.
+    pub fn storage_uri(&self) -> &str {
+        &self.storage_uri
+    }
+
+    /// The store's counters since the session opened.
+    pub fn store_stats(&self) -> DownsyncStoreStats {
+        self.store.stats().into()
+    }
+
+    /// [`crate::downsync`] through this session's store, as its
+    /// [`DownsyncOptions::block_store`]. The other store-side fields of `opts`
+    /// (`storage_uri`, `cache_path`, `cache_size_limit`, `remote_worker_count`,
+    /// `pool`, `s3_options`) are replaced by the session's, and
+    /// `version_local_store_index_paths` is ignored: the session already holds
+    /// the whole index.
+    pub async fn downsync(
+        &self,
+        mut opts: DownsyncOptions,
+    ) -> Result<DownsyncReport, LongtailError> {
+        opts.block_store = Some(self.store.clone());
+        opts.storage_uri = self.storage_uri.clone();
+        opts.cache_path = self.cache_path.clone();
+        opts.cache_size_limit = self.cache_size_limit;
+        opts.remote_worker_count = self.remote_worker_count;
+        opts.pool = Some(self.pool.clone());
+        opts.version_local_store_index_paths.clear();
+        #[cfg(feature = "s3")]
+        {
+            opts.s3_options = self.s3.clone();
+        }
+        crate::downsync::downsync(opts).await
+    }
+
+    /// [`crate::cp`] through this session's store: copy the asset at
+    /// `source_path` inside the version at `version_index_path` to
+    /// `target_path`.
+    pub async fn cp(
+        &self,
+        version_index_path: &str,
+        source_path: &str,
+        target_path: &str,
+    ) -> Result<(), LongtailError> {
+        let vi = read_version_index_from_uri(version_index_path, &self.s3).await?;
+        let (chunk_hashes, chunk_sizes) = crate::cp::asset_chunks(&vi, source_path)?;
+        let assembled =
+            crate::cp::assemble(&self.store, &chunk_hashes, &chunk_sizes, source_path).await;
+        let out = flush_store(&self.store, assembled).await?;
+        fs_util::write_to_uri(target_path, out.into(), &self.s3).await?;
+        Ok(())
+    }
+
+    /// [`crate::open_asset_stream`] through this session's store: stream the
+    /// asset at `source_path` inside the version at `version_index_path`,
+    /// fetching up to `read_ahead` blocks ahead of the reader.
+    pub async fn open_asset_stream(
+        &self,
+        version_index_path: &str,
+        source_path: &str,
+        read_ahead: usize,
+    ) -> Result<AssetStream, LongtailError> {
+        let vi = read_version_index_from_uri(version_index_path, &self.s3).await?;
+        crate::stream::stream_asset(self.store.clone(), &vi, source_path, read_ahead, false).await
+    }
+
+    /// A [`VersionFs`] over this session's store for the version at
+    /// `version_index_path`, keeping up to `block_cache_bytes` of decompressed
+    /// blocks. Closing it leaves the session open.
+    pub async fn version_fs(
+        &self,
+        version_index_path: &str,
+        block_cache_bytes: u64,
+    ) -> Result<VersionFs, LongtailError> {
+        let vi = read_version_index_from_uri(version_index_path, &self.s3).await?;
+        VersionFs::new(vi, self.store.clone(), block_cache_bytes).await
+    }
+
+    /// [`crate::validate_version`] against this session's index: confirm the
+    /// store covers every chunk the version at `version_index_path` needs.
+    pub async fn validate(&self, version_index_path: &str) -> Result<(), LongtailError> {
+        let vi = read_version_index_from_uri(version_index_path, &self.s3).await?;
+        let store_index = self.store.get_existing_content(&vi.chunk_hashes, 0).await?;
+        validate_store(&store_index, &vi).map_err(LongtailError::from)
+    }
+
+    /// Fetch every block the version at `version_index_path` needs into the
+    /// local cache, so a later downsync of it reads nothing from the store —
+    /// a pre-download while the previous version is still in use. Requires a
+    /// `cache_path`. Honors the session's cancellation token, reads in flight included.
+    pub async fn warm(&self, version_index_path: &str) -> Result<WarmReport, LongtailError> {
+        let Some(cache_root) = &self.cache_path else {
+            return Err(LongtailError::InvalidArgument(
+                "warming needs a cache; open the session with a cache_path".into(),
+            ));
+        };
+        check_cancel(&self.cancel)?;
+        let vi = read_version_index_from_uri(version_index_path, &self.s3).await?;
+        let store_index = self.store.get_existing_content(&vi.chunk_hashes, 0).await?;
+        validate_store(&store_index, &vi)?;
+
+        let mut report = WarmReport {
+            blocks: store_index.block_count(),
+            ..WarmReport::default()
+        };
+        let missing: Vec<u64> = store_index
+            .block_hashes
+            .iter()
+            .copied()
+            .filter(|&h| !longtail_store::is_block_cached(cache_root, h))
+            .collect();
+        report.already_cached = report.blocks - missing.len() as u32;
+
+        self.progress.phase("Warming cache");
+        self.store
+            .preflight_get_cancellable(&missing, &self.cancel)
+            .await?;
+        let workers =
+            longtail_store::resolved_worker_count(&self.storage_uri, self.remote_worker_count);
+        let sem = Arc::new(tokio::sync::Semaphore::new(workers.max(1)));
+        let mut tasks: tokio::task::JoinSet<Result<(), LongtailError>> =
+            tokio::task::JoinSet::new();
+        let mut first_err: Option<LongtailError> = None;
+        let total = missing.len() as u64;
+        for block_hash in missing {
+            if self.cancel.is_cancelled() {
+                first_err.get_or_insert(LongtailError::Cancelled);
+            }
+            while let Some(res) = tasks.try_join_next() {
+                settle_warm_task(res, &mut first_err, &mut report);
+            }
+            if first_err.is_some() {
+                break;
+            }
+            let permit = sem
+                .clone()
+                .acquire_owned()
+                .await
+                .expect("warm semaphore never closes");
+            let store = self.store.clone();
+            let cancel = self.cancel.clone();
+            tasks.spawn(async move {
+                let _permit = permit;
+                // The cache layer writes a miss back as it passes through;
+                // the decoded block itself is not wanted.
+                store
+                    .get_stored_block_cancellable(block_hash, &cancel)
+                    .await?;
+                Ok(())
+            });
+        }
+        while let Some(res) = tasks.join_next().await {
+            settle_warm_task(res, &mut first_err, &mut report);
+            self.progress.report(Progress {
+                done_items: report.fetched as u64,
+                total_items: total,
+                ..Progress::default()
+            });
+        }
+        let warmed = match first_err {
+            Some(e) => Err(e),
+            None => Ok(report),
+        };
+        flush_store(&self.store, warmed).await
+    }
+
+    /// Flush and close the store, then trim the cache to its budget.
+    pub async fn close(self) -> Result<(), LongtailError> {
+        crate::store_lifecycle::finish_store(&self.store, Ok(())).await
+    }
+}
+
+fn settle_warm_task(
+    res: Result<Result<(), LongtailError>, tokio::task::JoinError>,
+    first_err: &mut Option<LongtailError>,
+    report: &mut WarmReport,
+) {
+    match res {
+        Ok(Ok(())) => report.fetched += 1,
+        Ok(Err(e)) => {
+            first_err.get_or_insert(e);
+        }
+        Err(e) => {
+            first_err.get_or_insert(LongtailError::Internal(format!("warm task panicked: {e}")));
+        }
+    }
+}
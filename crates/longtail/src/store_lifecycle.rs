@@ -2,7 +2,7 @@
 
 use std::sync::Arc;
 
-use longtail_store::block_store::BlockStore;
+use longtail_store::block_store::{BlockStore, StatsSnapshot};
 
 use crate::error::LongtailError;
 
@@ -30,7 +30,25 @@ pub(crate) async fn finish_store<T>(
         store.close().await
     }
     .await;
+    settle(outcome, cleanup)
+}
+
+/// [`finish_store`] for a store that outlives the operation (a
+/// [`crate::StoreSession`]'s, or one the caller built): flush only. The
+/// eviction sweep waits for whoever owns the store to close it.
+pub(crate) async fn flush_store<T>(
+    store: &Arc<dyn BlockStore>,
+    outcome: Result<T, LongtailError>,
+) -> Result<T, LongtailError> {
+    let cleanup = store.flush().await;
+    settle(outcome, cleanup)
+}
 
+/// The operation's outcome, unless only the cleanup failed.
+fn settle<T>(
+    outcome: Result<T, LongtailError>,
+    cleanup: Result<(), longtail_store::StoreError>,
+) -> Result<T, LongtailError> {
     match (outcome, cleanup) {
         (Ok(value), Ok(())) => Ok(value),
         (Ok(_), Err(cleanup)) => Err(cleanup.into()),
@@ -44,3 +62,20 @@ pub(crate) async fn finish_store<T>(
         }
     }
 }
+
+/// The counters a store accumulated between two snapshots of it — one
+/// operation's share of a store that outlives it.
+pub(crate) fn stats_since(before: StatsSnapshot, after: StatsSnapshot) -> StatsSnapshot {
+    StatsSnapshot {
+        get_count: after.get_count.saturating_sub(before.get_count),
+        get_byte_count: after.get_byte_count.saturating_sub(before.get_byte_count),
+        get_chunk_count: after.get_chunk_count.saturating_sub(before.get_chunk_count),
+        get_retry_count: after.get_retry_count.saturating_sub(before.get_retry_count),
+        get_fail_count: after.get_fail_count.saturating_sub(before.get_fail_count),
+        put_count: after.put_count.saturating_sub(before.put_count),
+        put_byte_count: after.put_byte_count.saturating_sub(before.put_byte_count),
+        put_chunk_count: after.put_chunk_count.saturating_sub(before.put_chunk_count),
+        put_retry_count: after.put_retry_count.saturating_sub(before.put_retry_count),
+        put_fail_count: after.put_fail_count.saturating_sub(before.put_fail_count),
+    }
+}
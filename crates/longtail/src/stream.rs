@@ -0,0 +1,327 @@
+//! [`open_asset_stream`]: one asset of a version as an [`AsyncRead`], fetched
+//! block by block instead of assembled in memory the way [`crate::cp`] does.
+//!
+//! The asset's chunks are grouped into runs of consecutive chunks sharing a
+//! block. A background task fetches those blocks in asset order, at most
+//! `read_ahead` at a time, checks each chunk against its hash and hands the
+//! bytes to the reader through a channel of the same depth. Memory is bounded
+//! by the read-ahead, not the asset size: a block the asset returns to after
+//! using others is fetched again rather than held.
+//!
+//! Dropping the stream stops the fetches; the store is closed (or, for a
+//! [`crate::StoreSession`], flushed) once the task winds down either way.
+
+use std::collections::{HashSet, VecDeque};
+use std::io;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use longtail_core::VersionIndex;
+use longtail_store::AccessType;
+use longtail_store::block_store::BlockStore;
+use longtail_store::uri::{BlockStoreOpts, create_block_store_for_uri};
+use tokio::io::{AsyncRead, ReadBuf};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::cp::{asset_chunks, chunk_bytes, chunk_locations};
+use crate::error::LongtailError;
+#[cfg(feature = "s3")]
+use crate::fs_util::{S3OptionsArg, default_s3};
+use crate::hash_util::{SyncHasher, make_hasher};
+
+/// Blocks fetched ahead of the reader when none is asked for.
+pub const DEFAULT_READ_AHEAD: usize = 4;
+
+/// Options for [`open_asset_stream`].
+#[non_exhaustive]
+pub struct AssetStreamOptions {
+    pub storage_uri: String,
+    pub version_index_path: String,
+    /// Asset path inside the version index.
+    pub source_path: String,
+    /// Blocks fetched ahead of the reader; `0` is taken as `1`.
+    pub read_ahead: usize,
+    pub cache_path: Option<PathBuf>,
+    pub remote_worker_count: usize,
+    #[cfg(feature = "s3")]
+    pub s3_options: S3OptionsArg,
+}
+
+impl AssetStreamOptions {
+    pub fn new(
+        storage_uri: impl Into<String>,
+        version_index_path: impl Into<String>,
+        source_path: impl Into<String>,
+    ) -> Self {
+        AssetStreamOptions {
+            storage_uri: storage_uri.into(),
+            version_index_path: version_index_path.into(),
+            source_path: source_path.into(),
+            read_ahead: DEFAULT_READ_AHEAD,
+            cache_path: None,
+            remote_worker_count: 0,
+            #[cfg(feature = "s3")]
+            s3_options: default_s3(),
+        }
+    }
+}
+
+/// An asset's bytes, in order. Read errors carry the [`LongtailError`] that
+/// stopped the fetch as their [`io::Error::get_ref`] source; a chunk whose
+/// bytes do not match its hash is [`LongtailError::ValidationMismatch`]. A
+/// fetch that stops without saying why, short of the asset's size, is
+/// [`io::ErrorKind::UnexpectedEof`] rather than an early end of file.
+pub struct AssetStream {
+    rx: mpsc::Receiver<Result<Bytes, LongtailError>>,
+    current: Bytes,
+    size: u64,
+    /// Bytes received from the fetch so far.
+    received: u64,
+}
+
+impl AssetStream {
+    /// The asset's size, as the version index records it.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+impl AsyncRead for AssetStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        loop {
+            if !self.current.is_empty() {
+                let n = self.current.len().min(buf.remaining());
+                buf.put_slice(&self.current.split_to(n));
+                return Poll::Ready(Ok(()));
+            }
+            match self.rx.poll_recv(cx) {
+                Poll::Ready(Some(Ok(bytes))) => {
+                    self.received += bytes.len() as u64;
+                    self.current = bytes;
+                }
+                Poll::Ready(Some(Err(e))) => return Poll::Ready(Err(io::Error::other(e))),
+                Poll::Ready(None) if self.received < self.size => {
+                    return Poll::Ready(Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        format!(
+                            "the asset's fetch stopped after {} of {} bytes",
+                            self.received, self.size
+                        ),
+                    )));
+                }
+                Poll::Ready(None) => return Poll::Ready(Ok(())),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// Open the asset at `opts.source_path` of a version for streaming. The
+/// version index is read and the asset's chunks located in the store before
+/// this returns; blocks are fetched as the stream is read.
+pub async fn open_asset_stream(opts: AssetStreamOptions) -> Result<AssetStream, LongtailError> {
+    let vi = crate::inspect::read_version_index_from_uri(
+        &opts.version_index_path,
+        &crate::s3_arg!(opts),
+    )
+    .await?;
+    let store_opts = BlockStoreOpts {
+        access_type: AccessType::ReadOnly,
+        worker_count: opts.remote_worker_count,
+        cache_dir: opts.cache_path.clone(),
+        pool: Arc::new(crate::version::build_pool(1)?),
+        version_local_store_index: None,
+        max_block_bytes: None,
+        events: None,
+        pause: None,
+        #[cfg(feature = "s3")]
+        s3_options: opts.s3_options.clone(),
+    };
+    let store: Arc<dyn BlockStore> =
+        create_block_store_for_uri(&opts.storage_uri, store_opts).await?;
+    stream_asset(store, &vi, &opts.source_path, opts.read_ahead, true).await
+}
+
+/// One block fetch and the runs of the asset it serves, in asset order.
+struct Fetch {
+    block_hash: u64,
+    /// `(offset within the decompressed block, chunk sizes and hashes)`.
+    runs: Vec<(u64, Vec<(u32, u64)>)>,
+}
+
+/// Stream the asset at `source_path` of `vi` from `store`. With `close` the
+/// store is finished when the stream is done with it, else only flushed.
+pub(crate) async fn stream_asset(
+    store: Arc<dyn BlockStore>,
+    vi: &VersionIndex,
+    source_path: &str,
+    read_ahead: usize,
+    close: bool,
+) -> Result<AssetStream, LongtailError> {
+    let planned = async {
+        let (hashes, sizes) = asset_chunks(vi, source_path)?;
+        let hasher = make_hasher(vi.hash_identifier)?;
+        let store_index = store.get_existing_content(&hashes, 0).await?;
+        let location = chunk_locations(&store_index);
+        let mut fetches: Vec<Fetch> = Vec::new();
+        for (&ch, &size) in hashes.iter().zip(&sizes) {
+            let &(block_hash, within) = location.get(&ch).ok_or_else(|| {
+                LongtailError::InvalidArgument(format!(
+                    "chunk {ch:#018x} of `{source_path}` is not present in the store"
+                ))
+            })?;
+            match fetches.last_mut() {
+                Some(f) if f.block_hash == block_hash => {
+                    let (start, chunks) = f.runs.last_mut().expect("a fetch has a run");
+                    let end = *start + chunks.iter().map(|&(s, _)| s as u64).sum::<u64>();
+                    if end == within {
+                        chunks.push((size, ch));
+                    } else {
+                        f.runs.push((within, vec![(size, ch)]));
+                    }
+                }
+                _ => fetches.push(Fetch {
+                    block_hash,
+                    runs: vec![(within, vec![(size, ch)])],
+                }),
+            }
+        }
+        let mut seen = HashSet::new();
+        let order: Vec<u64> = fetches
+            .iter()
+            .map(|f| f.block_hash)
+            .filter(|&h| seen.insert(h))
+            .collect();
+        store.preflight_get(&order).await?;
+        Ok((fetches, hasher, sizes.iter().map(|&s| s as u64).sum()))
+    }
+    .await;
+    let (fetches, hasher, size) = match planned {
+        Ok(planned) => planned,
+        Err(e) => return Err(settle(&store, Err(e), close).await.unwrap_err()),
+    };
+
+    let read_ahead = read_ahead.max(1);
+    let (tx, rx) = mpsc::channel(read_ahead);
+    tokio::spawn(async move {
+        let fed = feed(&store, fetches, hasher, read_ahead, &tx).await;
+        if let Err(e) = settle(&store, fed, close).await {
+            let _ = tx.send(Err(e)).await;
+        }
+    });
+    Ok(AssetStream {
+        rx,
+        current: Bytes::new(),
+        size,
+        received: 0,
+    })
+}
+
+async fn settle(
+    store: &Arc<dyn BlockStore>,
+    outcome: Result<(), LongtailError>,
+    close: bool,
+) -> Result<(), LongtailError> {
+    if close {
+        crate::store_lifecycle::finish_store(store, outcome).await
+    } else {
+        crate::store_lifecycle::flush_store(store, outcome).await
+    }
+}
+
+/// A block being fetched: its decompressed payload.
+type BlockFetch = JoinHandle<Result<Vec<u8>, LongtailError>>;
+
+/// Fetch `fetches` in order, `read_ahead` at a time, and send their chunk
+/// bytes down `tx`. Stops quietly when the reader goes away.
+async fn feed(
+    store: &Arc<dyn BlockStore>,
+    fetches: Vec<Fetch>,
+    hasher: SyncHasher,
+    read_ahead: usize,
+    tx: &mpsc::Sender<Result<Bytes, LongtailError>>,
+) -> Result<(), LongtailError> {
+    let mut inflight: VecDeque<(Fetch, BlockFetch)> = VecDeque::new();
+    let mut fetches = fetches.into_iter();
+    let fed = async {
+        loop {
+            while inflight.len() < read_ahead {
+                let Some(f) = fetches.next() else { break };
+                let store = store.clone();
+                let block_hash = f.block_hash;
+                let task =
+                    tokio::spawn(
+                        async move { Ok(store.get_stored_block(block_hash).await?.payload) },
+                    );
+                inflight.push_back((f, task));
+            }
+            let Some((f, task)) = inflight.pop_front() else {
+                return Ok(());
+            };
+            let payload = task.await.map_err(|e| {
+                LongtailError::Internal(format!("block fetch task panicked: {e}"))
+            })??;
+            let payload = Bytes::from(payload);
+            for (within, chunks) in f.runs {
+                let len: u32 = chunks.iter().map(|&(s, _)| s).sum();
+                chunk_bytes(&payload, f.block_hash, within, len)?;
+                let mut offset = within as usize;
+                for &(size, want) in &chunks {
+                    let got = hasher.hash(&payload[offset..offset + size as usize]);
+                    if got != want {
+                        return Err(LongtailError::ValidationMismatch(format!(
+                            "block {:#018x} carries chunk {want:#018x} whose bytes hash to \
+                             {got:#018x}; the store's content does not match its index",
+                            f.block_hash
+                        )));
+                    }
+                    offset += size as usize;
+                }
+                let run = payload.slice(within as usize..offset);
+                if tx.send(Ok(run)).await.is_err() {
+                    return Ok(());
+                }
+            }
+        }
+    }
+    .await;
+    for (_, task) in inflight {
+        task.abort();
+    }
+    fed
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use tokio::io::AsyncReadExt;
+    use tokio::sync::mpsc;
+
+    use super::AssetStream;
+
+    #[tokio::test]
+    async fn a_feed_that_stops_short_is_an_unexpected_eof() {
+        let (tx, rx) = mpsc::channel(2);
+        let mut stream = AssetStream {
+            rx,
+            current: Bytes::new(),
+            size: 10,
+            received: 0,
+        };
+        tx.send(Ok(Bytes::from_static(b"abcd"))).await.unwrap();
+        // The feed task goes away (a panic, an abort) without an error.
+        drop(tx);
+        let mut got = Vec::new();
+        let e = stream.read_to_end(&mut got).await.unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof, "{e}");
+        assert_eq!(got, b"abcd");
+    }
+}
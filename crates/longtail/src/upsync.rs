@@ -23,9 +23,13 @@ use crate::error::LongtailError;
 use crate::fs_util::{self, S3OptionsArg};
 use crate::hash_util::make_hasher;
 use crate::options::{UpsyncOptions, UpsyncReport};
-use crate::path_filter::{RegexPathFilter, TARGET_INDEX_CACHE_NAME, relative_within};
-use crate::progress::{NullProgress, Progress, ProgressSink, RateLimited};
-use crate::version::create_version_index_from_folder;
+use crate::path_filter::{
+    APPLY_JOURNAL_NAME, FINGERPRINT_CACHE_NAME, RegexPathFilter, TARGET_INDEX_CACHE_NAME,
+    relative_within,
+};
+use crate::progress::{Event, NullProgress, Progress, ProgressSink, RateLimited};
+use crate::store_lifecycle;
+use crate::version::create_version_index_with_fingerprints;
 
 /// The default upsync block-packing parameters (golongtail `options.go`).
 pub const DEFAULT_TARGET_BLOCK_SIZE: u32 = 8 * 1024 * 1024; // 8 MiB
@@ -56,9 +60,15 @@ pub async fn upsync(opts: UpsyncOptions) -> Result<UpsyncReport, LongtailError>
     // A version index is a description of a folder, so a folder never carries one
     // as content. The target-index cache is the case that matters: it lives inside
     // a downsynced folder, so upsyncing that folder used to publish one machine's
-    // cache to every consumer of the version. A supplied `source_index_path` gets
-    // the same treatment when it sits inside the folder it describes.
-    let mut never_content = vec![TARGET_INDEX_CACHE_NAME.to_string()];
+    // cache to every consumer of the version; an interrupted download's apply
+    // journal and the fingerprint sidecar are the same kind of file. A supplied
+    // `source_index_path` gets the same treatment when it sits inside the folder
+    // it describes.
+    let mut never_content = vec![
+        TARGET_INDEX_CACHE_NAME.to_string(),
+        APPLY_JOURNAL_NAME.to_string(),
+        FINGERPRINT_CACHE_NAME.to_string(),
+    ];
     if let Some(src_index) = opts.source_index_path.as_deref().filter(|s| !s.is_empty())
         && let Some(rel) = relative_within(&source_folder, src_index)
     {
@@ -98,6 +108,7 @@ pub async fn upsync(opts: UpsyncOptions) -> Result<UpsyncReport, LongtailError>
         phases.push(crate::options::PhaseTiming {
             phase: name.to_string(),
             millis: ms,
+            idle_millis: 0,
         });
     };
 
@@ -120,7 +131,7 @@ pub async fn upsync(opts: UpsyncOptions) -> Result<UpsyncReport, LongtailError>
             let hash_id = crate::hash_util::hash_identifier_for_name(&opts.hash_algorithm)?;
             let hasher = make_hasher(hash_id)?;
             let on_scan = crate::version::scan_progress_forwarder(progress.clone());
-            create_version_index_from_folder(
+            create_version_index_with_fingerprints(
                 &source_folder,
                 &filter,
                 hasher.as_ref(),
@@ -128,7 +139,10 @@ pub async fn upsync(opts: UpsyncOptions) -> Result<UpsyncReport, LongtailError>
                 compression_tag,
                 &pool,
                 &cancel,
+                None,
                 Some(&on_scan),
+                None,
+                Some(&|path, bytes| progress.event(Event::AssetHashed { path, bytes })),
             )?
         };
     lap("index_version", &mut timer);
@@ -137,19 +151,27 @@ pub async fn upsync(opts: UpsyncOptions) -> Result<UpsyncReport, LongtailError>
     // be computed with the same algorithm the chunk hashes were).
     let hasher = make_hasher(version_index.hash_identifier)?;
 
-    // 2. Open the store ReadWrite (Compress(Remote), no cache).
-    let store_opts = BlockStoreOpts {
-        access_type: AccessType::ReadWrite,
-        worker_count: opts.remote_worker_count,
-        cache_dir: None,
-        pool: pool.clone(),
-        version_local_store_index: None,
-        max_block_bytes: None,
-        #[cfg(feature = "s3")]
-        s3_options: opts.s3_options.clone(),
+    // 2. Open the store ReadWrite (Compress(Remote), no cache), unless the
+    // caller built one.
+    let store: Arc<dyn BlockStore> = match &opts.block_store {
+        Some(store) => store.clone(),
+        None => {
+            let store_opts = BlockStoreOpts {
+                access_type: AccessType::ReadWrite,
+                worker_count: opts.remote_worker_count,
+                cache_dir: None,
+                pool: pool.clone(),
+                version_local_store_index: None,
+                max_block_bytes: None,
+                events: None,
+                pause: None,
+                #[cfg(feature = "s3")]
+                s3_options: opts.s3_options.clone(),
+            };
+            create_block_store_for_uri(&opts.storage_uri, store_opts).await?
+        }
     };
-    let store: Arc<dyn BlockStore> =
-        create_block_store_for_uri(&opts.storage_uri, store_opts).await?;
+    let stats_base = store.stats();
 
     // Fallible work runs inside this block so the flush + close below happen on
     // a cancel or a failure too: an interrupted upload has still written blocks
@@ -189,8 +211,13 @@ pub async fn upsync(opts: UpsyncOptions) -> Result<UpsyncReport, LongtailError>
     }
     .await;
 
-    let (existing, missing, wc) = crate::store_lifecycle::finish_store(&store, written).await?;
-    let store_stats = store.stats();
+    // A caller-built store outlives the run: flush it, leave closing to them.
+    let (existing, missing, wc) = if opts.block_store.is_some() {
+        store_lifecycle::flush_store(&store, written).await?
+    } else {
+        store_lifecycle::finish_store(&store, written).await?
+    };
+    let store_stats = store_lifecycle::stats_since(stats_base, store.stats());
     lap("write_content", &mut timer);
 
     // 6. Write the target `.lvi` (always, regardless of missing count).
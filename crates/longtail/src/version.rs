@@ -8,10 +8,12 @@ use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
 use longtail_core::{FileInfos, Hash, HpcdcChunker, VersionIndex, assemble_version_index};
+use longtail_store::PauseToken;
 use rayon::prelude::*;
 use tokio_util::sync::CancellationToken;
 
 use crate::error::LongtailError;
+use crate::fingerprint::FingerprintCache;
 use crate::fs_util;
 use crate::path_filter::RegexPathFilter;
 use crate::progress::{Progress, RateLimited};
@@ -31,6 +33,43 @@ pub fn create_version_index_from_folder<H: Hash + Sync + ?Sized>(
     pool: &rayon::ThreadPool,
     cancel: &CancellationToken,
     on_scan: Option<&(dyn Fn(u64, u64, u64, u64) + Sync)>,
+) -> Result<VersionIndex, LongtailError> {
+    create_version_index_with_fingerprints(
+        root,
+        filter,
+        hasher,
+        target_chunk_size,
+        compression_tag,
+        pool,
+        cancel,
+        None,
+        on_scan,
+        None,
+        None,
+    )
+}
+
+/// Called with the path and size of each file a scan reads and hashes.
+pub(crate) type OnHashed<'a> = dyn Fn(&str, u64) + Sync + 'a;
+
+/// [`create_version_index_from_folder`], taking the chunks of any file whose
+/// fingerprint is unchanged from `known` rather than reading it, and calling
+/// `on_hashed` with the path and size of each file it does read. With `None`
+/// for both, the same function. While `pause` is paused no further file is
+/// started.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn create_version_index_with_fingerprints<H: Hash + Sync + ?Sized>(
+    root: &Path,
+    filter: &RegexPathFilter,
+    hasher: &H,
+    target_chunk_size: u32,
+    compression_tag: u32,
+    pool: &rayon::ThreadPool,
+    cancel: &CancellationToken,
+    pause: Option<&PauseToken>,
+    on_scan: Option<&(dyn Fn(u64, u64, u64, u64) + Sync)>,
+    known: Option<&FingerprintCache>,
+    on_hashed: Option<&OnHashed<'_>>,
 ) -> Result<VersionIndex, LongtailError> {
     // Scan, then sort entries with FileInfos' exact byte-wise order so the
     // per-asset chunk lists align 1:1 with the assembled asset order.
@@ -54,15 +93,36 @@ pub fn create_version_index_from_folder<H: Hash + Sync + ?Sized>(
         entries
             .par_iter()
             .map(|entry| -> Result<Vec<(u64, u32)>, LongtailError> {
+                if let Some(pause) = pause {
+                    pause.wait_blocking(&|| cancel.is_cancelled());
+                }
                 if cancel.is_cancelled() {
                     return Err(LongtailError::Cancelled);
                 }
                 if entry.is_dir {
                     return Ok(Vec::new());
                 }
-                let mut f = fs_util::open_asset(root, &entry.relative_path)?;
-                let chunks =
-                    chunk_asset_streaming(&mut f, entry.size, &chunker, max_hash_size, hasher)?;
+                let recorded = known.and_then(|k| {
+                    let meta = fs_util::asset_metadata(root, &entry.relative_path)?;
+                    k.lookup(&entry.relative_path, &meta).map(<[_]>::to_vec)
+                });
+                let chunks = match recorded {
+                    Some(chunks) => chunks,
+                    None => {
+                        let mut f = fs_util::open_asset(root, &entry.relative_path)?;
+                        let chunks = chunk_asset_streaming(
+                            &mut f,
+                            entry.size,
+                            &chunker,
+                            max_hash_size,
+                            hasher,
+                        )?;
+                        if let Some(cb) = on_hashed {
+                            cb(&entry.relative_path, entry.size);
+                        }
+                        chunks
+                    }
+                };
                 if let Some(cb) = on_scan {
                     let f = done_files.fetch_add(1, Ordering::Relaxed) + 1;
                     let b = done_bytes.fetch_add(entry.size, Ordering::Relaxed) + entry.size;
@@ -83,6 +143,34 @@ pub fn create_version_index_from_folder<H: Hash + Sync + ?Sized>(
     ))
 }
 
+/// Whether the asset at `root/rel_path` is exactly `size` bytes chunking to
+/// `expected` (`(chunk_hash, size)` pairs, in order) — the per-asset half of a
+/// scan, for checking a few assets against an index without scanning the
+/// folder. A missing or unreadable asset is a mismatch, not an error.
+pub(crate) fn asset_matches_chunks<H: Hash + ?Sized>(
+    root: &Path,
+    rel_path: &str,
+    size: u64,
+    expected: &[(u64, u32)],
+    target_chunk_size: u32,
+    hasher: &H,
+) -> Result<bool, LongtailError> {
+    let Ok(mut f) = fs_util::open_asset(root, rel_path) else {
+        return Ok(false);
+    };
+    if f.metadata().map(|m| m.len()).ok() != Some(size) {
+        return Ok(false);
+    }
+    let chunker = HpcdcChunker::from_target(target_chunk_size)?;
+    let max_hash_size = (target_chunk_size as u64).saturating_mul(1024);
+    match chunk_asset_streaming(&mut f, size, &chunker, max_hash_size, hasher) {
+        Ok(chunks) => Ok(chunks == expected),
+        // Shrunk between the length check and the read.
+        Err(LongtailError::Io { .. }) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
 /// Chunk `asset_size` bytes read **sequentially** from `reader` into
 /// `(chunk_hash, size)` pairs, byte-identically to `longtail_core::chunk_asset`
 /// over the whole buffer.
@@ -0,0 +1,458 @@
+//! [`VersionFs`]: read-only random access to a version's files, straight from
+//! the block store — the capability of golongtail's `blockstorestorage`
+//! without its virtual-filesystem layer.
+//!
+//! Opening reads the version index and the store index narrowed to the
+//! version's chunks. [`VersionFs::read_at`] then binary-searches the asset's
+//! chunk offsets for the chunks covering the range, fetches only the blocks
+//! holding them, and slices the bytes out. Fetched blocks are checked against
+//! the store index and kept in a byte-bounded, least-recently-used cache, so
+//! neighbouring reads of a large asset cost one fetch per block.
+//!
+//! Errors follow `std::fs`: a path the version does not hold is
+//! [`LongtailError::Io`] with [`io::ErrorKind::NotFound`], reading a directory
+//! is [`io::ErrorKind::IsADirectory`], and listing a file is
+//! [`io::ErrorKind::NotADirectory`].
+
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use longtail_core::{StoreIndex, VersionIndex};
+use longtail_store::AccessType;
+use longtail_store::block_store::BlockStore;
+use longtail_store::uri::{BlockStoreOpts, create_block_store_for_uri};
+use serde::{Deserialize, Serialize};
+
+use crate::error::LongtailError;
+use crate::fs_util;
+#[cfg(feature = "s3")]
+use crate::fs_util::{S3OptionsArg, default_s3};
+use crate::hash_util::{SyncHasher, make_hasher};
+
+/// Decompressed block bytes a [`VersionFs`] keeps by default.
+pub const DEFAULT_BLOCK_CACHE_BYTES: u64 = 64 << 20;
+
+/// Options for [`VersionFs::open`].
+#[non_exhaustive]
+pub struct VersionFsOptions {
+    pub storage_uri: String,
+    pub version_index_path: String,
+    /// Decompressed block bytes kept in memory between reads. The block just
+    /// fetched is kept even when it alone exceeds this.
+    pub block_cache_bytes: u64,
+    /// Optional local cache directory (`.lrb` blocks), shared with downsync.
+    pub cache_path: Option<PathBuf>,
+    pub remote_worker_count: usize,
+    #[cfg(feature = "s3")]
+    pub s3_options: S3OptionsArg,
+}
+
+impl VersionFsOptions {
+    pub fn new(storage_uri: impl Into<String>, version_index_path: impl Into<String>) -> Self {
+        VersionFsOptions {
+            storage_uri: storage_uri.into(),
+            version_index_path: version_index_path.into(),
+            block_cache_bytes: DEFAULT_BLOCK_CACHE_BYTES,
+            cache_path: None,
+            remote_worker_count: 0,
+            #[cfg(feature = "s3")]
+            s3_options: default_s3(),
+        }
+    }
+}
+
+/// One file or directory of a version.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct VersionMetadata {
+    /// `/`-joined path from the version root, without a trailing `/`; `""` for
+    /// the root.
+    pub path: String,
+    pub is_dir: bool,
+    pub size: u64,
+    /// POSIX permission bits. The root, which the index does not record, is
+    /// `0o755`.
+    pub permissions: u16,
+    pub chunk_count: u32,
+    /// The asset's content hash; `0` for the root.
+    pub content_hash: u64,
+}
+
+impl VersionMetadata {
+    /// The last path component; `""` for the root.
+    pub fn name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or("")
+    }
+}
+
+/// A read-only view of one version's files. Methods take `&self` and may run
+/// concurrently. Call [`close`](Self::close) when done.
+pub struct VersionFs {
+    vi: VersionIndex,
+    store: Arc<dyn BlockStore>,
+    /// Whether [`close`](Self::close) finishes the store or only flushes it.
+    owns_store: bool,
+    /// Path (no trailing `/`) → asset.
+    assets: HashMap<String, usize>,
+    /// Directory path → its direct entries, by name.
+    children: HashMap<String, Vec<usize>>,
+    /// Parallel to `vi.asset_chunk_indexes`: each chunk's offset in its asset.
+    chunk_offsets: Vec<u64>,
+    store_index: StoreIndex,
+    /// chunk hash → (block index in `store_index`, offset in the block).
+    location: HashMap<u64, (usize, u64)>,
+    hasher: SyncHasher,
+    cache: Mutex<BlockCache>,
+}
+
+impl VersionFs {
+    /// Open the version at `opts.version_index_path` against the store at
+    /// `opts.storage_uri`.
+    pub async fn open(opts: VersionFsOptions) -> Result<VersionFs, LongtailError> {
+        let vi = crate::inspect::read_version_index_from_uri(
+            &opts.version_index_path,
+            &crate::s3_arg!(opts),
+        )
+        .await?;
+        let store_opts = BlockStoreOpts {
+            access_type: AccessType::ReadOnly,
+            worker_count: opts.remote_worker_count,
+            cache_dir: opts.cache_path.clone(),
+            pool: Arc::new(crate::version::build_pool(1)?),
+            version_local_store_index: None,
+            max_block_bytes: None,
+            events: None,
+            pause: None,
+            #[cfg(feature = "s3")]
+            s3_options: opts.s3_options.clone(),
+        };
+        let store: Arc<dyn BlockStore> =
+            create_block_store_for_uri(&opts.storage_uri, store_opts).await?;
+        match Self::build(vi, store.clone(), true, opts.block_cache_bytes).await {
+            Ok(fs) => Ok(fs),
+            Err(e) => crate::store_lifecycle::finish_store(&store, Err(e)).await,
+        }
+    }
+
+    /// A view of `vi` over a store the caller keeps: [`close`](Self::close)
+    /// flushes it and leaves it open.
+    pub async fn new(
+        vi: VersionIndex,
+        store: Arc<dyn BlockStore>,
+        block_cache_bytes: u64,
+    ) -> Result<VersionFs, LongtailError> {
+        Self::build(vi, store, false, block_cache_bytes).await
+    }
+
+    async fn build(
+        vi: VersionIndex,
+        store: Arc<dyn BlockStore>,
+        owns_store: bool,
+        block_cache_bytes: u64,
+    ) -> Result<VersionFs, LongtailError> {
+        let hasher = make_hasher(vi.hash_identifier)?;
+        let mut assets = HashMap::new();
+        let mut children: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut chunk_offsets = vec![0u64; vi.asset_chunk_indexes.len()];
+        for a in 0..vi.asset_count() as usize {
+            let path = fs_util::strip_trailing_slash(vi.path(a)?).to_string();
+            let parent = path.rsplit_once('/').map_or("", |(p, _)| p).to_string();
+            children.entry(parent).or_default().push(a);
+            let start = vi.asset_chunk_index_starts[a] as usize;
+            let count = vi.asset_chunk_counts[a] as usize;
+            let bad_map = || {
+                LongtailError::InvalidArgument(format!(
+                    "version index asset `{path}` names chunks outside the index"
+                ))
+            };
+            let indexes = vi
+                .asset_chunk_indexes
+                .get(start..)
+                .and_then(|tail| tail.get(..count))
+                .ok_or_else(bad_map)?;
+            let mut offset = 0u64;
+            for (k, &ci) in indexes.iter().enumerate() {
+                chunk_offsets[start + k] = offset;
+                offset += *vi.chunk_sizes.get(ci as usize).ok_or_else(bad_map)? as u64;
+            }
+            assets.insert(path, a);
+        }
+        for entries in children.values_mut() {
+            entries.sort_by(|&x, &y| vi.path(x).ok().cmp(&vi.path(y).ok()));
+        }
+
+        let store_index = store.get_existing_content(&vi.chunk_hashes, 0).await?;
+        let mut location = HashMap::new();
+        for b in 0..store_index.block_count() as usize {
+            let start = store_index.block_chunks_offsets[b] as usize;
+            let count = store_index.block_chunk_counts[b] as usize;
+            // Skip a block whose chunk range runs off the arrays, as
+            // `cp::chunk_locations` does; `block` relies on the range.
+            let end = start.saturating_add(count);
+            if end > store_index.chunk_hashes.len() || end > store_index.chunk_sizes.len() {
+                continue;
+            }
+            let mut within = 0u64;
+            for k in start..end {
+                location
+                    .entry(store_index.chunk_hashes[k])
+                    .or_insert((b, within));
+                within += store_index.chunk_sizes[k] as u64;
+            }
+        }
+        Ok(VersionFs {
+            vi,
+            store,
+            owns_store,
+            assets,
+            children,
+            chunk_offsets,
+            store_index,
+            location,
+            hasher,
+            cache: Mutex::new(BlockCache::new(block_cache_bytes)),
+        })
+    }
+
+    /// The version index being served.
+    pub fn version_index(&self) -> &VersionIndex {
+        &self.vi
+    }
+
+    /// The file or directory at `path` (`""`, `.` or `/` for the root).
+    pub fn metadata(&self, path: &str) -> Result<VersionMetadata, LongtailError> {
+        let path = normalize(path);
+        if path.is_empty() {
+            return Ok(VersionMetadata {
+                path: String::new(),
+                is_dir: true,
+                size: 0,
+                permissions: 0o755,
+                chunk_count: 0,
+                content_hash: 0,
+            });
+        }
+        Ok(self.entry(self.asset(path)?))
+    }
+
+    /// The entries directly inside the directory at `path`, by name.
+    pub fn read_dir(&self, path: &str) -> Result<Vec<VersionMetadata>, LongtailError> {
+        let path = normalize(path);
+        if !path.is_empty() && !self.entry(self.asset(path)?).is_dir {
+            return Err(io_error(
+                path,
+                io::ErrorKind::NotADirectory,
+                "not a directory",
+            ));
+        }
+        Ok(self
+            .children
+            .get(path)
+            .map(|entries| entries.iter().map(|&a| self.entry(a)).collect())
+            .unwrap_or_default())
+    }
+
+    /// Up to `len` bytes of the file at `path` from `offset`: fewer at the end
+    /// of the file, none past it.
+    pub async fn read_at(&self, path: &str, offset: u64, len: u64) -> Result<Bytes, LongtailError> {
+        let path = normalize(path);
+        let a = self.asset(path)?;
+        if self.vi.path(a)?.ends_with('/') {
+            return Err(io_error(
+                path,
+                io::ErrorKind::IsADirectory,
+                "is a directory",
+            ));
+        }
+        let size = self.vi.asset_sizes[a];
+        let end = offset.saturating_add(len).min(size);
+        if offset >= end {
+            return Ok(Bytes::new());
+        }
+        let start = self.vi.asset_chunk_index_starts[a] as usize;
+        let count = self.vi.asset_chunk_counts[a] as usize;
+        let offsets = &self.chunk_offsets[start..start + count];
+        // The chunk holding `offset`: the last one starting at or before it.
+        let first = offsets.partition_point(|&o| o <= offset) - 1;
+        let mut out = Vec::with_capacity((end - offset) as usize);
+        let chunks = &self.vi.asset_chunk_indexes[start..start + count];
+        for (&chunk_start, &ci) in offsets.iter().zip(chunks).skip(first) {
+            if chunk_start >= end {
+                break;
+            }
+            let ci = ci as usize;
+            let hash = self.vi.chunk_hashes[ci];
+            let chunk_len = self.vi.chunk_sizes[ci] as u64;
+            let &(b, within) = self.location.get(&hash).ok_or_else(|| {
+                LongtailError::InvalidArgument(format!(
+                    "chunk {hash:#018x} of `{path}` is not present in the store"
+                ))
+            })?;
+            let payload = self.block(b).await?;
+            // The part of this chunk inside [offset, end).
+            let from = offset.saturating_sub(chunk_start);
+            let to = (end - chunk_start).min(chunk_len);
+            let s = (within + from) as usize;
+            let e = (within + to) as usize;
+            let bytes = payload.get(s..e).ok_or_else(|| {
+                LongtailError::InvalidArgument(format!(
+                    "block {:#018x} shorter than indexed chunk range",
+                    self.store_index.block_hashes[b]
+                ))
+            })?;
+            out.extend_from_slice(bytes);
+        }
+        Ok(out.into())
+    }
+
+    /// Flush the store; close it too when [`open`](Self::open) opened it.
+    pub async fn close(self) -> Result<(), LongtailError> {
+        if self.owns_store {
+            crate::store_lifecycle::finish_store(&self.store, Ok(())).await
+        } else {
+            crate::store_lifecycle::flush_store(&self.store, Ok(())).await
+        }
+    }
+
+    fn asset(&self, path: &str) -> Result<usize, LongtailError> {
+        self.assets
+            .get(path)
+            .copied()
+            .ok_or_else(|| io_error(path, io::ErrorKind::NotFound, "not in the version"))
+    }
+
+    fn entry(&self, a: usize) -> VersionMetadata {
+        let raw = self.vi.path(a).unwrap_or_default();
+        VersionMetadata {
+            path: fs_util::strip_trailing_slash(raw).to_string(),
+            is_dir: raw.ends_with('/'),
+            size: self.vi.asset_sizes[a],
+            permissions: self.vi.permissions[a].bits(),
+            chunk_count: self.vi.asset_chunk_counts[a],
+            content_hash: self.vi.content_hashes[a],
+        }
+    }
+
+    /// Block `b` of the store index, decompressed and checked: from the cache
+    /// or fetched into it.
+    async fn block(&self, b: usize) -> Result<Bytes, LongtailError> {
+        let block_hash = self.store_index.block_hashes[b];
+        if let Some(payload) = self.cache.lock().expect("block cache").get(block_hash) {
+            return Ok(payload);
+        }
+        let payload = Bytes::from(self.store.get_stored_block(block_hash).await?.payload);
+        let start = self.store_index.block_chunks_offsets[b] as usize;
+        let count = self.store_index.block_chunk_counts[b] as usize;
+        let mut within = 0usize;
+        for k in start..start + count {
+            let size = self.store_index.chunk_sizes[k] as usize;
+            let want = self.store_index.chunk_hashes[k];
+            let got = payload
+                .get(within..within + size)
+                .map(|bytes| self.hasher.hash(bytes));
+            if got != Some(want) {
+                return Err(LongtailError::ValidationMismatch(format!(
+                    "block {block_hash:#018x} does not hold chunk {want:#018x} at {within}; \
+                     the store's content does not match its index"
+                )));
+            }
+            within += size;
+        }
+        self.cache
+            .lock()
+            .expect("block cache")
+            .insert(block_hash, payload.clone());
+        Ok(payload)
+    }
+}
+
+/// `path` without leading `/` or `./` and trailing `/`; the root is `""`.
+fn normalize(path: &str) -> &str {
+    let path = path.trim_start_matches("./").trim_matches('/');
+    if path == "." { "" } else { path }
+}
+
+fn io_error(path: &str, kind: io::ErrorKind, what: &str) -> LongtailError {
+    LongtailError::Io {
+        context: format!("`{path}`"),
+        source: io::Error::new(kind, what.to_string()),
+    }
+}
+
+/// Decompressed blocks, evicted least recently used first once over budget.
+struct BlockCache {
+    budget: u64,
+    bytes: u64,
+    tick: u64,
+    blocks: HashMap<u64, (Bytes, u64)>,
+}
+
+impl BlockCache {
+    fn new(budget: u64) -> BlockCache {
+        BlockCache {
+            budget,
+            bytes: 0,
+            tick: 0,
+            blocks: HashMap::new(),
+        }
+    }
+
+    fn get(&mut self, block_hash: u64) -> Option<Bytes> {
+        self.tick += 1;
+        let (payload, used) = self.blocks.get_mut(&block_hash)?;
+        *used = self.tick;
+        Some(payload.clone())
+    }
+
+    fn insert(&mut self, block_hash: u64, payload: Bytes) {
+        self.tick += 1;
+        let len = payload.len() as u64;
+        if let Some((old, _)) = self.blocks.insert(block_hash, (payload, self.tick)) {
+            self.bytes -= old.len() as u64;
+        }
+        self.bytes += len;
+        while self.bytes > self.budget && self.blocks.len() > 1 {
+            let (&oldest, _) = self
+                .blocks
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .expect("more than one block");
+            let (evicted, _) = self.blocks.remove(&oldest).expect("just found");
+            self.bytes -= evicted.len() as u64;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::{BlockCache, normalize};
+
+    #[test]
+    fn cache_evicts_least_recently_used_first() {
+        let mut cache = BlockCache::new(10);
+        cache.insert(1, Bytes::from(vec![0; 4]));
+        cache.insert(2, Bytes::from(vec![0; 4]));
+        assert!(cache.get(1).is_some());
+        cache.insert(3, Bytes::from(vec![0; 4]));
+        assert!(cache.get(2).is_none(), "2 was the least recently used");
+        assert!(cache.get(1).is_some() && cache.get(3).is_some());
+        // A block over budget on its own is still kept, alone.
+        cache.insert(4, Bytes::from(vec![0; 32]));
+        assert_eq!(cache.blocks.len(), 1);
+        assert!(cache.get(4).is_some());
+    }
+
+    #[test]
+    fn paths_normalize_to_index_form() {
+        assert_eq!(normalize(""), "");
+        assert_eq!(normalize("/"), "");
+        assert_eq!(normalize("."), "");
+        assert_eq!(normalize("./a/b/"), "a/b");
+        assert_eq!(normalize("/a"), "a");
+    }
+}
@@ -0,0 +1,221 @@
+//! `who-uses`: which versions, and which of their assets, depend on a block,
+//! a chunk or an asset path.
+//!
+//! A version depends on a block when a downsync of it would fetch the block:
+//! the block holds one of its chunks in the store index narrowed by
+//! `GetExistingContent(chunks, 0)`. A chunk more than one block holds resolves
+//! to the first, as the download does, so a block whose every chunk is stored
+//! again elsewhere may be used by no one.
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+use longtail_core::VersionIndex;
+use longtail_store::AccessType;
+use longtail_store::uri::{BlockStoreOpts, create_block_store_for_uri};
+use serde::{Deserialize, Serialize};
+
+use crate::error::LongtailError;
+#[cfg(feature = "s3")]
+use crate::fs_util::{S3OptionsArg, default_s3};
+use crate::inspect::read_version_index_from_uri;
+use crate::list::list_version_paths;
+
+/// What [`who_uses`] looks for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+#[serde(rename_all = "snake_case")]
+pub enum UsageTarget {
+    Block(u64),
+    Chunk(u64),
+    /// An asset path as the version index records it; a directory's ends in
+    /// `/`.
+    Path(String),
+}
+
+/// Options for [`who_uses`].
+#[non_exhaustive]
+pub struct WhoUsesOptions {
+    pub storage_uri: String,
+    pub target: UsageTarget,
+    /// The versions to search.
+    pub version_index_paths: Vec<String>,
+    /// Also search every `.lvi` under this URI, narrowed by
+    /// `versions_prefix`.
+    pub versions_uri: Option<String>,
+    pub versions_prefix: String,
+    pub remote_worker_count: usize,
+    #[cfg(feature = "s3")]
+    pub s3_options: S3OptionsArg,
+}
+
+impl WhoUsesOptions {
+    pub fn new(
+        storage_uri: impl Into<String>,
+        target: UsageTarget,
+        version_index_paths: Vec<String>,
+    ) -> Self {
+        WhoUsesOptions {
+            storage_uri: storage_uri.into(),
+            target,
+            version_index_paths,
+            versions_uri: None,
+            versions_prefix: String::new(),
+            remote_worker_count: 0,
+            #[cfg(feature = "s3")]
+            s3_options: default_s3(),
+        }
+    }
+}
+
+/// One version that depends on the target.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct VersionUse {
+    /// The version index URI.
+    pub path: String,
+    /// Its assets that depend on the target, by path.
+    pub assets: Vec<String>,
+    /// The blocks through which it does, ascending: the target itself for a
+    /// block, the block holding it for a chunk, the asset's blocks for a path.
+    /// A chunk no block holds adds none.
+    pub blocks: Vec<u64>,
+}
+
+/// The outcome of [`who_uses`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct WhoUsesReport {
+    /// Versions searched.
+    pub versions_checked: u32,
+    /// The ones that depend on the target, in the order searched.
+    pub users: Vec<VersionUse>,
+    /// Versions whose index could not be read, so were not searched.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<SkippedVersion>,
+}
+
+/// A version [`who_uses`] could not search.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct SkippedVersion {
+    /// The version index URI.
+    pub path: String,
+    pub error: String,
+}
+
+/// `who-uses`: search each version for assets depending on `opts.target`.
+pub async fn who_uses(opts: WhoUsesOptions) -> Result<WhoUsesReport, LongtailError> {
+    let s3 = crate::s3_arg!(opts);
+    let mut paths = opts.version_index_paths.clone();
+    if let Some(uri) = &opts.versions_uri {
+        let root = uri.trim_end_matches('/');
+        paths.extend(
+            list_version_paths(uri, &opts.versions_prefix, &s3)
+                .await?
+                .into_iter()
+                .map(|name| format!("{root}/{name}")),
+        );
+    }
+
+    let store_opts = BlockStoreOpts {
+        access_type: AccessType::ReadOnly,
+        worker_count: opts.remote_worker_count,
+        cache_dir: None,
+        pool: Arc::new(crate::version::build_pool(1)?),
+        version_local_store_index: None,
+        max_block_bytes: None,
+        events: None,
+        pause: None,
+        #[cfg(feature = "s3")]
+        s3_options: s3.clone(),
+    };
+    let store = create_block_store_for_uri(&opts.storage_uri, store_opts).await?;
+    let searched = async {
+        let mut report = WhoUsesReport::default();
+        for path in &paths {
+            // One unreadable version does not hide what the others say.
+            let vi = match read_version_index_from_uri(path, &s3).await {
+                Ok(vi) => vi,
+                Err(e) => {
+                    report.skipped.push(SkippedVersion {
+                        path: path.clone(),
+                        error: e.to_string(),
+                    });
+                    continue;
+                }
+            };
+            report.versions_checked += 1;
+            let si = store.get_existing_content(&vi.chunk_hashes, 0).await?;
+            // Each stored chunk's block, first occurrence winning.
+            let mut block_of: HashMap<u64, u64> = HashMap::new();
+            for b in 0..si.block_hashes.len() {
+                let start = si.block_chunks_offsets[b] as usize;
+                let count = si.block_chunk_counts[b] as usize;
+                for &h in &si.chunk_hashes[start..start + count] {
+                    block_of.entry(h).or_insert(si.block_hashes[b]);
+                }
+            }
+            report
+                .users
+                .extend(search(path, &vi, &block_of, &opts.target)?);
+        }
+        Ok(report)
+    }
+    .await;
+    crate::store_lifecycle::finish_store(&store, searched).await
+}
+
+/// How the version `vi` at `path` depends on `target`, or `None` when it does
+/// not.
+fn search(
+    path: &str,
+    vi: &VersionIndex,
+    block_of: &HashMap<u64, u64>,
+    target: &UsageTarget,
+) -> Result<Option<VersionUse>, LongtailError> {
+    let mut assets = Vec::new();
+    let mut blocks = BTreeSet::new();
+    for a in 0..vi.asset_count() as usize {
+        let start = vi.asset_chunk_index_starts[a] as usize;
+        let count = vi.asset_chunk_counts[a] as usize;
+        let mut chunks = vi.asset_chunk_indexes[start..start + count]
+            .iter()
+            .map(|&c| vi.chunk_hashes[c as usize]);
+        let uses = match target {
+            UsageTarget::Block(h) => {
+                let found = chunks.any(|c| block_of.get(&c) == Some(h));
+                if found {
+                    blocks.insert(*h);
+                }
+                found
+            }
+            UsageTarget::Chunk(h) => {
+                let found = chunks.any(|c| c == *h);
+                if found {
+                    blocks.extend(block_of.get(h));
+                }
+                found
+            }
+            UsageTarget::Path(p) => {
+                let found = vi.path(a)? == p;
+                if found {
+                    blocks.extend(chunks.filter_map(|c| block_of.get(&c)));
+                }
+                found
+            }
+        };
+        if uses {
+            assets.push(vi.path(a)?.to_string());
+        }
+    }
+    if assets.is_empty() {
+        return Ok(None);
+    }
+    assets.sort();
+    Ok(Some(VersionUse {
+        path: path.to_string(),
+        assets,
+        blocks: blocks.into_iter().collect(),
+    }))
+}
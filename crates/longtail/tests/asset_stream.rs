@@ -0,0 +1,123 @@
+//! `open_asset_stream` over a store where one asset spans many blocks: the
+//! bytes come out in order at any read-ahead, and a tampered block surfaces as
+//! a read error carrying the validation failure.
+
+use std::path::{Path, PathBuf};
+
+use longtail::{AssetStreamOptions, LongtailError, open_asset_stream};
+use longtail_testkit::publish::{noise, publish_small_blocks};
+use tokio::io::AsyncReadExt;
+
+fn blocks(dir: &Path) -> Vec<PathBuf> {
+    let mut out = Vec::new();
+    for e in std::fs::read_dir(dir).unwrap() {
+        let p = e.unwrap().path();
+        if p.is_dir() {
+            out.extend(blocks(&p));
+        } else if p.extension().is_some_and(|e| e == "lsb") {
+            out.push(p);
+        }
+    }
+    out
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn streams_a_many_block_asset_in_order() {
+    let tmp = tempfile::tempdir().unwrap();
+    let (src, store, lvi) = (
+        tmp.path().join("src"),
+        tmp.path().join("store"),
+        tmp.path().join("v.lvi"),
+    );
+    std::fs::create_dir_all(src.join("sub")).unwrap();
+    let data = noise(0x9E37_79B9_7F4A_7C15, 400_000);
+    std::fs::write(src.join("sub/big.bin"), &data).unwrap();
+    std::fs::write(src.join("small.txt"), b"small").unwrap();
+    publish_small_blocks(&src, &store, &lvi, "none").await;
+    assert!(
+        blocks(&store).len() > 4,
+        "the asset must span several blocks"
+    );
+
+    for read_ahead in [1, 3, 64] {
+        let mut opts = AssetStreamOptions::new(
+            store.to_string_lossy(),
+            lvi.to_string_lossy(),
+            "sub/big.bin",
+        );
+        opts.read_ahead = read_ahead;
+        let mut stream = open_asset_stream(opts).await.unwrap();
+        assert_eq!(stream.size(), data.len() as u64);
+        // Small reads, so a run is handed out over many calls.
+        let mut out = Vec::new();
+        let mut buf = [0u8; 1000];
+        loop {
+            let n = stream.read(&mut buf).await.unwrap();
+            if n == 0 {
+                break;
+            }
+            out.extend_from_slice(&buf[..n]);
+        }
+        assert!(out == data, "read_ahead {read_ahead}");
+    }
+
+    // Dropping a stream part-way is fine.
+    let mut stream = open_asset_stream(AssetStreamOptions::new(
+        store.to_string_lossy(),
+        lvi.to_string_lossy(),
+        "sub/big.bin",
+    ))
+    .await
+    .unwrap();
+    let mut head = [0u8; 10];
+    stream.read_exact(&mut head).await.unwrap();
+    assert_eq!(head, data[..10]);
+    drop(stream);
+
+    let err = open_asset_stream(AssetStreamOptions::new(
+        store.to_string_lossy(),
+        lvi.to_string_lossy(),
+        "missing.bin",
+    ))
+    .await
+    .err()
+    .unwrap();
+    assert!(matches!(err, LongtailError::InvalidArgument(_)), "{err}");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn a_tampered_block_fails_the_read() {
+    let tmp = tempfile::tempdir().unwrap();
+    let (src, store, lvi) = (
+        tmp.path().join("src"),
+        tmp.path().join("store"),
+        tmp.path().join("v.lvi"),
+    );
+    std::fs::create_dir_all(&src).unwrap();
+    std::fs::write(src.join("asset.bin"), vec![0xAB; 20_000]).unwrap();
+    publish_small_blocks(&src, &store, &lvi, "none").await;
+    // The tail of an uncompressed block is payload: flip a byte of it.
+    let block = blocks(&store).pop().unwrap();
+    let mut bytes = std::fs::read(&block).unwrap();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0xFF;
+    std::fs::write(&block, &bytes).unwrap();
+
+    let mut stream = open_asset_stream(AssetStreamOptions::new(
+        store.to_string_lossy(),
+        lvi.to_string_lossy(),
+        "asset.bin",
+    ))
+    .await
+    .unwrap();
+    let err = stream.read_to_end(&mut Vec::new()).await.unwrap_err();
+    let inner = err
+        .into_inner()
+        .unwrap()
+        .downcast::<LongtailError>()
+        .unwrap();
+    assert!(
+        matches!(*inner, LongtailError::ValidationMismatch(_)),
+        "{inner}"
+    );
+}
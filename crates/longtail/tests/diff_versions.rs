@@ -0,0 +1,93 @@
+//! `diff_versions` against the committed v1 → v2 chain, cross-checked with the
+//! plan a real update between the two makes.
+
+use longtail::{DiffVersionsOptions, DownsyncOptions, diff_versions, downsync, plan_downsync};
+use longtail_testkit::paths::{default_lvi, default_store};
+
+#[tokio::test]
+async fn lists_each_kind_of_change_with_sizes() {
+    let report = diff_versions(DiffVersionsOptions::new(
+        default_lvi("chain-v1.lvi"),
+        default_lvi("chain-v2.lvi"),
+    ))
+    .await
+    .unwrap();
+    let paths = |list: &[longtail::AssetChange]| -> Vec<String> {
+        list.iter().map(|c| c.path.clone()).collect()
+    };
+    assert_eq!(
+        paths(&report.added),
+        [
+            "folder2/",
+            "folder2/anotherabitoftextinasubfolder2.txt",
+            "renamed.txt",
+            "stuff.txt"
+        ]
+    );
+    assert_eq!(paths(&report.removed), ["to-delete.txt", "to-rename.txt"]);
+    assert_eq!(paths(&report.content_modified), ["abitoftext.txt"]);
+    assert_eq!(report.content_modified[0].previous_size, Some(19));
+    assert_eq!(report.content_modified[0].size, 39);
+    assert_eq!(paths(&report.permissions_modified), ["script.sh"]);
+    assert_eq!(
+        report.permissions_modified[0].previous_permissions,
+        Some(0o644)
+    );
+    assert_eq!(report.permissions_modified[0].permissions, 0o755);
+
+    // A rename keeps its content, so its chunks are required but not new.
+    assert!(
+        report.new_chunk_count < report.required_chunk_count,
+        "{report:?}"
+    );
+    assert!(
+        report.new_chunk_bytes < report.required_chunk_bytes,
+        "{report:?}"
+    );
+    assert!(report.download.is_none(), "no store was given");
+
+    let same = diff_versions(DiffVersionsOptions::new(
+        default_lvi("chain-v2.lvi"),
+        default_lvi("chain-v2.lvi"),
+    ))
+    .await
+    .unwrap();
+    assert_eq!(same, longtail::VersionDiffReport::default());
+}
+
+/// The estimate names the blocks the update's own plan fetches, and the
+/// compressed size is what those block files hold on disk.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn the_download_estimate_matches_the_update_plan() {
+    let mut opts =
+        DiffVersionsOptions::new(default_lvi("chain-v1.lvi"), default_lvi("chain-v2.lvi"));
+    opts.storage_uri = Some(default_store());
+    let estimate = diff_versions(opts).await.unwrap().download.unwrap();
+    assert_eq!((estimate.unlisted_blocks, estimate.missing_chunks), (0, 0));
+
+    let tmp = tempfile::tempdir().unwrap();
+    let target = tmp.path().to_string_lossy().into_owned();
+    let mut v1 = DownsyncOptions::new(
+        vec![default_lvi("chain-v1.lvi")],
+        default_store(),
+        target.clone(),
+    );
+    v1.cache_target_index = false;
+    downsync(v1).await.unwrap();
+    let mut v2 = DownsyncOptions::new(vec![default_lvi("chain-v2.lvi")], default_store(), target);
+    v2.cache_target_index = false;
+    let plan = plan_downsync(v2).await.unwrap();
+
+    assert_eq!(estimate.block_count as usize, plan.required_blocks.len());
+    assert_eq!(estimate.payload_bytes, plan.fetch_bytes);
+    let on_disk: u64 = plan
+        .required_blocks
+        .iter()
+        .map(|&h| {
+            let path = std::path::Path::new(&default_store())
+                .join(longtail_store::block_path("chunks", h));
+            std::fs::metadata(path).unwrap().len()
+        })
+        .sum();
+    assert_eq!(estimate.compressed_bytes, on_disk);
+}
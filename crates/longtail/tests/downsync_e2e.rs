@@ -2,9 +2,13 @@
 //! compared to the committed tree manifests. Linux-only; skipped under miri.
 
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 
-use longtail::{DownsyncOptions, Progress, ProgressSink, downsync};
+use longtail::{DownsyncOptions, Progress, ProgressSink, StoreError, downsync};
+use longtail_core::{StoreIndex, StoredBlock};
+use longtail_store::uri::{BlockStoreOpts, create_block_store_for_uri};
+use longtail_store::{AccessType, BlockStore, StatsSnapshot};
 use longtail_testkit::paths::fixtures_dir;
 use longtail_testkit::tree_manifest::TreeManifest;
 
@@ -592,3 +596,162 @@ fn walk_files(root: &std::path::Path) -> Vec<PathBuf> {
     }
     out
 }
+
+/// Counts the blocks that pass through it, and forwards everything.
+struct Counting {
+    inner: Arc<dyn BlockStore>,
+    gets: AtomicU64,
+    puts: AtomicU64,
+}
+
+#[async_trait::async_trait]
+impl BlockStore for Counting {
+    async fn put_stored_block(&self, block: StoredBlock) -> Result<(), StoreError> {
+        self.puts.fetch_add(1, Ordering::Relaxed);
+        self.inner.put_stored_block(block).await
+    }
+    async fn get_stored_block(&self, block_hash: u64) -> Result<StoredBlock, StoreError> {
+        self.gets.fetch_add(1, Ordering::Relaxed);
+        self.inner.get_stored_block(block_hash).await
+    }
+    async fn preflight_get(&self, block_hashes: &[u64]) -> Result<(), StoreError> {
+        self.inner.preflight_get(block_hashes).await
+    }
+    async fn get_existing_content(
+        &self,
+        chunk_hashes: &[u64],
+        min_block_usage_percent: u32,
+    ) -> Result<StoreIndex, StoreError> {
+        self.inner
+            .get_existing_content(chunk_hashes, min_block_usage_percent)
+            .await
+    }
+    async fn prune_blocks(&self, keep_block_hashes: &[u64]) -> Result<u32, StoreError> {
+        self.inner.prune_blocks(keep_block_hashes).await
+    }
+    async fn flush(&self) -> Result<(), StoreError> {
+        self.inner.flush().await
+    }
+    async fn close(&self) -> Result<(), StoreError> {
+        self.inner.close().await
+    }
+    fn stats(&self) -> StatsSnapshot {
+        self.inner.stats()
+    }
+}
+
+async fn counting(store: &std::path::Path, access_type: AccessType) -> Arc<Counting> {
+    let pool = Arc::new(rayon::ThreadPoolBuilder::new().build().unwrap());
+    let inner = create_block_store_for_uri(
+        &store.to_string_lossy(),
+        BlockStoreOpts::new(access_type, pool),
+    )
+    .await
+    .unwrap();
+    Arc::new(Counting {
+        inner,
+        gets: AtomicU64::new(0),
+        puts: AtomicU64::new(0),
+    })
+}
+
+/// A caller-built store stands in for the composed one on both paths: every
+/// block goes through it, and the run flushes it but leaves it open.
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn a_caller_built_store_carries_upsync_and_downsync() {
+    pin_umask();
+    let tmp = tempfile::tempdir().unwrap();
+    let (store, src) = (tmp.path().join("store"), tmp.path().join("src"));
+    let lvi = tmp.path().join("v1.lvi");
+    std::fs::create_dir_all(src.join("sub")).unwrap();
+    std::fs::write(src.join("a.txt"), "through a decorator\n").unwrap();
+    std::fs::write(src.join("sub/b.bin"), vec![7u8; 200_000]).unwrap();
+
+    let writer = counting(&store, AccessType::ReadWrite).await;
+    let mut up = longtail::UpsyncOptions::new(
+        src.to_string_lossy().into_owned(),
+        // Never opened: the caller's store replaces it.
+        "unused://",
+        lvi.to_string_lossy().into_owned(),
+    );
+    up.block_store = Some(writer.clone());
+    let report = longtail::upsync(up).await.expect("upsync");
+    assert!(report.blocks_written > 0);
+    assert_eq!(
+        writer.puts.load(Ordering::Relaxed),
+        report.blocks_written as u64
+    );
+    writer.close().await.unwrap();
+
+    let reader = counting(&store, AccessType::ReadOnly).await;
+    let target = tmp.path().join("out");
+    let mut down = DownsyncOptions::new(
+        vec![lvi.to_string_lossy().into_owned()],
+        "unused://",
+        target.to_string_lossy().into_owned(),
+    );
+    down.cache_target_index = false;
+    down.block_store = Some(reader.clone());
+    let report = downsync(down).await.expect("downsync");
+    assert_eq!(report.blocks_fetched, reader.gets.load(Ordering::Relaxed));
+    assert!(report.blocks_fetched > 0);
+    assert_eq!(
+        std::fs::read(target.join("sub/b.bin")).unwrap(),
+        std::fs::read(src.join("sub/b.bin")).unwrap()
+    );
+
+    // Still open: a second run over the same store reports only its own reads.
+    let again = tmp.path().join("again");
+    let mut down = DownsyncOptions::new(
+        vec![lvi.to_string_lossy().into_owned()],
+        "unused://",
+        again.to_string_lossy().into_owned(),
+    );
+    down.cache_target_index = false;
+    down.block_store = Some(reader.clone());
+    let second = downsync(down).await.expect("second downsync");
+    assert_eq!(second.blocks_fetched, report.blocks_fetched);
+    reader.close().await.unwrap();
+}
+
+/// A registered scheme carries a whole round trip: the store, and the `.lvi`
+/// upsync writes and downsync reads, all live behind it.
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn a_registered_scheme_carries_upsync_and_downsync() {
+    pin_umask();
+    let tmp = tempfile::tempdir().unwrap();
+    let backing = tmp.path().join("cas");
+    let root = backing.clone();
+    longtail_store::register_blob_scheme("studio-cas", move |uri: &str| {
+        let rest = uri.split_once("://").unwrap().1;
+        Ok(Box::new(longtail_store::FsBlobStore::new(
+            root.join(rest),
+            false,
+        )))
+    })
+    .unwrap();
+
+    let src = tmp.path().join("src");
+    std::fs::create_dir_all(&src).unwrap();
+    std::fs::write(src.join("a.txt"), "stored in-house\n").unwrap();
+    let up = longtail::UpsyncOptions::new(
+        src.to_string_lossy().into_owned(),
+        "studio-cas://game/store",
+        "studio-cas://game/versions/v1.lvi",
+    );
+    longtail::upsync(up).await.expect("upsync");
+    assert!(backing.join("game/versions/v1.lvi").is_file());
+
+    let target = tmp.path().join("out");
+    let mut down = DownsyncOptions::new(
+        vec!["studio-cas://game/versions/v1.lvi".into()],
+        "studio-cas://game/store",
+        target.to_string_lossy().into_owned(),
+    );
+    down.cache_target_index = false;
+    downsync(down).await.expect("downsync");
+    assert_eq!(
+        std::fs::read_to_string(target.join("a.txt")).unwrap(),
+        "stored in-house\n"
+    );
+}
@@ -0,0 +1,84 @@
+//! `explain_update` against the committed v1 → v2 chain: every byte of the
+//! download is attributed, and the totals are the ones `diff_versions`
+//! estimates.
+
+use longtail::{DiffVersionsOptions, ExplainUpdateOptions, diff_versions, explain_update};
+use longtail_testkit::paths::{default_lvi, default_store};
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn attributes_the_whole_download_to_the_changed_assets() {
+    let report = explain_update(ExplainUpdateOptions::new(
+        default_lvi("chain-v1.lvi"),
+        default_lvi("chain-v2.lvi"),
+        default_store(),
+    ))
+    .await
+    .unwrap();
+
+    let mut opts =
+        DiffVersionsOptions::new(default_lvi("chain-v1.lvi"), default_lvi("chain-v2.lvi"));
+    opts.storage_uri = Some(default_store());
+    let estimate = diff_versions(opts).await.unwrap().download.unwrap();
+    assert_eq!(report.block_count, estimate.block_count);
+    assert_eq!(
+        report.total.needed_bytes + report.total.unneeded_bytes,
+        estimate.payload_bytes
+    );
+    assert_eq!(report.total.compressed_bytes, estimate.compressed_bytes);
+    assert_eq!(report.missing_chunks, 0);
+
+    // The per-asset and per-block shares add up to the totals.
+    let sum = |costs: &mut dyn Iterator<Item = longtail::UpdateCost>| {
+        costs.fold((0, 0, 0), |(n, u, c), x| {
+            (
+                n + x.needed_bytes,
+                u + x.unneeded_bytes,
+                c + x.compressed_bytes,
+            )
+        })
+    };
+    let total = (
+        report.total.needed_bytes,
+        report.total.unneeded_bytes,
+        report.total.compressed_bytes,
+    );
+    assert_eq!(sum(&mut report.assets.iter().map(|a| a.cost)), total);
+    assert_eq!(sum(&mut report.blocks.iter().map(|b| b.cost)), total);
+
+    // Only assets the update writes appear, most expensive first.
+    let paths: Vec<&str> = report.assets.iter().map(|a| a.path.as_str()).collect();
+    for p in &paths {
+        assert!(
+            !["script.sh", "to-delete.txt", "to-rename.txt"].contains(p),
+            "{paths:?}"
+        );
+    }
+    assert!(paths.contains(&"abitoftext.txt"), "{paths:?}");
+    assert!(
+        report
+            .assets
+            .windows(2)
+            .all(|w| w[0].cost.compressed_bytes >= w[1].cost.compressed_bytes)
+    );
+    let folder2 = report
+        .directories
+        .iter()
+        .find(|d| d.path == "folder2/")
+        .expect("folder2/ holds a changed asset");
+    let under: u64 = report
+        .assets
+        .iter()
+        .filter(|a| a.path.starts_with("folder2/"))
+        .map(|a| a.cost.needed_bytes)
+        .sum();
+    assert_eq!(folder2.cost.needed_bytes, under);
+
+    let same = explain_update(ExplainUpdateOptions::new(
+        default_lvi("chain-v2.lvi"),
+        default_lvi("chain-v2.lvi"),
+        default_store(),
+    ))
+    .await
+    .unwrap();
+    assert_eq!(same, longtail::UpdateExplanation::default());
+}
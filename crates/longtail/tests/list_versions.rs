@@ -0,0 +1,72 @@
+//! `list_versions` over a folder holding a `put` layout beside loose version
+//! indexes.
+
+use longtail::{ListVersionsOptions, PutOptions, list_versions, put};
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn finds_put_layouts_and_loose_indexes() {
+    let tmp = tempfile::tempdir().unwrap();
+    let src = tmp.path().join("src");
+    std::fs::create_dir_all(src.join("sub")).unwrap();
+    std::fs::write(src.join("a.txt"), b"alpha").unwrap();
+    std::fs::write(src.join("sub/b.txt"), b"bravo bravo").unwrap();
+    let root = tmp.path().join("root");
+    std::fs::create_dir_all(&root).unwrap();
+    let config = root.join("game.json");
+    put(PutOptions::new(
+        config.to_string_lossy(),
+        src.to_string_lossy(),
+    ))
+    .await
+    .unwrap();
+    // A loose index with no get-config or version-local store index, and one
+    // that is not an index at all.
+    std::fs::copy(
+        root.join("version-data/version-index/game.lvi"),
+        root.join("loose.lvi"),
+    )
+    .unwrap();
+    std::fs::write(root.join("broken.lvi"), b"not an index").unwrap();
+    let root_uri = root.to_string_lossy().into_owned();
+
+    let versions = list_versions(ListVersionsOptions::new(root_uri.clone()))
+        .await
+        .unwrap();
+    let paths: Vec<&str> = versions.iter().map(|v| v.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        [
+            "broken.lvi",
+            "loose.lvi",
+            "version-data/version-index/game.lvi"
+        ]
+    );
+    let broken = &versions[0];
+    assert!(broken.error.is_some(), "{broken:?}");
+
+    let game = &versions[2];
+    assert_eq!(game.get_config.as_deref(), Some("game.json"));
+    assert_eq!(game.error, None);
+    // `sub/` is an asset too.
+    assert_eq!((game.asset_count, game.total_asset_size), (3, 16));
+    assert_eq!(
+        game.compression_types,
+        [longtail::compression_type_for_name("zstd").unwrap()]
+    );
+    assert!(game.last_modified.is_some());
+    let on_disk = std::fs::metadata(root.join("version-data/version-index/game.lvi")).unwrap();
+    assert_eq!(game.object_size, on_disk.len());
+
+    let loose = &versions[1];
+    assert_eq!((loose.get_config.as_ref(), loose.asset_count), (None, 3));
+    assert!(loose.compression_types.is_empty(), "no store was given");
+
+    // With the store, the loose index's compression comes from its blocks;
+    // the prefix narrows the listing.
+    let mut opts = ListVersionsOptions::new(root_uri);
+    opts.prefix = "loose".into();
+    opts.storage_uri = Some(root.join("store").to_string_lossy().into_owned());
+    let versions = list_versions(opts).await.unwrap();
+    assert_eq!(versions.len(), 1);
+    assert_eq!(versions[0].compression_types, game.compression_types);
+}
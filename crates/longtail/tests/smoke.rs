@@ -8,7 +8,9 @@ use std::sync::Mutex;
 use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 
 use longtail::{
-    DownsyncOptions, LongtailError, Progress, ProgressSink, downsync, downsync_blocking,
+    BlockSource, DownsyncOptions, DownsyncPlan, Event, LongtailError, PauseToken, Progress,
+    ProgressSink, StoreSession, StoreSessionOptions, StreamEvent, downsync, downsync_blocking,
+    downsync_with_events, execute_plan, plan_downsync,
 };
 use longtail_testkit::paths::fixtures_dir;
 use longtail_testkit::tree_manifest::TreeManifest;
@@ -440,6 +442,71 @@ impl ProgressSink for CancelOnPhase {
     }
 }
 
+/// Pauses the run when a named phase begins, and resumes it from another
+/// thread after `hold`.
+struct PauseOnPhase {
+    phase: &'static str,
+    token: PauseToken,
+    hold: std::time::Duration,
+}
+impl ProgressSink for PauseOnPhase {
+    fn on_progress(&self, _p: Progress) {}
+    fn on_phase(&self, phase: &str) {
+        if phase == self.phase {
+            self.token.pause();
+            let token = self.token.clone();
+            let hold = self.hold;
+            std::thread::spawn(move || {
+                std::thread::sleep(hold);
+                token.resume();
+            });
+        }
+    }
+}
+
+/// A pause parks the apply and resumes it in place: the run completes without
+/// being restarted, and the time it spent parked is reported as the phase's
+/// idle time rather than as work.
+#[test]
+fn a_paused_apply_resumes_in_place_and_reports_its_idle_time() {
+    pin_umask();
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(4)
+        .enable_all()
+        .build()
+        .unwrap();
+    let tmp = tempfile::tempdir().unwrap();
+    let target = tmp.path().join("out");
+    let mut opts = chain_opts(&target, "chain-v1.lvi");
+    opts.cache_target_index = false;
+    rt.block_on(downsync(opts)).expect("v1 downsync");
+
+    let hold = std::time::Duration::from_millis(300);
+    let token = PauseToken::new();
+    let mut opts = chain_opts(&target, "chain-v2.lvi");
+    opts.cache_target_index = false;
+    opts.pause = Some(token.clone());
+    opts.progress = Some(Arc::new(PauseOnPhase {
+        phase: "Updating version",
+        token: token.clone(),
+        hold,
+    }));
+    let report = rt.block_on(downsync(opts)).expect("paused downsync");
+    assert!(!token.is_paused());
+
+    let apply = report.phases.iter().find(|p| p.phase == "apply").unwrap();
+    assert!(
+        apply.idle_millis >= hold.as_millis() as u64 - 50,
+        "the pause is the apply's idle time: {apply:?}"
+    );
+    let idle: u64 = report.phases.iter().map(|p| p.idle_millis).sum();
+    assert_eq!(idle, apply.idle_millis, "{:?}", report.phases);
+    TreeManifest::capture(&target)
+        .unwrap()
+        .compare(&chain_manifest("chain-v2.json"), cfg!(windows))
+        .expect("resumed tree matches manifest");
+}
+
 /// Resume with the target-index cache on — the library and CLI default.
 ///
 /// Every other test in this file sets `cache_target_index = false`, so the
@@ -626,3 +693,483 @@ fn a_stale_cache_index_hides_damage_a_full_scan_finds() {
         "a full scan must heal what the cache hid"
     );
 }
+
+/// A run resumed from an apply journal checks the assets the journal names and
+/// nothing else.
+///
+/// The cancelled run leaves the journal behind with the v1 → v2 write set in
+/// it. Damage to an asset outside that set — one v1 and v2 share — is invisible
+/// to the resumed run, which is the point: it did not hash the whole tree. The
+/// journal is gone once the resume succeeds, so the run after that scans, and
+/// heals it.
+#[test]
+fn a_journaled_resume_verifies_only_what_the_journal_names() {
+    pin_umask();
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(4)
+        .enable_all()
+        .build()
+        .unwrap();
+    let tmp = tempfile::tempdir().unwrap();
+    let target = tmp.path().join("out");
+    let journal = target.join(longtail::APPLY_JOURNAL_NAME);
+    let journaled = |lvi: &str| {
+        let mut o = chain_opts(&target, lvi);
+        o.resume_journal = true;
+        o
+    };
+
+    rt.block_on(downsync(journaled("chain-v1.lvi")))
+        .expect("v1 downsync");
+    assert!(!journal.exists(), "a completed run leaves no journal");
+
+    let token = CancellationToken::new();
+    let mut opts = journaled("chain-v2.lvi");
+    opts.progress = Some(Arc::new(CancelOnPhase {
+        phase: "Updating version",
+        token: token.clone(),
+    }));
+    opts.cancel = Some(token.clone());
+    let result = rt.block_on(downsync(opts));
+    assert!(
+        matches!(result, Err(LongtailError::Cancelled)),
+        "expected Cancelled, got {result:?}"
+    );
+    assert!(journal.exists(), "a cancelled apply leaves its journal");
+
+    let untouched = target.join("folder/abitoftextinasubfolder.txt");
+    let good = std::fs::read(&untouched).expect("fixture asset");
+    std::fs::write(&untouched, b"torn").unwrap();
+
+    rt.block_on(downsync(journaled("chain-v2.lvi")))
+        .expect("journaled resume");
+    assert!(!journal.exists(), "a successful resume drops the journal");
+    assert_eq!(
+        std::fs::read(&untouched).unwrap(),
+        b"torn",
+        "an asset outside the journal is not re-checked"
+    );
+
+    let mut rescan = chain_opts(&target, "chain-v2.lvi");
+    rescan.cache_target_index = false;
+    rt.block_on(downsync(rescan)).expect("full-scan downsync");
+    std::fs::remove_file(target.join(".longtail.index.cache.lvi")).unwrap();
+    TreeManifest::capture(&target)
+        .unwrap()
+        .compare(&chain_manifest("chain-v2.json"), cfg!(windows))
+        .expect("the journaled assets were completed, and the scan healed the rest");
+    assert_eq!(std::fs::read(&untouched).unwrap(), good);
+}
+
+/// With the fingerprint sidecar, a scan re-hashes only files whose fingerprint
+/// moved — and still catches damage done after the install, which the cached
+/// target index cannot.
+///
+/// Trust is shown by lying to it: a sidecar entry whose recorded chunks are
+/// wrong but whose fingerprint still matches makes the run rewrite that asset,
+/// which it can only have decided without reading the file.
+#[test]
+fn the_fingerprint_cache_rehashes_only_what_changed() {
+    pin_umask();
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(4)
+        .enable_all()
+        .build()
+        .unwrap();
+    let tmp = tempfile::tempdir().unwrap();
+    let target = tmp.path().join("out");
+    let sidecar = target.join(longtail::FINGERPRINT_CACHE_NAME);
+    let opts = || {
+        let mut o = chain_opts(&target, "chain-v2.lvi");
+        o.cache_target_index = false;
+        o.fingerprint_cache = true;
+        o
+    };
+
+    rt.block_on(downsync(opts())).expect("v2 downsync");
+    assert!(sidecar.exists(), "a successful run writes the sidecar");
+
+    // Damage after the install: same length, new bytes, so only the change
+    // time gives it away.
+    let victim = target.join("abitoftext.txt");
+    let good = std::fs::read(&victim).expect("fixture asset");
+    std::fs::write(&victim, vec![b'x'; good.len()]).unwrap();
+    let report = rt.block_on(downsync(opts())).expect("healing downsync");
+    assert_eq!(
+        std::fs::read(&victim).unwrap(),
+        good,
+        "a moved fingerprint is re-hashed"
+    );
+    assert_eq!(report.assets_written, 1, "and nothing else is rewritten");
+
+    // Lie about an untouched asset's chunks.
+    let mut doc: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(&sidecar).unwrap()).unwrap();
+    let entry = doc["entries"]
+        .as_array_mut()
+        .unwrap()
+        .iter_mut()
+        .find(|e| e["path"] == "to-move.txt")
+        .expect("to-move.txt is recorded");
+    entry["chunks"][0][0] = serde_json::json!(1u64);
+    std::fs::write(&sidecar, serde_json::to_vec(&doc).unwrap()).unwrap();
+    let report = rt
+        .block_on(downsync(opts()))
+        .expect("downsync over the edited sidecar");
+    assert_eq!(
+        report.assets_written, 1,
+        "a matching fingerprint is taken at its word; if this is 0 the file was read"
+    );
+
+    // The sidecar lives inside the target, so the manifest does not carry it.
+    std::fs::remove_file(&sidecar).unwrap();
+    TreeManifest::capture(&target)
+        .unwrap()
+        .compare(&chain_manifest("chain-v2.json"), cfg!(windows))
+        .expect("tree matches manifest");
+}
+
+/// A plan reads the sources and the store index and nothing else: the target is
+/// not created, and every asset of a fresh install is counted as a write.
+#[test]
+fn a_plan_touches_nothing_and_counts_the_whole_version() {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let tmp = tempfile::tempdir().unwrap();
+    let target = tmp.path().join("out");
+    let plan = rt
+        .block_on(plan_downsync(base_opts(&target)))
+        .expect("plan");
+
+    assert!(!target.exists(), "planning must not create the target");
+    let zoo = longtail_core::VersionIndex::from_bytes(&std::fs::read(zoo_lvi()).unwrap()).unwrap();
+    assert_eq!(plan.assets_to_write, zoo.asset_count());
+    assert_eq!(plan.diff.added.len() as u32, zoo.asset_count());
+    assert_eq!(plan.assets_to_delete, 0);
+    assert_eq!(plan.write_bytes, zoo.asset_sizes.iter().sum::<u64>());
+    assert_eq!(plan.disk_delta, plan.write_bytes as i64);
+    assert!(!plan.required_blocks.is_empty());
+    assert_eq!(plan.cached_blocks, 0, "no cache path, nothing cached");
+    assert!(plan.fetch_bytes > 0);
+}
+
+/// A plan is computed against one state of the target; applying it to another
+/// would write the wrong diff. Executing refuses, and writes nothing.
+#[test]
+fn execute_plan_refuses_a_target_that_changed() {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let tmp = tempfile::tempdir().unwrap();
+    let target = tmp.path().join("out");
+    std::fs::create_dir_all(&target).unwrap();
+    let local = target.join("notes.txt");
+    std::fs::write(&local, b"before").unwrap();
+
+    let plan = rt
+        .block_on(plan_downsync(base_opts(&target)))
+        .expect("plan");
+    assert_eq!(plan.diff.removed, vec!["notes.txt".to_string()]);
+    std::fs::write(&local, b"after").unwrap();
+
+    let result = rt.block_on(execute_plan(plan));
+    assert!(
+        matches!(result, Err(LongtailError::TargetChanged(_))),
+        "expected TargetChanged, got {result:?}"
+    );
+    assert_eq!(
+        std::fs::read(&local).unwrap(),
+        b"after",
+        "nothing was applied"
+    );
+}
+
+/// Serializing keeps the report and drops the resolved run, so a plan read back
+/// from JSON is refused rather than applied against indexes it no longer has.
+#[test]
+fn a_deserialized_plan_is_a_report_only() {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let tmp = tempfile::tempdir().unwrap();
+    let plan = rt
+        .block_on(plan_downsync(base_opts(&tmp.path().join("out"))))
+        .expect("plan");
+    let json = serde_json::to_string(&plan).unwrap();
+    let back: DownsyncPlan = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.assets_to_write, plan.assets_to_write);
+    assert_eq!(back.required_blocks, plan.required_blocks);
+    assert!(back.version_diff().is_none());
+    let result = rt.block_on(execute_plan(back));
+    assert!(
+        matches!(result, Err(LongtailError::InvalidArgument(_))),
+        "got {result:?}"
+    );
+}
+
+/// Planning then executing is a downsync.
+#[test]
+fn execute_plan_matches_a_plain_downsync() {
+    pin_umask();
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let tmp = tempfile::tempdir().unwrap();
+    let target = tmp.path().join("out");
+    let plan = rt
+        .block_on(plan_downsync(base_opts(&target)))
+        .expect("plan");
+    let report = rt.block_on(execute_plan(plan)).expect("execute");
+    assert!(report.phases.iter().any(|p| p.phase == "check_target"));
+    TreeManifest::capture(&target)
+        .unwrap()
+        .compare(&zoo_manifest(), cfg!(windows))
+        .unwrap();
+}
+
+/// Records the per-asset and per-block events of a run.
+#[derive(Default)]
+struct EventLog {
+    deleted: Mutex<Vec<String>>,
+    completed: Mutex<Vec<String>>,
+    hashed: Mutex<Vec<String>>,
+    fetched: Mutex<Vec<BlockSource>>,
+}
+impl ProgressSink for EventLog {
+    fn on_progress(&self, _p: Progress) {}
+    fn on_event(&self, event: &Event<'_>) {
+        match *event {
+            Event::AssetDeleted { path } => self.deleted.lock().unwrap().push(path.into()),
+            Event::AssetCompleted { path, .. } => self.completed.lock().unwrap().push(path.into()),
+            Event::AssetHashed { path, .. } => self.hashed.lock().unwrap().push(path.into()),
+            Event::BlockFetched { source, .. } => self.fetched.lock().unwrap().push(source),
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn events_name_the_assets_and_where_each_block_came_from() {
+    pin_umask();
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(4)
+        .enable_all()
+        .build()
+        .unwrap();
+    let tmp = tempfile::tempdir().unwrap();
+    let cache = tmp.path().join("cache");
+    let run = |target: &Path, lvi: &str| {
+        let log = Arc::new(EventLog::default());
+        let mut o = chain_opts(target, lvi);
+        o.cache_target_index = false;
+        o.cache_path = Some(cache.clone());
+        o.progress = Some(log.clone());
+        rt.block_on(downsync(o)).expect("downsync");
+        log
+    };
+
+    let first = tmp.path().join("first");
+    let log = run(&first, "chain-v1.lvi");
+    let fetched = log.fetched.lock().unwrap().clone();
+    assert!(!fetched.is_empty());
+    assert!(
+        fetched.iter().all(|s| *s == BlockSource::Remote),
+        "an empty cache serves nothing"
+    );
+    assert!(
+        log.completed
+            .lock()
+            .unwrap()
+            .contains(&"to-delete.txt".to_string())
+    );
+
+    // The same version elsewhere: every block the first run fetched is cached.
+    let second = tmp.path().join("second");
+    let log = run(&second, "chain-v1.lvi");
+    let cached = log.fetched.lock().unwrap().clone();
+    assert_eq!(cached.len(), fetched.len());
+    assert!(cached.iter().all(|s| *s == BlockSource::Cache));
+
+    // Updating to v2 hashes what is there and deletes what v2 drops.
+    let log = run(&first, "chain-v2.lvi");
+    assert!(
+        log.hashed
+            .lock()
+            .unwrap()
+            .contains(&"to-delete.txt".to_string())
+    );
+    let mut deleted = log.deleted.lock().unwrap().clone();
+    deleted.sort();
+    assert_eq!(deleted, ["to-delete.txt", "to-rename.txt"]);
+}
+
+/// One session serves two versions, a warm, a validate and a cp, and reads the
+/// store index once for all of them.
+#[test]
+fn a_session_serves_repeated_operations_from_one_store() {
+    pin_umask();
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(4)
+        .enable_all()
+        .build()
+        .unwrap();
+    let tmp = tempfile::tempdir().unwrap();
+    let lvi = |name: &str| {
+        fixtures_dir()
+            .join("stores/default")
+            .join(name)
+            .to_string_lossy()
+            .into_owned()
+    };
+    rt.block_on(async {
+        let log = Arc::new(EventLog::default());
+        let mut so = StoreSessionOptions::new(store().to_string_lossy().into_owned());
+        so.cache_path = Some(tmp.path().join("cache"));
+        so.progress = Some(log.clone());
+        let session = StoreSession::open(so).await.expect("open");
+
+        // Pre-download v2 while "v1 is in use"; a second warm has nothing to do.
+        let warm = session.warm(&lvi("chain-v2.lvi")).await.expect("warm");
+        assert!(warm.fetched > 0);
+        assert_eq!(warm.already_cached, 0);
+        let again = session.warm(&lvi("chain-v2.lvi")).await.expect("warm");
+        assert_eq!(again.fetched, 0);
+        assert_eq!(again.already_cached, again.blocks);
+
+        session
+            .validate(&lvi("chain-v1.lvi"))
+            .await
+            .expect("validate");
+
+        for (target, name) in [("v2", "chain-v2"), ("v1", "chain-v1")] {
+            log.fetched.lock().unwrap().clear();
+            let target = tmp.path().join(target);
+            let mut o = chain_opts(&target, &format!("{name}.lvi"));
+            o.cache_target_index = false;
+            session.downsync(o).await.expect("downsync");
+            if name == "chain-v2" {
+                // Everything v2 needs was warmed: no block comes from the store.
+                let fetched = log.fetched.lock().unwrap().clone();
+                assert!(!fetched.is_empty());
+                assert!(fetched.iter().all(|s| *s == BlockSource::Cache));
+            }
+            TreeManifest::capture(&target)
+                .unwrap()
+                .compare(&chain_manifest(&format!("{name}.json")), cfg!(windows))
+                .expect("tree matches manifest");
+        }
+
+        let copied = tmp.path().join("copied.txt");
+        let asset = "folder/abitoftextinasubfolder.txt";
+        session
+            .cp(&lvi("chain-v1.lvi"), asset, &copied.to_string_lossy())
+            .await
+            .expect("cp");
+        assert_eq!(
+            std::fs::read(&copied).unwrap(),
+            std::fs::read(tmp.path().join("v1").join(asset)).unwrap()
+        );
+        session.close().await.expect("close");
+    });
+}
+
+/// Cancelling a session's warm cancels that warm only: the store it shares
+/// with later operations still reads blocks, and a downsync through it lands.
+#[test]
+fn a_cancelled_warm_leaves_the_session_usable() {
+    pin_umask();
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(4)
+        .enable_all()
+        .build()
+        .unwrap();
+    let tmp = tempfile::tempdir().unwrap();
+    let lvi = fixtures_dir()
+        .join("stores/default/chain-v2.lvi")
+        .to_string_lossy()
+        .into_owned();
+    // Cancels as the warm starts, after the index is read: the cancel reaches
+    // the store through the warm's own prefetch.
+    struct CancelOnWarm(CancellationToken);
+    impl ProgressSink for CancelOnWarm {
+        fn on_progress(&self, _: Progress) {}
+        fn on_phase(&self, phase: &str) {
+            if phase == "Warming cache" {
+                self.0.cancel();
+            }
+        }
+    }
+    rt.block_on(async {
+        let token = CancellationToken::new();
+        let mut so = StoreSessionOptions::new(store().to_string_lossy().into_owned());
+        so.cache_path = Some(tmp.path().join("cache"));
+        so.progress = Some(Arc::new(CancelOnWarm(token.clone())));
+        so.cancel = Some(token);
+        let session = StoreSession::open(so).await.expect("open");
+
+        let warm = session.warm(&lvi).await;
+        assert!(
+            matches!(warm, Err(LongtailError::Cancelled)),
+            "expected Cancelled, got {warm:?}"
+        );
+
+        let target = tmp.path().join("v2");
+        let mut o = chain_opts(&target, "chain-v2.lvi");
+        o.cache_target_index = false;
+        session
+            .downsync(o)
+            .await
+            .expect("downsync after a cancelled warm");
+        TreeManifest::capture(&target)
+            .unwrap()
+            .compare(&chain_manifest("chain-v2.json"), cfg!(windows))
+            .expect("tree matches manifest");
+        session.close().await.expect("close");
+    });
+}
+
+/// The stream carries a run's phases and per-asset events, then ends; the
+/// handle carries its result.
+#[test]
+fn event_streams_carry_a_downsync_and_an_upsync() {
+    pin_umask();
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(4)
+        .enable_all()
+        .build()
+        .unwrap();
+    let tmp = tempfile::tempdir().unwrap();
+    let target = tmp.path().join("target");
+    let mut o = chain_opts(&target, "chain-v1.lvi");
+    o.cache_target_index = false;
+
+    rt.block_on(async {
+        let (mut events, handle) = downsync_with_events(o);
+        let mut seen = Vec::new();
+        while let Some(event) = events.recv().await {
+            seen.push(event);
+        }
+        handle.await.unwrap().expect("downsync");
+        assert!(matches!(seen.first(), Some(StreamEvent::Phase { .. })));
+        assert!(
+            seen.contains(&StreamEvent::AssetCompleted {
+                path: "to-delete.txt".into(),
+                bytes: std::fs::metadata(target.join("to-delete.txt"))
+                    .unwrap()
+                    .len(),
+            })
+        );
+        assert!(
+            seen.iter()
+                .any(|e| matches!(e, StreamEvent::BlockFetched { .. }))
+        );
+
+        let up = longtail::UpsyncOptions::new(
+            target.to_string_lossy().into_owned(),
+            tmp.path().join("store").to_string_lossy().into_owned(),
+            tmp.path().join("v1.lvi").to_string_lossy().into_owned(),
+        );
+        let (mut events, handle) = longtail::upsync_with_events(up);
+        let mut hashed = Vec::new();
+        while let Some(event) = events.recv().await {
+            if let StreamEvent::AssetHashed { path, .. } = event {
+                hashed.push(path);
+            }
+        }
+        handle.await.unwrap().expect("upsync");
+        assert!(hashed.contains(&"to-delete.txt".to_string()));
+    });
+}
@@ -0,0 +1,60 @@
+//! `store_du` against the committed store, cross-checked with the keep-set a
+//! `prune_store` dry run computes: dropping one version keeps everything but
+//! its unique blocks.
+
+use longtail::{PruneStoreOptions, StoreDuOptions, prune_store, store_du};
+use longtail_testkit::paths::{default_lvi, default_store};
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn unique_blocks_are_what_a_prune_without_the_version_frees() {
+    let versions: Vec<String> = ["chain-v1.lvi", "chain-v2.lvi", "chain-v3.lvi", "zoo.lvi"]
+        .into_iter()
+        .map(default_lvi)
+        .collect();
+    let report = store_du(StoreDuOptions::new(default_store(), versions.clone()))
+        .await
+        .unwrap();
+    assert_eq!(report.versions.len(), versions.len());
+
+    for (i, v) in report.versions.iter().enumerate() {
+        assert_eq!(v.missing_chunks, 0, "{v:?}");
+        assert_eq!(v.unique_bytes + v.shared_bytes, v.bytes, "{v:?}");
+        let mut others = versions.clone();
+        others.remove(i);
+        let mut opts = PruneStoreOptions::new(default_store(), others);
+        opts.dry_run = true;
+        let kept = prune_store(opts).await.unwrap().keep_blocks as u32;
+        assert_eq!(kept, report.block_count - v.unique_block_count, "{v:?}");
+    }
+
+    // Every shared block of a version shows up in some overlap with it.
+    for (i, v) in report.versions.iter().enumerate() {
+        let overlapping: u64 = report
+            .overlaps
+            .iter()
+            .filter(|o| o.first == i || o.second == i)
+            .map(|o| o.bytes)
+            .sum();
+        assert!(overlapping >= v.shared_bytes, "{v:?} {report:?}");
+    }
+    // The chain versions build on one another; the zoo shares nothing.
+    assert!(report.overlaps.iter().all(|o| o.second != 3), "{report:?}");
+    assert!(!report.overlaps.is_empty(), "{report:?}");
+}
+
+/// A version listed twice is one version: its blocks stay its own.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn a_version_listed_twice_is_counted_once() {
+    let versions: Vec<String> = ["chain-v1.lvi", "zoo.lvi", "chain-v1.lvi"]
+        .into_iter()
+        .map(default_lvi)
+        .collect();
+    let report = store_du(StoreDuOptions::new(default_store(), versions))
+        .await
+        .unwrap();
+    assert_eq!(report.versions.len(), 2, "{report:?}");
+    assert!(report.overlaps.is_empty(), "{report:?}");
+    for v in &report.versions {
+        assert_eq!(v.unique_bytes, v.bytes, "{v:?}");
+    }
+}
@@ -0,0 +1,113 @@
+//! `VersionFs` over a store where one asset spans many blocks: metadata and
+//! listings come from the index, and a range read fetches only the blocks
+//! covering it, once.
+
+use std::io;
+
+use longtail::{LongtailError, StoreSession, StoreSessionOptions, VersionFs, VersionFsOptions};
+use longtail_testkit::publish::{noise, publish_small_blocks};
+
+fn kind(err: LongtailError) -> io::ErrorKind {
+    match err {
+        LongtailError::Io { source, .. } => source.kind(),
+        other => panic!("not an io error: {other}"),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn reads_ranges_lists_directories_and_fetches_only_covering_blocks() {
+    let tmp = tempfile::tempdir().unwrap();
+    let (src, store, lvi) = (
+        tmp.path().join("src"),
+        tmp.path().join("store"),
+        tmp.path().join("v.lvi"),
+    );
+    std::fs::create_dir_all(src.join("data/empty")).unwrap();
+    let big = noise(0x2545_F491_4F6C_DD1D, 500_000);
+    std::fs::write(src.join("data/big.bin"), &big).unwrap();
+    std::fs::write(src.join("readme.txt"), b"hello").unwrap();
+    publish_small_blocks(&src, &store, &lvi, "zstd").await;
+
+    let session = StoreSession::open(StoreSessionOptions::new(store.to_string_lossy()))
+        .await
+        .unwrap();
+    let fs = session
+        .version_fs(&lvi.to_string_lossy(), longtail::DEFAULT_BLOCK_CACHE_BYTES)
+        .await
+        .unwrap();
+
+    let root: Vec<String> = fs
+        .read_dir("")
+        .unwrap()
+        .into_iter()
+        .map(|m| m.path)
+        .collect();
+    assert_eq!(root, ["data", "readme.txt"]);
+    let data = fs.read_dir("/data/").unwrap();
+    let names: Vec<&str> = data.iter().map(|m| m.name()).collect();
+    assert_eq!(names, ["big.bin", "empty"]);
+    assert!(data[1].is_dir && !data[0].is_dir);
+    let meta = fs.metadata("data/big.bin").unwrap();
+    assert_eq!(meta.size, big.len() as u64);
+    assert!(meta.chunk_count > 8, "{meta:?}");
+    assert!(fs.metadata(".").unwrap().is_dir);
+
+    // A small read in the middle fetches a block or two, and again nothing.
+    let before = session.store_stats().get_count;
+    let got = fs.read_at("data/big.bin", 250_000, 100).await.unwrap();
+    assert_eq!(&got[..], &big[250_000..250_100]);
+    let fetched = session.store_stats().get_count - before;
+    assert!((1..=2).contains(&fetched), "{fetched} blocks for 100 bytes");
+    fs.read_at("data/big.bin", 250_010, 50).await.unwrap();
+    assert_eq!(session.store_stats().get_count - before, fetched);
+
+    // Ranges across chunk boundaries, at the ends, and past the end.
+    for (offset, len) in [(0, 1), (8000, 20_000), (123_456, 200_000), (499_990, 100)] {
+        let got = fs.read_at("data/big.bin", offset, len).await.unwrap();
+        let end = (offset + len).min(big.len() as u64) as usize;
+        assert!(got[..] == big[offset as usize..end], "{offset}+{len}");
+    }
+    assert!(
+        fs.read_at("data/big.bin", 600_000, 10)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    assert_eq!(&fs.read_at("readme.txt", 1, 3).await.unwrap()[..], b"ell");
+
+    assert_eq!(
+        kind(fs.metadata("nope").unwrap_err()),
+        io::ErrorKind::NotFound
+    );
+    let dir_read = fs.read_at("data", 0, 1).await.unwrap_err();
+    assert_eq!(kind(dir_read), io::ErrorKind::IsADirectory);
+    assert_eq!(
+        kind(fs.read_dir("readme.txt").unwrap_err()),
+        io::ErrorKind::NotADirectory
+    );
+
+    fs.close().await.unwrap();
+    session.close().await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn opens_its_own_store() {
+    let tmp = tempfile::tempdir().unwrap();
+    let (src, store, lvi) = (
+        tmp.path().join("src"),
+        tmp.path().join("store"),
+        tmp.path().join("v.lvi"),
+    );
+    std::fs::create_dir_all(&src).unwrap();
+    std::fs::write(src.join("a.txt"), b"alpha bravo").unwrap();
+    publish_small_blocks(&src, &store, &lvi, "zstd").await;
+
+    let fs = VersionFs::open(VersionFsOptions::new(
+        store.to_string_lossy(),
+        lvi.to_string_lossy(),
+    ))
+    .await
+    .unwrap();
+    assert_eq!(&fs.read_at("a.txt", 6, 100).await.unwrap()[..], b"bravo");
+    fs.close().await.unwrap();
+}
@@ -0,0 +1,84 @@
+//! `who_uses` against the committed chain store: a block, a chunk and a path
+//! each lead back to the versions whose downsync needs them.
+
+use longtail::{UsageTarget, WhoUsesOptions, read_version_index_from_uri, who_uses};
+use longtail_testkit::paths::{default_lvi, default_store, default_stores_dir};
+
+fn chain() -> Vec<String> {
+    ["chain-v1.lvi", "chain-v2.lvi", "chain-v3.lvi"]
+        .into_iter()
+        .map(default_lvi)
+        .collect()
+}
+
+async fn users(target: UsageTarget, versions: Vec<String>) -> longtail::WhoUsesReport {
+    who_uses(WhoUsesOptions::new(default_store(), target, versions))
+        .await
+        .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn a_path_then_its_block_then_a_chunk_lead_to_the_same_versions() {
+    // `stuff.txt` arrives in v2 and stays in v3.
+    let by_path = users(UsageTarget::Path("stuff.txt".into()), chain()).await;
+    assert_eq!(by_path.versions_checked, 3);
+    let paths: Vec<&str> = by_path.users.iter().map(|u| u.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        [default_lvi("chain-v2.lvi"), default_lvi("chain-v3.lvi")]
+    );
+    assert!(by_path.users.iter().all(|u| u.assets == ["stuff.txt"]));
+    let block = by_path.users[0].blocks[0];
+
+    // Every version fetching that block, with the assets it holds chunks of.
+    let by_block = users(UsageTarget::Block(block), chain()).await;
+    for u in &by_block.users {
+        assert_eq!(u.blocks, [block]);
+        assert!(u.assets.contains(&"stuff.txt".to_string()), "{u:?}");
+    }
+    assert!(by_block.users.len() >= 2, "{by_block:?}");
+
+    // A chunk of `stuff.txt` resolves to the same block.
+    let v2 = read_version_index_from_uri(&default_lvi("chain-v2.lvi"), &Default::default())
+        .await
+        .unwrap();
+    let asset = (0..v2.asset_count() as usize)
+        .find(|&a| v2.path(a).unwrap() == "stuff.txt")
+        .unwrap();
+    let chunk = v2.chunk_hashes
+        [v2.asset_chunk_indexes[v2.asset_chunk_index_starts[asset] as usize] as usize];
+    let by_chunk = users(UsageTarget::Chunk(chunk), chain()).await;
+    assert_eq!(by_chunk.users.len(), 2, "{by_chunk:?}");
+    assert!(by_chunk.users.iter().all(|u| u.blocks == [block]));
+
+    let nobody = users(UsageTarget::Block(0x1234), chain()).await;
+    assert_eq!((nobody.versions_checked, nobody.users.len()), (3, 0));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn versions_can_come_from_a_listing() {
+    let mut opts = WhoUsesOptions::new(
+        default_store(),
+        UsageTarget::Path("stuff.txt".into()),
+        Vec::new(),
+    );
+    opts.versions_uri = Some(default_stores_dir().to_string_lossy().into_owned());
+    opts.versions_prefix = "chain".into();
+    let report = who_uses(opts).await.unwrap();
+    assert_eq!(report.versions_checked, 3);
+    assert_eq!(report.users.len(), 2);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn an_unreadable_version_is_skipped_not_fatal() {
+    let tmp = tempfile::tempdir().unwrap();
+    let broken = tmp.path().join("broken.lvi");
+    std::fs::write(&broken, b"not a version index").unwrap();
+    let mut versions = chain();
+    versions.insert(1, broken.to_string_lossy().into_owned());
+    let report = users(UsageTarget::Path("stuff.txt".into()), versions).await;
+    assert_eq!(report.versions_checked, 3);
+    assert_eq!(report.users.len(), 2);
+    assert_eq!(report.skipped.len(), 1, "{report:?}");
+    assert_eq!(report.skipped[0].path, broken.to_string_lossy());
+}
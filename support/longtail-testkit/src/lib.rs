@@ -12,6 +12,7 @@ pub mod corpus;
 pub mod data;
 pub mod fixture_manifest;
 pub mod paths;
+pub mod publish;
 pub mod tree_manifest;
 
 #[cfg(feature = "differential")]
@@ -42,6 +42,29 @@ pub fn fixtures_dir() -> PathBuf {
     workspace_root().join("fixtures")
 }
 
+/// The committed `fixtures/stores/default/` directory: the default store and
+/// the version indexes published into it.
+pub fn default_stores_dir() -> PathBuf {
+    fixtures_dir().join("stores/default")
+}
+
+/// The committed default block store, as a storage URI.
+pub fn default_store() -> String {
+    default_stores_dir()
+        .join("store")
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// A version index committed beside the default store (`chain-v1.lvi`,
+/// `zoo.lvi`, …), as a URI.
+pub fn default_lvi(name: &str) -> String {
+    default_stores_dir()
+        .join(name)
+        .to_string_lossy()
+        .into_owned()
+}
+
 /// The upstream `chunker.input` test fixture inside the longtail submodule.
 pub fn upstream_chunker_input() -> PathBuf {
     workspace_root().join("support/longtail-sys/longtail/test/testdata/chunker.input")
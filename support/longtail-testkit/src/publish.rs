@@ -0,0 +1,36 @@
+//! Scratch content for facade tests that need an asset spread over many
+//! blocks: pseudo-random bytes, and an upsync that packs them small.
+
+#[cfg(feature = "facade")]
+use std::path::Path;
+
+/// `len` pseudo-random bytes (xorshift from `seed`), so chunking finds
+/// boundaries and blocks fill up.
+pub fn noise(seed: u64, len: usize) -> Vec<u8> {
+    let mut x = seed;
+    (0..len)
+        .map(|_| {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x as u8
+        })
+        .collect()
+}
+
+/// Upsync `src` into `store`, writing its version index to `lvi`, with 8 KiB
+/// chunks and at most four to a 32 KiB block. `compression` is an upsync
+/// compression algorithm name (`"none"`, `"zstd"`, …).
+#[cfg(feature = "facade")]
+pub async fn publish_small_blocks(src: &Path, store: &Path, lvi: &Path, compression: &str) {
+    let mut up = longtail::UpsyncOptions::new(
+        src.to_string_lossy().into_owned(),
+        store.to_string_lossy().into_owned(),
+        lvi.to_string_lossy().into_owned(),
+    );
+    up.target_chunk_size = 8192;
+    up.target_block_size = 32768;
+    up.max_chunks_per_block = 4;
+    up.compression_algorithm = compression.to_string();
+    longtail::upsync(up).await.expect("upsync");
+}